@@ -6,13 +6,16 @@
 //!     cargo run -p evals --example eval -- --model claude-haiku-4-5 --out report.json
 //! ```
 //! Never prints the key. Opt-in only — no key → clear error, no run.
+//! `--cache <file>` answers requests identical to an earlier run from a
+//! SQLite response cache instead of paying for them again.
 
 use std::sync::Arc;
 
 use evals::corpus::load_corpus;
 use evals::report::{render_table, SuiteReport};
 use evals::run::run_scenario;
-use harness::AnthropicProvider;
+use harness::{AnthropicProvider, CachingProvider, LlmProvider};
+use murmur_core::SqliteResponseCache;
 
 #[tokio::main]
 async fn main() -> std::process::ExitCode {
@@ -23,9 +26,10 @@ async fn main() -> std::process::ExitCode {
 }
 
 async fn run() -> Result<(), String> {
-    // arg parse: --model, --out, --scenario (repeatable), --fixtures <dir>
+    // arg parse: --model, --out, --scenario (repeatable), --fixtures <dir>, --cache <file>
     let mut model = "claude-haiku-4-5".to_string();
     let mut out: Option<String> = None;
+    let mut cache: Option<String> = None;
     let mut only: Vec<String> = Vec::new();
     let mut fixtures = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures").to_string();
     let mut argv = std::env::args().skip(1);
@@ -35,7 +39,8 @@ async fn run() -> Result<(), String> {
             "--out" => out = Some(argv.next().ok_or("--out needs a path")?),
             "--scenario" => only.push(argv.next().ok_or("--scenario needs an id")?),
             "--fixtures" => fixtures = argv.next().ok_or("--fixtures needs a dir")?,
-            "-h" | "--help" => return Err("usage: eval [--model M] [--out report.json] [--scenario id]... [--fixtures dir]".into()),
+            "--cache" => cache = Some(argv.next().ok_or("--cache needs a path")?),
+            "-h" | "--help" => return Err("usage: eval [--model M] [--out report.json] [--scenario id]... [--fixtures dir] [--cache file]".into()),
             other => return Err(format!("unexpected arg: {other}")),
         }
     }
@@ -50,7 +55,11 @@ async fn run() -> Result<(), String> {
         if corpus.is_empty() { return Err("no scenarios matched --scenario".into()); }
     }
 
-    let provider = Arc::new(AnthropicProvider::new(api_key, &model));
+    let mut provider: Arc<dyn LlmProvider> = Arc::new(AnthropicProvider::new(api_key, &model));
+    if let Some(path) = &cache {
+        let cache = SqliteResponseCache::open(path).map_err(|e| format!("cannot open cache {path}: {e}"))?;
+        provider = Arc::new(CachingProvider::new(provider, &model, Arc::new(cache)));
+    }
     let mut reports = Vec::new();
     for scenario in &corpus {
         eprintln!("running {} ...", scenario.id);
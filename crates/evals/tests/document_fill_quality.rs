@@ -13,7 +13,7 @@
 use std::sync::{Arc, Mutex};
 
 use harness::{
-    CompletionResponse, ContentBlock, LlmProvider, Memory, MockProvider, StopReason, Usage,
+    CompletionResponse, ContentBlock, LlmProvider, Memory, MockProvider, ServedBy, StopReason, Usage,
 };
 use murmur_core::{
     DocumentBuilder, DocumentSchema, ItemSource, SchemaField, SchemaSection, Store,
@@ -23,7 +23,8 @@ fn tool_use(name: &str, input: serde_json::Value) -> CompletionResponse {
     CompletionResponse {
         content: vec![ContentBlock::ToolUse { id: "tu".into(), name: name.into(), input }],
         stop_reason: StopReason::ToolUse,
-        usage: Usage { input_tokens: 30, output_tokens: 8 },
+        usage: Usage { input_tokens: 30, output_tokens: 8, ..Default::default() },
+        model: None,
     }
 }
 
@@ -112,6 +113,7 @@ fn processed_session(store: &Store) -> String {
             &session.id,
             "Punch list for unit twelve: faucet cartridge, dead outlet, closet hinge.",
             &Usage::default(),
+            &ServedBy::default(),
             &ids,
         )
         .unwrap();
@@ -14,11 +14,12 @@ fn tool_use(name: &str, input: serde_json::Value) -> CompletionResponse {
     CompletionResponse {
         content: vec![ContentBlock::ToolUse { id: "tu".into(), name: name.into(), input }],
         stop_reason: StopReason::ToolUse,
-        usage: Usage { input_tokens: 50, output_tokens: 10 },
+        usage: Usage { input_tokens: 50, output_tokens: 10, ..Default::default() },
+        model: None,
     }
 }
 fn end_turn(t: &str) -> CompletionResponse {
-    CompletionResponse { content: vec![ContentBlock::Text { text: t.into() }], stop_reason: StopReason::EndTurn, usage: Usage { input_tokens: 20, output_tokens: 4 } }
+    CompletionResponse { content: vec![ContentBlock::Text { text: t.into() }], stop_reason: StopReason::EndTurn, usage: Usage { input_tokens: 20, output_tokens: 4, ..Default::default() }, model: None }
 }
 fn summary(t: &str) -> CompletionResponse {
     tool_use("write_notes", serde_json::json!({ "summary": t }))
@@ -24,7 +24,8 @@ fn tool_use(name: &str, input: serde_json::Value) -> CompletionResponse {
     CompletionResponse {
         content: vec![ContentBlock::ToolUse { id: "tu".into(), name: name.into(), input }],
         stop_reason: StopReason::ToolUse,
-        usage: Usage { input_tokens: 30, output_tokens: 8 },
+        usage: Usage { input_tokens: 30, output_tokens: 8, ..Default::default() },
+        model: None,
     }
 }
 
@@ -32,7 +33,8 @@ fn end_turn(t: &str) -> CompletionResponse {
     CompletionResponse {
         content: vec![ContentBlock::Text { text: t.into() }],
         stop_reason: StopReason::EndTurn,
-        usage: Usage { input_tokens: 10, output_tokens: 2 },
+        usage: Usage { input_tokens: 10, output_tokens: 2, ..Default::default() },
+        model: None,
     }
 }
 
@@ -11,6 +11,7 @@
 //! builder just wrote — never `latest_document_artifact`, since multiple
 //! documents can now coexist for one session.
 
+use harness::{with_priority, Priority};
 use murmur_core::DocumentBuilder;
 
 use crate::convert;
@@ -30,8 +31,8 @@ impl MurmurEngine {
             self.memory.clone(),
             self.memory_store.clone(),
         );
-        let outcome = builder
-            .build(&session_id, &kind)
+        // The user is waiting on this tap: first in line on a rate limiter.
+        let outcome = with_priority(Priority::Interactive, builder.build(&session_id, &kind))
             .await
             .map_err(|e| EngineError::Document(e.to_string()))?;
         let artifact = {
@@ -73,7 +74,8 @@ mod tests {
         CompletionResponse {
             content: vec![ContentBlock::ToolUse { id: "tu".into(), name: name.into(), input }],
             stop_reason: StopReason::ToolUse,
-            usage: Usage { input_tokens: 10, output_tokens: 5 },
+            usage: Usage { input_tokens: 10, output_tokens: 5, ..Default::default() },
+            model: None,
         }
     }
 
@@ -81,7 +83,8 @@ mod tests {
         CompletionResponse {
             content: vec![ContentBlock::Text { text: text.into() }],
             stop_reason: StopReason::EndTurn,
-            usage: Usage { input_tokens: 10, output_tokens: 5 },
+            usage: Usage { input_tokens: 10, output_tokens: 5, ..Default::default() },
+            model: None,
         }
     }
 
@@ -112,6 +115,8 @@ mod tests {
                 live: Arc::new(MockProvider::new(vec![])),
                 processing: Arc::new(MockProvider::new(responses)),
                 reflection: Arc::new(MockProvider::new(vec![])),
+                local: None,
+                batches: None,
             },
         );
         let session = engine.clone().begin_walk(None, "landscape".into()).unwrap();
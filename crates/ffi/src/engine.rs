@@ -5,8 +5,14 @@
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 
-use harness::{AnthropicProvider, FileMemoryStore, LlmProvider, Memory, MemoryStore};
-use murmur_core::Store;
+use harness::{
+    AnthropicProvider, BatchProvider, CacheBounds, CachingProvider, LimitedProvider, LlmProvider, LocalApi,
+    LocalProvider, LocalToolMode, Memory, MemoryStore, OpenAiProvider, RateLimiter, RateLimits, ResponseCache,
+    RetryingProvider, RoutingProvider,
+};
+use murmur_core::{SqliteMemoryStore, SqliteResponseCache, Store};
+
+use crate::provider_error::ProviderErrorKind;
 
 /// Fallible-path errors that cross the FFI boundary as a thrown error rather
 /// than a panic (Plan 07 CANON: no panics across FFI). `flat_error` means the
@@ -66,6 +72,12 @@ pub enum EngineError {
     /// only (never an api key).
     #[error("schema error: {0}")]
     Schema(String),
+    /// A provider failure that retrying alone won't fix — today a rejected
+    /// api key, surfaced by `retry_failed_sessions`. The typed `kind` stays
+    /// Rust-side (flat error); Swift gets the variant plus the message
+    /// ("provider error: API key rejected (HTTP 401)"). Never the key itself.
+    #[error("provider error: {}{}", kind.describe(), http_status.map(|s| format!(" (HTTP {s})")).unwrap_or_default())]
+    Provider { kind: ProviderErrorKind, http_status: Option<u16> },
 }
 
 /// Config crossing the FFI boundary. `api_key` is an opaque `String` from the
@@ -102,6 +114,140 @@ pub struct EngineConfig {
     /// suspected hallucinations. Not secret: fine to print in `Debug`. Swift
     /// `sttnsp=<float>` launch arg overrides it.
     pub stt_no_speech_prob_threshold: f32,
+    /// Per-purpose endpoint overrides (see `ProviderEndpoint`). `None` (the
+    /// default) = Anthropic at `base_url` with `api_key`, as before.
+    #[uniffi(default = None)]
+    pub endpoint_live: Option<ProviderEndpoint>,
+    #[uniffi(default = None)]
+    pub endpoint_processing: Option<ProviderEndpoint>,
+    #[uniffi(default = None)]
+    pub endpoint_reflection: Option<ProviderEndpoint>,
+    /// Offline fallback mode (see `LocalFallback`). `None` (the default) =
+    /// an unreachable cloud fails the walk into the retry bucket, as before.
+    #[uniffi(default = None)]
+    pub local_fallback: Option<LocalFallback>,
+    /// Client-side limits on each cloud key (see `RateLimitConfig`). `None`
+    /// (the default) = calls aren't coordinated, as before.
+    #[uniffi(default = None)]
+    pub rate_limits: Option<RateLimitConfig>,
+    /// Answers repeated identical cloud calls from an on-device cache (see
+    /// `ResponseCacheConfig`). `None` (the default) = every call is sent.
+    #[uniffi(default = None)]
+    pub response_cache: Option<ResponseCacheConfig>,
+    /// Sends `retry_failed_sessions`' notes calls through the Message Batches
+    /// API at half price instead of waiting on them; `resume_batches`
+    /// finishes those walks once the batch ends, at engine start and whenever
+    /// the host polls. Needs an Anthropic processing endpoint — ignored on
+    /// any other. `false` (the default) = every call is synchronous.
+    #[uniffi(default = false)]
+    pub batch_processing: bool,
+}
+
+/// Requests per minute, input tokens per minute and calls in flight, applied
+/// per distinct (protocol, base_url, key) across every purpose that uses it.
+/// Lanes decide who goes first when it's saturated: finishing a walk and
+/// building a document go ahead of live ticks, which go ahead of retries,
+/// reprocessing and reflection. `None` leaves a dimension unlimited.
+#[derive(uniffi::Record, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RateLimitConfig {
+    #[uniffi(default = None)]
+    pub requests_per_minute: Option<u32>,
+    #[uniffi(default = None)]
+    pub input_tokens_per_minute: Option<u64>,
+    #[uniffi(default = None)]
+    pub max_in_flight: Option<u32>,
+}
+
+impl From<RateLimitConfig> for RateLimits {
+    fn from(config: RateLimitConfig) -> Self {
+        RateLimits {
+            requests_per_minute: config.requests_per_minute,
+            input_tokens_per_minute: config.input_tokens_per_minute,
+            max_in_flight: config.max_in_flight.map(|n| n as usize),
+        }
+    }
+}
+
+/// Where and how long cloud answers are kept for reuse. A walk reprocessed
+/// unchanged, or a document rebuilt from the same notes, sends requests
+/// byte-identical to last time; those are answered from the cache at no
+/// cost. Retrying a failed walk always asks the model again. The file is
+/// disposable — deleting it only costs misses. `None` leaves a bound off.
+#[derive(uniffi::Record, Clone, Debug, PartialEq, Eq)]
+pub struct ResponseCacheConfig {
+    /// SQLite file for the cache, separate from `db_path`.
+    pub path: String,
+    #[uniffi(default = None)]
+    pub ttl_secs: Option<u64>,
+    #[uniffi(default = None)]
+    pub max_bytes: Option<u64>,
+}
+
+impl ResponseCacheConfig {
+    /// The cache, or `None` when its file can't be opened: calls then just
+    /// go uncached rather than failing engine startup.
+    fn open(&self) -> Option<Arc<dyn ResponseCache>> {
+        let cache = SqliteResponseCache::open(&self.path).ok()?.with_bounds(CacheBounds {
+            ttl: self.ttl_secs.map(std::time::Duration::from_secs),
+            max_bytes: self.max_bytes,
+        });
+        Some(Arc::new(cache))
+    }
+}
+
+/// An on-device inference server (Ollama `/api/chat` shape) to process
+/// walks on when the cloud can't be reached. Its results are provisional:
+/// `retry_failed_sessions` reprocesses them with the cloud model once the
+/// connection is back. No key — it never leaves the device.
+#[derive(uniffi::Record, Clone, Debug)]
+pub struct LocalFallback {
+    /// Server root; `None` = Ollama's default `http://127.0.0.1:11434`.
+    #[uniffi(default = None)]
+    pub base_url: Option<String>,
+    pub model: String,
+    /// `true` when the server and model support native tool calls; `false`
+    /// (the default) emulates them through constrained JSON output.
+    #[uniffi(default = false)]
+    pub native_tools: bool,
+    /// `true` for a server on the OpenAI-compatible `/v1/chat/completions`
+    /// route (llama.cpp-server); `false` (the default) speaks Ollama's
+    /// `/api/chat`.
+    #[uniffi(default = false)]
+    pub openai_compatible: bool,
+}
+
+/// Which wire protocol a purpose's endpoint speaks.
+#[derive(uniffi::Enum, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ProviderProtocol {
+    /// Anthropic Messages API (`/v1/messages`).
+    Anthropic,
+    /// OpenAI-style chat completions (`/v1/chat/completions`) — OpenAI or
+    /// any compatible gateway.
+    OpenAiChat,
+}
+
+/// Routes one purpose somewhere other than the default Anthropic endpoint.
+/// `base_url` is the API root without `/v1`; `None` = the protocol's own
+/// default for `OpenAiChat`, or `EngineConfig::base_url` for `Anthropic`.
+/// `api_key: None` reuses `EngineConfig::api_key` (one-key gateways). Same
+/// redaction rule as `EngineConfig`: `Debug` never prints the key.
+#[derive(uniffi::Record, Clone)]
+pub struct ProviderEndpoint {
+    pub protocol: ProviderProtocol,
+    #[uniffi(default = None)]
+    pub base_url: Option<String>,
+    #[uniffi(default = None)]
+    pub api_key: Option<String>,
+}
+
+impl std::fmt::Debug for ProviderEndpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProviderEndpoint")
+            .field("protocol", &self.protocol)
+            .field("base_url", &self.base_url)
+            .field("api_key", &self.api_key.as_ref().map(|_| "<redacted>"))
+            .finish()
+    }
 }
 
 impl std::fmt::Debug for EngineConfig {
@@ -119,13 +265,34 @@ impl std::fmt::Debug for EngineConfig {
             .field("stt_use_gpu", &self.stt_use_gpu)
             .field("stt_vad_rms_threshold", &self.stt_vad_rms_threshold)
             .field("stt_no_speech_prob_threshold", &self.stt_no_speech_prob_threshold)
+            .field("endpoint_live", &self.endpoint_live)
+            .field("endpoint_processing", &self.endpoint_processing)
+            .field("endpoint_reflection", &self.endpoint_reflection)
+            .field("local_fallback", &self.local_fallback)
+            .field("rate_limits", &self.rate_limits)
+            .field("response_cache", &self.response_cache)
+            .field("batch_processing", &self.batch_processing)
             .finish()
     }
 }
 
 /// Three routing purposes (D11): `live` (cheap), `processing` (strong),
-/// `reflection` (cheap). One `AnthropicProvider` per distinct (model, key,
-/// base_url), `Arc`-deduped across purposes that share a model.
+/// `reflection` (cheap). Each purpose speaks the protocol its
+/// `ProviderEndpoint` names (Anthropic when unset). One provider per
+/// distinct (protocol, base_url, key, model), `Arc`-deduped across purposes
+/// that share all four. Each is wrapped in a `RetryingProvider`, so a
+/// 429/529 or a dropped connection is retried with backoff instead of
+/// failing the session outright; with `rate_limits` set, each attempt first
+/// passes the limiter its (protocol, base_url, key) shares with every other
+/// model on that key; with `response_cache` set, a repeated request is
+/// answered from it before any of that. `processing` and `reflection` then fall
+/// through to the live model (`RoutingProvider`) when their own is still
+/// overloaded or unreachable after those retries — a cheaper answer beats a
+/// failed walk. A purpose that already shares the live provider gets no
+/// chain. `local` is the offline fallback, when
+/// configured — deliberately NOT retry-wrapped: a local server that isn't
+/// running won't be by the next backoff tick. `batches` is the processing
+/// model's Message Batches client, with `batch_processing` set.
 ///
 /// `pub` (not `pub(crate)`) so `crates/ffi/tests/bridge_e2e.rs` can inject
 /// mock providers via `MurmurEngine::with_providers` — never crosses FFI (no
@@ -135,26 +302,111 @@ pub struct Providers {
     pub live: Arc<dyn LlmProvider>,
     pub processing: Arc<dyn LlmProvider>,
     pub reflection: Arc<dyn LlmProvider>,
+    pub local: Option<Arc<dyn LlmProvider>>,
+    pub batches: Option<Arc<dyn BatchProvider>>,
 }
 
 fn build_providers(config: &EngineConfig) -> Providers {
-    let mut cache: HashMap<String, Arc<dyn LlmProvider>> = HashMap::new();
-    let mut make = |model: &str| -> Arc<dyn LlmProvider> {
+    type Key = (ProviderProtocol, Option<String>, String, String);
+    let mut cache: HashMap<Key, Arc<dyn LlmProvider>> = HashMap::new();
+    let mut limiters: HashMap<(ProviderProtocol, Option<String>, String), Arc<RateLimiter>> = HashMap::new();
+    let response_cache = config.response_cache.as_ref().and_then(ResponseCacheConfig::open);
+    let mut make = |model: &str, endpoint: Option<&ProviderEndpoint>| -> Arc<dyn LlmProvider> {
+        let protocol = endpoint.map_or(ProviderProtocol::Anthropic, |e| e.protocol);
+        let base_url = match endpoint.and_then(|e| e.base_url.clone()) {
+            Some(base) => Some(base),
+            None if protocol == ProviderProtocol::Anthropic => config.base_url.clone(),
+            None => None,
+        };
+        let api_key =
+            endpoint.and_then(|e| e.api_key.clone()).unwrap_or_else(|| config.api_key.clone());
         cache
-            .entry(model.to_string())
+            .entry((protocol, base_url.clone(), api_key.clone(), model.to_string()))
             .or_insert_with(|| {
-                let mut provider = AnthropicProvider::new(config.api_key.clone(), model.to_string());
-                if let Some(base) = &config.base_url {
-                    provider = provider.with_base_url(base.clone());
+                let key = (protocol, base_url.clone(), api_key.clone());
+                let inner: Arc<dyn LlmProvider> = match protocol {
+                    ProviderProtocol::Anthropic => {
+                        let mut provider = AnthropicProvider::new(api_key, model.to_string());
+                        if let Some(base) = base_url {
+                            provider = provider.with_base_url(base);
+                        }
+                        Arc::new(provider)
+                    }
+                    ProviderProtocol::OpenAiChat => {
+                        let mut provider = OpenAiProvider::new(api_key, model.to_string());
+                        if let Some(base) = base_url {
+                            provider = provider.with_base_url(base);
+                        }
+                        Arc::new(provider)
+                    }
+                };
+                // Under the retry layer, so every attempt is admitted.
+                let inner = match config.rate_limits {
+                    Some(limits) => {
+                        let limiter = limiters
+                            .entry(key)
+                            .or_insert_with(|| Arc::new(RateLimiter::new(limits.into())))
+                            .clone();
+                        Arc::new(LimitedProvider::new(inner, limiter))
+                    }
+                    None => inner,
+                };
+                let retrying: Arc<dyn LlmProvider> = Arc::new(RetryingProvider::new(inner));
+                match &response_cache {
+                    Some(cache) => Arc::new(CachingProvider::new(retrying, model, cache.clone())),
+                    None => retrying,
                 }
-                Arc::new(provider) as Arc<dyn LlmProvider>
             })
             .clone()
     };
+    let live = make(&config.model_live, config.endpoint_live.as_ref());
+    let falling_back_to_live = |model: &str, primary: Arc<dyn LlmProvider>| -> Arc<dyn LlmProvider> {
+        if Arc::ptr_eq(&primary, &live) {
+            return primary;
+        }
+        Arc::new(RoutingProvider::new(model, primary).with_fallback(config.model_live.clone(), live.clone()))
+    };
+    let processing = falling_back_to_live(
+        &config.model_processing,
+        make(&config.model_processing, config.endpoint_processing.as_ref()),
+    );
+    let reflection = falling_back_to_live(
+        &config.model_reflection,
+        make(&config.model_reflection, config.endpoint_reflection.as_ref()),
+    );
+    // Batches aren't retried or rate-limited per call: a submission is one
+    // request, and a failed one fails the staged walks into the retry bucket.
+    let batches = match config.endpoint_processing.as_ref() {
+        _ if !config.batch_processing => None,
+        Some(endpoint) if endpoint.protocol != ProviderProtocol::Anthropic => None,
+        endpoint => {
+            let api_key =
+                endpoint.and_then(|e| e.api_key.clone()).unwrap_or_else(|| config.api_key.clone());
+            let mut provider = AnthropicProvider::new(api_key, config.model_processing.clone());
+            if let Some(base) = endpoint.and_then(|e| e.base_url.clone()).or_else(|| config.base_url.clone()) {
+                provider = provider.with_base_url(base);
+            }
+            Some(Arc::new(provider) as Arc<dyn BatchProvider>)
+        }
+    };
     Providers {
-        live: make(&config.model_live),
-        processing: make(&config.model_processing),
-        reflection: make(&config.model_reflection),
+        live,
+        processing,
+        reflection,
+        local: config.local_fallback.as_ref().map(|fallback| {
+            let mut provider = LocalProvider::new(fallback.model.clone());
+            if let Some(base) = &fallback.base_url {
+                provider = provider.with_base_url(base.clone());
+            }
+            if fallback.native_tools {
+                provider = provider.with_tool_mode(LocalToolMode::Native);
+            }
+            if fallback.openai_compatible {
+                provider = provider.with_api(LocalApi::OpenAiCompatible);
+            }
+            Arc::new(provider) as Arc<dyn LlmProvider>
+        }),
+        batches,
     }
 }
 
@@ -215,16 +467,22 @@ impl MurmurEngine {
     pub fn new(config: EngineConfig) -> Result<Arc<Self>, EngineError> {
         let store = Store::open(&config.db_path, config.device_id.clone())
             .map_err(|e| EngineError::Store(e.to_string()))?;
-        let memory_store: Arc<dyn MemoryStore> =
-            Arc::new(FileMemoryStore::new(format!("{}.memory.json", config.db_path)));
+        // Memory lives in the store; a pre-v14 install's JSON file moves in
+        // on first open. Never fatal: losing old memory beats an app that
+        // can't start.
+        if let Err(e) = store.import_memory_file(format!("{}.memory.json", config.db_path)) {
+            eprintln!("murmur-ffi: legacy memory not imported: {e}");
+        }
+        let store = Arc::new(Mutex::new(store));
+        let memory_store: Arc<dyn MemoryStore> = Arc::new(SqliteMemoryStore::new(store.clone()));
         let memory = memory_store.load().unwrap_or_default();
         let providers = build_providers(&config);
         let runtime = Arc::new(
             tokio::runtime::Runtime::new().map_err(|e| EngineError::Runtime(e.to_string()))?,
         );
         let runtime_handle = runtime.handle().clone();
-        Ok(Arc::new(MurmurEngine {
-            store: Arc::new(Mutex::new(store)),
+        let engine = Arc::new(MurmurEngine {
+            store,
             memory: Arc::new(Mutex::new(memory)),
             memory_store,
             providers,
@@ -237,7 +495,20 @@ impl MurmurEngine {
             #[cfg(feature = "whisper")]
             stt_warm: Mutex::new(None),
             _runtime: Some(runtime),
-        }))
+        });
+        // Walks whose batched notes came back while the app was closed
+        // finish now, in the background. Never fatal: what doesn't finish
+        // here does on the next start or host poll. The task holds the
+        // processor's handles, not the engine (whose runtime it runs on).
+        if engine.providers.batches.is_some() {
+            let processor = engine.retry_processor();
+            engine.runtime_handle.spawn(async move {
+                if let Err(e) = processor.resume_batches().await {
+                    eprintln!("murmur-ffi: batches not resumed: {e}");
+                }
+            });
+        }
+        Ok(engine)
     }
 }
 
@@ -385,6 +656,13 @@ mod tests {
             stt_use_gpu: true,
             stt_vad_rms_threshold: 0.0,
             stt_no_speech_prob_threshold: 0.6,
+            endpoint_live: None,
+            endpoint_processing: None,
+            endpoint_reflection: None,
+            local_fallback: None,
+            rate_limits: None,
+            response_cache: None,
+            batch_processing: false,
         };
         let printed = format!("{cfg:?}");
         assert!(!printed.contains("sk-super-secret"), "api key must never be printable");
@@ -416,6 +694,13 @@ mod tests {
             stt_use_gpu: true,
             stt_vad_rms_threshold: 0.0,
             stt_no_speech_prob_threshold: 0.6,
+            endpoint_live: None,
+            endpoint_processing: None,
+            endpoint_reflection: None,
+            local_fallback: None,
+            rate_limits: None,
+            response_cache: None,
+            batch_processing: false,
         };
         let providers = build_providers(&cfg);
         assert!(Arc::ptr_eq(&providers.live, &providers.reflection));
@@ -439,6 +724,13 @@ mod tests {
             stt_use_gpu: true,
             stt_vad_rms_threshold: 0.01,
             stt_no_speech_prob_threshold: 0.42,
+            endpoint_live: None,
+            endpoint_processing: None,
+            endpoint_reflection: None,
+            local_fallback: None,
+            rate_limits: None,
+            response_cache: None,
+            batch_processing: false,
         };
         let engine = MurmurEngine::new(cfg).expect("engine construction with :memory: store");
         assert_eq!(engine.stt_vad_rms_threshold, 0.01, "vad threshold threaded onto the engine");
@@ -466,10 +758,95 @@ mod tests {
             stt_use_gpu: true,
             stt_vad_rms_threshold: 0.0,
             stt_no_speech_prob_threshold: 0.6,
+            endpoint_live: None,
+            endpoint_processing: None,
+            endpoint_reflection: None,
+            local_fallback: None,
+            rate_limits: None,
+            response_cache: None,
+            batch_processing: false,
         };
         assert!(matches!(MurmurEngine::new(cfg), Err(EngineError::Store(_))));
     }
 
+    #[test]
+    fn memory_moves_into_the_store_on_first_open_and_persists_there() {
+        let dir = std::env::temp_dir().join(format!("murmur-ffi-memory-{}", murmur_core::new_id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db_path = dir.join("murmur.db").to_string_lossy().into_owned();
+        let mut legacy = Memory::default();
+        legacy.remember("vocabulary", "french drain", 10);
+        std::fs::write(format!("{db_path}.memory.json"), serde_json::to_string(&legacy).unwrap()).unwrap();
+        let cfg = EngineConfig {
+            db_path: db_path.clone(),
+            device_id: "dev".into(),
+            api_key: "sk-test".into(),
+            base_url: None,
+            model_live: "claude-haiku-4-5".into(),
+            model_processing: "claude-sonnet-4-5".into(),
+            model_reflection: "claude-haiku-4-5".into(),
+            stt_model_path: None,
+            stt_flush_on_finish: true,
+            stt_use_gpu: true,
+            stt_vad_rms_threshold: 0.0,
+            stt_no_speech_prob_threshold: 0.6,
+            endpoint_live: None,
+            endpoint_processing: None,
+            endpoint_reflection: None,
+            local_fallback: None,
+            rate_limits: None,
+            response_cache: None,
+            batch_processing: false,
+        };
+
+        let engine = MurmurEngine::new(cfg.clone()).unwrap();
+        assert_eq!(engine.list_vocabulary().unwrap(), vec!["french drain"]);
+        engine.add_vocabulary_term("sleeper wall".into()).unwrap();
+        drop(engine);
+
+        assert!(!std::path::Path::new(&format!("{db_path}.memory.json")).exists(), "imported once");
+        let reopened = MurmurEngine::new(cfg).unwrap();
+        assert_eq!(reopened.list_vocabulary().unwrap(), vec!["french drain", "sleeper wall"]);
+        drop(reopened);
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn a_garbage_legacy_memory_file_does_not_stop_the_engine() {
+        let dir = std::env::temp_dir().join(format!("murmur-ffi-memory-{}", murmur_core::new_id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db_path = dir.join("murmur.db").to_string_lossy().into_owned();
+        std::fs::write(format!("{db_path}.memory.json"), "\u{0}garbage{").unwrap();
+        let cfg = EngineConfig {
+            db_path: db_path.clone(),
+            device_id: "dev".into(),
+            api_key: "sk-test".into(),
+            base_url: None,
+            model_live: "claude-haiku-4-5".into(),
+            model_processing: "claude-sonnet-4-5".into(),
+            model_reflection: "claude-haiku-4-5".into(),
+            stt_model_path: None,
+            stt_flush_on_finish: true,
+            stt_use_gpu: true,
+            stt_vad_rms_threshold: 0.0,
+            stt_no_speech_prob_threshold: 0.6,
+            endpoint_live: None,
+            endpoint_processing: None,
+            endpoint_reflection: None,
+            local_fallback: None,
+            rate_limits: None,
+            response_cache: None,
+            batch_processing: false,
+        };
+
+        let engine = MurmurEngine::new(cfg.clone()).unwrap();
+        assert!(engine.list_vocabulary().unwrap().is_empty());
+        drop(engine);
+        assert!(std::path::Path::new(&format!("{db_path}.memory.json.corrupt")).exists(), "moved aside");
+        assert!(MurmurEngine::new(cfg).is_ok(), "and later launches start too");
+        std::fs::remove_dir_all(dir).ok();
+    }
+
     // --- Plan 20 Stage 3: warm_stt (D6/D7/D8) ------------------------------
 
     /// A `None` model path (text-only engine) warms to Ok — a no-op in both
@@ -491,6 +868,13 @@ mod tests {
             stt_use_gpu: true,
             stt_vad_rms_threshold: 0.0,
             stt_no_speech_prob_threshold: 0.6,
+            endpoint_live: None,
+            endpoint_processing: None,
+            endpoint_reflection: None,
+            local_fallback: None,
+            rate_limits: None,
+            response_cache: None,
+            batch_processing: false,
         };
         let engine = MurmurEngine::new(cfg).unwrap();
         engine.warm_stt().expect("no model path -> warm is a no-op Ok");
@@ -522,6 +906,13 @@ mod tests {
             stt_use_gpu: true,
             stt_vad_rms_threshold: 0.0,
             stt_no_speech_prob_threshold: 0.6,
+            endpoint_live: None,
+            endpoint_processing: None,
+            endpoint_reflection: None,
+            local_fallback: None,
+            rate_limits: None,
+            response_cache: None,
+            batch_processing: false,
         };
         let engine = MurmurEngine::new(cfg).unwrap();
         engine.warm_stt().expect("first warm loads the model");
@@ -567,9 +958,311 @@ mod tests {
             stt_use_gpu: true,
             stt_vad_rms_threshold: 0.0,
             stt_no_speech_prob_threshold: 0.6,
+            endpoint_live: None,
+            endpoint_processing: None,
+            endpoint_reflection: None,
+            local_fallback: None,
+            rate_limits: None,
+            response_cache: None,
+            batch_processing: false,
         };
         let providers = build_providers(&cfg);
         assert!(Arc::ptr_eq(&providers.live, &providers.reflection), "same model shares one Arc");
         assert!(!Arc::ptr_eq(&providers.live, &providers.processing));
+        assert!(providers.batches.is_none(), "no batches unless asked for");
+    }
+
+    #[test]
+    fn batch_processing_needs_an_anthropic_processing_endpoint() {
+        let cfg = EngineConfig {
+            db_path: ":memory:".into(),
+            device_id: "dev".into(),
+            api_key: "sk-test".into(),
+            base_url: None,
+            model_live: "claude-haiku-4-5".into(),
+            model_processing: "claude-sonnet-4-5".into(),
+            model_reflection: "claude-haiku-4-5".into(),
+            stt_model_path: None,
+            stt_flush_on_finish: true,
+            stt_use_gpu: true,
+            stt_vad_rms_threshold: 0.0,
+            stt_no_speech_prob_threshold: 0.6,
+            endpoint_live: None,
+            endpoint_processing: None,
+            endpoint_reflection: None,
+            local_fallback: None,
+            rate_limits: None,
+            response_cache: None,
+            batch_processing: true,
+        };
+        assert!(build_providers(&cfg).batches.is_some());
+        assert!(format!("{cfg:?}").contains("batch_processing: true"));
+
+        let openai = ProviderEndpoint {
+            protocol: ProviderProtocol::OpenAiChat,
+            base_url: Some("https://gateway.example".into()),
+            api_key: None,
+        };
+        let cfg = EngineConfig { endpoint_processing: Some(openai), ..cfg };
+        assert!(build_providers(&cfg).batches.is_none(), "only Anthropic has a batch API");
+    }
+
+    #[test]
+    fn per_purpose_endpoints_split_the_dedupe_and_redact_their_keys() {
+        let openai = ProviderEndpoint {
+            protocol: ProviderProtocol::OpenAiChat,
+            base_url: Some("https://gateway.example".into()),
+            api_key: Some("sk-openai-secret".into()),
+        };
+        let cfg = EngineConfig {
+            db_path: ":memory:".into(),
+            device_id: "dev".into(),
+            api_key: "sk-test".into(),
+            base_url: None,
+            model_live: "small-model".into(),
+            model_processing: "claude-sonnet-4-5".into(),
+            model_reflection: "small-model".into(),
+            stt_model_path: None,
+            stt_flush_on_finish: true,
+            stt_use_gpu: true,
+            stt_vad_rms_threshold: 0.0,
+            stt_no_speech_prob_threshold: 0.6,
+            endpoint_live: Some(openai.clone()),
+            endpoint_processing: None,
+            endpoint_reflection: Some(openai.clone()),
+            local_fallback: None,
+            rate_limits: None,
+            response_cache: None,
+            batch_processing: false,
+        };
+        let providers = build_providers(&cfg);
+        assert!(Arc::ptr_eq(&providers.live, &providers.reflection), "same endpoint + model shares");
+
+        // Same model, but reflection back on the default protocol: split.
+        let cfg = EngineConfig { endpoint_reflection: None, ..cfg };
+        let providers = build_providers(&cfg);
+        assert!(!Arc::ptr_eq(&providers.live, &providers.reflection));
+
+        let printed = format!("{cfg:?}");
+        assert!(printed.contains("OpenAiChat") && printed.contains("gateway.example"));
+        assert!(!printed.contains("sk-openai-secret"), "endpoint keys must never be printable");
+        assert!(providers.local.is_none(), "no fallback unless configured");
+    }
+
+    #[tokio::test]
+    async fn an_overloaded_processing_model_falls_back_to_the_live_model() {
+        use wiremock::matchers::method;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        // A retry-after past the retry deadline: the processing model gives
+        // up at once instead of backing off, and the chain moves on.
+        let processing = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(529).insert_header("retry-after", "600"))
+            .expect(1)
+            .mount(&processing)
+            .await;
+        let live = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "content": [{"type": "text", "text": "ok"}],
+                "stop_reason": "end_turn",
+                "usage": {"input_tokens": 12, "output_tokens": 3}
+            })))
+            .expect(1)
+            .mount(&live)
+            .await;
+        let cfg = EngineConfig {
+            db_path: ":memory:".into(),
+            device_id: "dev".into(),
+            api_key: "sk-test".into(),
+            base_url: Some(live.uri()),
+            model_live: "claude-haiku-4-5".into(),
+            model_processing: "claude-sonnet-4-5".into(),
+            model_reflection: "claude-haiku-4-5".into(),
+            stt_model_path: None,
+            stt_flush_on_finish: true,
+            stt_use_gpu: true,
+            stt_vad_rms_threshold: 0.0,
+            stt_no_speech_prob_threshold: 0.6,
+            endpoint_live: None,
+            endpoint_processing: Some(ProviderEndpoint {
+                protocol: ProviderProtocol::Anthropic,
+                base_url: Some(processing.uri()),
+                api_key: None,
+            }),
+            endpoint_reflection: None,
+            local_fallback: None,
+            rate_limits: None,
+            response_cache: None,
+            batch_processing: false,
+        };
+        let providers = build_providers(&cfg);
+        let request = harness::CompletionRequest {
+            system: "sys".into(),
+            messages: vec![harness::Message::user_text("hi")],
+            tools: vec![],
+            max_tokens: 64,
+            tool_choice: None,
+            cache: Default::default(),
+            thinking: None,
+        };
+        let response = providers.processing.complete(request).await.unwrap();
+        assert_eq!(response.model.as_deref(), Some("claude-haiku-4-5"), "served by the live model");
+    }
+
+    #[tokio::test]
+    async fn rate_limits_are_shared_by_every_model_on_one_key() {
+        use wiremock::matchers::method;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({
+                        "content": [{"type": "text", "text": "ok"}],
+                        "stop_reason": "end_turn",
+                        "usage": {"input_tokens": 12, "output_tokens": 3}
+                    }))
+                    .set_delay(std::time::Duration::from_millis(200)),
+            )
+            .expect(2)
+            .mount(&server)
+            .await;
+        let cfg = EngineConfig {
+            db_path: ":memory:".into(),
+            device_id: "dev".into(),
+            api_key: "sk-test".into(),
+            base_url: Some(server.uri()),
+            model_live: "claude-haiku-4-5".into(),
+            model_processing: "claude-sonnet-4-5".into(),
+            model_reflection: "claude-haiku-4-5".into(),
+            stt_model_path: None,
+            stt_flush_on_finish: true,
+            stt_use_gpu: true,
+            stt_vad_rms_threshold: 0.0,
+            stt_no_speech_prob_threshold: 0.6,
+            endpoint_live: None,
+            endpoint_processing: None,
+            endpoint_reflection: None,
+            local_fallback: None,
+            rate_limits: Some(RateLimitConfig { max_in_flight: Some(1), ..Default::default() }),
+            response_cache: None,
+            batch_processing: false,
+        };
+        let providers = build_providers(&cfg);
+        assert!(Arc::ptr_eq(&providers.live, &providers.reflection), "limits don't break the dedupe");
+        let request = harness::CompletionRequest {
+            system: "sys".into(),
+            messages: vec![harness::Message::user_text("hi")],
+            tools: vec![],
+            max_tokens: 64,
+            tool_choice: None,
+            cache: Default::default(),
+            thinking: None,
+        };
+        let started = std::time::Instant::now();
+        let (live, processing) =
+            tokio::join!(providers.live.complete(request.clone()), providers.processing.complete(request));
+        assert!(live.is_ok() && processing.is_ok());
+        assert!(started.elapsed() >= std::time::Duration::from_millis(400), "one call in flight at a time");
+        assert!(format!("{cfg:?}").contains("max_in_flight: Some(1)"));
+    }
+
+    #[tokio::test]
+    async fn a_configured_response_cache_answers_a_repeated_request() {
+        use wiremock::matchers::method;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "content": [{"type": "text", "text": "ok"}],
+                "stop_reason": "end_turn",
+                "usage": {"input_tokens": 12, "output_tokens": 3}
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let dir = std::env::temp_dir().join(format!("murmur-ffi-cache-{}", murmur_core::new_id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let cfg = EngineConfig {
+            db_path: ":memory:".into(),
+            device_id: "dev".into(),
+            api_key: "sk-test".into(),
+            base_url: Some(server.uri()),
+            model_live: "claude-haiku-4-5".into(),
+            model_processing: "claude-sonnet-4-5".into(),
+            model_reflection: "claude-haiku-4-5".into(),
+            stt_model_path: None,
+            stt_flush_on_finish: true,
+            stt_use_gpu: true,
+            stt_vad_rms_threshold: 0.0,
+            stt_no_speech_prob_threshold: 0.6,
+            endpoint_live: None,
+            endpoint_processing: None,
+            endpoint_reflection: None,
+            local_fallback: None,
+            rate_limits: None,
+            response_cache: Some(ResponseCacheConfig {
+                path: dir.join("cache.db").to_string_lossy().into_owned(),
+                ttl_secs: Some(86_400),
+                max_bytes: None,
+            }),
+            batch_processing: false,
+        };
+        let providers = build_providers(&cfg);
+        assert!(Arc::ptr_eq(&providers.live, &providers.reflection), "the cache doesn't break the dedupe");
+        let request = harness::CompletionRequest {
+            system: "sys".into(),
+            messages: vec![harness::Message::user_text("hi")],
+            tools: vec![],
+            max_tokens: 64,
+            tool_choice: None,
+            cache: Default::default(),
+            thinking: None,
+        };
+        let first = providers.processing.complete(request.clone()).await.unwrap();
+        assert_eq!(first.usage.output_tokens, 3);
+        let repeat = providers.processing.complete(request).await.unwrap();
+        assert_eq!(repeat.content, first.content);
+        assert_eq!(repeat.usage, harness::Usage::default(), "a hit bills nothing");
+        assert!(format!("{cfg:?}").contains("ttl_secs: Some(86400)"));
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn local_fallback_builds_an_unretried_local_provider() {
+        let cfg = EngineConfig {
+            db_path: ":memory:".into(),
+            device_id: "dev".into(),
+            api_key: "sk-test".into(),
+            base_url: None,
+            model_live: "small-model".into(),
+            model_processing: "claude-sonnet-4-5".into(),
+            model_reflection: "small-model".into(),
+            stt_model_path: None,
+            stt_flush_on_finish: true,
+            stt_use_gpu: true,
+            stt_vad_rms_threshold: 0.0,
+            stt_no_speech_prob_threshold: 0.6,
+            endpoint_live: None,
+            endpoint_processing: None,
+            endpoint_reflection: None,
+            local_fallback: Some(LocalFallback {
+                base_url: Some("http://127.0.0.1:8080".into()),
+                model: "llama3.2".into(),
+                native_tools: false,
+                openai_compatible: true,
+            }),
+            rate_limits: None,
+            response_cache: None,
+            batch_processing: false,
+        };
+        let providers = build_providers(&cfg);
+        let local = providers.local.expect("configured fallback is built");
+        assert!(!Arc::ptr_eq(&local, &providers.processing));
+        assert!(format!("{cfg:?}").contains("llama3.2"));
     }
 }
@@ -28,6 +28,11 @@ pub enum WalkEvent {
     /// The volatile, un-finalized preview tail (D4). Displayed greyed; NEVER
     /// persisted, NEVER extracted.
     TranscriptPreview { text: String },
+    /// The narrative summary so far, while `finish()` is still streaming the
+    /// notes pass. Each event carries the whole summary seen so far (not a
+    /// delta) — display-only; `finish()`'s returned `NotesPayload` is the
+    /// authority and may differ if the pass fails.
+    NotesPreview { summary: String },
 }
 
 /// Foreign-implemented listener — `with_foreign`, never `callback_interface`
@@ -181,7 +181,8 @@ mod tests {
         CompletionResponse {
             content: vec![ContentBlock::ToolUse { id: "tu".into(), name: name.into(), input }],
             stop_reason: StopReason::ToolUse,
-            usage: Usage { input_tokens: 10, output_tokens: 5 },
+            usage: Usage { input_tokens: 10, output_tokens: 5, ..Default::default() },
+            model: None,
         }
     }
 
@@ -189,7 +190,8 @@ mod tests {
         CompletionResponse {
             content: vec![ContentBlock::Text { text: text.into() }],
             stop_reason: StopReason::EndTurn,
-            usage: Usage { input_tokens: 10, output_tokens: 5 },
+            usage: Usage { input_tokens: 10, output_tokens: 5, ..Default::default() },
+            model: None,
         }
     }
 
@@ -202,6 +204,8 @@ mod tests {
                 live: Arc::new(MockProvider::new(vec![])),
                 processing: Arc::new(MockProvider::new(processing)),
                 reflection: Arc::new(MockProvider::new(vec![])),
+                local: None,
+                batches: None,
             },
         )
     }
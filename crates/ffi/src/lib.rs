@@ -11,8 +11,10 @@ pub mod document_build;
 pub mod engine;
 pub mod events;
 pub mod items;
+pub mod memory_history;
 pub mod notes;
 pub mod photos;
+pub mod provider_error;
 pub mod schemas;
 pub mod session;
 pub mod session_retry;
@@ -21,10 +23,18 @@ pub mod vocabulary;
 
 pub use convert::document_payload;
 pub use document::{DocField, DocLine, DocumentPayload};
-pub use engine::{EngineConfig, EngineError, MurmurEngine, Providers};
+pub use engine::{
+    EngineConfig, EngineError, LocalFallback, MurmurEngine, ProviderEndpoint, ProviderProtocol,
+    Providers,
+};
 pub use events::{BoardItem, WalkEvent, WalkEventListener};
+pub use memory_history::{
+    MemoryEntryChange, MemoryEntryRecord, MemoryRevision, MemoryRevisionCause, MemoryScope, MemorySectionDiff,
+    MemorySource,
+};
 pub use notes::{NotesBucket, NotesEntry, NotesPayload};
 pub use photos::PhotoRef;
+pub use provider_error::{ProviderErrorKind, ProviderFailure};
 pub use schemas::{DocumentSchema, SchemaField, SchemaSection};
 pub use session::WalkSession;
 pub use sessions_read::{WalkStatus, WalkSummary};
@@ -0,0 +1,257 @@
+//! Memory version history across FFI: the revision log core keeps for every
+//! save that changed memory (`Store::memory_revisions`), a structured diff
+//! between any two revisions, and restore-to-revision. Restoring appends a
+//! new revision — the log is never rewound, so a restore can itself be
+//! undone. Job and client memories (`murmur_core::MemoryScope`) share the
+//! log, each revision saying whose memory it is.
+
+use harness::FactSource;
+
+use crate::engine::{EngineError, MurmurEngine};
+
+/// Why a revision happened (`harness::MemoryChange`).
+#[derive(uniffi::Enum, Clone, Debug, PartialEq)]
+pub enum MemoryRevisionCause {
+    Reflection { churn: f32 },
+    /// The agent's `update_memory` tool, during `session_id` when known.
+    MemoryTool { session_id: Option<String> },
+    Vocabulary,
+    SeedPack { pack: String },
+    Restore { revision_id: String },
+    /// Moved in from the pre-v14 memory file.
+    Import,
+    /// Memory as it stood when the log started.
+    Baseline,
+    Other,
+}
+
+impl From<harness::MemoryChange> for MemoryRevisionCause {
+    fn from(change: harness::MemoryChange) -> Self {
+        use harness::MemoryChange;
+        match change {
+            MemoryChange::Reflection { churn } => MemoryRevisionCause::Reflection { churn },
+            MemoryChange::MemoryTool { session } => MemoryRevisionCause::MemoryTool { session_id: session },
+            MemoryChange::Vocabulary => MemoryRevisionCause::Vocabulary,
+            MemoryChange::SeedPack { pack } => MemoryRevisionCause::SeedPack { pack },
+            MemoryChange::Restore { revision } => MemoryRevisionCause::Restore { revision_id: revision },
+            MemoryChange::Import => MemoryRevisionCause::Import,
+            MemoryChange::Baseline => MemoryRevisionCause::Baseline,
+            MemoryChange::Other => MemoryRevisionCause::Other,
+        }
+    }
+}
+
+/// Whose memory a revision is of.
+#[derive(uniffi::Enum, Clone, Debug, PartialEq)]
+pub enum MemoryScope {
+    Global,
+    Job { job_id: String },
+    /// Client names are stored lowercased with whitespace collapsed.
+    Client { client: String },
+}
+
+impl From<murmur_core::MemoryScope> for MemoryScope {
+    fn from(scope: murmur_core::MemoryScope) -> Self {
+        match scope {
+            murmur_core::MemoryScope::Global => MemoryScope::Global,
+            murmur_core::MemoryScope::Job(job_id) => MemoryScope::Job { job_id },
+            murmur_core::MemoryScope::Client(client) => MemoryScope::Client { client },
+        }
+    }
+}
+
+/// One revision log row; the memory itself stays behind the diff call.
+#[derive(uniffi::Record, Clone, Debug, PartialEq)]
+pub struct MemoryRevision {
+    pub id: String,
+    pub scope: MemoryScope,
+    pub cause: MemoryRevisionCause,
+    /// Epoch SECONDS.
+    pub created_at: u64,
+    /// Entries in the memory after this revision.
+    pub entry_count: u32,
+}
+
+#[derive(uniffi::Enum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MemorySource {
+    Inferred,
+    Stated,
+    Corrected,
+}
+
+impl From<FactSource> for MemorySource {
+    fn from(source: FactSource) -> Self {
+        match source {
+            FactSource::Inferred => MemorySource::Inferred,
+            FactSource::Stated => MemorySource::Stated,
+            FactSource::Corrected => MemorySource::Corrected,
+        }
+    }
+}
+
+#[derive(uniffi::Record, Clone, Debug, PartialEq)]
+pub struct MemoryEntryRecord {
+    pub text: String,
+    pub source: MemorySource,
+    pub session_id: Option<String>,
+    /// Epoch SECONDS.
+    pub last_touched: u64,
+}
+
+impl From<harness::MemoryEntry> for MemoryEntryRecord {
+    fn from(entry: harness::MemoryEntry) -> Self {
+        MemoryEntryRecord {
+            text: entry.text,
+            source: entry.source.into(),
+            session_id: entry.session,
+            last_touched: entry.last_touched,
+        }
+    }
+}
+
+/// An entry whose text stayed while its source or timestamp moved.
+#[derive(uniffi::Record, Clone, Debug, PartialEq)]
+pub struct MemoryEntryChange {
+    pub before: MemoryEntryRecord,
+    pub after: MemoryEntryRecord,
+}
+
+/// One section's difference between two revisions. Sections without one
+/// aren't listed.
+#[derive(uniffi::Record, Clone, Debug, PartialEq)]
+pub struct MemorySectionDiff {
+    pub section: String,
+    pub added: Vec<MemoryEntryRecord>,
+    pub removed: Vec<MemoryEntryRecord>,
+    pub changed: Vec<MemoryEntryChange>,
+}
+
+#[uniffi::export]
+impl MurmurEngine {
+    /// The memory revision log, newest first, at most `limit` rows.
+    pub fn list_memory_revisions(&self, limit: u32) -> Result<Vec<MemoryRevision>, EngineError> {
+        let store = self.store.lock().map_err(|_| Self::history_err("store lock poisoned"))?;
+        let revisions = store.memory_revisions(limit as usize).map_err(|e| Self::history_err(e.to_string()))?;
+        Ok(revisions
+            .into_iter()
+            .map(|revision| MemoryRevision {
+                entry_count: revision.memory.sections.values().map(Vec::len).sum::<usize>() as u32,
+                id: revision.id,
+                scope: revision.scope.into(),
+                cause: revision.change.into(),
+                created_at: revision.created_at,
+            })
+            .collect())
+    }
+
+    /// What changed from revision `from_id` to revision `to_id`, per section.
+    pub fn diff_memory_revisions(
+        &self,
+        from_id: String,
+        to_id: String,
+    ) -> Result<Vec<MemorySectionDiff>, EngineError> {
+        let store = self.store.lock().map_err(|_| Self::history_err("store lock poisoned"))?;
+        let diff = store.diff_memory_revisions(&from_id, &to_id).map_err(|e| Self::history_err(e.to_string()))?;
+        Ok(diff
+            .sections
+            .into_iter()
+            .map(|(section, d)| MemorySectionDiff {
+                section,
+                added: d.added.into_iter().map(Into::into).collect(),
+                removed: d.removed.into_iter().map(Into::into).collect(),
+                changed: d
+                    .changed
+                    .into_iter()
+                    .map(|c| MemoryEntryChange { before: c.before.into(), after: c.after.into() })
+                    .collect(),
+            })
+            .collect())
+    }
+
+    /// Makes revision `id`'s memory the live one of its scope — on disk
+    /// first, then, for the user's own memory, the engine's copy every later
+    /// walk reads. Not meant to race an active walk: an `update_memory` call
+    /// landing in between is overwritten.
+    pub fn restore_memory_revision(&self, id: String) -> Result<(), EngineError> {
+        let (scope, restored) = {
+            let store = self.store.lock().map_err(|_| Self::history_err("store lock poisoned"))?;
+            let scope = store.memory_revision(&id).map_err(|e| Self::history_err(e.to_string()))?.scope;
+            (scope, store.restore_memory_revision(&id).map_err(|e| Self::history_err(e.to_string()))?)
+        }; // store guard dropped before the memory guard (never both at once)
+        if scope == murmur_core::MemoryScope::Global {
+            *self.memory.lock().map_err(|_| Self::history_err("memory lock poisoned"))? = restored;
+        }
+        Ok(())
+    }
+}
+
+impl MurmurEngine {
+    fn history_err(msg: impl Into<String>) -> EngineError {
+        EngineError::Memory(msg.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::EngineConfig;
+
+    fn engine() -> std::sync::Arc<MurmurEngine> {
+        MurmurEngine::new(EngineConfig {
+            db_path: ":memory:".into(),
+            device_id: "dev".into(),
+            api_key: "sk-test".into(),
+            base_url: None,
+            model_live: "claude-haiku-4-5".into(),
+            model_processing: "claude-sonnet-4-5".into(),
+            model_reflection: "claude-haiku-4-5".into(),
+            stt_model_path: None,
+            stt_flush_on_finish: true,
+            stt_use_gpu: true,
+            stt_vad_rms_threshold: 0.0,
+            stt_no_speech_prob_threshold: 0.6,
+            endpoint_live: None,
+            endpoint_processing: None,
+            endpoint_reflection: None,
+            local_fallback: None,
+            rate_limits: None,
+            response_cache: None,
+            batch_processing: false,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn vocabulary_edits_are_logged_diffed_and_restorable() {
+        let engine = engine();
+        engine.seed_vocabulary("landscaping".into(), 1, vec!["french drain".into()]).unwrap();
+        engine.add_vocabulary_term("sleeper wall".into()).unwrap();
+        engine.remove_vocabulary_term("french drain".into()).unwrap();
+
+        let log = engine.list_memory_revisions(10).unwrap();
+        let causes: Vec<_> = log.iter().map(|r| r.cause.clone()).collect();
+        assert_eq!(
+            causes,
+            vec![
+                MemoryRevisionCause::Vocabulary,
+                MemoryRevisionCause::Vocabulary,
+                MemoryRevisionCause::SeedPack { pack: "landscaping:1".into() },
+            ]
+        );
+        assert_eq!(log[0].entry_count, 2, "sleeper wall + the seed marker");
+        assert!(log.iter().all(|r| r.scope == MemoryScope::Global));
+
+        let diff = engine.diff_memory_revisions(log[1].id.clone(), log[0].id.clone()).unwrap();
+        assert_eq!(diff.len(), 1);
+        assert_eq!(diff[0].section, "vocabulary");
+        assert_eq!(diff[0].removed[0].text, "french drain");
+        assert_eq!(diff[0].removed[0].source, MemorySource::Stated);
+
+        engine.restore_memory_revision(log[1].id.clone()).unwrap();
+        assert_eq!(engine.list_vocabulary().unwrap(), vec!["french drain", "sleeper wall"]);
+        let newest = &engine.list_memory_revisions(1).unwrap()[0];
+        assert_eq!(newest.cause, MemoryRevisionCause::Restore { revision_id: log[1].id.clone() });
+
+        assert!(matches!(engine.restore_memory_revision("nope".into()), Err(EngineError::Memory(_))));
+    }
+}
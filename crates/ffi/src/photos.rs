@@ -5,6 +5,8 @@
 //! method is panic-free across FFI (Plan 07 CANON): a poisoned lock or a
 //! store error surfaces as `EngineError::Photo`, never a panic.
 
+use murmur_core::{PhotoBytes, PhotoVision};
+
 use crate::engine::{EngineError, MurmurEngine};
 
 /// A display-copy-free projection of `murmur_core::Photo` (D-Plan07 posture):
@@ -16,15 +18,37 @@ pub struct PhotoRef {
     pub session_id: String,
     pub item_id: Option<String>,
     pub filename: String,
+    /// What the vision pass saw; `None` until `describe_photos` has run.
+    pub caption: Option<String>,
     pub captured_at: u64,
 }
 
+/// One photo's bytes, handed over for a single `describe_photos` call and
+/// dropped when it returns — core still never stores bytes.
+#[derive(uniffi::Record, Clone, Debug)]
+pub struct PhotoImage {
+    pub photo_id: String,
+    /// "image/jpeg", "image/png", "image/gif" or "image/webp".
+    pub media_type: String,
+    pub bytes: Vec<u8>,
+}
+
+/// What a `describe_photos` call changed; the shell re-lists photos and
+/// items to show it.
+#[derive(uniffi::Record, Clone, Debug, PartialEq)]
+pub struct PhotoVisionSummary {
+    pub captioned: u32,
+    pub attached: u32,
+    pub new_item_ids: Vec<String>,
+}
+
 fn photo_ref(p: &murmur_core::Photo) -> PhotoRef {
     PhotoRef {
         id: p.id.clone(),
         session_id: p.session_id.clone(),
         item_id: p.item_id.clone(),
         filename: p.filename.clone(),
+        caption: p.caption.clone(),
         captured_at: p.captured_at,
     }
 }
@@ -82,6 +106,33 @@ impl MurmurEngine {
     }
 }
 
+#[uniffi::export(async_runtime = "tokio")]
+impl MurmurEngine {
+    /// The photo vision pass over a processed session: captions each photo,
+    /// attaches it to the item it clearly shows, and drafts items only the
+    /// photos reveal. Every error — a photo outside the session, a session
+    /// not yet processed, a failed model call — surfaces as `Photo`.
+    pub async fn describe_photos(
+        &self,
+        session_id: String,
+        photos: Vec<PhotoImage>,
+    ) -> Result<PhotoVisionSummary, EngineError> {
+        let photos: Vec<PhotoBytes> = photos
+            .into_iter()
+            .map(|p| PhotoBytes { photo_id: p.photo_id, media_type: p.media_type, bytes: p.bytes })
+            .collect();
+        let outcome = PhotoVision::new(self.providers.processing.clone(), self.store.clone())
+            .describe(&session_id, &photos)
+            .await
+            .map_err(|e| Self::photo_err(e.to_string()))?;
+        Ok(PhotoVisionSummary {
+            captioned: outcome.captioned as u32,
+            attached: outcome.attached as u32,
+            new_item_ids: outcome.new_item_ids,
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -106,14 +157,20 @@ mod tests {
     // Takes an already-opened Store so tests can start a session on it first,
     // then hand ownership to the engine (with_providers consumes the Store).
     fn engine_with(store: murmur_core::Store) -> Arc<MurmurEngine> {
+        engine_processing_with(store, MockProvider::new(vec![]))
+    }
+
+    fn engine_processing_with(store: murmur_core::Store, processing: MockProvider) -> Arc<MurmurEngine> {
         MurmurEngine::with_providers(
             store,
             Memory::default(),
             Arc::new(SpyStore { saved: StdMutex::new(Vec::new()) }),
             Providers {
                 live: Arc::new(MockProvider::new(vec![])),
-                processing: Arc::new(MockProvider::new(vec![])),
+                processing: Arc::new(processing),
                 reflection: Arc::new(MockProvider::new(vec![])),
+                local: None,
+                batches: None,
             },
         )
     }
@@ -149,4 +206,38 @@ mod tests {
         e.remove_photo(gone.id).unwrap();
         assert_eq!(e.list_live_photo_filenames().unwrap(), vec!["keep.jpg".to_string()]);
     }
+
+    #[tokio::test]
+    async fn describe_photos_captions_through_the_processing_provider() {
+        let store = murmur_core::Store::open_in_memory("device-a").unwrap();
+        let sid = store.start_session(None).unwrap().id;
+        store.append_transcript(&sid, "walk").unwrap();
+        store.end_and_record_session(&sid).unwrap();
+        store.finish_session_processed(
+            &sid,
+            "done",
+            &harness::Usage::default(),
+            &harness::ServedBy::default(),
+            &[],
+        )
+        .unwrap();
+        let photo = store.add_photo(&sid, None, "a.jpg", None).unwrap();
+        let provider = MockProvider::new(vec![harness::CompletionResponse {
+            content: vec![harness::ContentBlock::ToolUse {
+                id: "tu".into(),
+                name: "describe_photos".into(),
+                input: serde_json::json!({"photos": [{"photo_id": photo.id, "caption": "a cracked step"}]}),
+            }],
+            stop_reason: harness::StopReason::ToolUse,
+            usage: harness::Usage::default(),
+            model: None,
+        }]);
+        let e = engine_processing_with(store, provider);
+        let image = PhotoImage { photo_id: photo.id.clone(), media_type: "image/jpeg".into(), bytes: vec![0xff, 0xd8] };
+
+        let summary = e.describe_photos(sid.clone(), vec![image.clone()]).await.unwrap();
+        assert_eq!(summary, PhotoVisionSummary { captioned: 1, attached: 0, new_item_ids: vec![] });
+        assert_eq!(e.list_photos(sid).unwrap()[0].caption.as_deref(), Some("a cracked step"));
+        assert!(matches!(e.describe_photos("nope".into(), vec![image]).await, Err(EngineError::Photo(_))));
+    }
 }
@@ -0,0 +1,78 @@
+//! The provider-error taxonomy at the FFI boundary: why a walk failed to
+//! process, typed, so the app can say "API key rejected" or "You're offline"
+//! instead of a generic failure. Mirrors `harness::ProviderErrorKind`
+//! one-to-one (the core type stays UniFFI-free, D1).
+
+/// See `harness::ProviderErrorKind` for what each kind covers.
+#[derive(uniffi::Enum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProviderErrorKind {
+    Auth,
+    RateLimited,
+    Overloaded,
+    BadRequest,
+    Server,
+    Unreachable,
+    InvalidResponse,
+}
+
+impl From<harness::ProviderErrorKind> for ProviderErrorKind {
+    fn from(kind: harness::ProviderErrorKind) -> Self {
+        match kind {
+            harness::ProviderErrorKind::Auth => ProviderErrorKind::Auth,
+            harness::ProviderErrorKind::RateLimited => ProviderErrorKind::RateLimited,
+            harness::ProviderErrorKind::Overloaded => ProviderErrorKind::Overloaded,
+            harness::ProviderErrorKind::BadRequest => ProviderErrorKind::BadRequest,
+            harness::ProviderErrorKind::Server => ProviderErrorKind::Server,
+            harness::ProviderErrorKind::Unreachable => ProviderErrorKind::Unreachable,
+            harness::ProviderErrorKind::InvalidResponse => ProviderErrorKind::InvalidResponse,
+        }
+    }
+}
+
+impl ProviderErrorKind {
+    /// Short, user-presentable wording — also the `EngineError::Provider`
+    /// message, which is all a flat error carries to Swift.
+    pub(crate) fn describe(self) -> &'static str {
+        match self {
+            ProviderErrorKind::Auth => "API key rejected",
+            ProviderErrorKind::RateLimited => "rate limited",
+            ProviderErrorKind::Overloaded => "service overloaded",
+            ProviderErrorKind::BadRequest => "request rejected",
+            ProviderErrorKind::Server => "service error",
+            ProviderErrorKind::Unreachable => "service unreachable",
+            ProviderErrorKind::InvalidResponse => "unusable response",
+        }
+    }
+}
+
+/// The provider failure recorded on a `Failed` walk.
+#[derive(uniffi::Record, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ProviderFailure {
+    pub kind: ProviderErrorKind,
+    /// The HTTP status, when the provider answered with one.
+    pub http_status: Option<u16>,
+}
+
+impl From<murmur_core::SessionFailure> for ProviderFailure {
+    fn from(failure: murmur_core::SessionFailure) -> Self {
+        ProviderFailure { kind: failure.kind.into(), http_status: failure.http_status }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn session_failure_maps_kind_and_status() {
+        let failure = murmur_core::SessionFailure {
+            kind: harness::ProviderErrorKind::Auth,
+            http_status: Some(401),
+        };
+        assert_eq!(
+            ProviderFailure::from(failure),
+            ProviderFailure { kind: ProviderErrorKind::Auth, http_status: Some(401) }
+        );
+        assert_eq!(ProviderErrorKind::Auth.describe(), "API key rejected");
+    }
+}
@@ -199,7 +199,8 @@ mod tests {
         CompletionResponse {
             content: vec![ContentBlock::ToolUse { id: "tu".into(), name: name.into(), input }],
             stop_reason: StopReason::ToolUse,
-            usage: Usage { input_tokens: 10, output_tokens: 5 },
+            usage: Usage { input_tokens: 10, output_tokens: 5, ..Default::default() },
+            model: None,
         }
     }
 
@@ -207,7 +208,8 @@ mod tests {
         CompletionResponse {
             content: vec![ContentBlock::Text { text: text.into() }],
             stop_reason: StopReason::EndTurn,
-            usage: Usage { input_tokens: 10, output_tokens: 5 },
+            usage: Usage { input_tokens: 10, output_tokens: 5, ..Default::default() },
+            model: None,
         }
     }
 
@@ -220,6 +222,8 @@ mod tests {
                 live: Arc::new(MockProvider::new(vec![])),
                 processing: Arc::new(MockProvider::new(processing)),
                 reflection: Arc::new(MockProvider::new(vec![])),
+                local: None,
+                batches: None,
             },
         )
     }
@@ -5,7 +5,7 @@ use std::collections::HashMap;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Condvar, Mutex as StdMutex};
 
-use harness::{LlmProvider, Memory, MemoryStore};
+use harness::{with_priority, CancelToken, LlmProvider, Memory, MemoryStore, Priority};
 use murmur_core::{
     doc_kind_for_template, parse_notes_artifact, LiveExtractOutcome, LiveExtractor,
     SessionProcessor, Store,
@@ -26,8 +26,13 @@ pub struct WalkSession {
     /// (D3b/D7): `finish()` acquires it and holds it across `process().await`,
     /// so no live tick can interleave with end-of-session processing.
     extractor: Arc<TokioMutex<LiveExtractor>>,
+    /// Fired by `cancel()` so a live pass in flight stops spending instead of
+    /// running to completion while `cancel()` waits on the tick guard.
+    cancel: CancelToken,
     listener: StdMutex<Option<Arc<dyn WalkEventListener>>>,
     processing_provider: Arc<dyn LlmProvider>,
+    /// The offline fallback (`Providers::local`), when configured.
+    local_fallback: Option<Arc<dyn LlmProvider>>,
     memory: Arc<StdMutex<Memory>>,
     memory_store: Arc<dyn MemoryStore>,
     runtime_handle: tokio::runtime::Handle,
@@ -92,6 +97,37 @@ impl Drop for WalkSession {
     }
 }
 
+/// Stream sink for `finish()`: follows the `write_notes` tool input as it
+/// streams and emits a `NotesPreview` each time the decoded summary grows.
+/// Extraction-pass deltas (add_item inputs, the confirmation line) are not
+/// surfaced — the terminal board snapshot covers them.
+fn notes_preview_sink(listener: Arc<dyn WalkEventListener>) -> harness::StreamSink {
+    // (index of the open write_notes block, its input so far, last emitted summary)
+    let state = StdMutex::new((None::<usize>, String::new(), String::new()));
+    Arc::new(move |event| {
+        let Ok(mut state) = state.lock() else { return };
+        let (notes_index, input, emitted) = &mut *state;
+        match event {
+            harness::StreamEvent::ToolUseStart { index, name, .. } if name == "write_notes" => {
+                *notes_index = Some(index);
+                input.clear();
+            }
+            harness::StreamEvent::ToolInputDelta { index, partial_json }
+                if *notes_index == Some(index) =>
+            {
+                input.push_str(&partial_json);
+                if let Some(summary) = murmur_core::partial_summary(input) {
+                    if summary.len() > emitted.len() {
+                        *emitted = summary.clone();
+                        listener.on_event(WalkEvent::NotesPreview { summary });
+                    }
+                }
+            }
+            _ => {}
+        }
+    })
+}
+
 /// Assemble the ≤100-term STT bias vocabulary at `begin_walk` (D8): the user's
 /// memory `vocabulary` section plus an optional small per-template seed, capped
 /// at `SttConfig::max_bias_terms`. Reads an existing memory section — no new
@@ -114,6 +150,7 @@ impl WalkSession {
         store: Arc<StdMutex<Store>>,
         extractor: LiveExtractor,
         processing_provider: Arc<dyn LlmProvider>,
+        local_fallback: Option<Arc<dyn LlmProvider>>,
         memory: Arc<StdMutex<Memory>>,
         memory_store: Arc<dyn MemoryStore>,
         runtime_handle: tokio::runtime::Handle,
@@ -121,12 +158,15 @@ impl WalkSession {
         stt: Option<Arc<stt::SttStream>>,
         flush_on_finish: bool,
     ) -> Arc<Self> {
+        let cancel = CancelToken::new();
         Arc::new(WalkSession {
             session_id,
             store,
-            extractor: Arc::new(TokioMutex::new(extractor)),
+            extractor: Arc::new(TokioMutex::new(extractor.with_cancel(cancel.clone()))),
+            cancel,
             listener: StdMutex::new(None),
             processing_provider,
+            local_fallback,
             memory,
             memory_store,
             runtime_handle,
@@ -165,6 +205,7 @@ impl WalkSession {
             store,
             extractor,
             processing_provider,
+            None,
             memory,
             memory_store,
             runtime_handle,
@@ -577,6 +618,7 @@ impl MurmurEngine {
             self.store.clone(),
             extractor,
             self.providers.processing.clone(),
+            self.providers.local.clone(),
             self.memory.clone(),
             self.memory_store.clone(),
             self.runtime_handle.clone(),
@@ -656,8 +698,13 @@ impl WalkSession {
                     Ok(LiveExtractOutcome::Extracted { .. }) => session.emit_board_snapshot(),
                     // Skipped (too little new transcript / not recording) and a
                     // model-side Failed pass (D9: offline/LLM-down) are swallowed by
-                    // design — capture is safe and the next tick retries.
-                    Ok(LiveExtractOutcome::Skipped | LiveExtractOutcome::Failed { .. }) => {}
+                    // design — capture is safe and the next tick retries. Cancelled
+                    // means `cancel()` is tearing the session down.
+                    Ok(
+                        LiveExtractOutcome::Skipped
+                        | LiveExtractOutcome::Failed { .. }
+                        | LiveExtractOutcome::Cancelled { .. },
+                    ) => {}
                     // A genuine store fault — surfaced (carry-note 4) instead of
                     // silently discarded. Never crashes the tick loop.
                     Err(e) => session.record_tick_fault(&format!("maybe_extract: {e}")),
@@ -714,6 +761,8 @@ impl WalkSession {
     ///   call on an already-ended session) -> `degraded_notes()`.
     /// - `process()` fails outright (offline/LLM-down, D9) -> queued notes
     ///   built from the live board — capture is never lost.
+    ///   With a local fallback configured, offline instead yields the
+    ///   on-device model's notes, still `queued: true` (provisional).
     ///
     /// The empty-transcript short circuit is no longer a separate branch:
     /// `process()` succeeds either way (with summary `"(empty session)"` for
@@ -748,19 +797,28 @@ impl WalkSession {
             return self.degraded_notes();
         }
 
-        let processor = SessionProcessor::new(
+        let mut processor = SessionProcessor::new(
             self.processing_provider.clone(),
             self.store.clone(),
             self.memory.clone(),
             self.memory_store.clone(),
         );
-        match processor.process(&self.session_id).await {
+        if let Some(listener) = self.listener.lock().unwrap().clone() {
+            processor = processor.with_stream_sink(notes_preview_sink(listener));
+        }
+        if let Some(local) = &self.local_fallback {
+            processor = processor.with_local_fallback(local.clone());
+        }
+        // DONE was just tapped and the notes screen is up: interactive lane.
+        match with_priority(Priority::Interactive, processor.process(&self.session_id)).await {
             Ok(outcome) => {
                 self.emit_board_snapshot();
                 // Processed either way (normal or the empty-transcript short
-                // circuit, whose summary is "(empty session)") — not queued.
+                // circuit, whose summary is "(empty session)"). Not queued —
+                // unless it was the on-device fallback's provisional result,
+                // which still owes a cloud pass.
                 let summary = outcome.session.summary.unwrap_or_default();
-                self.partial_notes(&summary, false)
+                self.partial_notes(&summary, outcome.session.provisional)
             }
             // Offline / LLM-down degradation (D9): the session did NOT reach
             // Processed, so there's real pending work — queued: true, summary
@@ -782,6 +840,9 @@ impl WalkSession {
         if !self.try_enter_terminal() {
             return; // already finished or cancelled — nothing to do
         }
+        // Abort a live pass in flight first: it holds the tick guard, and
+        // would otherwise keep calling the model until it finished.
+        self.cancel.cancel();
         // Exclude ticks (D3b), then stop the pump deterministically before we
         // touch the store — no detached thread can append mid-tombstone.
         let _tick_guard = self.extractor.lock().await;
@@ -840,7 +901,8 @@ mod tests {
         CompletionResponse {
             content: vec![ContentBlock::ToolUse { id: "tu".into(), name: name.into(), input }],
             stop_reason: StopReason::ToolUse,
-            usage: Usage { input_tokens: 10, output_tokens: 5 },
+            usage: Usage { input_tokens: 10, output_tokens: 5, ..Default::default() },
+            model: None,
         }
     }
 
@@ -848,7 +910,8 @@ mod tests {
         CompletionResponse {
             content: vec![ContentBlock::Text { text: text.into() }],
             stop_reason: StopReason::EndTurn,
-            usage: Usage { input_tokens: 10, output_tokens: 5 },
+            usage: Usage { input_tokens: 10, output_tokens: 5, ..Default::default() },
+            model: None,
         }
     }
 
@@ -891,6 +954,7 @@ mod tests {
             store,
             extractor,
             processing_provider,
+            None,
             memory,
             Arc::new(NullMemoryStore),
             tokio::runtime::Handle::current(),
@@ -914,6 +978,8 @@ mod tests {
                 ])),
                 processing: Arc::new(MockProvider::new(vec![])),
                 reflection: Arc::new(MockProvider::new(vec![])),
+                local: None,
+                batches: None,
             },
         );
         let session = engine.begin_walk(None, "landscape".into()).unwrap();
@@ -942,6 +1008,8 @@ mod tests {
             live: Arc::new(MockProvider::new(vec![])),
             processing: Arc::new(MockProvider::new(vec![])),
             reflection: Arc::new(MockProvider::new(vec![])),
+            local: None,
+            batches: None,
         }
     }
 
@@ -1248,7 +1316,7 @@ mod tests {
                             board_text = Some(item.text.clone());
                         }
                     }
-                    Some(WalkEvent::TranscriptPreview { .. }) => {}
+                    Some(WalkEvent::TranscriptPreview { .. } | WalkEvent::NotesPreview { .. }) => {}
                     None => break,
                 }
                 if committed.is_some() && board_text.is_some() {
@@ -1279,6 +1347,8 @@ mod tests {
                 live: Arc::new(MockProvider::new(vec![])),
                 processing: Arc::new(MockProvider::new(vec![])),
                 reflection: Arc::new(MockProvider::new(vec![])),
+                local: None,
+                batches: None,
             },
         );
         let session = engine.begin_walk(None, "landscape".into()).unwrap();
@@ -1317,6 +1387,13 @@ mod tests {
             stt_use_gpu: true, // host-side smoke — Metal is fine here
             stt_vad_rms_threshold: 0.0,
             stt_no_speech_prob_threshold: 0.6,
+            endpoint_live: None,
+            endpoint_processing: None,
+            endpoint_reflection: None,
+            local_fallback: None,
+            rate_limits: None,
+            response_cache: None,
+            batch_processing: false,
         };
         let engine = MurmurEngine::new(cfg).expect("engine construction");
         let session =
@@ -1463,6 +1540,38 @@ mod tests {
         let _ = session.clone().finish().await;
     }
 
+    #[tokio::test]
+    async fn cancel_abandons_a_live_pass_in_flight() {
+        /// Signals that the pass reached the model, then never answers.
+        struct Hangs(mpsc::UnboundedSender<()>);
+        #[async_trait::async_trait]
+        impl LlmProvider for Hangs {
+            async fn complete(&self, _req: CompletionRequest) -> Result<CompletionResponse, HarnessError> {
+                let _ = self.0.send(());
+                std::future::pending().await
+            }
+        }
+        let store = Store::open_in_memory("device-a").unwrap();
+        let sid = store.start_session(None).unwrap().id;
+        let store = Arc::new(StdMutex::new(store));
+        let memory = Arc::new(StdMutex::new(Memory::default()));
+        let (tx, mut called) = mpsc::unbounded_channel();
+        let mut extractor = LiveExtractor::new(Arc::new(Hangs(tx)), store.clone(), memory.clone(), &sid);
+        extractor.min_new_chars = 1;
+        let session = test_session(sid.clone(), store.clone(), extractor, Arc::new(MockProvider::new(vec![])), memory);
+
+        session.clone().append_transcript("order twelve two by tens for the deck".into());
+        called.recv().await.expect("the live pass reached the model");
+
+        // The tick holds the guard cancel() needs; without the token this
+        // would wait on the model forever.
+        tokio::time::timeout(std::time::Duration::from_secs(2), session.clone().cancel())
+            .await
+            .expect("cancel() waited on the live pass");
+        assert!(store.lock().unwrap().get_session(&sid).is_err());
+        assert_eq!(session.tick_store_fault_count(), 0, "a cancelled pass is not a fault");
+    }
+
     #[tokio::test]
     async fn dropping_the_last_handle_stops_the_pump() {
         // Review finding 1: no finish(), no cancel() — the host just drops its
@@ -1517,6 +1626,8 @@ mod tests {
                 live: Arc::new(MockProvider::new(vec![])),
                 processing: Arc::new(MockProvider::new(vec![])),
                 reflection: Arc::new(MockProvider::new(vec![])),
+                local: None,
+                batches: None,
             },
         );
         // WRITE half (the new FFI path):
@@ -1598,6 +1709,50 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn finish_streams_growing_notes_previews_before_the_terminal_snapshot() {
+        let store = Store::open_in_memory("device-a").unwrap();
+        let sid = store.start_session(None).unwrap().id;
+        store.append_transcript(&sid, "order twelve two by tens for the deck framing").unwrap();
+        let store = Arc::new(StdMutex::new(store));
+        let memory = Arc::new(StdMutex::new(Memory::default()));
+        let extractor =
+            LiveExtractor::new(Arc::new(MockProvider::new(vec![])), store.clone(), memory.clone(), &sid);
+        let processing_provider: Arc<dyn LlmProvider> = Arc::new(
+            MockProvider::new(vec![
+                tool_use("add_item", serde_json::json!({"kind": "todo", "text": "order 12 2x10s"})),
+                end_turn("done"),
+                summary_response("Lumber ordered for the deck."),
+            ])
+            .chunked(5),
+        );
+        let session = test_session(sid, store, extractor, processing_provider, memory);
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        session.clone().set_event_listener(Arc::new(ChannelListener(tx)));
+
+        let payload = session.clone().finish().await;
+        assert_eq!(payload.summary, "Lumber ordered for the deck.");
+
+        let mut events = Vec::new();
+        while let Ok(event) = rx.try_recv() {
+            events.push(event);
+        }
+        let previews: Vec<String> = events
+            .iter()
+            .filter_map(|e| match e {
+                WalkEvent::NotesPreview { summary } => Some(summary.clone()),
+                _ => None,
+            })
+            .collect();
+        assert!(previews.len() > 1, "the summary arrives incrementally: {previews:?}");
+        assert!(previews.windows(2).all(|w| w[1].starts_with(&w[0])), "each preview extends the last");
+        assert_eq!(previews.last().unwrap(), "Lumber ordered for the deck.");
+        assert!(
+            matches!(events.last(), Some(WalkEvent::BoardUpdated { .. })),
+            "the terminal snapshot still comes last"
+        );
+    }
+
     // --- Plan 14 Task 5: NotesPayload.notes wiring -------------------------
 
     fn session_with_transcript(text: &str) -> (Arc<StdMutex<Store>>, String) {
@@ -1715,6 +1870,45 @@ mod tests {
         assert!(payload.queued, "process() failed outright -> queued: true (D9)");
     }
 
+    /// Offline fallback mode: the cloud is unreachable, so `finish()` hands
+    /// back the on-device model's notes — marked queued, since the cloud
+    /// pass is still owed.
+    #[tokio::test]
+    async fn finish_offline_with_a_local_fallback_yields_provisional_notes() {
+        struct Offline;
+        #[async_trait::async_trait]
+        impl LlmProvider for Offline {
+            async fn complete(&self, _req: CompletionRequest) -> Result<CompletionResponse, HarnessError> {
+                Err(HarnessError::ProviderUnreachable("no route to host".into()))
+            }
+        }
+        let engine = MurmurEngine::with_providers(
+            Store::open_in_memory("device-a").unwrap(),
+            Memory::default(),
+            Arc::new(NullMemoryStore),
+            Providers {
+                live: Arc::new(MockProvider::new(vec![])),
+                processing: Arc::new(Offline),
+                reflection: Arc::new(MockProvider::new(vec![])),
+                local: Some(Arc::new(MockProvider::new(vec![
+                    end_turn("nothing to extract"),
+                    summary_response("Mulch the front beds."),
+                ]))),
+                batches: None,
+            },
+        );
+        let session = engine.clone().begin_walk(None, "landscape".into()).unwrap();
+        let sid = session.session_id();
+        engine.store.lock().unwrap().append_transcript(&sid, "mulch the front beds").unwrap();
+
+        let payload = session.finish().await;
+        assert_eq!(payload.summary, "Mulch the front beds.");
+        assert!(payload.queued, "provisional until the cloud reprocess");
+        let stored = engine.store.lock().unwrap().get_session(&sid).unwrap();
+        assert_eq!(stored.status, murmur_core::SessionStatus::Processed);
+        assert!(stored.provisional);
+    }
+
     // --- Plan 20 Stage 2: load_notes / the ONE reconstruction funnel -------
 
     /// WE-A (the D1 equality contract): for a session `finish()` has returned
@@ -1743,6 +1937,8 @@ mod tests {
                     ),
                 ])),
                 reflection: Arc::new(MockProvider::new(vec![])),
+                local: None,
+                batches: None,
             },
         );
         let session = engine.clone().begin_walk(None, "landscape".into()).unwrap();
@@ -9,17 +9,21 @@
 //! `build_document`: a `Failed` session's `WalkSession` handle is long gone
 //! by the time the user reopens the app.
 
-use murmur_core::SessionProcessor;
+use murmur_core::{CoreError, SessionProcessor};
 
 use crate::engine::{EngineError, MurmurEngine};
+use crate::provider_error::ProviderErrorKind;
 
 #[uniffi::export(async_runtime = "tokio")]
 impl MurmurEngine {
     /// Retries every `Failed` session once (oldest-first, capped — see
     /// `SessionProcessor::retry_failed_sessions`'s doc comment for the
-    /// no-loop / cap-ordering rationale). Returns the count that reached
-    /// `Processed` — thin on purpose; the host re-reads its own session
-    /// list/history view rather than this call threading payloads back.
+    /// no-loop / cap-ordering rationale), then reprocesses provisional
+    /// (on-device) results with the cloud model. Returns the count that
+    /// reached a cloud-`Processed` result — thin on purpose; the host
+    /// re-reads its own session list/history view rather than this call
+    /// threading payloads back. With a local fallback configured, a retry
+    /// that is still offline lands provisional and isn't counted.
     ///
     /// Lock hygiene: `SessionProcessor::retry_failed_sessions` takes the
     /// store lock only for the short synchronous list-query inside its own
@@ -30,18 +34,73 @@ impl MurmurEngine {
     /// A still-Failed session (still offline, LLM still down) is not an
     /// error here — it's simply not counted in the returned total; only a
     /// poisoned lock or a store fault surfaces as `EngineError::Session`.
+    /// The exception is a rejected api key: every retry will fail the same
+    /// way until the user fixes it, so that surfaces as
+    /// `EngineError::Provider` (kind `Auth`) instead of a silent `0`.
+    ///
+    /// With `batch_processing`, a retry's notes call goes out in a batch:
+    /// that walk isn't counted here but by the `resume_batches` that
+    /// finishes it.
     pub async fn retry_failed_sessions(&self) -> Result<u32, EngineError> {
-        let processor = SessionProcessor::new(
+        let processor = self.retry_processor();
+        let mut results = processor
+            .retry_failed_sessions()
+            .await
+            .map_err(|e| EngineError::Session(e.to_string()))?;
+        results.extend(
+            processor
+                .reprocess_provisional_sessions()
+                .await
+                .map_err(|e| EngineError::Session(e.to_string()))?,
+        );
+        let rejected = results.iter().find_map(|(_, r)| match r {
+            Err(CoreError::Agent(e)) if e.provider_kind() == Some(harness::ProviderErrorKind::Auth) => {
+                Some(e.http_status())
+            }
+            _ => None,
+        });
+        if let Some(http_status) = rejected {
+            return Err(EngineError::Provider { kind: ProviderErrorKind::Auth, http_status });
+        }
+        Ok(results
+            .iter()
+            .filter(|(_, r)| matches!(r, Ok(outcome) if !outcome.session.provisional && outcome.batch_id.is_none()))
+            .count() as u32)
+    }
+
+    /// Finishes the walks whose batched notes call has come back, and
+    /// returns how many reached `Processed`. Runs once at engine start; the
+    /// host may poll it while a batch is out. `0` without `batch_processing`.
+    pub async fn resume_batches(&self) -> Result<u32, EngineError> {
+        if self.providers.batches.is_none() {
+            return Ok(0);
+        }
+        let results = self
+            .retry_processor()
+            .resume_batches()
+            .await
+            .map_err(|e| EngineError::Session(e.to_string()))?;
+        Ok(results.iter().filter(|(_, r)| r.is_ok()).count() as u32)
+    }
+}
+
+impl MurmurEngine {
+    /// The processor behind the retry drains: the processing model, the
+    /// offline fallback and the batch client, each when configured.
+    pub(crate) fn retry_processor(&self) -> SessionProcessor {
+        let mut processor = SessionProcessor::new(
             self.providers.processing.clone(),
             self.store.clone(),
             self.memory.clone(),
             self.memory_store.clone(),
         );
-        let results = processor
-            .retry_failed_sessions()
-            .await
-            .map_err(|e| EngineError::Session(e.to_string()))?;
-        Ok(results.iter().filter(|(_, r)| r.is_ok()).count() as u32)
+        if let Some(local) = &self.providers.local {
+            processor = processor.with_local_fallback(local.clone());
+        }
+        if let Some(batches) = &self.providers.batches {
+            processor = processor.with_batches(batches.clone());
+        }
+        processor
     }
 }
 
@@ -51,7 +110,7 @@ mod tests {
 
     use harness::{
         CompletionResponse, ContentBlock, HarnessError, Memory, MemoryStore, MockProvider,
-        StopReason, Usage,
+        ServedBy, StopReason, Usage,
     };
     use murmur_core::{SessionStatus, Store};
 
@@ -73,7 +132,8 @@ mod tests {
         CompletionResponse {
             content: vec![ContentBlock::Text { text: text.into() }],
             stop_reason: StopReason::EndTurn,
-            usage: Usage { input_tokens: 10, output_tokens: 5 },
+            usage: Usage { input_tokens: 10, output_tokens: 5, ..Default::default() },
+            model: None,
         }
     }
 
@@ -85,7 +145,8 @@ mod tests {
                 input: serde_json::json!({"summary": text}),
             }],
             stop_reason: StopReason::ToolUse,
-            usage: Usage { input_tokens: 10, output_tokens: 5 },
+            usage: Usage { input_tokens: 10, output_tokens: 5, ..Default::default() },
+            model: None,
         }
     }
 
@@ -101,6 +162,8 @@ mod tests {
                 live: Arc::new(MockProvider::new(vec![])),
                 processing: Arc::new(MockProvider::new(processing_responses)),
                 reflection: Arc::new(MockProvider::new(vec![])),
+                local: None,
+                batches: None,
             },
         )
     }
@@ -143,6 +206,68 @@ mod tests {
         assert_eq!(store.get_session(&session.id).unwrap().status, SessionStatus::Failed);
     }
 
+    #[tokio::test]
+    async fn retry_failed_sessions_surfaces_a_rejected_api_key() {
+        struct RejectsKey;
+        #[async_trait::async_trait]
+        impl harness::LlmProvider for RejectsKey {
+            async fn complete(
+                &self,
+                _req: harness::CompletionRequest,
+            ) -> Result<CompletionResponse, HarnessError> {
+                Err(HarnessError::ProviderStatus { status: 401, retry_after: None, body: "invalid x-api-key".into() })
+            }
+        }
+        let store = Store::open_in_memory("device-a").unwrap();
+        let session = store.start_session(None).unwrap();
+        store.append_transcript(&session.id, "we need lumber").unwrap();
+        store.end_and_record_session(&session.id).unwrap();
+        store.mark_session_failed(&session.id).unwrap();
+        let engine = MurmurEngine::with_providers(
+            store,
+            Memory::default(),
+            Arc::new(NullMemoryStore),
+            Providers {
+                live: Arc::new(MockProvider::new(vec![])),
+                processing: Arc::new(RejectsKey),
+                reflection: Arc::new(MockProvider::new(vec![])),
+                local: None,
+                batches: None,
+            },
+        );
+
+        let err = engine.retry_failed_sessions().await.unwrap_err();
+        assert!(matches!(
+            err,
+            EngineError::Provider { kind: ProviderErrorKind::Auth, http_status: Some(401) }
+        ));
+        assert_eq!(err.to_string(), "provider error: API key rejected (HTTP 401)");
+        let store = engine.store.lock().unwrap();
+        let failed = store.get_session(&session.id).unwrap();
+        assert_eq!(failed.status, SessionStatus::Failed);
+        assert_eq!(failed.failure.map(|f| f.kind), Some(harness::ProviderErrorKind::Auth));
+    }
+
+    #[tokio::test]
+    async fn retry_failed_sessions_upgrades_a_provisional_walk_to_the_cloud_result() {
+        let store = Store::open_in_memory("device-a").unwrap();
+        let session = store.start_session(None).unwrap();
+        store.append_transcript(&session.id, "we need lumber").unwrap();
+        store.end_and_record_session(&session.id).unwrap();
+        store
+            .finish_session_provisional(&session.id, "on-device notes", &Usage::default(), &ServedBy::default(), &[])
+            .unwrap();
+
+        let engine =
+            engine_with(store, vec![end_turn("nothing to extract"), summary_response("cloud notes")]);
+
+        assert_eq!(engine.retry_failed_sessions().await.unwrap(), 1);
+        let store = engine.store.lock().unwrap();
+        let upgraded = store.get_session(&session.id).unwrap();
+        assert!(!upgraded.provisional);
+        assert_eq!(upgraded.summary.as_deref(), Some("cloud notes"));
+    }
+
     #[tokio::test]
     async fn retry_failed_sessions_zero_with_nothing_failed() {
         let store = Store::open_in_memory("device-a").unwrap();
@@ -191,4 +316,75 @@ mod tests {
         assert_eq!(s.status, SessionStatus::Processed);
         assert_eq!(s.summary.as_deref(), Some("(empty session)"));
     }
+
+    #[tokio::test]
+    async fn a_batched_retry_is_counted_by_the_resume_that_finishes_it() {
+        use wiremock::matchers::{method, path};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let batch = |status: &str| {
+            ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": "msgbatch_1",
+                "processing_status": status,
+                "request_counts": {"processing": 0, "succeeded": 0, "errored": 0, "canceled": 0, "expired": 0},
+            }))
+        };
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/messages/batches"))
+            .respond_with(batch("in_progress"))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v1/messages/batches/msgbatch_1"))
+            .respond_with(batch("ended"))
+            .mount(&server)
+            .await;
+
+        let store = Store::open_in_memory("device-a").unwrap();
+        let session = store.start_session(None).unwrap();
+        store.append_transcript(&session.id, "we need lumber").unwrap();
+        store.end_and_record_session(&session.id).unwrap();
+        store.mark_session_failed(&session.id).unwrap();
+        let batches = harness::AnthropicProvider::new("sk-test", "claude-batch").with_base_url(server.uri());
+        let engine = MurmurEngine::with_providers(
+            store,
+            Memory::default(),
+            Arc::new(NullMemoryStore),
+            Providers {
+                live: Arc::new(MockProvider::new(vec![])),
+                processing: Arc::new(MockProvider::new(vec![end_turn("nothing to extract")])),
+                reflection: Arc::new(MockProvider::new(vec![])),
+                local: None,
+                batches: Some(Arc::new(batches)),
+            },
+        );
+
+        assert_eq!(engine.retry_failed_sessions().await.unwrap(), 0, "notes still out in the batch");
+        let job = engine.store.lock().unwrap().list_batch_jobs("notes").unwrap().remove(0);
+        let result = serde_json::json!({"custom_id": format!("{}_notes", job.id), "result": {
+            "type": "succeeded",
+            "message": {
+                "model": "claude-batch",
+                "content": [{"type": "tool_use", "id": "tu", "name": "write_notes", "input": {"summary": "recovered"}}],
+                "stop_reason": "tool_use",
+                "usage": {"input_tokens": 30, "output_tokens": 4},
+            },
+        }});
+        Mock::given(method("GET"))
+            .and(path("/v1/messages/batches/msgbatch_1/results"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(format!("{result}\n")))
+            .mount(&server)
+            .await;
+
+        assert_eq!(engine.resume_batches().await.unwrap(), 1);
+        let store = engine.store.lock().unwrap();
+        assert_eq!(store.get_session(&session.id).unwrap().summary.as_deref(), Some("recovered"));
+    }
+
+    #[tokio::test]
+    async fn resume_batches_is_a_no_op_without_batch_processing() {
+        let engine = engine_with(Store::open_in_memory("device-a").unwrap(), vec![]);
+        assert_eq!(engine.resume_batches().await.unwrap(), 0);
+    }
 }
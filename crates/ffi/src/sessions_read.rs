@@ -7,6 +7,7 @@
 //! surfaces (Conflict Note).
 
 use crate::engine::{EngineError, MurmurEngine};
+use crate::provider_error::ProviderFailure;
 
 /// A finished walk's status at the FFI boundary (D3). Core's
 /// `AwaitingProcessing` maps to `Processing` (Swift's `.processing`);
@@ -15,13 +16,16 @@ use crate::engine::{EngineError, MurmurEngine};
 pub enum WalkStatus {
     Processing,
     Processed,
-    Failed,
+    /// `failure` is the provider error that failed the last attempt; `None`
+    /// for a non-provider failure, a crash-swept zombie, or a pre-v8 row.
+    Failed { failure: Option<ProviderFailure> },
 }
 
 /// One board walk-log row (D2): a lightweight projection — NO transcript.
-/// `queued = status != Processed` (the same predicate `NotesPayload.queued`
-/// carries); `has_document` = a live `document` artifact exists (a
-/// built-and-kept walk).
+/// `queued = status != Processed`, or a provisional (on-device) result still
+/// owed its cloud pass — the same predicate `NotesPayload.queued` carries;
+/// `has_document` = a live `document` artifact exists (a built-and-kept
+/// walk).
 #[derive(uniffi::Record, Clone, Debug, PartialEq)]
 pub struct WalkSummary {
     pub id: String,
@@ -39,10 +43,13 @@ pub struct WalkSummary {
     pub queued: bool,
 }
 
-fn walk_status(status: murmur_core::SessionStatus) -> WalkStatus {
+fn walk_status(
+    status: murmur_core::SessionStatus,
+    failure: Option<murmur_core::SessionFailure>,
+) -> WalkStatus {
     match status {
         murmur_core::SessionStatus::Processed => WalkStatus::Processed,
-        murmur_core::SessionStatus::Failed => WalkStatus::Failed,
+        murmur_core::SessionStatus::Failed => WalkStatus::Failed { failure: failure.map(Into::into) },
         // Recording is filtered out by the core query (D3); defensive map to
         // Processing rather than a panic across FFI if that ever regressed.
         murmur_core::SessionStatus::AwaitingProcessing | murmur_core::SessionStatus::Recording => {
@@ -55,12 +62,12 @@ pub(crate) fn walk_summary(core: &murmur_core::WalkSummary) -> WalkSummary {
     WalkSummary {
         id: core.id.clone(),
         doc_kind: murmur_core::doc_kind_for_template(core.template.as_deref()).to_string(),
-        status: walk_status(core.status),
+        status: walk_status(core.status, core.failure),
         summary: core.summary.clone().unwrap_or_default(),
         started_at: core.started_at,
         item_count: core.item_count.min(u32::MAX as u64) as u32,
         has_document: core.has_document,
-        queued: core.status != murmur_core::SessionStatus::Processed,
+        queued: core.status != murmur_core::SessionStatus::Processed || core.provisional,
     }
 }
 
@@ -111,6 +118,8 @@ mod tests {
                 live: Arc::new(MockProvider::new(vec![])),
                 processing: Arc::new(MockProvider::new(vec![])),
                 reflection: Arc::new(MockProvider::new(vec![])),
+                local: None,
+                batches: None,
             },
         )
     }
@@ -159,7 +168,7 @@ mod tests {
         assert_eq!(wp.doc_kind, "estimate", "landscape template -> estimate kind");
 
         let wf = walks.iter().find(|w| w.id == f.id).unwrap();
-        assert_eq!(wf.status, WalkStatus::Failed);
+        assert_eq!(wf.status, WalkStatus::Failed { failure: None });
         assert!(wf.queued, "Failed -> queued (gating predicate)");
         assert_eq!(wf.summary, "", "never-Processed summary reads back empty");
         assert_eq!(wf.item_count, 3);
@@ -170,6 +179,57 @@ mod tests {
         assert!(wa.queued);
     }
 
+    #[tokio::test]
+    async fn list_sessions_carries_the_provider_failure_on_failed_walks() {
+        let store = Store::open_in_memory("device-a").unwrap();
+        let f = store.start_session(None).unwrap();
+        store.end_session(&f.id).unwrap();
+        let failure = murmur_core::SessionFailure {
+            kind: harness::ProviderErrorKind::Auth,
+            http_status: Some(401),
+        };
+        store.finish_session_failed(
+            &f.id,
+            &harness::Usage::default(),
+            &harness::ServedBy::default(),
+            Some(failure),
+        )
+        .unwrap();
+
+        let engine = engine_over(store);
+        let walks = engine.list_sessions().unwrap();
+        assert_eq!(
+            walks[0].status,
+            WalkStatus::Failed {
+                failure: Some(ProviderFailure {
+                    kind: crate::ProviderErrorKind::Auth,
+                    http_status: Some(401),
+                }),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn provisional_walk_is_processed_but_still_queued() {
+        let store = Store::open_in_memory("device-a").unwrap();
+        let s = store.start_session(None).unwrap();
+        store.end_session(&s.id).unwrap();
+        store
+            .finish_session_provisional(
+                &s.id,
+                "on-device notes",
+                &harness::Usage::default(),
+                &harness::ServedBy::default(),
+                &[],
+            )
+            .unwrap();
+
+        let walks = engine_over(store).list_sessions().unwrap();
+        assert_eq!(walks[0].status, WalkStatus::Processed);
+        assert_eq!(walks[0].summary, "on-device notes");
+        assert!(walks[0].queued, "the cloud pass is still owed");
+    }
+
     /// D2 (Plan 04 lesson), compile-enforced at the FFI boundary too: the
     /// record has no transcript field — exhaustive destructuring fails to
     /// compile if one is ever added.
@@ -4,7 +4,7 @@
 //! `harness::UpdateMemoryTool` (mutate under the lock, clamp the global cap,
 //! snapshot, release, persist). Panic-free across FFI (Plan 07 CANON).
 
-use harness::{FactSource, VocabAdd, DEFAULT_WORD_CAP};
+use harness::{FactSource, MemoryChange, VocabAdd, DEFAULT_WORD_CAP};
 
 use crate::engine::{EngineError, MurmurEngine};
 
@@ -71,7 +71,9 @@ impl MurmurEngine {
             mem.clamp_to_cap(DEFAULT_WORD_CAP); // global 500-word invariant, like UpdateMemoryTool
             mem.clone()
         };
-        self.memory_store.save(&snapshot).map_err(|e| EngineError::Store(e.to_string()))?;
+        self.memory_store
+            .save_change(&snapshot, &MemoryChange::Vocabulary)
+            .map_err(|e| EngineError::Store(e.to_string()))?;
         Ok(snapshot.vocabulary_terms().into_iter().map(str::to_string).collect())
     }
 
@@ -136,7 +138,9 @@ impl MurmurEngine {
             };
             (report, snapshot)
         }; // lock dropped here, before the save (CRUD discipline)
-        self.memory_store.save(&snapshot).map_err(|e| EngineError::Store(e.to_string()))?;
+        self.memory_store
+            .save_change(&snapshot, &MemoryChange::SeedPack { pack: key })
+            .map_err(|e| EngineError::Store(e.to_string()))?;
         Ok(report)
     }
 
@@ -148,7 +152,9 @@ impl MurmurEngine {
             mem.remove_vocabulary_term(&term);
             mem.clone()
         };
-        self.memory_store.save(&snapshot).map_err(|e| EngineError::Store(e.to_string()))?;
+        self.memory_store
+            .save_change(&snapshot, &MemoryChange::Vocabulary)
+            .map_err(|e| EngineError::Store(e.to_string()))?;
         Ok(snapshot.vocabulary_terms().into_iter().map(str::to_string).collect())
     }
 }
@@ -189,6 +195,8 @@ mod tests {
                 live: Arc::new(MockProvider::new(vec![])),
                 processing: Arc::new(MockProvider::new(vec![])),
                 reflection: Arc::new(MockProvider::new(vec![])),
+                local: None,
+                batches: None,
             },
         )
     }
@@ -357,13 +365,17 @@ mod tests {
         assert!(prompt.contains("boxwood"));
         assert!(!prompt.contains("_seeds") && !prompt.contains("landscape:1"));
 
-        // (b) LLM half: the agent/reflection context renders the section.
+        // (b) LLM half: the agent/reflection context renders the section —
+        // the agent's relevance-selected view too, even with nothing to spend.
         let ctx = memory.to_prompt();
         assert!(ctx.contains("## vocabulary"));
         assert!(ctx.contains("- boxwood"));
+        let selected = memory.to_prompt_for("an unrelated transcript", 0, 0);
+        assert!(selected.contains("- boxwood"));
 
         // (c) the marker reaches NEITHER consumer.
         assert!(!ctx.contains("_seeds") && !ctx.contains("landscape:1"));
+        assert!(!selected.contains("_seeds") && !selected.contains("landscape:1"));
     }
 
     #[tokio::test]
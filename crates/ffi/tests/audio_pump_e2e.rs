@@ -38,7 +38,8 @@ fn tool_use(name: &str, input: serde_json::Value) -> CompletionResponse {
     CompletionResponse {
         content: vec![ContentBlock::ToolUse { id: "tu".into(), name: name.into(), input }],
         stop_reason: StopReason::ToolUse,
-        usage: Usage { input_tokens: 10, output_tokens: 5 },
+        usage: Usage { input_tokens: 10, output_tokens: 5, ..Default::default() },
+        model: None,
     }
 }
 
@@ -46,7 +47,8 @@ fn end_turn(text: &str) -> CompletionResponse {
     CompletionResponse {
         content: vec![ContentBlock::Text { text: text.into() }],
         stop_reason: StopReason::EndTurn,
-        usage: Usage { input_tokens: 10, output_tokens: 5 },
+        usage: Usage { input_tokens: 10, output_tokens: 5, ..Default::default() },
+        model: None,
     }
 }
 
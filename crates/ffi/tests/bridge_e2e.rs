@@ -39,7 +39,8 @@ fn tool_use(name: &str, input: serde_json::Value) -> CompletionResponse {
     CompletionResponse {
         content: vec![ContentBlock::ToolUse { id: "tu".into(), name: name.into(), input }],
         stop_reason: StopReason::ToolUse,
-        usage: Usage { input_tokens: 10, output_tokens: 5 },
+        usage: Usage { input_tokens: 10, output_tokens: 5, ..Default::default() },
+        model: None,
     }
 }
 
@@ -47,7 +48,8 @@ fn end_turn(text: &str) -> CompletionResponse {
     CompletionResponse {
         content: vec![ContentBlock::Text { text: text.into() }],
         stop_reason: StopReason::EndTurn,
-        usage: Usage { input_tokens: 10, output_tokens: 5 },
+        usage: Usage { input_tokens: 10, output_tokens: 5, ..Default::default() },
+        model: None,
     }
 }
 
@@ -73,6 +75,8 @@ async fn begin_append_live_snapshot_finish_returns_notes() {
                 summary_response("Landscape walk: mulch and haul planned."),
             ])),
             reflection: Arc::new(MockProvider::new(vec![])),
+            local: None,
+            batches: None,
         },
     );
 
@@ -134,6 +138,8 @@ async fn finish_degrades_to_a_partial_queued_document_when_offline() {
             ])),
             processing: Arc::new(FailingProvider),
             reflection: Arc::new(MockProvider::new(vec![])),
+            local: None,
+            batches: None,
         },
     );
 
@@ -197,6 +203,8 @@ async fn a_tick_mid_finish_never_observes_an_empty_board() {
                 first: AtomicBool::new(true),
             }),
             reflection: Arc::new(MockProvider::new(vec![])),
+            local: None,
+            batches: None,
         },
     );
 
@@ -239,6 +247,8 @@ async fn finish_on_a_silent_walk_returns_truthful_empty_notes_without_panicking(
             live: Arc::new(MockProvider::new(vec![])),
             processing: Arc::new(MockProvider::new(vec![])),
             reflection: Arc::new(MockProvider::new(vec![])),
+            local: None,
+            batches: None,
         },
     );
 
@@ -264,6 +274,8 @@ async fn finish_on_a_whitespace_only_transcript_also_returns_empty_notes() {
             live: Arc::new(MockProvider::new(vec![])),
             processing: Arc::new(MockProvider::new(vec![])),
             reflection: Arc::new(MockProvider::new(vec![])),
+            local: None,
+            batches: None,
         },
     );
 
@@ -298,6 +310,8 @@ async fn a_second_finish_call_returns_the_already_known_notes_instead_of_panicki
                 summary_response("Landscape walk: mulch and haul planned."),
             ])),
             reflection: Arc::new(MockProvider::new(vec![])),
+            local: None,
+            batches: None,
         },
     );
 
@@ -1,9 +1,18 @@
 use std::sync::Arc;
+use std::time::Instant;
 
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+
+use crate::budget::Budget;
+use crate::cancel::CancelToken;
+use crate::compaction::Compaction;
 use crate::error::HarnessError;
 use crate::llm::{
-    CompletionRequest, ContentBlock, LlmProvider, Message, Role, StopReason, ToolSpec, Usage,
+    CacheHints, CompletionRequest, ContentBlock, LlmProvider, Message, Role, StopReason,
+    StreamSink, ToolSpec, Usage,
 };
+use crate::observer::{SharedObserver, ToolCallReport};
 use crate::tool::ToolRegistry;
 
 #[derive(Clone, Debug)]
@@ -11,6 +20,10 @@ pub struct AgentConfig {
     pub system_prompt: String,
     pub max_turns: usize,
     pub max_tokens: u32,
+    /// Extended-thinking budget per turn (see `CompletionRequest::thinking`).
+    /// Each turn's thinking blocks are echoed back with the rest of its
+    /// content, as a tool-use loop requires.
+    pub thinking: Option<u32>,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -23,6 +36,8 @@ pub struct TurnOutcome {
     pub usage: Usage,
     /// Stop reason of the final provider response (Unknown for unrecognized reasons).
     pub stop_reason: StopReason,
+    /// The model(s) that served this run's calls, and what each spent.
+    pub model: ServedBy,
 }
 
 /// An error from [`Agent::run`] or [`ReflectionEngine::reflect`] that carries
@@ -36,6 +51,8 @@ pub struct RunError {
     /// completed (e.g. a turn-1 provider error or a reflection provider error);
     /// a mid-loop failure carries the prior turns' usage.
     pub usage: Usage,
+    /// The model(s) that served the completed calls behind `usage`.
+    pub model: ServedBy,
 }
 
 impl std::fmt::Display for RunError {
@@ -60,40 +77,215 @@ pub struct Agent {
     provider: Arc<dyn LlmProvider>,
     tools: ToolRegistry,
     config: AgentConfig,
+    stream_sink: Option<StreamSink>,
+    cache: CacheHints,
+    tool_concurrency: usize,
+    /// The observer and the run name it reports under.
+    observer: Option<(SharedObserver, String)>,
+    budget: Budget,
+    cancel: CancelToken,
+    compaction: Option<Compaction>,
 }
 
+/// Default bound on concurrently running tool calls within one turn.
+const DEFAULT_TOOL_CONCURRENCY: usize = 8;
+
 impl Agent {
     pub fn new(provider: Arc<dyn LlmProvider>, tools: ToolRegistry, config: AgentConfig) -> Self {
-        Agent { provider, tools, config }
+        Agent {
+            provider,
+            tools,
+            config,
+            stream_sink: None,
+            cache: CacheHints::default(),
+            tool_concurrency: DEFAULT_TOOL_CONCURRENCY,
+            observer: None,
+            budget: Budget::default(),
+            cancel: CancelToken::default(),
+            compaction: None,
+        }
+    }
+
+    /// Every turn goes through `LlmProvider::stream` and forwards its deltas
+    /// to `sink` (text and tool-input fragments of every turn, in order).
+    /// The run's result is identical to the non-streaming path.
+    pub fn with_stream_sink(mut self, sink: StreamSink) -> Self {
+        self.stream_sink = Some(sink);
+        self
+    }
+
+    /// Marks the system prompt / tool list as cacheable on every turn. The
+    /// tools are the registry's, fixed for the run; the offsets index into
+    /// `AgentConfig::system_prompt`.
+    pub fn with_cache_hints(mut self, cache: CacheHints) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    /// At most `limit` concurrency-safe tool calls of a turn run at once
+    /// (1 = strictly one after another). Clamped to at least 1.
+    pub fn with_tool_concurrency(mut self, limit: usize) -> Self {
+        self.tool_concurrency = limit.max(1);
+        self
+    }
+
+    /// Reports every request, response and tool call of each run to
+    /// `observer`, under the name `run`.
+    pub fn with_observer(mut self, observer: SharedObserver, run: impl Into<String>) -> Self {
+        self.observer = Some((observer, run.into()));
+        self
+    }
+
+    /// Caps the run's cumulative spend. Checked before every provider call:
+    /// a call that could cross a limit isn't made, and the run stops with
+    /// `HarnessError::BudgetExceeded`; an output or dollar limit also
+    /// lowers the call's `max_tokens` to what's left.
+    pub fn with_budget(mut self, budget: Budget) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    /// Stops the run when `cancel` fires: before the next turn, or by
+    /// abandoning the provider call in flight. A turn's tool calls always
+    /// run to completion. The run fails with `HarnessError::Cancelled`.
+    pub fn with_cancel(mut self, cancel: CancelToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// Keeps each turn's request under `compaction`'s threshold by
+    /// collapsing old tool exchanges into a ledger; the messages passed to
+    /// `run` are pinned. Budget checks and observers see the compacted
+    /// request, `TurnOutcome::messages` the full history.
+    pub fn with_compaction(mut self, compaction: Compaction) -> Self {
+        self.compaction = Some(compaction);
+        self
     }
 
     fn tool_specs(&self) -> Vec<ToolSpec> {
         self.tools.specs()
     }
 
-    pub async fn run(&self, mut messages: Vec<Message>) -> Result<TurnOutcome, RunError> {
+    async fn execute_tool(&self, turn: usize, id: String, name: String, input: serde_json::Value) -> ContentBlock {
+        if let Some((observer, run)) = &self.observer {
+            observer.tool_started(run, turn, &id, &name, &input);
+        }
+        let started = Instant::now();
+        let (content, is_error) = match self.tools.execute(&name, input).await {
+            Ok(content) => (content, false),
+            Err(e) => (e.to_string(), true),
+        };
+        if let Some((observer, run)) = &self.observer {
+            let report = ToolCallReport {
+                id: &id,
+                name: &name,
+                content: &content,
+                is_error,
+                elapsed: started.elapsed(),
+            };
+            observer.tool_finished(run, turn, &report);
+        }
+        ContentBlock::ToolResult { tool_use_id: id, content, is_error }
+    }
+
+    /// Runs one turn's tool calls, returning their results in call order.
+    /// Consecutive concurrency-safe calls form a batch that runs with up to
+    /// `tool_concurrency` in flight; any other call is a barrier — it starts
+    /// after everything before it has finished, and nothing after it starts
+    /// until it has.
+    async fn execute_tools(
+        &self,
+        turn: usize,
+        tool_uses: Vec<(String, String, serde_json::Value)>,
+    ) -> Vec<ContentBlock> {
+        let mut results = Vec::with_capacity(tool_uses.len());
+        let mut batch = Vec::new();
+        for (id, name, input) in tool_uses {
+            if self.tools.is_concurrency_safe(&name) {
+                batch.push((id, name, input));
+                continue;
+            }
+            results.extend(self.execute_batch(turn, std::mem::take(&mut batch)).await);
+            results.push(self.execute_tool(turn, id, name, input).await);
+        }
+        results.extend(self.execute_batch(turn, batch).await);
+        results
+    }
+
+    async fn execute_batch(
+        &self,
+        turn: usize,
+        batch: Vec<(String, String, serde_json::Value)>,
+    ) -> Vec<ContentBlock> {
+        // `buffered` (not `buffer_unordered`) yields in input order.
+        stream::iter(batch)
+            .map(|(id, name, input)| self.execute_tool(turn, id, name, input))
+            .buffered(self.tool_concurrency)
+            .collect()
+            .await
+    }
+
+    pub async fn run(&self, messages: Vec<Message>) -> Result<TurnOutcome, RunError> {
+        let result = self.run_turns(messages).await;
+        if let Some((observer, run)) = &self.observer {
+            match &result {
+                Ok(outcome) => observer.run_finished(run, &outcome.usage),
+                Err(e) => observer.run_aborted(run, &e.source, &e.usage),
+            }
+        }
+        result
+    }
+
+    async fn run_turns(&self, mut messages: Vec<Message>) -> Result<TurnOutcome, RunError> {
         let mut usage = Usage::default();
+        let mut model = ServedBy::default();
+        let pinned = messages.len();
 
-        for _ in 0..self.config.max_turns {
+        for turn in 0..self.config.max_turns {
+            if self.cancel.is_cancelled() {
+                return Err(RunError { source: HarnessError::Cancelled { usage }, usage, model });
+            }
+            let mut request = CompletionRequest {
+                system: self.config.system_prompt.clone(),
+                messages: messages.clone(),
+                tools: self.tool_specs(),
+                max_tokens: self.config.max_tokens,
+                tool_choice: None,
+                cache: self.cache.clone(),
+                thinking: self.config.thinking,
+            };
+            if let Some(compaction) = &self.compaction {
+                compaction.apply(&mut request, pinned);
+            }
+            request.max_tokens = self.budget.admit(&usage, &request).map_err(|limit| RunError {
+                source: HarnessError::BudgetExceeded { limit, usage },
+                usage,
+                model: model.clone(),
+            })?;
+            if let Some((observer, run)) = &self.observer {
+                observer.request_sent(run, turn, &request);
+            }
+            let started = Instant::now();
+            let call = async {
+                match &self.stream_sink {
+                    Some(sink) => self.provider.stream(request, sink.as_ref()).await,
+                    None => self.provider.complete(request).await,
+                }
+            };
             let response = self
-                .provider
-                .complete(CompletionRequest {
-                    system: self.config.system_prompt.clone(),
-                    messages: messages.clone(),
-                    tools: self.tool_specs(),
-                    max_tokens: self.config.max_tokens,
-                    tool_choice: None,
-                })
+                .cancel
+                .guard(usage, call)
                 .await
-                .map_err(|e| RunError { source: e, usage })?;
+                .map_err(|e| RunError { source: e, usage, model: model.clone() })?;
+            if let Some((observer, run)) = &self.observer {
+                observer.response_received(run, turn, &response, started.elapsed());
+            }
             usage.add(&response.usage);
+            model.add(response.model.as_deref(), &response.usage);
             let stop_reason = response.stop_reason;
-
-            let content: Vec<ContentBlock> = response
-                .content
-                .into_iter()
-                .filter(|b| !matches!(b, ContentBlock::Unknown))
-                .collect();
+            // Every block goes back verbatim — thinking (with its signature)
+            // and blocks this crate doesn't know included.
+            let content = response.content;
 
             let tool_uses: Vec<(String, String, serde_json::Value)> = content
                 .iter()
@@ -115,31 +307,71 @@ impl Agent {
                     .collect::<Vec<_>>()
                     .join("\n");
                 messages.push(Message { role: Role::Assistant, content });
-                return Ok(TurnOutcome { text, messages, usage, stop_reason });
+                return Ok(TurnOutcome { text, messages, usage, stop_reason, model });
             }
 
             messages.push(Message { role: Role::Assistant, content });
 
-            let mut results = Vec::with_capacity(tool_uses.len());
-            for (id, name, input) in tool_uses {
-                let block = match self.tools.execute(&name, input).await {
-                    Ok(content) => ContentBlock::ToolResult {
-                        tool_use_id: id,
-                        content,
-                        is_error: false,
-                    },
-                    Err(e) => ContentBlock::ToolResult {
-                        tool_use_id: id,
-                        content: e.to_string(),
-                        is_error: true,
-                    },
-                };
-                results.push(block);
-            }
+            let results = self.execute_tools(turn, tool_uses).await;
             messages.push(Message { role: Role::User, content: results });
         }
 
-        Err(RunError { source: HarnessError::MaxTurns(self.config.max_turns), usage })
+        Err(RunError { source: HarnessError::MaxTurns(self.config.max_turns), usage, model })
+    }
+}
+
+/// Which models served a run, each with what it spent, in first-served
+/// order. A run a fallback answered in part is logged as one usage row per
+/// model (`rows`) rather than one summed row under both names.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServedBy(Vec<(String, Usage)>);
+
+impl ServedBy {
+    /// Books one call's spend to the model that served it. A call with no
+    /// known model books nothing here; `rows` still accounts for it.
+    pub fn add(&mut self, model: Option<&str>, usage: &Usage) {
+        let Some(model) = model else { return };
+        match self.0.iter_mut().find(|(m, _)| m == model) {
+            Some((_, spent)) => spent.add(usage),
+            None => self.0.push((model.to_string(), *usage)),
+        }
+    }
+
+    /// Folds another run's attribution into this one.
+    pub fn merge(&mut self, other: &ServedBy) {
+        for (model, usage) in &other.0 {
+            self.add(Some(model), usage);
+        }
+    }
+
+    /// The serving models, ", "-joined — for display, never for accounting.
+    pub fn label(&self) -> Option<String> {
+        (!self.0.is_empty()).then(|| self.0.iter().map(|(m, _)| m.as_str()).collect::<Vec<_>>().join(", "))
+    }
+
+    /// The usage rows for a run that spent `total`: one per serving model,
+    /// plus a model-less row for any spend no model was booked for (a
+    /// cancelled call's share). With no known model, the single row is the
+    /// whole `total`, zero or not.
+    pub fn rows(&self, total: &Usage) -> Vec<(Option<&str>, Usage)> {
+        let mut rows: Vec<(Option<&str>, Usage)> = self.0.iter().map(|(m, u)| (Some(m.as_str()), *u)).collect();
+        let mut booked = Usage::default();
+        for (_, usage) in &self.0 {
+            booked.add(usage);
+        }
+        let rest = Usage {
+            input_tokens: total.input_tokens.saturating_sub(booked.input_tokens),
+            output_tokens: total.output_tokens.saturating_sub(booked.output_tokens),
+            cache_creation_input_tokens: total
+                .cache_creation_input_tokens
+                .saturating_sub(booked.cache_creation_input_tokens),
+            cache_read_input_tokens: total.cache_read_input_tokens.saturating_sub(booked.cache_read_input_tokens),
+            thinking_tokens: total.thinking_tokens.saturating_sub(booked.thinking_tokens),
+        };
+        if rows.is_empty() || rest != Usage::default() {
+            rows.push((None, rest));
+        }
+        rows
     }
 }
 
@@ -152,7 +384,7 @@ mod tests {
     use std::sync::{Arc, Mutex};
 
     fn usage1() -> Usage {
-        Usage { input_tokens: 10, output_tokens: 20 }
+        Usage { input_tokens: 10, output_tokens: 20, ..Default::default() }
     }
 
     fn text_end(s: &str) -> CompletionResponse {
@@ -160,6 +392,7 @@ mod tests {
             content: vec![ContentBlock::Text { text: s.into() }],
             stop_reason: StopReason::EndTurn,
             usage: usage1(),
+            model: None,
         }
     }
 
@@ -172,6 +405,7 @@ mod tests {
             }],
             stop_reason: StopReason::ToolUse,
             usage: usage1(),
+            model: None,
         }
     }
 
@@ -212,6 +446,7 @@ mod tests {
                 system_prompt: "you are a field agent".into(),
                 max_turns: 5,
                 max_tokens: 1000,
+                thinking: None,
             },
         );
         (agent, provider)
@@ -246,7 +481,7 @@ mod tests {
         assert_eq!(out.text, "all done");
         assert_eq!(calls.lock().unwrap().as_slice(), &[serde_json::json!({"x": 1})]);
         // usage accumulated over two provider calls
-        assert_eq!(out.usage, Usage { input_tokens: 20, output_tokens: 40 });
+        assert_eq!(out.usage, Usage { input_tokens: 20, output_tokens: 40, ..Default::default() });
 
         // second request must carry assistant tool_use then user tool_result
         let reqs = provider.requests();
@@ -266,6 +501,42 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn compaction_shrinks_requests_without_changing_the_run() {
+        async fn run(compaction: Option<Compaction>) -> (TurnOutcome, Vec<serde_json::Value>, Vec<CompletionRequest>) {
+            let calls = Arc::new(Mutex::new(Vec::new()));
+            let mut reg = ToolRegistry::new();
+            reg.register(Recorder { calls: calls.clone(), reply: Ok("saved ".repeat(100)) });
+            let mut script: Vec<_> = (0..4).map(|n| tool_call("recorder", serde_json::json!({"n": n}))).collect();
+            script.push(text_end("all done"));
+            let (mut agent, provider) = agent_with(script, reg);
+            if let Some(compaction) = compaction {
+                agent = agent.with_compaction(compaction);
+            }
+            let out = agent.run(vec![Message::user_text("transcript")]).await.unwrap();
+            let calls = calls.lock().unwrap().clone();
+            (out, calls, provider.requests())
+        }
+
+        let (plain, plain_calls, plain_reqs) = run(None).await;
+        let (compact, compact_calls, compact_reqs) = run(Some(Compaction::new(200).with_keep_recent(1))).await;
+
+        assert_eq!(compact, plain, "same text, usage and full history");
+        assert_eq!(compact_calls, plain_calls, "every tool ran once, with the same input");
+        let size = |r: &CompletionRequest| crate::budget::estimate_prompt_tokens(r);
+        for (compact, plain) in compact_reqs.iter().zip(&plain_reqs) {
+            assert!(size(compact) <= size(plain));
+        }
+        let last = compact_reqs.last().unwrap();
+        assert!(size(last) < size(plain_reqs.last().unwrap()));
+        // The transcript stays pinned, the ledger follows it, and the newest
+        // exchange goes back verbatim.
+        assert_eq!(last.messages.len(), 3);
+        assert_eq!(last.messages[0].content[0], ContentBlock::Text { text: "transcript".into() });
+        assert!(matches!(&last.messages[0].content[1], ContentBlock::Text { text } if text.contains(r#"recorder {"n":2}"#)));
+        assert_eq!(&last.messages[1..], &plain_reqs.last().unwrap().messages[7..]);
+    }
+
     #[tokio::test]
     async fn failing_tool_becomes_error_result_not_abort() {
         let mut reg = ToolRegistry::new();
@@ -304,22 +575,84 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn unknown_blocks_are_dropped_and_stop_reason_surfaced() {
-        let response = CompletionResponse {
-            content: vec![
-                ContentBlock::Unknown,
-                ContentBlock::Text { text: "fine".into() },
-            ],
-            stop_reason: StopReason::Unknown,
-            usage: usage1(),
-        };
-        let (agent, _provider) = agent_with(vec![response], ToolRegistry::new());
+    async fn thinking_and_unknown_blocks_are_echoed_verbatim_across_turns() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let mut reg = ToolRegistry::new();
+        reg.register(Recorder { calls, reply: Ok("saved".into()) });
+        let thinking = ContentBlock::Thinking { thinking: "two todos, one price".into(), signature: "sig".into() };
+        let server = ContentBlock::Unknown(serde_json::json!({"type": "server_tool_use", "id": "srv_1"}));
+        let mut first = tool_call("recorder", serde_json::json!({"x": 1}));
+        first.content.splice(0..0, [thinking.clone(), ContentBlock::RedactedThinking { data: "opaque".into() }]);
+        first.content.push(server.clone());
+        let last = CompletionResponse { stop_reason: StopReason::Unknown, ..text_end("fine") };
+
+        let provider = Arc::new(MockProvider::new(vec![first.clone(), last]));
+        let agent = Agent::new(
+            provider.clone(),
+            reg,
+            AgentConfig { system_prompt: "sys".into(), max_turns: 5, max_tokens: 4000, thinking: Some(2048) },
+        );
         let out = agent.run(vec![Message::user_text("hi")]).await.unwrap();
         assert_eq!(out.text, "fine");
         assert_eq!(out.stop_reason, StopReason::Unknown);
-        // the appended assistant message must not contain the Unknown block
-        let assistant = out.messages.last().unwrap();
-        assert_eq!(assistant.content, vec![ContentBlock::Text { text: "fine".into() }]);
+
+        let reqs = provider.requests();
+        assert!(reqs.iter().all(|r| r.thinking == Some(2048)));
+        // The tool turn goes back exactly as it came: thinking first, with
+        // its signature, and the unknown block untouched.
+        assert_eq!(reqs[1].messages[1], Message { role: Role::Assistant, content: first.content });
+        assert_eq!(reqs[1].messages[1].content[3], server);
+    }
+
+    #[tokio::test]
+    async fn stream_sink_sees_every_turns_deltas_and_outcome_is_unchanged() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let mut reg = ToolRegistry::new();
+        reg.register(Recorder { calls: calls.clone(), reply: Ok("saved".into()) });
+        let provider = Arc::new(
+            MockProvider::new(vec![
+                tool_call("recorder", serde_json::json!({"x": 1})),
+                text_end("all done"),
+            ])
+            .chunked(3),
+        );
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink_events = events.clone();
+        let agent = Agent::new(
+            provider,
+            reg,
+            AgentConfig {
+                system_prompt: "you are a field agent".into(),
+                max_turns: 5,
+                max_tokens: 1000,
+                thinking: None,
+            },
+        )
+        .with_stream_sink(Arc::new(move |e| sink_events.lock().unwrap().push(e)));
+
+        let out = agent.run(vec![Message::user_text("go")]).await.unwrap();
+        assert_eq!(out.text, "all done");
+        assert_eq!(calls.lock().unwrap().len(), 1);
+
+        let events = events.lock().unwrap();
+        let input: String = events
+            .iter()
+            .filter_map(|e| match e {
+                StreamEvent::ToolInputDelta { partial_json, .. } => Some(partial_json.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(input, r#"{"x":1}"#);
+        let text: Vec<&str> = events
+            .iter()
+            .filter_map(|e| match e {
+                StreamEvent::TextDelta { text, .. } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(text, vec!["all", " do", "ne"]);
+        // one usage event per provider call
+        assert_eq!(events.iter().filter(|e| matches!(e, StreamEvent::Usage(_))).count(), 2);
     }
 
     #[tokio::test]
@@ -337,7 +670,259 @@ mod tests {
         let err = agent.run(vec![Message::user_text("go")]).await.unwrap_err();
         assert!(matches!(err.source, HarnessError::MaxTurns(5)));
         // all 5 turns' usage is preserved in the error
-        assert_eq!(err.usage, Usage { input_tokens: 50, output_tokens: 100 });
+        assert_eq!(err.usage, Usage { input_tokens: 50, output_tokens: 100, ..Default::default() });
         assert_eq!(provider.requests().len(), 5);
     }
+
+    #[tokio::test]
+    async fn mid_run_provider_failure_keeps_its_kind_and_prior_usage() {
+        struct RejectsSecondCall(MockProvider);
+        #[async_trait::async_trait]
+        impl LlmProvider for RejectsSecondCall {
+            async fn complete(&self, req: CompletionRequest) -> Result<CompletionResponse, HarnessError> {
+                if self.0.requests().is_empty() {
+                    return self.0.complete(req).await;
+                }
+                Err(HarnessError::ProviderStatus { status: 401, retry_after: None, body: "invalid x-api-key".into() })
+            }
+        }
+
+        let mut reg = ToolRegistry::new();
+        reg.register(Recorder { calls: Arc::new(Mutex::new(Vec::new())), reply: Ok("ok".into()) });
+        let provider = RejectsSecondCall(MockProvider::new(vec![tool_call("recorder", serde_json::json!({}))]));
+        let agent = Agent::new(
+            Arc::new(provider),
+            reg,
+            AgentConfig { system_prompt: "sys".into(), max_turns: 5, max_tokens: 1000, thinking: None },
+        );
+        let err = agent.run(vec![Message::user_text("go")]).await.unwrap_err();
+        assert_eq!(err.source.provider_kind(), Some(crate::ProviderErrorKind::Auth));
+        assert_eq!(err.source.http_status(), Some(401));
+        assert_eq!(err.usage, usage1());
+    }
+
+    #[test]
+    fn served_by_books_each_models_spend_apart_in_first_served_order() {
+        let mut model = ServedBy::default();
+        model.add(None, &usage1());
+        assert_eq!(model.label(), None);
+        assert_eq!(model.rows(&Usage::default()), vec![(None, Usage::default())]);
+        for served in ["claude-sonnet-4-5", "claude-haiku-4-5", "claude-sonnet-4-5"] {
+            model.add(Some(served), &usage1());
+        }
+        assert_eq!(model.label().as_deref(), Some("claude-sonnet-4-5, claude-haiku-4-5"));
+
+        // The unattributed call's share gets a row of its own.
+        let mut total = usage1();
+        for _ in 0..3 {
+            total.add(&usage1());
+        }
+        let doubled = Usage { input_tokens: 20, output_tokens: 40, ..Default::default() };
+        assert_eq!(
+            model.rows(&total),
+            vec![(Some("claude-sonnet-4-5"), doubled), (Some("claude-haiku-4-5"), usage1()), (None, usage1())]
+        );
+    }
+
+    /// Sleeps, then logs its start/end against the other calls.
+    struct Slow {
+        name: &'static str,
+        safe: bool,
+        log: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl Tool for Slow {
+        fn name(&self) -> &str {
+            self.name
+        }
+        fn description(&self) -> &str {
+            "takes a while"
+        }
+        fn input_schema(&self) -> serde_json::Value {
+            serde_json::json!({"type": "object"})
+        }
+        async fn execute(&self, input: serde_json::Value) -> Result<String, HarnessError> {
+            let n = input["n"].as_u64().unwrap_or_default();
+            self.log.lock().unwrap().push(format!("start {} {n}", self.name));
+            tokio::time::sleep(std::time::Duration::from_millis(60)).await;
+            self.log.lock().unwrap().push(format!("end {} {n}", self.name));
+            Ok(format!("{} #{n}", self.name))
+        }
+        fn is_concurrency_safe(&self) -> bool {
+            self.safe
+        }
+    }
+
+    fn multi_tool_turn(calls: &[(&str, u64)]) -> CompletionResponse {
+        CompletionResponse {
+            content: calls
+                .iter()
+                .map(|(name, n)| ContentBlock::ToolUse {
+                    id: format!("tu_{n}"),
+                    name: (*name).into(),
+                    input: serde_json::json!({"n": n}),
+                })
+                .collect(),
+            stop_reason: StopReason::ToolUse,
+            usage: usage1(),
+            model: None,
+        }
+    }
+
+    async fn run_slow_turn(calls: &[(&str, u64)], limit: usize) -> (TurnOutcome, Vec<String>, std::time::Duration) {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mut reg = ToolRegistry::new();
+        reg.register(Slow { name: "add", safe: true, log: log.clone() });
+        reg.register(Slow { name: "save", safe: false, log: log.clone() });
+        let (agent, _provider) = agent_with(vec![multi_tool_turn(calls), text_end("done")], reg);
+        let agent = agent.with_tool_concurrency(limit);
+        let started = std::time::Instant::now();
+        let out = agent.run(vec![Message::user_text("go")]).await.unwrap();
+        let elapsed = started.elapsed();
+        let log = log.lock().unwrap().clone();
+        (out, log, elapsed)
+    }
+
+    #[tokio::test]
+    async fn safe_tool_calls_overlap_and_the_transcript_is_unchanged() {
+        let calls: Vec<(&str, u64)> = (0..5).map(|n| ("add", n)).collect();
+        let (sequential, _, slow) = run_slow_turn(&calls, 1).await;
+        let (parallel, _, fast) = run_slow_turn(&calls, 8).await;
+
+        assert_eq!(parallel.messages, sequential.messages, "identical transcript");
+        let results = &parallel.messages[2].content;
+        let ids: Vec<&str> = results
+            .iter()
+            .map(|b| match b {
+                ContentBlock::ToolResult { tool_use_id, .. } => tool_use_id.as_str(),
+                other => panic!("{other:?}"),
+            })
+            .collect();
+        assert_eq!(ids, ["tu_0", "tu_1", "tu_2", "tu_3", "tu_4"], "results stay in call order");
+        assert!(slow >= std::time::Duration::from_millis(300), "{slow:?}");
+        assert!(fast < std::time::Duration::from_millis(200), "{fast:?}");
+    }
+
+    #[tokio::test]
+    async fn concurrency_is_bounded_and_unsafe_calls_run_alone() {
+        let (_, log, _) = run_slow_turn(&[("add", 0), ("add", 1), ("add", 2), ("save", 3), ("add", 4)], 2).await;
+        let pos = |entry: &str| log.iter().position(|e| e == entry).unwrap();
+        // Bound of 2: call 2 waits for a slot.
+        assert!(pos("start add 1") < pos("end add 0"));
+        assert!(pos("start add 2") > pos("end add 0"));
+        // The unsafe call is a barrier on both sides.
+        assert!(pos("start save 3") > pos("end add 2"));
+        assert!(pos("start add 4") > pos("end save 3"));
+    }
+
+    #[tokio::test]
+    async fn observer_sees_the_whole_timeline_including_an_abort() {
+        let mut reg = ToolRegistry::new();
+        reg.register(Recorder { calls: Arc::new(Mutex::new(Vec::new())), reply: Err("disk full".into()) });
+        let timeline = Arc::new(crate::observer::tests::Timeline::default());
+        let (agent, _provider) = agent_with(vec![tool_call("recorder", serde_json::json!({}))], reg);
+        let agent = agent.with_observer(timeline.clone(), "processing");
+
+        // The script runs out on turn 1: the abort still reaches the observer.
+        let err = agent.run(vec![Message::user_text("go")]).await.unwrap_err();
+        assert!(matches!(err.source, HarnessError::Provider(_)));
+        let lines = timeline.0.lock().unwrap().clone();
+        assert_eq!(
+            lines[..5],
+            [
+                "processing#0 request (1 messages)",
+                "processing#0 response ToolUse",
+                "processing#0 start recorder tu_1",
+                "processing#0 finish recorder tu_1 error=true",
+                "processing#1 request (3 messages)",
+            ]
+        );
+        assert_eq!(lines[5], "processing aborted after 10 in: provider error: mock script exhausted");
+        assert_eq!(lines.len(), 6);
+    }
+
+    #[tokio::test]
+    async fn budget_stops_a_looping_run_before_the_call_that_would_cross_it() {
+        let mut reg = ToolRegistry::new();
+        reg.register(Recorder { calls: Arc::new(Mutex::new(Vec::new())), reply: Ok("again".into()) });
+        let responses = (0..5).map(|_| tool_call("recorder", serde_json::json!({}))).collect();
+        let (agent, provider) = agent_with(responses, reg);
+        // 20 output tokens per turn (the mock ignores the cap): the third
+        // call may use 10, the fourth has nothing left.
+        let agent = agent.with_budget(crate::Budget { max_output_tokens: Some(50), ..Default::default() });
+
+        let err = agent.run(vec![Message::user_text("go")]).await.unwrap_err();
+        let spent = Usage { input_tokens: 30, output_tokens: 60, ..Default::default() };
+        assert_eq!(err.usage, spent);
+        assert!(matches!(
+            &err.source,
+            HarnessError::BudgetExceeded { limit, usage } if limit == "50 output tokens" && *usage == spent
+        ));
+        let reqs = provider.requests();
+        assert_eq!(reqs.len(), 3, "the fourth call is never made");
+        assert_eq!(reqs.iter().map(|r| r.max_tokens).collect::<Vec<_>>(), [50, 30, 10]);
+    }
+
+    /// Cancels the run's token from inside a tool call.
+    struct CancelsRun(CancelToken);
+
+    #[async_trait::async_trait]
+    impl Tool for CancelsRun {
+        fn name(&self) -> &str {
+            "stop"
+        }
+        fn description(&self) -> &str {
+            "cancels the run"
+        }
+        fn input_schema(&self) -> serde_json::Value {
+            serde_json::json!({"type": "object"})
+        }
+        async fn execute(&self, _input: serde_json::Value) -> Result<String, HarnessError> {
+            self.0.cancel();
+            Ok("stopping".into())
+        }
+    }
+
+    #[tokio::test]
+    async fn cancel_between_turns_stops_before_the_next_call_with_the_spend_so_far() {
+        let cancel = CancelToken::new();
+        let mut reg = ToolRegistry::new();
+        reg.register(CancelsRun(cancel.clone()));
+        let (agent, provider) =
+            agent_with(vec![tool_call("stop", serde_json::json!({})), text_end("unreached")], reg);
+        let err = agent.with_cancel(cancel).run(vec![Message::user_text("go")]).await.unwrap_err();
+        assert!(matches!(err.source, HarnessError::Cancelled { usage } if usage == usage1()));
+        assert_eq!(err.usage, usage1());
+        assert_eq!(provider.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn cancel_abandons_the_provider_call_in_flight() {
+        struct Hangs;
+        #[async_trait::async_trait]
+        impl LlmProvider for Hangs {
+            async fn complete(&self, _: CompletionRequest) -> Result<CompletionResponse, HarnessError> {
+                tokio::time::sleep(std::time::Duration::from_secs(30)).await;
+                Ok(text_end("too late"))
+            }
+        }
+        let cancel = CancelToken::new();
+        let agent = Agent::new(
+            Arc::new(Hangs),
+            ToolRegistry::new(),
+            AgentConfig { system_prompt: "sys".into(), max_turns: 5, max_tokens: 1000, thinking: None },
+        )
+        .with_cancel(cancel.clone());
+        tokio::spawn(async move {
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            cancel.cancel();
+        });
+        let err = tokio::time::timeout(std::time::Duration::from_secs(5), agent.run(vec![Message::user_text("go")]))
+            .await
+            .expect("the run stops without waiting out the call")
+            .unwrap_err();
+        assert!(matches!(err.source, HarnessError::Cancelled { .. }));
+        assert_eq!(err.usage, Usage::default());
+    }
 }
@@ -0,0 +1,59 @@
+//! Message batches: many requests submitted together and answered
+//! asynchronously — within 24 hours, usually minutes — at half the
+//! synchronous price. For work nobody is waiting on. The caller keeps the
+//! batch id (across app launches if need be), polls `batch_status`, and
+//! fetches `batch_results` once the batch has ended.
+
+use serde::Deserialize;
+
+use crate::error::HarnessError;
+use crate::llm::{CompletionRequest, CompletionResponse};
+
+/// Where a batch is in its lifecycle.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchState {
+    InProgress,
+    /// A cancel was requested; requests still running will finish.
+    Canceling,
+    /// Every request has an answer (possibly an error); results can be fetched.
+    Ended,
+}
+
+/// How many of a batch's requests are in each state.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+pub struct BatchCounts {
+    pub processing: u32,
+    pub succeeded: u32,
+    pub errored: u32,
+    pub canceled: u32,
+    pub expired: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BatchStatus {
+    pub id: String,
+    pub state: BatchState,
+    pub counts: BatchCounts,
+}
+
+/// One request's answer, under the `custom_id` it was submitted with. A
+/// request that errored, was canceled, or expired before it ran is an `Err`.
+#[derive(Debug)]
+pub struct BatchResult {
+    pub custom_id: String,
+    pub response: Result<CompletionResponse, HarnessError>,
+}
+
+#[async_trait::async_trait]
+pub trait BatchProvider: Send + Sync {
+    /// Submits `requests`, each under a `custom_id` unique within the batch
+    /// (1-64 characters of `[A-Za-z0-9_-]`). Streaming doesn't apply.
+    async fn submit_batch(&self, requests: Vec<(String, CompletionRequest)>) -> Result<BatchStatus, HarnessError>;
+
+    async fn batch_status(&self, batch_id: &str) -> Result<BatchStatus, HarnessError>;
+
+    /// Every request's answer, in no particular order. Only valid once the
+    /// batch has `Ended`.
+    async fn batch_results(&self, batch_id: &str) -> Result<Vec<BatchResult>, HarnessError>;
+}
@@ -0,0 +1,185 @@
+//! Cumulative spend limits for one agent run. `max_turns` alone lets a
+//! looping run burn every turn at full context before it stops; a budget is
+//! checked before each provider call and stops the run as soon as the next
+//! call could cross it.
+
+use crate::context::approx_tokens;
+use crate::llm::{CompletionRequest, ContentBlock, Usage};
+
+/// Per-million-token list prices, in US dollars.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ModelPrice {
+    pub input_per_mtok: f64,
+    pub output_per_mtok: f64,
+}
+
+/// Known models by id prefix, most specific first.
+const PRICE_TABLE: &[(&str, ModelPrice)] = &[
+    ("claude-opus-4-5", ModelPrice { input_per_mtok: 5.0, output_per_mtok: 25.0 }),
+    ("claude-opus-4", ModelPrice { input_per_mtok: 15.0, output_per_mtok: 75.0 }),
+    ("claude-sonnet-4", ModelPrice { input_per_mtok: 3.0, output_per_mtok: 15.0 }),
+    ("claude-haiku-4-5", ModelPrice { input_per_mtok: 1.0, output_per_mtok: 5.0 }),
+    ("claude-3-5-haiku", ModelPrice { input_per_mtok: 0.8, output_per_mtok: 4.0 }),
+    ("gpt-4o-mini", ModelPrice { input_per_mtok: 0.15, output_per_mtok: 0.6 }),
+    ("gpt-4o", ModelPrice { input_per_mtok: 2.5, output_per_mtok: 10.0 }),
+];
+
+impl ModelPrice {
+    /// The list price for `model`, matched by id prefix (so dated snapshots
+    /// like `claude-haiku-4-5-20251001` resolve). `None` for a model the
+    /// table doesn't know — including every local model.
+    pub fn for_model(model: &str) -> Option<Self> {
+        PRICE_TABLE.iter().find(|(prefix, _)| model.starts_with(prefix)).map(|(_, price)| *price)
+    }
+
+    /// What `usage` cost, with cache writes/reads at their own rates
+    /// (`Usage::billable_input_tokens`).
+    pub fn cost_usd(&self, usage: &Usage) -> f64 {
+        (usage.billable_input_tokens() as f64 * self.input_per_mtok
+            + usage.output_tokens as f64 * self.output_per_mtok)
+            / 1_000_000.0
+    }
+}
+
+/// A dollar ceiling, priced with `price`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CostLimit {
+    pub max_usd: f64,
+    pub price: ModelPrice,
+}
+
+/// Limits on one run's cumulative usage. Every limit is optional; the
+/// default limits nothing.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Budget {
+    /// Prompt tokens across all calls, cache writes and reads included.
+    pub max_input_tokens: Option<u64>,
+    pub max_output_tokens: Option<u64>,
+    pub max_cost: Option<CostLimit>,
+}
+
+impl Budget {
+    pub fn is_unlimited(&self) -> bool {
+        *self == Budget::default()
+    }
+
+    /// Checks the next call against what `spent` already used. Its prompt
+    /// is estimated (`approx_tokens` of everything sent); its output is
+    /// capped instead — `Ok` carries the `max_tokens` the call may use, at
+    /// most the request's own. `Err` names the limit the call would cross.
+    pub(crate) fn admit(&self, spent: &Usage, request: &CompletionRequest) -> Result<u32, String> {
+        let prompt = estimate_prompt_tokens(request);
+        let mut max_tokens = u64::from(request.max_tokens);
+        if let Some(limit) = self.max_input_tokens {
+            let input = spent.input_tokens + spent.cache_creation_input_tokens + spent.cache_read_input_tokens;
+            if input + prompt > limit {
+                return Err(format!("{limit} input tokens"));
+            }
+        }
+        if let Some(limit) = self.max_output_tokens {
+            max_tokens = max_tokens.min(limit.saturating_sub(spent.output_tokens));
+            if max_tokens == 0 {
+                return Err(format!("{limit} output tokens"));
+            }
+        }
+        if let Some(CostLimit { max_usd, price }) = self.max_cost {
+            let committed = price.cost_usd(spent) + prompt as f64 * price.input_per_mtok / 1_000_000.0;
+            let affordable = ((max_usd - committed) * 1_000_000.0 / price.output_per_mtok).floor();
+            if affordable < 1.0 {
+                return Err(format!("${max_usd:.2}"));
+            }
+            max_tokens = max_tokens.min(affordable as u64);
+        }
+        Ok(max_tokens as u32)
+    }
+}
+
+/// What one image costs at most: the API downscales anything larger than
+/// ~1.15 megapixels, and bills (width × height) / 750 tokens.
+pub(crate) const IMAGE_TOKENS: u64 = 1600;
+
+/// Rough prompt size of a request: system, messages and tool specs as sent.
+/// Images count as `IMAGE_TOKENS` each, not as their base64 text.
+pub(crate) fn estimate_prompt_tokens(request: &CompletionRequest) -> u64 {
+    let mut images = 0;
+    let mut messages = String::new();
+    for block in request.messages.iter().flat_map(|m| &m.content) {
+        match block {
+            ContentBlock::Image { .. } => images += 1,
+            block => messages.push_str(&serde_json::to_string(block).unwrap_or_default()),
+        }
+    }
+    let tools = serde_json::to_string(&request.tools).unwrap_or_default();
+    (approx_tokens(&request.system) + approx_tokens(&messages) + approx_tokens(&tools)) as u64
+        + images * IMAGE_TOKENS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::{CacheHints, Message};
+
+    fn request(max_tokens: u32) -> CompletionRequest {
+        CompletionRequest {
+            system: "s".repeat(400),
+            messages: vec![Message::user_text("hello")],
+            tools: vec![],
+            max_tokens,
+            tool_choice: None,
+            cache: CacheHints::default(),
+            thinking: None,
+        }
+    }
+
+    #[test]
+    fn prices_resolve_by_prefix_and_count_cache_rates() {
+        let haiku = ModelPrice::for_model("claude-haiku-4-5-20251001").unwrap();
+        assert_eq!(haiku, ModelPrice { input_per_mtok: 1.0, output_per_mtok: 5.0 });
+        assert_eq!(ModelPrice::for_model("gpt-4o-mini-2024-07-18").unwrap().input_per_mtok, 0.15);
+        assert_eq!(ModelPrice::for_model("llama3.2"), None);
+        let usage = Usage { input_tokens: 1_000_000, output_tokens: 200_000, cache_read_input_tokens: 1_000_000, ..Default::default() };
+        assert!((haiku.cost_usd(&usage) - 2.1).abs() < 1e-9);
+    }
+
+    #[test]
+    fn unlimited_admits_the_request_as_is() {
+        assert!(Budget::default().is_unlimited());
+        assert_eq!(Budget::default().admit(&Usage::default(), &request(512)), Ok(512));
+    }
+
+    #[test]
+    fn input_limit_counts_the_next_prompt() {
+        let budget = Budget { max_input_tokens: Some(1_000), ..Default::default() };
+        let spent = Usage { input_tokens: 800, ..Default::default() };
+        // ~100 system tokens + a few for the message: still under.
+        assert_eq!(budget.admit(&spent, &request(512)), Ok(512));
+        let spent = Usage { input_tokens: 600, cache_read_input_tokens: 300, ..Default::default() };
+        assert_eq!(budget.admit(&spent, &request(512)), Err("1000 input tokens".into()));
+    }
+
+    #[test]
+    fn output_and_cost_limits_cap_max_tokens_then_stop() {
+        let budget = Budget { max_output_tokens: Some(1_000), ..Default::default() };
+        let spent = Usage { output_tokens: 900, ..Default::default() };
+        assert_eq!(budget.admit(&spent, &request(512)), Ok(100));
+        let spent = Usage { output_tokens: 1_000, ..Default::default() };
+        assert_eq!(budget.admit(&spent, &request(512)), Err("1000 output tokens".into()));
+
+        let price = ModelPrice { input_per_mtok: 1.0, output_per_mtok: 10.0 };
+        let budget = Budget { max_cost: Some(CostLimit { max_usd: 0.01, price }), ..Default::default() };
+        // $0.0095 spent + ~$0.0001 of prompt leaves room for ~40 output tokens.
+        let spent = Usage { input_tokens: 1_500, output_tokens: 800, ..Default::default() };
+        let allowed = budget.admit(&spent, &request(512)).unwrap();
+        assert!((30..=40).contains(&allowed), "{allowed}");
+        let spent = Usage { input_tokens: 1_500, output_tokens: 850, ..Default::default() };
+        assert_eq!(budget.admit(&spent, &request(512)), Err("$0.01".into()));
+    }
+
+    #[test]
+    fn images_count_at_their_billed_size_not_their_base64() {
+        let mut req = request(512);
+        let text_only = estimate_prompt_tokens(&req);
+        req.messages[0].content.push(ContentBlock::image("image/jpeg", &[0u8; 300_000]));
+        assert_eq!(estimate_prompt_tokens(&req), text_only + IMAGE_TOKENS);
+    }
+}
@@ -0,0 +1,110 @@
+//! Cooperative cancellation. A run checks its token between turns and races
+//! every provider call against it, so cancelling stops the spend at once
+//! instead of after the run's next write is rejected.
+
+use std::future::Future;
+use std::pin::pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use futures::future::{select, Either};
+use tokio::sync::Notify;
+
+use crate::error::HarnessError;
+use crate::llm::Usage;
+
+#[derive(Debug, Default)]
+struct Inner {
+    cancelled: AtomicBool,
+    notify: Notify,
+}
+
+/// Clones share one flag: cancelling any clone cancels them all, for good.
+/// The default token is simply never cancelled.
+#[derive(Clone, Debug, Default)]
+pub struct CancelToken(Arc<Inner>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        CancelToken::default()
+    }
+
+    /// Idempotent.
+    pub fn cancel(&self) {
+        self.0.cancelled.store(true, Ordering::SeqCst);
+        self.0.notify.notify_waiters();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Resolves once the token is cancelled (immediately if it already is).
+    pub async fn cancelled(&self) {
+        let mut notified = pin!(self.0.notify.notified());
+        // Registered before the flag is read, so a `cancel` landing in
+        // between still wakes us.
+        notified.as_mut().enable();
+        if self.is_cancelled() {
+            return;
+        }
+        notified.await;
+    }
+
+    /// Runs `call` unless the token is cancelled first, in which case `call`
+    /// is dropped mid-flight — an in-flight HTTP request is abandoned — and
+    /// the result is `HarnessError::Cancelled` carrying `spent`, the usage
+    /// the caller had already accumulated.
+    pub async fn guard<T>(
+        &self,
+        spent: Usage,
+        call: impl Future<Output = Result<T, HarnessError>>,
+    ) -> Result<T, HarnessError> {
+        if self.is_cancelled() {
+            return Err(HarnessError::Cancelled { usage: spent });
+        }
+        match select(pin!(call), pin!(self.cancelled())).await {
+            Either::Left((result, _)) => result,
+            Either::Right(((), _)) => Err(HarnessError::Cancelled { usage: spent }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn guard_passes_results_through_until_cancelled() {
+        let token = CancelToken::new();
+        assert_eq!(token.guard(Usage::default(), async { Ok(7) }).await.unwrap(), 7);
+
+        token.clone().cancel();
+        assert!(token.is_cancelled());
+        let spent = Usage { input_tokens: 10, output_tokens: 2, ..Default::default() };
+        let err = token.guard(spent, async { Ok(7) }).await.unwrap_err();
+        assert!(matches!(err, HarnessError::Cancelled { usage } if usage == spent));
+    }
+
+    #[tokio::test]
+    async fn cancel_abandons_a_call_in_flight() {
+        let token = CancelToken::new();
+        let canceller = token.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            canceller.cancel();
+        });
+        let started = std::time::Instant::now();
+        let err = token
+            .guard(Usage::default(), async {
+                tokio::time::sleep(Duration::from_secs(5)).await;
+                Ok(())
+            })
+            .await
+            .unwrap_err();
+        assert!(matches!(err, HarnessError::Cancelled { .. }));
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,185 @@
+//! Conversation compaction for agent runs. Every turn resends the whole
+//! history, so a long tool loop pays for its early turns again on every
+//! later one. Once a request would exceed `Compaction::threshold_tokens`,
+//! its oldest exchanges (an assistant turn plus its tool results) are
+//! collapsed into a ledger — one line per tool call — appended to the
+//! run's opening messages, which are pinned as sent. Only the request
+//! shrinks: tools still run exactly once, and `TurnOutcome::messages`
+//! keeps the full history.
+
+use crate::budget::estimate_prompt_tokens;
+use crate::llm::{CompletionRequest, ContentBlock, Message, Role};
+
+/// Opens the ledger text block.
+pub const LEDGER_HEADER: &str = "Earlier turns of this run, compacted. You already made these tool calls \
+                                 (name, input → result) — don't repeat them:";
+
+/// Longest tool result kept in a ledger line, in chars.
+const LEDGER_RESULT_CHARS: usize = 160;
+
+/// When and how far an agent run compacts (`Agent::with_compaction`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Compaction {
+    /// Compact a request whose estimated prompt (system, messages and tool
+    /// specs, chars/4) is over this.
+    pub threshold_tokens: u64,
+    /// Most recent exchanges always sent verbatim — at least one, so the
+    /// last turn's thinking blocks go back as the API requires.
+    pub keep_recent: usize,
+}
+
+impl Compaction {
+    pub fn new(threshold_tokens: u64) -> Self {
+        Compaction { threshold_tokens, keep_recent: 2 }
+    }
+
+    pub fn with_keep_recent(mut self, keep_recent: usize) -> Self {
+        self.keep_recent = keep_recent.max(1);
+        self
+    }
+
+    /// Compacts `request` in place, collapsing the oldest exchanges after
+    /// the first `pinned` messages one at a time until it fits or only
+    /// `keep_recent` are left. A request under the threshold is untouched.
+    pub(crate) fn apply(&self, request: &mut CompletionRequest, pinned: usize) {
+        if estimate_prompt_tokens(request) <= self.threshold_tokens {
+            return;
+        }
+        let pinned = pinned.min(request.messages.len());
+        let exchanges = (request.messages.len() - pinned) / 2;
+        let collapsible = exchanges.saturating_sub(self.keep_recent.max(1));
+        if collapsible == 0 {
+            return;
+        }
+        let full = std::mem::take(&mut request.messages);
+        for collapsed in 1..=collapsible {
+            request.messages = compacted(&full, pinned, collapsed);
+            if estimate_prompt_tokens(request) <= self.threshold_tokens {
+                return;
+            }
+        }
+    }
+}
+
+/// `full` with the first `collapsed` exchanges after `pinned` replaced by
+/// their ledger. The ledger joins the last pinned message when that is the
+/// user's, so roles keep alternating.
+fn compacted(full: &[Message], pinned: usize, collapsed: usize) -> Vec<Message> {
+    let split = pinned + 2 * collapsed;
+    let mut ledger = String::from(LEDGER_HEADER);
+    for exchange in full[pinned..split].chunks(2) {
+        let results = exchange.get(1).map(|m| m.content.as_slice()).unwrap_or_default();
+        for block in &exchange[0].content {
+            let ContentBlock::ToolUse { id, name, input } = block else { continue };
+            let (content, is_error) = results
+                .iter()
+                .find_map(|b| match b {
+                    ContentBlock::ToolResult { tool_use_id, content, is_error } if tool_use_id == id => {
+                        Some((content.as_str(), *is_error))
+                    }
+                    _ => None,
+                })
+                .unwrap_or(("(no result)", false));
+            let error = if is_error { "error: " } else { "" };
+            ledger.push_str(&format!("\n- {name} {input} → {error}{}", clip(content)));
+        }
+    }
+
+    let mut messages = full[..pinned].to_vec();
+    match messages.last_mut() {
+        Some(last) if last.role == Role::User => last.content.push(ContentBlock::Text { text: ledger }),
+        _ => messages.push(Message::user_text(ledger)),
+    }
+    messages.extend_from_slice(&full[split..]);
+    messages
+}
+
+fn clip(text: &str) -> String {
+    let text = text.trim();
+    match text.char_indices().nth(LEDGER_RESULT_CHARS) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::CacheHints;
+
+    fn exchange(n: usize) -> [Message; 2] {
+        let id = format!("tu_{n}");
+        [
+            Message {
+                role: Role::Assistant,
+                content: vec![
+                    ContentBlock::Text { text: "adding".into() },
+                    ContentBlock::ToolUse {
+                        id: id.clone(),
+                        name: "add_item".into(),
+                        input: serde_json::json!({"text": format!("item {n}")}),
+                    },
+                ],
+            },
+            Message {
+                role: Role::User,
+                content: vec![ContentBlock::ToolResult {
+                    tool_use_id: id,
+                    content: format!("added todo: item {n} {}", "x".repeat(400)),
+                    is_error: n == 1,
+                }],
+            },
+        ]
+    }
+
+    fn request(exchanges: usize) -> CompletionRequest {
+        let mut messages = vec![Message::user_text("transcript")];
+        messages.extend((0..exchanges).flat_map(exchange));
+        CompletionRequest {
+            system: "system".into(),
+            messages,
+            tools: vec![],
+            max_tokens: 100,
+            tool_choice: None,
+            cache: CacheHints::default(),
+            thinking: None,
+        }
+    }
+
+    #[test]
+    fn under_the_threshold_nothing_changes() {
+        let mut req = request(4);
+        let before = req.clone();
+        Compaction::new(100_000).apply(&mut req, 1);
+        assert_eq!(req, before);
+    }
+
+    #[test]
+    fn oldest_exchanges_collapse_into_a_ledger_on_the_pinned_message() {
+        let mut req = request(5);
+        let full = req.messages.clone();
+        Compaction::new(0).with_keep_recent(2).apply(&mut req, 1);
+
+        // transcript + ledger, then the two newest exchanges verbatim.
+        assert_eq!(req.messages.len(), 5);
+        assert_eq!(req.messages[0].content[0], ContentBlock::Text { text: "transcript".into() });
+        let ContentBlock::Text { text: ledger } = &req.messages[0].content[1] else { panic!("no ledger") };
+        assert!(ledger.starts_with(LEDGER_HEADER));
+        assert_eq!(ledger.lines().count(), 4, "header + one line per collapsed call");
+        assert!(ledger.contains(r#"- add_item {"text":"item 0"} → added todo: item 0"#));
+        assert!(ledger.contains("→ error: added todo: item 1"));
+        assert!(ledger.contains('…'), "long results are clipped");
+        assert_eq!(&req.messages[1..], &full[7..]);
+    }
+
+    #[test]
+    fn collapses_only_as_far_as_it_needs_to() {
+        let mut req = request(6);
+        let full_size = estimate_prompt_tokens(&req);
+        let threshold = full_size - 50;
+        Compaction::new(threshold).apply(&mut req, 1);
+        assert!(estimate_prompt_tokens(&req) <= threshold);
+        // One exchange's worth (~110 tokens) was enough.
+        assert_eq!(req.messages.len(), 1 + 2 * 5);
+    }
+}
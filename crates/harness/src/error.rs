@@ -1,9 +1,24 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::llm::Usage;
+
 #[derive(Debug, thiserror::Error)]
 pub enum HarnessError {
     /// Provider errors are stringified at the boundary deliberately: these
     /// errors will cross an FFI boundary later, where source chains don't travel.
     #[error("provider error: {0}")]
     Provider(String),
+    /// The provider answered with a non-success HTTP status. Kept apart from
+    /// `Provider` so a retry layer can tell a 429/529 from a 400, and honor
+    /// the server's `retry-after` hint.
+    #[error("provider error: HTTP {status}: {body}")]
+    ProviderStatus { status: u16, retry_after: Option<Duration>, body: String },
+    /// The request got no HTTP answer: connect failure, timeout, or a
+    /// connection dropped mid-body.
+    #[error("provider unreachable: {0}")]
+    ProviderUnreachable(String),
     #[error("storage error: {0}")]
     Storage(String),
     #[error("unknown tool: {0}")]
@@ -12,4 +27,143 @@ pub enum HarnessError {
     Tool { name: String, message: String },
     #[error("agent exceeded max turns ({0})")]
     MaxTurns(usize),
+    /// The next provider call would have crossed the run's `Budget`. Carries
+    /// what the run spent before stopping.
+    #[error("agent budget exceeded ({limit}) after {} input / {} output tokens", usage.input_tokens, usage.output_tokens)]
+    BudgetExceeded { limit: String, usage: Usage },
+    /// The run's `CancelToken` fired. Carries what the run spent before it
+    /// stopped; a call abandoned mid-flight adds nothing.
+    #[error("cancelled after {} input / {} output tokens", usage.input_tokens, usage.output_tokens)]
+    Cancelled { usage: Usage },
+}
+
+/// What kind of provider failure an error is — the part a user (or the app)
+/// can act on. Derived from the error, never guessed from message text.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProviderErrorKind {
+    /// 401/403: the api key was rejected or lacks access.
+    Auth,
+    /// 429.
+    RateLimited,
+    /// 529 (and 503): the provider is shedding load.
+    Overloaded,
+    /// Any other 4xx: the request itself was refused (bad model, too large).
+    BadRequest,
+    /// Any other 5xx.
+    Server,
+    /// No HTTP answer at all: offline, DNS, connect failure, timeout.
+    Unreachable,
+    /// An answer arrived but was unusable: unparseable body, a truncated
+    /// stream, or a forced tool call the model skipped.
+    InvalidResponse,
+}
+
+impl ProviderErrorKind {
+    pub fn from_status(status: u16) -> Self {
+        match status {
+            401 | 403 => ProviderErrorKind::Auth,
+            429 => ProviderErrorKind::RateLimited,
+            503 | 529 => ProviderErrorKind::Overloaded,
+            500..=599 => ProviderErrorKind::Server,
+            _ => ProviderErrorKind::BadRequest,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ProviderErrorKind::Auth => "auth",
+            ProviderErrorKind::RateLimited => "rate_limited",
+            ProviderErrorKind::Overloaded => "overloaded",
+            ProviderErrorKind::BadRequest => "bad_request",
+            ProviderErrorKind::Server => "server",
+            ProviderErrorKind::Unreachable => "unreachable",
+            ProviderErrorKind::InvalidResponse => "invalid_response",
+        }
+    }
+
+    /// Inverse of `as_str`; `None` for a string this build doesn't know.
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "auth" => Some(ProviderErrorKind::Auth),
+            "rate_limited" => Some(ProviderErrorKind::RateLimited),
+            "overloaded" => Some(ProviderErrorKind::Overloaded),
+            "bad_request" => Some(ProviderErrorKind::BadRequest),
+            "server" => Some(ProviderErrorKind::Server),
+            "unreachable" => Some(ProviderErrorKind::Unreachable),
+            "invalid_response" => Some(ProviderErrorKind::InvalidResponse),
+            _ => None,
+        }
+    }
+}
+
+impl HarnessError {
+    /// The provider-failure kind, or `None` for errors that aren't the
+    /// provider's (storage, tools, max turns, budget, cancellation).
+    pub fn provider_kind(&self) -> Option<ProviderErrorKind> {
+        match self {
+            HarnessError::Provider(_) => Some(ProviderErrorKind::InvalidResponse),
+            HarnessError::ProviderStatus { status, .. } => Some(ProviderErrorKind::from_status(*status)),
+            HarnessError::ProviderUnreachable(_) => Some(ProviderErrorKind::Unreachable),
+            _ => None,
+        }
+    }
+
+    /// The HTTP status the provider answered with, when it answered with one.
+    pub fn http_status(&self) -> Option<u16> {
+        match self {
+            HarnessError::ProviderStatus { status, .. } => Some(*status),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_codes_map_to_kinds() {
+        let kind = |status| {
+            HarnessError::ProviderStatus { status, retry_after: None, body: String::new() }.provider_kind()
+        };
+        assert_eq!(kind(401), Some(ProviderErrorKind::Auth));
+        assert_eq!(kind(403), Some(ProviderErrorKind::Auth));
+        assert_eq!(kind(429), Some(ProviderErrorKind::RateLimited));
+        assert_eq!(kind(529), Some(ProviderErrorKind::Overloaded));
+        assert_eq!(kind(503), Some(ProviderErrorKind::Overloaded));
+        assert_eq!(kind(500), Some(ProviderErrorKind::Server));
+        assert_eq!(kind(400), Some(ProviderErrorKind::BadRequest));
+        assert_eq!(kind(404), Some(ProviderErrorKind::BadRequest));
+    }
+
+    #[test]
+    fn non_status_errors_have_a_kind_only_when_they_are_the_providers() {
+        let offline = HarnessError::ProviderUnreachable("dns error".into());
+        assert_eq!(offline.provider_kind(), Some(ProviderErrorKind::Unreachable));
+        assert_eq!(offline.http_status(), None);
+        let garbled = HarnessError::Provider("bad response body".into());
+        assert_eq!(garbled.provider_kind(), Some(ProviderErrorKind::InvalidResponse));
+        assert_eq!(HarnessError::MaxTurns(3).provider_kind(), None);
+        let over = HarnessError::BudgetExceeded { limit: "1000 output tokens".into(), usage: Usage::default() };
+        assert_eq!(over.provider_kind(), None);
+        assert_eq!(HarnessError::Storage("disk full".into()).provider_kind(), None);
+    }
+
+    #[test]
+    fn kind_round_trips_through_str() {
+        for kind in [
+            ProviderErrorKind::Auth,
+            ProviderErrorKind::RateLimited,
+            ProviderErrorKind::Overloaded,
+            ProviderErrorKind::BadRequest,
+            ProviderErrorKind::Server,
+            ProviderErrorKind::Unreachable,
+            ProviderErrorKind::InvalidResponse,
+        ] {
+            assert_eq!(ProviderErrorKind::parse(kind.as_str()), Some(kind));
+            assert_eq!(serde_json::to_value(kind).unwrap(), kind.as_str());
+        }
+        assert_eq!(ProviderErrorKind::parse("bogus"), None);
+    }
 }
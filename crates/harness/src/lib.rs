@@ -1,28 +1,50 @@
 pub mod agent;
+pub mod batch;
+pub mod budget;
+pub mod cancel;
+pub mod compaction;
 pub mod context;
 pub mod error;
 pub mod llm;
 pub mod memory;
 pub mod mock;
+pub mod observer;
 pub mod providers;
 pub mod reflection;
+pub mod replay;
+pub mod schema;
+pub mod structured;
 pub mod tool;
 
-pub use agent::{Agent, AgentConfig, RunError, TurnOutcome};
+pub use agent::{Agent, AgentConfig, RunError, ServedBy, TurnOutcome};
+pub use batch::{BatchCounts, BatchProvider, BatchResult, BatchState, BatchStatus};
+pub use budget::{Budget, CostLimit, ModelPrice};
+pub use cancel::CancelToken;
+pub use compaction::Compaction;
 pub use context::{approx_tokens, budget_chars, AssembledContext, ContextAssembler, ContextSection};
-pub use error::HarnessError;
+pub use error::{HarnessError, ProviderErrorKind};
 pub use llm::{
-    CompletionRequest, CompletionResponse, ContentBlock, LlmProvider, Message, Role, StopReason,
-    ToolSpec, Usage,
+    emit_response_events, CacheHints, CompletionRequest, CompletionResponse, ContentBlock,
+    ImageSource, LlmProvider, Message, Role, StopReason, StreamEvent, StreamSink, ToolSpec, Usage,
 };
 pub use mock::MockProvider;
-pub use providers::AnthropicProvider;
+pub use observer::{observed_call, AgentObserver, SharedObserver, ToolCallReport};
+pub use providers::{
+    bypass_cache, with_priority, AnthropicProvider, CacheBounds, CachedResponse, CachingProvider, LimitedProvider,
+    LocalApi, LocalProvider, LocalToolMode, OpenAiProvider, Priority, RateLimiter, RateLimits, ResponseCache,
+    RetryPolicy, RetryStats, RetryingProvider, RouteRule, RoutingProvider,
+};
 pub use memory::{
     FactSource, Memory, MemoryEntry, VocabAdd, DEFAULT_WORD_CAP, MAX_VOCABULARY_TERMS,
     MAX_VOCABULARY_TERM_WORDS, VOCABULARY_SECTION,
 };
+pub use memory::fact::TypedFact;
+pub use memory::history::{EntryChange, MemoryChange, MemoryDiff, SectionDiff};
+pub use memory::relevance::DEFAULT_MEMORY_BUDGET_TOKENS;
 pub use memory::store::{FileMemoryStore, MemoryStore};
 pub use memory::tool::{Clock, UpdateMemoryTool};
 pub use reflection::engine::{ReflectionEngine, ReflectionOutcome};
 pub use reflection::policy::{ReflectionPolicy, ReflectionSignals};
-pub use tool::{Tool, ToolRegistry};
+pub use replay::ReplayProvider;
+pub use structured::{forced_tool_call, forced_tool_input, ForcedOutput};
+pub use tool::{parse_input, Tool, ToolRegistry};
@@ -25,11 +25,48 @@ pub enum ContentBlock {
         content: String,
         is_error: bool,
     },
-    /// Any block type this crate doesn't know yet (e.g. thinking, server_tool_use).
-    /// Lenient on purpose. Unknown blocks are dropped before messages are re-sent
-    /// to a provider (see Agent::run) — we can't faithfully round-trip them.
-    #[serde(other)]
-    Unknown,
+    /// An image the model should look at. User turns only.
+    Image {
+        source: ImageSource,
+    },
+    /// Extended thinking. `signature` must come back unchanged: the API
+    /// rejects an assistant turn whose thinking was edited or dropped while
+    /// it's still in a tool-use loop.
+    Thinking {
+        thinking: String,
+        signature: String,
+    },
+    /// Thinking the API encrypted; `data` is opaque and echoed as-is.
+    RedactedThinking {
+        data: String,
+    },
+    /// Any block type this crate doesn't know yet (e.g. server_tool_use),
+    /// kept as the raw JSON it arrived as — type tag included — so it's
+    /// re-sent verbatim. Lenient on purpose: an unknown block must never
+    /// fail response parsing.
+    #[serde(untagged)]
+    Unknown(serde_json::Value),
+}
+
+/// Where an image's bytes come from. Inline base64 is the only source the
+/// pipeline uses — images never leave the device as URLs.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ImageSource {
+    Base64 { media_type: String, data: String },
+}
+
+impl ContentBlock {
+    /// An inline image block; `media_type` is e.g. "image/jpeg".
+    pub fn image(media_type: impl Into<String>, bytes: &[u8]) -> Self {
+        use base64::Engine as _;
+        ContentBlock::Image {
+            source: ImageSource::Base64 {
+                media_type: media_type.into(),
+                data: base64::engine::general_purpose::STANDARD.encode(bytes),
+            },
+        }
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -63,6 +100,25 @@ pub struct CompletionRequest {
     pub max_tokens: u32,
     /// Force the model to call this tool by name (None = model decides).
     pub tool_choice: Option<String>,
+    /// Stable prefixes the provider may cache across calls.
+    pub cache: CacheHints,
+    /// Extended-thinking budget in tokens (None = off). Part of
+    /// `max_tokens`, not on top of it. Providers without extended thinking
+    /// ignore it.
+    pub thinking: Option<u32>,
+}
+
+/// Which leading parts of a request repeat verbatim across calls, so a
+/// provider with prompt caching can bill them at the cached rate. Providers
+/// without explicit caching ignore this. The default caches nothing.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CacheHints {
+    /// The tool list is stable.
+    pub tools: bool,
+    /// Byte offsets into `system` that each end a stable prefix, ascending —
+    /// e.g. the end of the fixed instructions, then `system.len()` to also
+    /// cover a memory block that changes less often than the messages do.
+    pub system_prefixes: Vec<usize>,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -77,16 +133,38 @@ pub enum StopReason {
     Unknown,
 }
 
+/// Token counts for one or more calls. With prompt caching, `input_tokens`
+/// is only the uncached part of the prompt; the cached prefix is counted in
+/// `cache_creation_input_tokens` (written this call) or
+/// `cache_read_input_tokens` (served from cache).
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Usage {
     pub input_tokens: u64,
     pub output_tokens: u64,
+    #[serde(default)]
+    pub cache_creation_input_tokens: u64,
+    #[serde(default)]
+    pub cache_read_input_tokens: u64,
+    /// The part of `output_tokens` spent thinking — already counted there,
+    /// so never billed twice. See each provider for how it's measured.
+    #[serde(default)]
+    pub thinking_tokens: u64,
 }
 
 impl Usage {
     pub fn add(&mut self, other: &Usage) {
         self.input_tokens += other.input_tokens;
         self.output_tokens += other.output_tokens;
+        self.cache_creation_input_tokens += other.cache_creation_input_tokens;
+        self.cache_read_input_tokens += other.cache_read_input_tokens;
+        self.thinking_tokens += other.thinking_tokens;
+    }
+
+    /// The prompt priced in plain input tokens: cache writes cost 1.25x,
+    /// cache reads 0.1x (Anthropic's multipliers). What a per-input-token
+    /// rate should be applied to for the real, discounted cost.
+    pub fn billable_input_tokens(&self) -> u64 {
+        self.input_tokens + self.cache_creation_input_tokens * 5 / 4 + self.cache_read_input_tokens / 10
     }
 }
 
@@ -96,11 +174,90 @@ pub struct CompletionResponse {
     pub content: Vec<ContentBlock>,
     pub stop_reason: StopReason,
     pub usage: Usage,
+    /// The model that served the call, when the provider knows it. A
+    /// `RoutingProvider` may answer from a different model than the one the
+    /// caller configured first; usage attribution reads this.
+    pub model: Option<String>,
+}
+
+/// One incremental piece of a streamed completion. `index` is the content
+/// block's position in the final response, so a sink can reassemble blocks
+/// without knowing the provider's wire format.
+#[derive(Clone, Debug, PartialEq)]
+pub enum StreamEvent {
+    /// A fragment of a text block's text.
+    TextDelta { index: usize, text: String },
+    /// A tool_use block opened; its input arrives as `ToolInputDelta`s.
+    ToolUseStart { index: usize, id: String, name: String },
+    /// A fragment of a tool_use block's input JSON — NOT valid JSON on its own.
+    ToolInputDelta { index: usize, partial_json: String },
+    /// Final token usage for the whole call, emitted once at the end.
+    Usage(Usage),
+}
+
+/// Shared callback for streamed events. Called inline from the provider's
+/// read loop — keep it cheap and never block in it.
+pub type StreamSink = std::sync::Arc<dyn Fn(StreamEvent) + Send + Sync>;
+
+/// Replays a finished response as stream events: each text block and each
+/// tool_use input split into `chunk_chars`-sized deltas (`None` = one delta
+/// per block), then the usage. Thinking and unknown blocks emit nothing but
+/// keep their index.
+pub fn emit_response_events(
+    response: &CompletionResponse,
+    chunk_chars: Option<usize>,
+    sink: &(dyn Fn(StreamEvent) + Send + Sync),
+) {
+    let chunks = |s: &str| -> Vec<String> {
+        match chunk_chars {
+            Some(n) if n > 0 => {
+                let chars: Vec<char> = s.chars().collect();
+                chars.chunks(n).map(|c| c.iter().collect()).collect()
+            }
+            _ => vec![s.to_string()],
+        }
+    };
+    for (index, block) in response.content.iter().enumerate() {
+        match block {
+            ContentBlock::Text { text } => {
+                for text in chunks(text) {
+                    sink(StreamEvent::TextDelta { index, text });
+                }
+            }
+            ContentBlock::ToolUse { id, name, input } => {
+                sink(StreamEvent::ToolUseStart { index, id: id.clone(), name: name.clone() });
+                for partial_json in chunks(&input.to_string()) {
+                    sink(StreamEvent::ToolInputDelta { index, partial_json });
+                }
+            }
+            ContentBlock::ToolResult { .. }
+            | ContentBlock::Image { .. }
+            | ContentBlock::Thinking { .. }
+            | ContentBlock::RedactedThinking { .. }
+            | ContentBlock::Unknown(_) => {}
+        }
+    }
+    sink(StreamEvent::Usage(response.usage));
 }
 
 #[async_trait::async_trait]
 pub trait LlmProvider: Send + Sync {
     async fn complete(&self, req: CompletionRequest) -> Result<CompletionResponse, HarnessError>;
+
+    /// Streaming variant of [`complete`](Self::complete): pushes deltas into
+    /// `sink` as they arrive and still returns the fully assembled response,
+    /// so callers treat it exactly like `complete` once it resolves. The
+    /// default runs `complete` and replays the result as one delta per block
+    /// — correct for every provider, just not incremental.
+    async fn stream(
+        &self,
+        req: CompletionRequest,
+        sink: &(dyn Fn(StreamEvent) + Send + Sync),
+    ) -> Result<CompletionResponse, HarnessError> {
+        let response = self.complete(req).await?;
+        emit_response_events(&response, None, sink);
+        Ok(response)
+    }
 }
 
 #[cfg(test)]
@@ -138,16 +295,76 @@ mod tests {
 
     #[test]
     fn usage_adds() {
-        let mut u = Usage { input_tokens: 10, output_tokens: 5 };
-        u.add(&Usage { input_tokens: 3, output_tokens: 7 });
-        assert_eq!(u, Usage { input_tokens: 13, output_tokens: 12 });
+        let mut u = Usage { input_tokens: 10, output_tokens: 5, ..Default::default() };
+        u.add(&Usage { input_tokens: 3, output_tokens: 7, ..Default::default() });
+        assert_eq!(u, Usage { input_tokens: 13, output_tokens: 12, ..Default::default() });
     }
 
     #[test]
-    fn unknown_content_block_type_parses_leniently() {
+    fn thinking_blocks_round_trip_with_their_signature() {
         let v = serde_json::json!({"type": "thinking", "thinking": "hmm", "signature": "sig"});
-        let back: ContentBlock = serde_json::from_value(v).unwrap();
-        assert_eq!(back, ContentBlock::Unknown);
+        let block: ContentBlock = serde_json::from_value(v.clone()).unwrap();
+        assert_eq!(block, ContentBlock::Thinking { thinking: "hmm".into(), signature: "sig".into() });
+        assert_eq!(serde_json::to_value(&block).unwrap(), v);
+
+        let v = serde_json::json!({"type": "redacted_thinking", "data": "opaque"});
+        let block: ContentBlock = serde_json::from_value(v.clone()).unwrap();
+        assert_eq!(block, ContentBlock::RedactedThinking { data: "opaque".into() });
+        assert_eq!(serde_json::to_value(&block).unwrap(), v);
+    }
+
+    #[test]
+    fn image_blocks_serialize_as_inline_base64() {
+        let block = ContentBlock::image("image/png", b"png!");
+        let v = serde_json::json!({
+            "type": "image",
+            "source": {"type": "base64", "media_type": "image/png", "data": "cG5nIQ=="},
+        });
+        assert_eq!(serde_json::to_value(&block).unwrap(), v);
+        assert_eq!(serde_json::from_value::<ContentBlock>(v).unwrap(), block);
+    }
+
+    #[test]
+    fn unknown_content_block_type_parses_leniently_and_round_trips() {
+        let v = serde_json::json!({"type": "server_tool_use", "id": "srv_1", "name": "web_search", "input": {}});
+        let block: ContentBlock = serde_json::from_value(v.clone()).unwrap();
+        assert_eq!(block, ContentBlock::Unknown(v.clone()));
+        assert_eq!(serde_json::to_value(&block).unwrap(), v);
+    }
+
+    #[test]
+    fn response_events_chunk_text_and_tool_input_then_report_usage() {
+        let response = CompletionResponse {
+            content: vec![
+                ContentBlock::Text { text: "abcde".into() },
+                ContentBlock::Thinking { thinking: "hmm".into(), signature: "sig".into() },
+                ContentBlock::ToolUse {
+                    id: "tu_1".into(),
+                    name: "echo".into(),
+                    input: serde_json::json!({"a": 1}),
+                },
+            ],
+            stop_reason: StopReason::ToolUse,
+            usage: Usage { input_tokens: 3, output_tokens: 4, ..Default::default() },
+            model: None,
+        };
+        let events = std::sync::Mutex::new(Vec::new());
+        emit_response_events(&response, Some(2), &|e| events.lock().unwrap().push(e));
+        let events = events.into_inner().unwrap();
+        assert_eq!(
+            events,
+            vec![
+                StreamEvent::TextDelta { index: 0, text: "ab".into() },
+                StreamEvent::TextDelta { index: 0, text: "cd".into() },
+                StreamEvent::TextDelta { index: 0, text: "e".into() },
+                StreamEvent::ToolUseStart { index: 2, id: "tu_1".into(), name: "echo".into() },
+                StreamEvent::ToolInputDelta { index: 2, partial_json: "{\"".into() },
+                StreamEvent::ToolInputDelta { index: 2, partial_json: "a\"".into() },
+                StreamEvent::ToolInputDelta { index: 2, partial_json: ":1".into() },
+                StreamEvent::ToolInputDelta { index: 2, partial_json: "}".into() },
+                StreamEvent::Usage(Usage { input_tokens: 3, output_tokens: 4, ..Default::default() }),
+            ]
+        );
     }
 
     #[test]
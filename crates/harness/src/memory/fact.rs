@@ -0,0 +1,164 @@
+//! Typed facts: the optional structured payload of a memory entry, for
+//! facts other code needs to read back rather than only show a model — a
+//! person's phone to match a contact, a remembered price for the pricing
+//! pass. The entry's text stays the fact's one-line `summary`, so entries
+//! are still matched, diffed and stored by text.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TypedFact {
+    Person {
+        name: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        role: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        phone: Option<String>,
+    },
+    /// What the user charges for `item`, per `unit` when there is one.
+    Price {
+        item: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        unit: Option<String>,
+        amount_cents: i64,
+    },
+    /// How the user (or a client, in a scoped memory) wants `topic` done.
+    Preference { topic: String, value: String },
+}
+
+fn present(field: &Option<String>) -> Option<&str> {
+    field.as_deref().map(str::trim).filter(|s| !s.is_empty())
+}
+
+impl TypedFact {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            TypedFact::Person { .. } => "person",
+            TypedFact::Price { .. } => "price",
+            TypedFact::Preference { .. } => "preference",
+        }
+    }
+
+    /// Whether the fields that identify the fact are filled in.
+    pub fn is_valid(&self) -> bool {
+        match self {
+            TypedFact::Person { name, .. } => !name.trim().is_empty(),
+            TypedFact::Price { item, amount_cents, .. } => !item.trim().is_empty() && *amount_cents >= 0,
+            TypedFact::Preference { topic, value } => !topic.trim().is_empty() && !value.trim().is_empty(),
+        }
+    }
+
+    /// Whether `other` is a fact about the same thing, perhaps with changed
+    /// details: the same person by name, the same item and unit's price, the
+    /// same topic's preference. Case and surrounding space don't count.
+    pub fn same_subject(&self, other: &TypedFact) -> bool {
+        let same = |a: &str, b: &str| a.trim().eq_ignore_ascii_case(b.trim());
+        match (self, other) {
+            (TypedFact::Person { name: a, .. }, TypedFact::Person { name: b, .. }) => same(a, b),
+            (TypedFact::Price { item: a, unit: ua, .. }, TypedFact::Price { item: b, unit: ub, .. }) => {
+                same(a, b) && same(present(ua).unwrap_or(""), present(ub).unwrap_or(""))
+            }
+            (TypedFact::Preference { topic: a, .. }, TypedFact::Preference { topic: b, .. }) => same(a, b),
+            _ => false,
+        }
+    }
+
+    /// One line in the house style ("Dev — framer"): the text of the entry
+    /// holding this fact.
+    pub fn summary(&self) -> String {
+        match self {
+            TypedFact::Person { name, role, phone } => std::iter::once(name.trim())
+                .chain(present(role))
+                .chain(present(phone))
+                .collect::<Vec<_>>()
+                .join(" — "),
+            TypedFact::Price { item, unit, amount_cents } => {
+                let amount = format!("${}.{:02}", amount_cents / 100, amount_cents % 100);
+                match present(unit) {
+                    Some(unit) => format!("{} — {amount}/{unit}", item.trim()),
+                    None => format!("{} — {amount}", item.trim()),
+                }
+            }
+            TypedFact::Preference { topic, value } => format!("{}: {}", topic.trim(), value.trim()),
+        }
+    }
+
+    /// How prompts show the fact: the summary tagged with its kind, so a
+    /// reflection can tell typed lines apart and repeat them verbatim.
+    pub fn prompt_line(&self) -> String {
+        format!("[{}] {}", self.kind(), self.summary())
+    }
+
+    /// JSON schema of a fact, shared by the tools that write them.
+    pub(crate) fn schema() -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "description": "typed form of the fact: a person (name, role?, phone?), a price (item, unit?, amount_cents) or a preference (topic, value)",
+            "properties": {
+                "kind": { "type": "string", "enum": ["person", "price", "preference"] },
+                "name": { "type": "string" },
+                "role": { "type": "string" },
+                "phone": { "type": "string" },
+                "item": { "type": "string" },
+                "unit": { "type": "string", "description": "e.g. each, hour, linear foot" },
+                "amount_cents": { "type": "integer" },
+                "topic": { "type": "string" },
+                "value": { "type": "string" }
+            },
+            "required": ["kind"]
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summaries_follow_the_house_style_and_skip_missing_fields() {
+        let dave = TypedFact::Person {
+            name: "Dave".into(),
+            role: Some("irrigation sub".into()),
+            phone: Some("555-0142".into()),
+        };
+        assert_eq!(dave.summary(), "Dave — irrigation sub — 555-0142");
+        assert_eq!(dave.prompt_line(), "[person] Dave — irrigation sub — 555-0142");
+        let bare = TypedFact::Person { name: "Ana".into(), role: None, phone: Some(" ".into()) };
+        assert_eq!(bare.summary(), "Ana");
+
+        let price = TypedFact::Price { item: "french drain".into(), unit: Some("linear foot".into()), amount_cents: 4505 };
+        assert_eq!(price.summary(), "french drain — $45.05/linear foot");
+        let pref = TypedFact::Preference { topic: "mulch".into(), value: "darker than last year".into() };
+        assert_eq!(pref.prompt_line(), "[preference] mulch: darker than last year");
+    }
+
+    #[test]
+    fn facts_round_trip_with_a_kind_tag_and_are_validated() {
+        let json = serde_json::json!({"kind": "price", "item": "sod", "amount_cents": 110});
+        let fact: TypedFact = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(fact, TypedFact::Price { item: "sod".into(), unit: None, amount_cents: 110 });
+        assert_eq!(serde_json::to_value(&fact).unwrap(), json);
+        assert!(fact.is_valid());
+        assert!(!TypedFact::Person { name: " ".into(), role: None, phone: None }.is_valid());
+        assert!(!TypedFact::Price { item: "sod".into(), unit: None, amount_cents: -1 }.is_valid());
+    }
+
+    #[test]
+    fn same_subject_matches_on_identity_not_details() {
+        let dave = TypedFact::Person { name: "Dave".into(), role: Some("plumber".into()), phone: None };
+        let dave_now = TypedFact::Person { name: " dave".into(), role: None, phone: Some("555-0142".into()) };
+        assert!(dave.same_subject(&dave_now));
+        assert!(!dave.same_subject(&TypedFact::Person { name: "Dev".into(), role: None, phone: None }));
+
+        let sod = TypedFact::Price { item: "sod".into(), unit: Some("sq ft".into()), amount_cents: 110 };
+        let raised = TypedFact::Price { item: "Sod".into(), unit: Some("sq ft".into()), amount_cents: 125 };
+        assert!(sod.same_subject(&raised));
+        let per_pallet = TypedFact::Price { item: "sod".into(), unit: Some("pallet".into()), amount_cents: 110 };
+        assert!(!sod.same_subject(&per_pallet));
+        assert!(!sod.same_subject(&TypedFact::Preference { topic: "sod".into(), value: "fescue".into() }));
+
+        let mulch = TypedFact::Preference { topic: "mulch".into(), value: "dark".into() };
+        assert!(mulch.same_subject(&TypedFact::Preference { topic: "mulch".into(), value: "dark brown".into() }));
+    }
+}
@@ -0,0 +1,138 @@
+//! What stores that keep a memory revision log need from the harness: why a
+//! save happened (`MemoryChange`, passed to `MemoryStore::save_change`) and
+//! what it changed (`Memory::diff`).
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::memory::{Memory, MemoryEntry};
+
+/// Why memory was saved. Recorded with the revision a save produces.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum MemoryChange {
+    /// A reflection rewrote the memory.
+    Reflection { churn: f32 },
+    /// The agent's `update_memory` tool, during `session` when known.
+    MemoryTool { session: Option<String> },
+    /// The user edited their vocabulary.
+    Vocabulary,
+    /// A trade seed pack (`"{trade}:{version}"`) was applied.
+    SeedPack { pack: String },
+    /// An earlier revision was restored.
+    Restore { revision: String },
+    /// Memory moved in from a legacy file.
+    Import,
+    /// Memory as it stood when the log started.
+    Baseline,
+    /// A plain `MemoryStore::save`, which doesn't say why.
+    Other,
+}
+
+/// One entry whose text stayed while its provenance or timestamp moved.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct EntryChange {
+    pub before: MemoryEntry,
+    pub after: MemoryEntry,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SectionDiff {
+    pub added: Vec<MemoryEntry>,
+    pub removed: Vec<MemoryEntry>,
+    pub changed: Vec<EntryChange>,
+}
+
+impl SectionDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Per-section difference between two memories. Sections with no
+/// difference are left out.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct MemoryDiff {
+    pub sections: BTreeMap<String, SectionDiff>,
+}
+
+impl MemoryDiff {
+    pub fn is_empty(&self) -> bool {
+        self.sections.is_empty()
+    }
+}
+
+impl Memory {
+    /// What changed from `self` to `to`. Entries are matched by section and
+    /// exact text — a reworded fact is one removed and one added. Internal
+    /// sections are compared like any other, so seed markers show up.
+    pub fn diff(&self, to: &Memory) -> MemoryDiff {
+        let empty = Vec::new();
+        let mut diff = MemoryDiff::default();
+        let names = self.sections.keys().chain(to.sections.keys());
+        for name in names {
+            if diff.sections.contains_key(name) {
+                continue;
+            }
+            let before = self.sections.get(name).unwrap_or(&empty);
+            let after = to.sections.get(name).unwrap_or(&empty);
+            let mut section = SectionDiff::default();
+            for entry in after {
+                match before.iter().find(|e| e.text == entry.text) {
+                    None => section.added.push(entry.clone()),
+                    Some(old) if old != entry => {
+                        section.changed.push(EntryChange { before: old.clone(), after: entry.clone() })
+                    }
+                    Some(_) => {}
+                }
+            }
+            section.removed =
+                before.iter().filter(|e| !after.iter().any(|a| a.text == e.text)).cloned().collect();
+            diff.sections.insert(name.clone(), section);
+        }
+        diff.sections.retain(|_, section| !section.is_empty());
+        diff
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::FactSource;
+
+    #[test]
+    fn diff_reports_added_removed_and_changed_entries_per_section() {
+        let mut before = Memory::default();
+        before.remember("people", "Dev — framer", 10);
+        before.remember("vocabulary", "french drain", 10);
+        before.remember("vocabulary", "sleeper wall", 10);
+
+        let mut after = before.clone();
+        after.remember_from("people", "Dev — framer", 20, FactSource::Corrected, Some("s1".into()));
+        after.forget("vocabulary", "french drain");
+        after.remember("vocabulary", "ledger board", 20);
+
+        let diff = before.diff(&after);
+        assert_eq!(diff.sections.len(), 2);
+        let people = &diff.sections["people"];
+        assert!(people.added.is_empty() && people.removed.is_empty());
+        assert_eq!(people.changed[0].before.source, FactSource::Inferred);
+        assert_eq!(people.changed[0].after.source, FactSource::Corrected);
+        let vocabulary = &diff.sections["vocabulary"];
+        assert_eq!(vocabulary.added[0].text, "ledger board");
+        assert_eq!(vocabulary.removed[0].text, "french drain");
+        assert!(vocabulary.changed.is_empty());
+
+        assert!(after.diff(&after).is_empty());
+        assert_eq!(after.diff(&before).sections["vocabulary"].added[0].text, "french drain");
+    }
+
+    #[test]
+    fn changes_round_trip_with_a_kind_tag() {
+        let change = MemoryChange::MemoryTool { session: Some("s1".into()) };
+        let json = serde_json::to_value(&change).unwrap();
+        assert_eq!(json, serde_json::json!({"kind": "memory_tool", "session": "s1"}));
+        assert_eq!(serde_json::from_value::<MemoryChange>(json).unwrap(), change);
+    }
+}
@@ -1,3 +1,6 @@
+pub mod fact;
+pub mod history;
+pub mod relevance;
 pub mod store;
 pub mod tool;
 
@@ -5,6 +8,8 @@ use std::collections::BTreeMap;
 
 use serde::{Deserialize, Serialize};
 
+use crate::memory::fact::TypedFact;
+
 /// Default word cap for a whole memory (spec §7: reflection compresses, never accumulates).
 pub const DEFAULT_WORD_CAP: usize = 500;
 
@@ -80,6 +85,21 @@ pub struct MemoryEntry {
     pub source: FactSource,
     /// Session id this fact came from, if known.
     pub session: Option<String>,
+    /// Structured form of the fact, when it was written as one; `text` is
+    /// then the fact's `summary`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fact: Option<TypedFact>,
+}
+
+impl MemoryEntry {
+    /// What a prompt shows for this entry: a typed fact's tagged line, else
+    /// the text.
+    pub fn prompt_text(&self) -> String {
+        match &self.fact {
+            Some(fact) => fact.prompt_line(),
+            None => self.text.clone(),
+        }
+    }
 }
 
 /// Sectioned agent memory. Section names are consumer-defined strings
@@ -130,10 +150,36 @@ impl Memory {
                 last_touched: now,
                 source,
                 session,
+                fact: None,
             }),
         }
     }
 
+    /// `remember_from` for a typed fact, stored under its summary. An entry
+    /// already holding that text takes the fact (the newest fields win).
+    pub fn remember_fact(
+        &mut self,
+        section: &str,
+        fact: TypedFact,
+        now: u64,
+        source: FactSource,
+        session: Option<String>,
+    ) {
+        let text = fact.summary();
+        self.remember_from(section, &text, now, source, session);
+        if let Some(entry) = self.sections.get_mut(section).and_then(|es| es.iter_mut().find(|e| e.text == text)) {
+            entry.fact = Some(fact);
+        }
+    }
+
+    /// Every typed fact outside internal sections, with its section.
+    pub fn facts(&self) -> impl Iterator<Item = (&str, &TypedFact)> {
+        self.sections
+            .iter()
+            .filter(|(name, _)| !is_internal_section(name))
+            .flat_map(|(name, entries)| entries.iter().filter_map(move |e| Some((name.as_str(), e.fact.as_ref()?))))
+    }
+
     /// Removes the exact `text` from `section`. Returns whether anything was removed.
     /// Sections left empty are dropped.
     pub fn forget(&mut self, section: &str, text: &str) -> bool {
@@ -169,8 +215,9 @@ impl Memory {
             .unwrap_or_default()
     }
 
-    /// Markdown rendering for prompt injection: `## section` headers, `- ` entries,
-    /// sections in BTreeMap (alphabetical) order. Empty memory renders as "".
+    /// Markdown rendering for prompt injection: `## section` headers, `- ` entries
+    /// (typed facts as their tagged line), sections in BTreeMap (alphabetical)
+    /// order. Empty memory renders as "".
     pub fn to_prompt(&self) -> String {
         self.render(false)
     }
@@ -196,7 +243,7 @@ impl Memory {
             out.push('\n');
             for e in entries {
                 out.push_str("- ");
-                out.push_str(&e.text);
+                out.push_str(&e.prompt_text());
                 if annotate_corrected && e.source == FactSource::Corrected {
                     out.push_str(" [corrected]");
                 }
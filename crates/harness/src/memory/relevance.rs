@@ -0,0 +1,197 @@
+//! Relevance-selected memory for prompts. Rather than render every section
+//! into every call, a caller passes what the call is about (the transcript
+//! window, the item list) and a token budget; entries are scored by lexical
+//! overlap with that text, recency, and `FactSource` rank, and the best ones
+//! that fit are rendered through `ContextAssembler`.
+//!
+//! Vocabulary and user-corrected facts are always included, budget or not:
+//! vocabulary steers spelling of the trade's jargon whatever the text says,
+//! and a correction the model can't see is a correction it will repeat.
+
+use std::collections::BTreeSet;
+
+use crate::context::{approx_tokens, ContextAssembler, ContextSection};
+use crate::memory::{is_internal_section, FactSource, Memory, MemoryEntry, VOCABULARY_SECTION};
+
+/// Default memory budget for one prompt (chars/4 ≈ tokens).
+pub const DEFAULT_MEMORY_BUDGET_TOKENS: usize = 400;
+
+/// Age at which an entry's recency score has halved.
+const RECENCY_HALF_LIFE_SECS: f32 = 30.0 * 24.0 * 3600.0;
+
+/// Overlap dominates: an entry the text mentions beats a fresh one it doesn't.
+const OVERLAP_WEIGHT: f32 = 3.0;
+const RECENCY_WEIGHT: f32 = 1.0;
+const SOURCE_WEIGHT: f32 = 0.5;
+
+/// Too common to say anything about relevance.
+const STOPWORDS: &[&str] = &[
+    "and", "are", "but", "for", "from", "has", "have", "not", "our", "that", "the", "this", "was", "were",
+    "will", "with", "you", "your",
+];
+
+/// Lowercased words of three or more letters, minus stopwords, with a plural
+/// `s` dropped so "drains" meets "drain".
+fn terms(text: &str) -> BTreeSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| w.chars().count() >= 3)
+        .map(|w| {
+            let w = w.to_lowercase();
+            match w.strip_suffix('s') {
+                Some(stem) if stem.chars().count() >= 3 && !stem.ends_with('s') => stem.to_string(),
+                _ => w,
+            }
+        })
+        .filter(|w| !STOPWORDS.contains(&w.as_str()))
+        .collect()
+}
+
+fn pinned(section: &str, entry: &MemoryEntry) -> bool {
+    section == VOCABULARY_SECTION || entry.source == FactSource::Corrected
+}
+
+/// What one entry costs in the rendered prompt.
+fn entry_tokens(entry: &MemoryEntry) -> usize {
+    approx_tokens(&format!("- {}\n", entry.prompt_text()))
+}
+
+fn score(entry: &MemoryEntry, query: &BTreeSet<String>, now: u64) -> f32 {
+    let words = terms(&entry.text);
+    let overlap = if words.is_empty() {
+        0.0
+    } else {
+        words.intersection(query).count() as f32 / words.len() as f32
+    };
+    let age = now.saturating_sub(entry.last_touched) as f32;
+    let recency = 0.5f32.powf(age / RECENCY_HALF_LIFE_SECS);
+    let source = entry.source.rank() as f32 / FactSource::Corrected.rank() as f32;
+    OVERLAP_WEIGHT * overlap + RECENCY_WEIGHT * recency + SOURCE_WEIGHT * source
+}
+
+impl Memory {
+    /// The subset of this memory worth showing a call about `query`: every
+    /// vocabulary term and corrected fact, then the highest-scoring other
+    /// entries while they fit in `budget_tokens`. Entries keep their order
+    /// within a section, so the same memory renders the same way from call
+    /// to call. Internal sections never make it in.
+    pub fn relevant_to(&self, query: &str, now: u64, budget_tokens: usize) -> Memory {
+        let query = terms(query);
+        let mut keep: Vec<(&str, usize)> = Vec::new();
+        let mut spent = 0;
+        let mut candidates = Vec::new();
+        for (name, entries) in &self.sections {
+            if is_internal_section(name) {
+                continue;
+            }
+            for (position, entry) in entries.iter().enumerate() {
+                if pinned(name, entry) {
+                    spent += entry_tokens(entry);
+                    keep.push((name, position));
+                } else {
+                    candidates.push((score(entry, &query, now), name.as_str(), position, entry));
+                }
+            }
+        }
+        // Best first; equal scores resolve by section, then position.
+        candidates.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.cmp(b.1)).then(a.2.cmp(&b.2)));
+        for (_, name, position, entry) in candidates {
+            let cost = entry_tokens(entry);
+            if spent + cost <= budget_tokens {
+                spent += cost;
+                keep.push((name, position));
+            }
+        }
+
+        let mut selected = Memory::default();
+        for (name, entries) in &self.sections {
+            let kept: Vec<MemoryEntry> = entries
+                .iter()
+                .enumerate()
+                .filter(|(position, _)| keep.contains(&(name.as_str(), *position)))
+                .map(|(_, entry)| entry.clone())
+                .collect();
+            if !kept.is_empty() {
+                selected.sections.insert(name.clone(), kept);
+            }
+        }
+        selected
+    }
+
+    /// [`Memory::relevant_to`] rendered for prompt injection, one
+    /// `## section` block each like [`Memory::to_prompt`]. Empty when
+    /// nothing is selected.
+    pub fn to_prompt_for(&self, query: &str, now: u64, budget_tokens: usize) -> String {
+        let selected = self.relevant_to(query, now, budget_tokens);
+        let sections: Vec<ContextSection> = selected
+            .sections
+            .iter()
+            .map(|(name, entries)| {
+                let content =
+                    entries.iter().map(|e| format!("- {}", e.prompt_text())).collect::<Vec<_>>().join("\n");
+                // Selection already fit the budget; the assembler only lays out.
+                ContextSection { title: name.clone(), budget_tokens: approx_tokens(&content), content }
+            })
+            .collect();
+        ContextAssembler::assemble(&sections).text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: u64 = 24 * 3600;
+
+    #[test]
+    fn the_budget_goes_to_entries_the_text_is_about() {
+        let now = 100 * DAY;
+        let mut m = Memory::default();
+        m.remember("pricing", "french drains run $45 per linear foot", now - 60 * DAY);
+        m.remember("pricing", "sod is $1.10 per square foot installed", now);
+        m.remember("people", "Dev — framer, prefers morning calls", now);
+
+        let budget = entry_tokens(&m.sections["pricing"][0]);
+        let picked = m.relevant_to("client wants a french drain along the fence", now, budget);
+        assert_eq!(picked.section_texts("pricing"), vec!["french drains run $45 per linear foot"]);
+        assert!(!picked.sections.contains_key("people"), "an unrelated fresh fact loses to a mentioned old one");
+
+        let unrelated = m.relevant_to("nothing in common here", now, budget);
+        assert!(
+            !unrelated.section_texts("pricing").contains(&"french drains run $45 per linear foot"),
+            "with no overlap, recency decides"
+        );
+    }
+
+    #[test]
+    fn vocabulary_and_corrected_facts_are_always_included() {
+        let mut m = Memory::default();
+        m.add_vocabulary_term("sleeper wall", 0, FactSource::Stated);
+        m.remember_from("people", "Dev not Dave", 0, FactSource::Corrected, None);
+        m.remember("people", "likes early starts", 0);
+        m.mark_pack_seeded("landscape:1");
+
+        let picked = m.relevant_to("unrelated", 0, 0);
+        assert_eq!(picked.vocabulary_terms(), vec!["sleeper wall"]);
+        assert_eq!(picked.section_texts("people"), vec!["Dev not Dave"]);
+        assert!(!picked.is_pack_seeded("landscape:1"), "internal sections never reach a prompt");
+    }
+
+    #[test]
+    fn a_memory_within_budget_renders_in_full_and_in_order() {
+        let mut m = Memory::default();
+        m.remember("people", "Dev — framer", 10);
+        m.remember("people", "Ana — tile", 20);
+        m.remember("jobs", "Johnson remodel — active", 10);
+        assert_eq!(
+            m.to_prompt_for("anything", 20, DEFAULT_MEMORY_BUDGET_TOKENS),
+            "## jobs\n- Johnson remodel — active\n\n## people\n- Dev — framer\n- Ana — tile"
+        );
+        assert_eq!(Memory::default().to_prompt_for("anything", 0, DEFAULT_MEMORY_BUDGET_TOKENS), "");
+    }
+
+    #[test]
+    fn terms_drop_short_words_stopwords_and_plurals() {
+        let t = terms("The drains and a French-drain, for Dev's yard");
+        assert_eq!(t.into_iter().collect::<Vec<_>>(), vec!["dev", "drain", "french", "yard"]);
+    }
+}
@@ -1,12 +1,20 @@
 use std::path::PathBuf;
 
 use crate::error::HarnessError;
+use crate::memory::history::MemoryChange;
 use crate::memory::Memory;
 
 /// Persistence seam for [`Memory`]. File-backed in production; swap for tests.
 pub trait MemoryStore: Send + Sync {
     fn load(&self) -> Result<Memory, HarnessError>;
     fn save(&self, memory: &Memory) -> Result<(), HarnessError>;
+
+    /// `save`, saying why. A store that keeps a revision log records
+    /// `change` with the revision; the default just saves.
+    fn save_change(&self, memory: &Memory, change: &MemoryChange) -> Result<(), HarnessError> {
+        let _ = change;
+        self.save(memory)
+    }
 }
 
 /// JSON file store with atomic writes (write to `.tmp`, then rename) and
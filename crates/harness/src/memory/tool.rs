@@ -1,9 +1,13 @@
 use std::sync::{Arc, Mutex};
 
+use serde::Deserialize;
+
 use crate::error::HarnessError;
+use crate::memory::fact::TypedFact;
+use crate::memory::history::MemoryChange;
 use crate::memory::store::MemoryStore;
 use crate::memory::{FactSource, Memory, DEFAULT_WORD_CAP};
-use crate::tool::Tool;
+use crate::tool::{parse_input, Tool};
 
 /// Injectable clock (unix seconds) so tests are deterministic.
 pub type Clock = Arc<dyn Fn() -> u64 + Send + Sync>;
@@ -25,6 +29,8 @@ pub struct UpdateMemoryTool {
     store: Arc<dyn MemoryStore>,
     clock: Clock,
     session: Option<String>,
+    /// Memories besides the user's that the input's `scope` can pick.
+    scopes: Vec<ScopeTarget>,
     /// Word cap enforced after every successful remember (spec §7: memory never
     /// grows unbounded, even between reflections).
     pub word_cap: usize,
@@ -40,7 +46,22 @@ impl UpdateMemoryTool {
         store: Arc<dyn MemoryStore>,
         clock: Clock,
     ) -> Self {
-        UpdateMemoryTool { memory, store, clock, session: None, word_cap: DEFAULT_WORD_CAP }
+        UpdateMemoryTool { memory, store, clock, session: None, scopes: Vec::new(), word_cap: DEFAULT_WORD_CAP }
+    }
+
+    /// Lets the agent write a second memory as `scope: "{name}"` — one kept
+    /// for what `about` describes (say, the job being discussed) rather than
+    /// for the user. Same rules and word cap as the user's memory, which
+    /// stays the default.
+    pub fn with_scope(
+        mut self,
+        name: impl Into<String>,
+        about: impl Into<String>,
+        memory: Arc<Mutex<Memory>>,
+        store: Arc<dyn MemoryStore>,
+    ) -> Self {
+        self.scopes.push(ScopeTarget { name: name.into(), about: about.into(), memory, store });
+        self
     }
 
     /// Tags every fact this tool records with a session id.
@@ -54,6 +75,36 @@ impl UpdateMemoryTool {
     }
 }
 
+/// The `scope` naming the user's own memory.
+const USER_SCOPE: &str = "user";
+
+struct ScopeTarget {
+    name: String,
+    about: String,
+    memory: Arc<Mutex<Memory>>,
+    store: Arc<dyn MemoryStore>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Op {
+    Remember,
+    Forget,
+}
+
+#[derive(Deserialize)]
+struct Input {
+    op: Op,
+    section: String,
+    /// Required unless `fact` is given, which brings its own text.
+    text: Option<String>,
+    fact: Option<TypedFact>,
+    /// Default: inferred.
+    source: Option<FactSource>,
+    /// Default: the user's memory.
+    scope: Option<String>,
+}
+
 #[async_trait::async_trait]
 impl Tool for UpdateMemoryTool {
     fn name(&self) -> &str {
@@ -65,58 +116,89 @@ impl Tool for UpdateMemoryTool {
     }
 
     fn input_schema(&self) -> serde_json::Value {
-        serde_json::json!({
+        let mut schema = serde_json::json!({
             "type": "object",
             "properties": {
                 "op": { "type": "string", "enum": ["remember", "forget"] },
                 "section": { "type": "string", "description": "e.g. vocabulary, people, projects, preferences" },
-                "text": { "type": "string", "description": "one short fact" },
+                "text": { "type": "string", "description": "one short fact; omit when giving `fact`" },
+                "fact": TypedFact::schema(),
                 "source": { "type": "string", "enum": ["stated", "inferred", "corrected"], "description": "how you know this; default inferred" }
             },
-            "required": ["op", "section", "text"]
-        })
+            "required": ["op", "section"]
+        });
+        // Offered only when there is a choice, so the plain tool's schema
+        // (and its cached prefix) is unchanged.
+        if !self.scopes.is_empty() {
+            let names: Vec<&str> =
+                std::iter::once(USER_SCOPE).chain(self.scopes.iter().map(|t| t.name.as_str())).collect();
+            let about: Vec<String> = self.scopes.iter().map(|t| format!("{}: {}", t.name, t.about)).collect();
+            schema["properties"]["scope"] = serde_json::json!({
+                "type": "string",
+                "enum": names,
+                "description": format!(
+                    "whose fact this is; default user. Facts that only matter for one of these go there — {}",
+                    about.join("; ")
+                )
+            });
+        }
+        schema
     }
 
     async fn execute(&self, input: serde_json::Value) -> Result<String, HarnessError> {
-        let op = input["op"].as_str().ok_or_else(|| Self::err("missing 'op'"))?;
-        let section = input["section"].as_str().ok_or_else(|| Self::err("missing 'section'"))?;
+        let Input { op, section, text, fact, source, scope } = parse_input(self.name(), input)?;
+        if fact.as_ref().is_some_and(|f| !f.is_valid()) {
+            return Err(Self::err("fact is missing a required field"));
+        }
+        // A typed fact is stored (and so forgotten) under its summary.
+        let text = match (&fact, text) {
+            (Some(fact), _) => fact.summary(),
+            (None, Some(text)) => text,
+            (None, None) => return Err(Self::err("give `text` or `fact`")),
+        };
+        let (memory, store) = match scope.as_deref() {
+            None | Some(USER_SCOPE) => (&self.memory, &self.store),
+            Some(name) => match self.scopes.iter().find(|t| t.name == name) {
+                Some(target) => (&target.memory, &target.store),
+                None => return Err(Self::err(format!("unknown scope: {name}"))),
+            },
+        };
+        // Replies name a non-default scope so the transcript shows where a fact went.
+        let place = match scope.as_deref() {
+            None | Some(USER_SCOPE) => section.clone(),
+            Some(name) => format!("{section} ({name})"),
+        };
         // Internal (`_`-prefixed) sections are cap/prune-exempt bookkeeping
         // (Plan 15 D5-15: seed markers) — the agent may not touch them, or a
         // forged marker would be immortal.
-        if crate::memory::is_internal_section(section) {
+        if crate::memory::is_internal_section(&section) {
             return Err(Self::err(format!("section '{section}' is internal and cannot be written")));
         }
-        let text = input["text"].as_str().ok_or_else(|| Self::err("missing 'text'"))?;
-        let source = match input.get("source").and_then(|s| s.as_str()) {
-            None => FactSource::Inferred,
-            Some("inferred") => FactSource::Inferred,
-            Some("stated") => FactSource::Stated,
-            Some("corrected") => FactSource::Corrected,
-            Some(other) => return Err(Self::err(format!("unknown source: {other}"))),
-        };
-
         let snapshot = {
-            let mut mem = self.memory.lock().map_err(|_| Self::err("memory lock poisoned"))?;
+            let mut mem = memory.lock().map_err(|_| Self::err("memory lock poisoned"))?;
             match op {
-                "remember" => {
-                    mem.remember_from(section, text, (self.clock)(), source, self.session.clone());
+                Op::Remember => {
+                    let source = source.unwrap_or(FactSource::Inferred);
+                    let session = self.session.clone();
+                    match fact {
+                        Some(fact) => mem.remember_fact(&section, fact, (self.clock)(), source, session),
+                        None => mem.remember_from(&section, &text, (self.clock)(), source, session),
+                    }
                     mem.clamp_to_cap(self.word_cap);
                 }
-                "forget" => {
-                    if !mem.forget(section, text) {
-                        return Err(Self::err(format!("no entry in {section} matching: {text}")));
+                Op::Forget => {
+                    if !mem.forget(&section, &text) {
+                        return Err(Self::err(format!("no entry in {place} matching: {text}")));
                     }
                 }
-                other => return Err(Self::err(format!("unknown op: {other}"))),
             }
             mem.clone()
         };
-        self.store.save(&snapshot)?;
+        store.save_change(&snapshot, &MemoryChange::MemoryTool { session: self.session.clone() })?;
 
         Ok(match op {
-            "remember" => format!("remembered in {section}: {text}"),
-            "forget" => format!("forgot from {section}: {text}"),
-            _ => unreachable!("op validated above"),
+            Op::Remember => format!("remembered in {place}: {text}"),
+            Op::Forget => format!("forgot from {place}: {text}"),
         })
     }
 }
@@ -267,6 +349,76 @@ mod tests {
         assert!(matches!(err, HarnessError::Tool { .. }));
     }
 
+    #[tokio::test]
+    async fn a_scope_routes_the_fact_to_its_own_memory_and_store() {
+        let user_store = SpyStore::new();
+        let job_store = SpyStore::new();
+        let (tool, user_memory) = tool_with(user_store.clone());
+        let job_memory = Arc::new(Mutex::new(Memory::default()));
+        let tool = tool.with_scope("job", "the Johnson patio job", job_memory.clone(), job_store.clone());
+
+        let schema = tool.input_schema();
+        assert_eq!(schema["properties"]["scope"]["enum"], serde_json::json!(["user", "job"]));
+        assert!(schema["properties"]["scope"]["description"].as_str().unwrap().contains("Johnson patio"));
+
+        let input = serde_json::json!({"op": "remember", "section": "access", "text": "gate code 4412", "scope": "job"});
+        let out = tool.execute(input).await.unwrap();
+        assert_eq!(out, "remembered in access (job): gate code 4412");
+        assert_eq!(job_memory.lock().unwrap().section_texts("access"), vec!["gate code 4412"]);
+        assert_eq!(job_store.saved.lock().unwrap().len(), 1);
+        assert!(user_memory.lock().unwrap().sections.is_empty());
+        assert!(user_store.saved.lock().unwrap().is_empty());
+
+        tool.execute(serde_json::json!({"op": "remember", "section": "people", "text": "Dev", "scope": "user"}))
+            .await
+            .unwrap();
+        assert_eq!(user_memory.lock().unwrap().section_texts("people"), vec!["Dev"]);
+
+        let err = tool
+            .execute(serde_json::json!({"op": "remember", "section": "x", "text": "y", "scope": "client"}))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, HarnessError::Tool { .. }));
+    }
+
+    #[tokio::test]
+    async fn a_typed_fact_is_stored_under_its_summary() {
+        let store = SpyStore::new();
+        let (tool, memory) = tool_with(store.clone());
+        let fact = serde_json::json!({"kind": "person", "name": "Dave", "role": "irrigation sub", "phone": "555-0142"});
+        let out = tool
+            .execute(serde_json::json!({"op": "remember", "section": "people", "fact": fact, "source": "stated"}))
+            .await
+            .unwrap();
+        assert_eq!(out, "remembered in people: Dave — irrigation sub — 555-0142");
+        {
+            let m = memory.lock().unwrap();
+            let entry = &m.sections["people"][0];
+            assert_eq!(entry.prompt_text(), "[person] Dave — irrigation sub — 555-0142");
+            assert_eq!(serde_json::to_value(entry.fact.as_ref().unwrap()).unwrap(), fact);
+            assert_eq!(entry.source, FactSource::Stated);
+        }
+
+        let bad = serde_json::json!({"kind": "price", "item": "", "amount_cents": 100});
+        let err = tool
+            .execute(serde_json::json!({"op": "remember", "section": "pricing", "fact": bad}))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, HarnessError::Tool { .. }));
+        let err = tool.execute(serde_json::json!({"op": "remember", "section": "people"})).await.unwrap_err();
+        assert!(matches!(err, HarnessError::Tool { .. }), "text or fact is required");
+
+        tool.execute(serde_json::json!({"op": "forget", "section": "people", "fact": fact})).await.unwrap();
+        assert!(memory.lock().unwrap().section_texts("people").is_empty());
+        assert_eq!(store.saved.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn the_plain_tool_offers_no_scope() {
+        let (tool, _memory) = tool_with(SpyStore::new());
+        assert!(tool.input_schema()["properties"].get("scope").is_none());
+    }
+
     #[tokio::test]
     async fn bad_input_is_a_tool_error() {
         let store = SpyStore::new();
@@ -2,12 +2,17 @@ use std::collections::VecDeque;
 use std::sync::Mutex;
 
 use crate::error::HarnessError;
-use crate::llm::{CompletionRequest, CompletionResponse, LlmProvider};
+use crate::llm::{
+    emit_response_events, CompletionRequest, CompletionResponse, LlmProvider, StreamEvent,
+};
 
 /// Scripted LlmProvider for tests: returns queued responses in order and records every request. Ships in the library so downstream crates can use it.
 pub struct MockProvider {
     responses: Mutex<VecDeque<CompletionResponse>>,
     requests: Mutex<Vec<CompletionRequest>>,
+    /// `stream()` splits each scripted text/tool-input block into deltas of
+    /// this many chars (`None` = one delta per block, like the trait default).
+    stream_chunk_chars: Option<usize>,
 }
 
 impl MockProvider {
@@ -15,9 +20,17 @@ impl MockProvider {
         MockProvider {
             responses: Mutex::new(responses.into()),
             requests: Mutex::new(Vec::new()),
+            stream_chunk_chars: None,
         }
     }
 
+    /// Scripts chunked streams: `stream()` replays every response as
+    /// `chars`-sized deltas, so sinks see several fragments per block.
+    pub fn chunked(mut self, chars: usize) -> Self {
+        self.stream_chunk_chars = Some(chars);
+        self
+    }
+
     pub fn requests(&self) -> Vec<CompletionRequest> {
         self.requests.lock().unwrap().clone()
     }
@@ -33,6 +46,16 @@ impl LlmProvider for MockProvider {
             .pop_front()
             .ok_or_else(|| HarnessError::Provider("mock script exhausted".into()))
     }
+
+    async fn stream(
+        &self,
+        req: CompletionRequest,
+        sink: &(dyn Fn(StreamEvent) + Send + Sync),
+    ) -> Result<CompletionResponse, HarnessError> {
+        let response = self.complete(req).await?;
+        emit_response_events(&response, self.stream_chunk_chars, sink);
+        Ok(response)
+    }
 }
 
 #[cfg(test)]
@@ -44,7 +67,8 @@ mod tests {
         CompletionResponse {
             content: vec![ContentBlock::Text { text: s.into() }],
             stop_reason: StopReason::EndTurn,
-            usage: Usage { input_tokens: 1, output_tokens: 1 },
+            usage: Usage { input_tokens: 1, output_tokens: 1, ..Default::default() },
+            model: None,
         }
     }
 
@@ -57,6 +81,8 @@ mod tests {
             tools: vec![],
             max_tokens: 100,
             tool_choice: None,
+            cache: CacheHints::default(),
+            thinking: None,
         };
         let r1 = mock.complete(req.clone()).await.unwrap();
         let r2 = mock.complete(req.clone()).await.unwrap();
@@ -66,6 +92,32 @@ mod tests {
         assert_eq!(mock.requests()[0].system, "sys");
     }
 
+    #[tokio::test]
+    async fn chunked_stream_splits_blocks_and_returns_the_full_response() {
+        let mock = MockProvider::new(vec![text_response("hello")]).chunked(2);
+        let req = CompletionRequest {
+            system: String::new(),
+            messages: vec![Message::user_text("hi")],
+            tools: vec![],
+            max_tokens: 10,
+            tool_choice: None,
+            cache: CacheHints::default(),
+            thinking: None,
+        };
+        let deltas = Mutex::new(Vec::new());
+        let resp = mock
+            .stream(req, &|e| {
+                if let StreamEvent::TextDelta { text, .. } = e {
+                    deltas.lock().unwrap().push(text);
+                }
+            })
+            .await
+            .unwrap();
+        assert_eq!(deltas.into_inner().unwrap(), vec!["he", "ll", "o"]);
+        assert_eq!(resp.content, vec![ContentBlock::Text { text: "hello".into() }]);
+        assert_eq!(mock.requests().len(), 1);
+    }
+
     #[tokio::test]
     async fn errors_when_script_is_exhausted() {
         let mock = MockProvider::new(vec![]);
@@ -75,6 +127,8 @@ mod tests {
             tools: vec![],
             max_tokens: 1,
             tool_choice: None,
+            cache: CacheHints::default(),
+            thinking: None,
         };
         let err = mock.complete(req).await.unwrap_err();
         assert!(matches!(err, crate::HarnessError::Provider(_)));
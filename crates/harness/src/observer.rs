@@ -0,0 +1,154 @@
+//! Lifecycle hooks for tracing what a run actually did: every request sent,
+//! response received, tool call, and how the run ended. Observers only
+//! watch — they can't alter the run, and every callback defaults to a no-op
+//! so an implementation overrides just what it records.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::error::HarnessError;
+use crate::llm::{CompletionRequest, CompletionResponse, LlmProvider, StreamSink, Usage};
+
+/// One finished tool call, as reported to `AgentObserver::tool_finished`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ToolCallReport<'a> {
+    pub id: &'a str,
+    pub name: &'a str,
+    /// The tool's output, or the error text sent back to the model.
+    pub content: &'a str,
+    pub is_error: bool,
+    pub elapsed: Duration,
+}
+
+/// Callbacks are made inline from the run (tool callbacks from concurrent
+/// calls may interleave) — keep them cheap and never block in them.
+///
+/// `run` names what the run is for ("processing", "live_extraction",
+/// "reflection", ...) so one observer can be shared across pipelines; `turn`
+/// is the 0-based provider call within that run.
+pub trait AgentObserver: Send + Sync {
+    fn request_sent(&self, _run: &str, _turn: usize, _request: &CompletionRequest) {}
+
+    /// `elapsed` covers the whole provider call, streaming included.
+    fn response_received(
+        &self,
+        _run: &str,
+        _turn: usize,
+        _response: &CompletionResponse,
+        _elapsed: Duration,
+    ) {
+    }
+
+    fn tool_started(&self, _run: &str, _turn: usize, _id: &str, _name: &str, _input: &serde_json::Value) {}
+
+    fn tool_finished(&self, _run: &str, _turn: usize, _report: &ToolCallReport<'_>) {}
+
+    /// The run completed; `usage` is its total across every turn.
+    fn run_finished(&self, _run: &str, _usage: &Usage) {}
+
+    /// The run failed; `usage` is what was spent before it did (see
+    /// `RunError::usage`).
+    fn run_aborted(&self, _run: &str, _error: &HarnessError, _usage: &Usage) {}
+}
+
+/// Shared handle the pipelines hold, like `StreamSink`.
+pub type SharedObserver = Arc<dyn AgentObserver>;
+
+/// One provider call (streamed through `sink` when given), reported to
+/// `observer` as a single-turn run. Judging the response's content is the
+/// caller's business: a completed call is `run_finished` even if its tool
+/// block turns out to be unusable.
+pub async fn observed_call(
+    provider: &dyn LlmProvider,
+    request: CompletionRequest,
+    sink: Option<&StreamSink>,
+    observer: Option<&dyn AgentObserver>,
+    run: &str,
+) -> Result<CompletionResponse, HarnessError> {
+    if let Some(observer) = observer {
+        observer.request_sent(run, 0, &request);
+    }
+    let started = Instant::now();
+    let result = match sink {
+        Some(sink) => provider.stream(request, sink.as_ref()).await,
+        None => provider.complete(request).await,
+    };
+    if let Some(observer) = observer {
+        match &result {
+            Ok(response) => {
+                observer.response_received(run, 0, response, started.elapsed());
+                observer.run_finished(run, &response.usage);
+            }
+            Err(e) => observer.run_aborted(run, e, &Usage::default()),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::llm::{ContentBlock, Message, StopReason};
+    use crate::mock::MockProvider;
+
+    /// Records every callback as one line, for asserting on the timeline.
+    #[derive(Default)]
+    pub(crate) struct Timeline(pub Mutex<Vec<String>>);
+
+    impl AgentObserver for Timeline {
+        fn request_sent(&self, run: &str, turn: usize, request: &CompletionRequest) {
+            self.0.lock().unwrap().push(format!("{run}#{turn} request ({} messages)", request.messages.len()));
+        }
+        fn response_received(&self, run: &str, turn: usize, response: &CompletionResponse, _: Duration) {
+            self.0.lock().unwrap().push(format!("{run}#{turn} response {:?}", response.stop_reason));
+        }
+        fn tool_started(&self, run: &str, turn: usize, id: &str, name: &str, _: &serde_json::Value) {
+            self.0.lock().unwrap().push(format!("{run}#{turn} start {name} {id}"));
+        }
+        fn tool_finished(&self, run: &str, turn: usize, report: &ToolCallReport<'_>) {
+            self.0.lock().unwrap().push(format!(
+                "{run}#{turn} finish {} {} error={}",
+                report.name, report.id, report.is_error
+            ));
+        }
+        fn run_finished(&self, run: &str, usage: &Usage) {
+            self.0.lock().unwrap().push(format!("{run} finished {}/{}", usage.input_tokens, usage.output_tokens));
+        }
+        fn run_aborted(&self, run: &str, error: &HarnessError, usage: &Usage) {
+            self.0.lock().unwrap().push(format!("{run} aborted after {} in: {error}", usage.input_tokens));
+        }
+    }
+
+    fn request() -> CompletionRequest {
+        CompletionRequest {
+            system: "sys".into(),
+            messages: vec![Message::user_text("hi")],
+            tools: vec![],
+            max_tokens: 16,
+            tool_choice: None,
+            cache: Default::default(),
+            thinking: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn a_single_call_is_a_one_turn_run() {
+        let provider = MockProvider::new(vec![CompletionResponse {
+            content: vec![ContentBlock::Text { text: "ok".into() }],
+            stop_reason: StopReason::EndTurn,
+            usage: Usage { input_tokens: 3, output_tokens: 1, ..Default::default() },
+            model: None,
+        }]);
+        let timeline = Timeline::default();
+        observed_call(&provider, request(), None, Some(&timeline), "summary").await.unwrap();
+        // Exhausted script: the provider error is the abort reason.
+        observed_call(&provider, request(), None, Some(&timeline), "summary").await.unwrap_err();
+
+        let lines = timeline.0.into_inner().unwrap();
+        assert_eq!(lines[..3], ["summary#0 request (1 messages)", "summary#0 response EndTurn", "summary finished 3/1"]);
+        assert_eq!(lines[3], "summary#0 request (1 messages)");
+        assert!(lines[4].starts_with("summary aborted after 0 in: "), "{}", lines[4]);
+    }
+}
@@ -1,10 +1,19 @@
 use serde::Deserialize;
 
+use crate::batch::{BatchCounts, BatchProvider, BatchResult, BatchState, BatchStatus};
+use crate::context::approx_tokens;
 use crate::error::HarnessError;
+use crate::providers::status_error;
 use crate::llm::{
-    CompletionRequest, CompletionResponse, ContentBlock, LlmProvider, StopReason, Usage,
+    CompletionRequest, CompletionResponse, ContentBlock, LlmProvider, StopReason, StreamEvent,
+    Usage,
 };
 
+/// Whole-call timeout for a streamed request. The client-wide 60 s timeout
+/// covers the full body, which a long streamed summary can legitimately
+/// outlast while still making steady progress.
+const STREAM_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(300);
+
 pub struct AnthropicProvider {
     client: reqwest::Client,
     api_key: String,
@@ -33,18 +42,8 @@ impl AnthropicProvider {
         self.base_url = base_url.trim_end_matches('/').to_string();
         self
     }
-}
-
-#[derive(Deserialize)]
-struct ApiResponse {
-    content: Vec<ContentBlock>,
-    stop_reason: StopReason,
-    usage: Usage,
-}
 
-#[async_trait::async_trait]
-impl LlmProvider for AnthropicProvider {
-    async fn complete(&self, req: CompletionRequest) -> Result<CompletionResponse, HarnessError> {
+    fn request_body(&self, req: &CompletionRequest) -> serde_json::Value {
         let mut body = serde_json::json!({
             "model": self.model,
             "max_tokens": req.max_tokens,
@@ -55,43 +54,496 @@ impl LlmProvider for AnthropicProvider {
         if let Some(name) = &req.tool_choice {
             body["tool_choice"] = serde_json::json!({"type": "tool", "name": name});
         }
+        if let Some(budget) = thinking_budget(req) {
+            body["thinking"] = serde_json::json!({"type": "enabled", "budget_tokens": budget});
+        }
+        let mut breakpoints = 0;
+        if req.cache.tools {
+            if let Some(last) = body["tools"].as_array_mut().and_then(|t| t.last_mut()) {
+                last["cache_control"] = ephemeral();
+                breakpoints += 1;
+            }
+        }
+        let blocks = system_blocks(&req.system, &req.cache.system_prefixes, MAX_BREAKPOINTS - breakpoints);
+        if !blocks.is_empty() {
+            body["system"] = serde_json::Value::Array(blocks);
+        }
+        body
+    }
 
-        let resp = self
-            .client
-            .post(format!("{}/v1/messages", self.base_url))
+    fn post(&self, body: &serde_json::Value) -> reqwest::RequestBuilder {
+        self.request(reqwest::Method::POST, "/v1/messages").json(body)
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        self.client
+            .request(method, format!("{}{path}", self.base_url))
             .header("x-api-key", &self.api_key)
             .header("authorization", format!("Bearer {}", self.api_key))
             .header("anthropic-version", "2023-06-01")
-            .json(&body)
+    }
+
+    /// Sends a batches-endpoint request and returns its body.
+    async fn batch_call(&self, request: reqwest::RequestBuilder) -> Result<String, HarnessError> {
+        let resp = request
             .send()
             .await
-            .map_err(|e| HarnessError::Provider(e.to_string()))?;
+            .map_err(|e| HarnessError::ProviderUnreachable(e.to_string()))?;
+        if !resp.status().is_success() {
+            return Err(status_error(resp).await);
+        }
+        resp.text().await.map_err(|e| HarnessError::ProviderUnreachable(e.to_string()))
+    }
 
-        let status = resp.status();
-        let text = resp
-            .text()
+    fn response(&self, parsed: ApiResponse) -> CompletionResponse {
+        let usage = Usage { thinking_tokens: thinking_tokens(&parsed.content), ..parsed.usage };
+        CompletionResponse {
+            content: parsed.content,
+            stop_reason: parsed.stop_reason,
+            usage,
+            model: Some(parsed.model.unwrap_or_else(|| self.model.clone())),
+        }
+    }
+}
+
+/// The smallest thinking budget the Messages API accepts.
+const MIN_THINKING_BUDGET: u32 = 1024;
+
+/// The thinking budget to send, if any. The API rejects thinking with a
+/// forced tool, a budget under 1024, or one not below `max_tokens` — so a
+/// budget is trimmed to leave at least one output token (a budget cap may
+/// have lowered `max_tokens` since the caller chose it), and thinking is
+/// left off for the call when what remains is too small.
+fn thinking_budget(req: &CompletionRequest) -> Option<u32> {
+    let budget = req.thinking?.min(req.max_tokens.saturating_sub(1));
+    (req.tool_choice.is_none() && budget >= MIN_THINKING_BUDGET).then_some(budget)
+}
+
+/// The API doesn't break thinking out of `output_tokens`; estimate it from
+/// the thinking text returned. A floor: redacted blocks count for nothing,
+/// and models that summarize their thinking bill for more than they show.
+fn thinking_tokens(content: &[ContentBlock]) -> u64 {
+    content
+        .iter()
+        .map(|block| match block {
+            ContentBlock::Thinking { thinking, .. } => approx_tokens(thinking) as u64,
+            _ => 0,
+        })
+        .sum()
+}
+
+/// The Messages API rejects a request with more than four `cache_control`
+/// breakpoints.
+const MAX_BREAKPOINTS: usize = 4;
+
+fn ephemeral() -> serde_json::Value {
+    serde_json::json!({"type": "ephemeral"})
+}
+
+/// Splits `system` into text blocks at the hinted prefix ends, each marked
+/// as a cache breakpoint, plus an unmarked remainder. Offsets that are out
+/// of range or not on a char boundary are dropped; past `budget`, the
+/// latest (longest) prefixes win since they cover the earlier ones. Empty
+/// when there is nothing to mark, so the plain-string form is kept.
+fn system_blocks(system: &str, prefixes: &[usize], budget: usize) -> Vec<serde_json::Value> {
+    let mut ends: Vec<usize> = prefixes
+        .iter()
+        .copied()
+        .filter(|&end| end > 0 && end <= system.len() && system.is_char_boundary(end))
+        .collect();
+    ends.sort_unstable();
+    ends.dedup();
+    let ends = &ends[ends.len().saturating_sub(budget)..];
+    let mut blocks = Vec::new();
+    let mut start = 0;
+    for &end in ends {
+        blocks.push(serde_json::json!({
+            "type": "text",
+            "text": &system[start..end],
+            "cache_control": ephemeral(),
+        }));
+        start = end;
+    }
+    if !blocks.is_empty() && start < system.len() {
+        blocks.push(serde_json::json!({"type": "text", "text": &system[start..]}));
+    }
+    blocks
+}
+
+#[derive(Deserialize)]
+struct ApiResponse {
+    content: Vec<ContentBlock>,
+    stop_reason: StopReason,
+    usage: Usage,
+    /// Only read from batch results, where one provider's batch may in
+    /// principle carry several models; a direct call is the configured one.
+    #[serde(default, skip)]
+    model: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ApiBatch {
+    id: String,
+    processing_status: BatchState,
+    request_counts: BatchCounts,
+}
+
+impl From<ApiBatch> for BatchStatus {
+    fn from(batch: ApiBatch) -> Self {
+        BatchStatus { id: batch.id, state: batch.processing_status, counts: batch.request_counts }
+    }
+}
+
+/// One line of a batch's JSONL results.
+#[derive(Deserialize)]
+struct ApiBatchLine {
+    custom_id: String,
+    result: ApiBatchOutcome,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ApiBatchOutcome {
+    Succeeded { message: ApiBatchMessage },
+    Errored { error: serde_json::Value },
+    Canceled,
+    Expired,
+}
+
+#[derive(Deserialize)]
+struct ApiBatchMessage {
+    #[serde(default)]
+    model: Option<String>,
+    #[serde(flatten)]
+    response: ApiResponse,
+}
+
+/// A content block under construction from `content_block_*` events.
+enum PartialBlock {
+    Text(String),
+    ToolUse { id: String, name: String, json: String },
+    Thinking { thinking: String, signature: String },
+    /// Arrives whole in its `content_block_start`.
+    RedactedThinking(String),
+    /// A block type this crate doesn't know: its start event as sent, plus
+    /// any `input_json_delta`s (as server tool calls stream their input).
+    /// Other deltas are ignored.
+    Unknown { block: serde_json::Value, json: String },
+    /// An index no `content_block_start` has filled (yet).
+    Missing,
+}
+
+/// Folds the Messages API server-sent-event stream into a
+/// `CompletionResponse`, forwarding deltas to the sink as it goes.
+#[derive(Default)]
+struct StreamAssembler {
+    blocks: Vec<PartialBlock>,
+    stop_reason: Option<StopReason>,
+    usage: Usage,
+}
+
+impl StreamAssembler {
+    fn block_mut(&mut self, index: usize) -> &mut PartialBlock {
+        if self.blocks.len() <= index {
+            self.blocks.resize_with(index + 1, || PartialBlock::Missing);
+        }
+        &mut self.blocks[index]
+    }
+
+    /// Applies one event's `data:` payload. An `error` event (e.g. overloaded
+    /// mid-stream) fails the whole call — a half-built response is never
+    /// returned as if it were complete.
+    fn apply(
+        &mut self,
+        data: &str,
+        sink: &(dyn Fn(StreamEvent) + Send + Sync),
+    ) -> Result<(), HarnessError> {
+        let event: serde_json::Value = serde_json::from_str(data)
+            .map_err(|e| HarnessError::Provider(format!("bad stream event: {e}: {data}")))?;
+        let index = event["index"].as_u64().unwrap_or(0) as usize;
+        match event["type"].as_str().unwrap_or_default() {
+            "message_start" => {
+                let usage = &event["message"]["usage"];
+                self.usage.input_tokens = usage["input_tokens"].as_u64().unwrap_or(0);
+                self.usage.output_tokens = usage["output_tokens"].as_u64().unwrap_or(0);
+                self.usage.cache_creation_input_tokens =
+                    usage["cache_creation_input_tokens"].as_u64().unwrap_or(0);
+                self.usage.cache_read_input_tokens =
+                    usage["cache_read_input_tokens"].as_u64().unwrap_or(0);
+            }
+            "content_block_start" => {
+                let block = &event["content_block"];
+                let partial = match block["type"].as_str().unwrap_or_default() {
+                    "text" => {
+                        let text = block["text"].as_str().unwrap_or_default().to_string();
+                        if !text.is_empty() {
+                            sink(StreamEvent::TextDelta { index, text: text.clone() });
+                        }
+                        PartialBlock::Text(text)
+                    }
+                    "tool_use" => {
+                        let id = block["id"].as_str().unwrap_or_default().to_string();
+                        let name = block["name"].as_str().unwrap_or_default().to_string();
+                        sink(StreamEvent::ToolUseStart { index, id: id.clone(), name: name.clone() });
+                        PartialBlock::ToolUse { id, name, json: String::new() }
+                    }
+                    "thinking" => PartialBlock::Thinking {
+                        thinking: block["thinking"].as_str().unwrap_or_default().to_string(),
+                        signature: block["signature"].as_str().unwrap_or_default().to_string(),
+                    },
+                    "redacted_thinking" => {
+                        PartialBlock::RedactedThinking(block["data"].as_str().unwrap_or_default().to_string())
+                    }
+                    _ => PartialBlock::Unknown { block: block.clone(), json: String::new() },
+                };
+                *self.block_mut(index) = partial;
+            }
+            "content_block_delta" => {
+                let delta = &event["delta"];
+                match (self.block_mut(index), delta["type"].as_str().unwrap_or_default()) {
+                    (PartialBlock::Text(text), "text_delta") => {
+                        let piece = delta["text"].as_str().unwrap_or_default();
+                        text.push_str(piece);
+                        sink(StreamEvent::TextDelta { index, text: piece.to_string() });
+                    }
+                    (PartialBlock::ToolUse { json, .. }, "input_json_delta") => {
+                        let piece = delta["partial_json"].as_str().unwrap_or_default();
+                        json.push_str(piece);
+                        sink(StreamEvent::ToolInputDelta { index, partial_json: piece.to_string() });
+                    }
+                    (PartialBlock::Thinking { thinking, .. }, "thinking_delta") => {
+                        thinking.push_str(delta["thinking"].as_str().unwrap_or_default());
+                    }
+                    (PartialBlock::Thinking { signature, .. }, "signature_delta") => {
+                        signature.push_str(delta["signature"].as_str().unwrap_or_default());
+                    }
+                    (PartialBlock::Unknown { json, .. }, "input_json_delta") => {
+                        json.push_str(delta["partial_json"].as_str().unwrap_or_default());
+                    }
+                    _ => {}
+                }
+            }
+            "message_delta" => {
+                if let Some(reason) = event["delta"].get("stop_reason").filter(|r| !r.is_null()) {
+                    self.stop_reason = Some(
+                        serde_json::from_value(reason.clone()).unwrap_or(StopReason::Unknown),
+                    );
+                }
+                // message_delta usage is cumulative for the call, not a delta.
+                let usage = &event["usage"];
+                if let Some(out) = usage["output_tokens"].as_u64() {
+                    self.usage.output_tokens = out;
+                }
+                if let Some(input) = usage["input_tokens"].as_u64() {
+                    self.usage.input_tokens = input;
+                }
+                if let Some(created) = usage["cache_creation_input_tokens"].as_u64() {
+                    self.usage.cache_creation_input_tokens = created;
+                }
+                if let Some(read) = usage["cache_read_input_tokens"].as_u64() {
+                    self.usage.cache_read_input_tokens = read;
+                }
+            }
+            "error" => {
+                // Same taxonomy as a pre-stream HTTP error, so an overload
+                // that lands after the 200 is classified (and retried) alike.
+                let status = match event["error"]["type"].as_str().unwrap_or_default() {
+                    "invalid_request_error" => 400,
+                    "authentication_error" => 401,
+                    "permission_error" => 403,
+                    "not_found_error" => 404,
+                    "request_too_large" => 413,
+                    "rate_limit_error" => 429,
+                    "overloaded_error" => 529,
+                    _ => 500,
+                };
+                return Err(HarnessError::ProviderStatus {
+                    status,
+                    retry_after: None,
+                    body: event["error"].to_string(),
+                });
+            }
+            // ping, content_block_stop, message_stop, and future event types.
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn finish(
+        self,
+        sink: &(dyn Fn(StreamEvent) + Send + Sync),
+    ) -> Result<CompletionResponse, HarnessError> {
+        let stop_reason = self.stop_reason.ok_or_else(|| {
+            HarnessError::Provider("stream ended before the message completed".into())
+        })?;
+        let content = self
+            .blocks
+            .into_iter()
+            .filter(|b| !matches!(b, PartialBlock::Missing))
+            .map(|b| match b {
+                PartialBlock::Text(text) => Ok(ContentBlock::Text { text }),
+                PartialBlock::ToolUse { id, name, json } => {
+                    // A tool with no input parameters streams no deltas at all.
+                    let input = if json.trim().is_empty() {
+                        serde_json::json!({})
+                    } else {
+                        serde_json::from_str(&json).map_err(|e| {
+                            HarnessError::Provider(format!("bad tool input json: {e}: {json}"))
+                        })?
+                    };
+                    Ok(ContentBlock::ToolUse { id, name, input })
+                }
+                PartialBlock::Thinking { thinking, signature } => Ok(ContentBlock::Thinking { thinking, signature }),
+                PartialBlock::RedactedThinking(data) => Ok(ContentBlock::RedactedThinking { data }),
+                PartialBlock::Unknown { mut block, json } => {
+                    if !json.trim().is_empty() {
+                        block["input"] = serde_json::from_str(&json).map_err(|e| {
+                            HarnessError::Provider(format!("bad block input json: {e}: {json}"))
+                        })?;
+                    }
+                    Ok(ContentBlock::Unknown(block))
+                }
+                PartialBlock::Missing => unreachable!("filtered above"),
+            })
+            .collect::<Result<Vec<_>, HarnessError>>()?;
+        let usage = Usage { thinking_tokens: thinking_tokens(&content), ..self.usage };
+        sink(StreamEvent::Usage(usage));
+        Ok(CompletionResponse { content, stop_reason, usage, model: None })
+    }
+}
+
+/// Joins the `data:` lines of one SSE event (`None` for comment/keep-alive
+/// events that carry no data).
+fn sse_data(event: &str) -> Option<String> {
+    let lines: Vec<&str> = event
+        .lines()
+        .filter_map(|l| l.strip_prefix("data:"))
+        .map(|l| l.strip_prefix(' ').unwrap_or(l))
+        .collect();
+    (!lines.is_empty()).then(|| lines.join("\n"))
+}
+
+#[async_trait::async_trait]
+impl LlmProvider for AnthropicProvider {
+    async fn complete(&self, req: CompletionRequest) -> Result<CompletionResponse, HarnessError> {
+        let body = self.request_body(&req);
+        let resp = self
+            .post(&body)
+            .send()
             .await
-            .map_err(|e| HarnessError::Provider(e.to_string()))?;
+            .map_err(|e| HarnessError::ProviderUnreachable(e.to_string()))?;
 
-        if !status.is_success() {
-            return Err(HarnessError::Provider(format!("HTTP {status}: {text}")));
+        if !resp.status().is_success() {
+            return Err(status_error(resp).await);
         }
+        let text = resp
+            .text()
+            .await
+            .map_err(|e| HarnessError::ProviderUnreachable(e.to_string()))?;
 
         let parsed: ApiResponse = serde_json::from_str(&text)
             .map_err(|e| HarnessError::Provider(format!("bad response body: {e}: {text}")))?;
+        Ok(self.response(parsed))
+    }
 
-        Ok(CompletionResponse {
-            content: parsed.content,
-            stop_reason: parsed.stop_reason,
-            usage: parsed.usage,
-        })
+    async fn stream(
+        &self,
+        req: CompletionRequest,
+        sink: &(dyn Fn(StreamEvent) + Send + Sync),
+    ) -> Result<CompletionResponse, HarnessError> {
+        let mut body = self.request_body(&req);
+        body["stream"] = serde_json::json!(true);
+        let mut resp = self
+            .post(&body)
+            .timeout(STREAM_TIMEOUT)
+            .send()
+            .await
+            .map_err(|e| HarnessError::ProviderUnreachable(e.to_string()))?;
+
+        if !resp.status().is_success() {
+            // Errors before the stream opens come back as a plain JSON body.
+            return Err(status_error(resp).await);
+        }
+
+        // Buffer raw bytes, not a String: a chunk boundary can split a UTF-8
+        // sequence, and `\n\n` never occurs inside one.
+        let mut assembler = StreamAssembler::default();
+        let mut buf: Vec<u8> = Vec::new();
+        while let Some(chunk) = resp
+            .chunk()
+            .await
+            .map_err(|e| HarnessError::ProviderUnreachable(e.to_string()))?
+        {
+            buf.extend(chunk.iter().filter(|b| **b != b'\r'));
+            while let Some(end) = buf.windows(2).position(|w| w == b"\n\n") {
+                let raw: Vec<u8> = buf.drain(..end + 2).collect();
+                if let Some(data) = sse_data(&String::from_utf8_lossy(&raw)) {
+                    assembler.apply(&data, sink)?;
+                }
+            }
+        }
+        let response = assembler.finish(sink)?;
+        Ok(CompletionResponse { model: Some(self.model.clone()), ..response })
+    }
+}
+
+#[async_trait::async_trait]
+impl BatchProvider for AnthropicProvider {
+    async fn submit_batch(&self, requests: Vec<(String, CompletionRequest)>) -> Result<BatchStatus, HarnessError> {
+        let requests: Vec<serde_json::Value> = requests
+            .iter()
+            .map(|(custom_id, req)| serde_json::json!({"custom_id": custom_id, "params": self.request_body(req)}))
+            .collect();
+        let body = serde_json::json!({ "requests": requests });
+        let text = self
+            .batch_call(self.request(reqwest::Method::POST, "/v1/messages/batches").json(&body))
+            .await?;
+        let batch: ApiBatch = serde_json::from_str(&text)
+            .map_err(|e| HarnessError::Provider(format!("bad batch body: {e}: {text}")))?;
+        Ok(batch.into())
+    }
+
+    async fn batch_status(&self, batch_id: &str) -> Result<BatchStatus, HarnessError> {
+        let path = format!("/v1/messages/batches/{batch_id}");
+        let text = self.batch_call(self.request(reqwest::Method::GET, &path)).await?;
+        let batch: ApiBatch = serde_json::from_str(&text)
+            .map_err(|e| HarnessError::Provider(format!("bad batch body: {e}: {text}")))?;
+        Ok(batch.into())
+    }
+
+    async fn batch_results(&self, batch_id: &str) -> Result<Vec<BatchResult>, HarnessError> {
+        let path = format!("/v1/messages/batches/{batch_id}/results");
+        let text = self.batch_call(self.request(reqwest::Method::GET, &path)).await?;
+        text.lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                let line: ApiBatchLine = serde_json::from_str(line)
+                    .map_err(|e| HarnessError::Provider(format!("bad batch result: {e}: {line}")))?;
+                let response = match line.result {
+                    ApiBatchOutcome::Succeeded { message } => {
+                        Ok(self.response(ApiResponse { model: message.model, ..message.response }))
+                    }
+                    ApiBatchOutcome::Errored { error } => {
+                        let message = error["error"]["message"].as_str().map_or_else(|| error.to_string(), str::to_string);
+                        Err(HarnessError::Provider(format!("batch request errored: {message}")))
+                    }
+                    ApiBatchOutcome::Canceled => Err(HarnessError::Provider("batch request canceled".into())),
+                    ApiBatchOutcome::Expired => {
+                        Err(HarnessError::Provider("batch request expired before it ran".into()))
+                    }
+                };
+                Ok(BatchResult { custom_id: line.custom_id, response })
+            })
+            .collect()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::batch::*;
     use crate::llm::*;
+    use crate::HarnessError;
     use wiremock::matchers::{header, method, path};
     use wiremock::{Mock, MockServer, ResponseTemplate};
 
@@ -106,6 +558,8 @@ mod tests {
             }],
             max_tokens: 256,
             tool_choice: None,
+            cache: CacheHints::default(),
+            thinking: None,
         }
     }
 
@@ -133,7 +587,7 @@ mod tests {
         let resp = provider.complete(request()).await.unwrap();
 
         assert_eq!(resp.stop_reason, StopReason::ToolUse);
-        assert_eq!(resp.usage, Usage { input_tokens: 42, output_tokens: 7 });
+        assert_eq!(resp.usage, Usage { input_tokens: 42, output_tokens: 7, ..Default::default() });
         assert_eq!(resp.content.len(), 2);
         assert!(matches!(&resp.content[1], ContentBlock::ToolUse { name, .. } if name == "echo"));
 
@@ -163,14 +617,50 @@ mod tests {
             AnthropicProvider::new("bad-key", "claude-haiku-4-5-20251001").with_base_url(server.uri());
         let err = provider.complete(request()).await.unwrap_err();
         match err {
-            crate::HarnessError::Provider(msg) => {
-                assert!(msg.contains("401"));
-                assert!(msg.contains("invalid x-api-key"));
+            crate::HarnessError::ProviderStatus { status, retry_after, body } => {
+                assert_eq!(status, 401);
+                assert_eq!(retry_after, None);
+                assert!(body.contains("invalid x-api-key"));
             }
             other => panic!("wrong error: {other:?}"),
         }
     }
 
+    #[tokio::test]
+    async fn rate_limit_keeps_the_retry_after_hint() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/messages"))
+            .respond_with(
+                ResponseTemplate::new(429)
+                    .insert_header("retry-after", "7")
+                    .set_body_json(serde_json::json!({
+                        "type": "error",
+                        "error": {"type": "rate_limit_error", "message": "slow down"}
+                    })),
+            )
+            .mount(&server)
+            .await;
+
+        let provider =
+            AnthropicProvider::new("sk-test", "claude-haiku-4-5-20251001").with_base_url(server.uri());
+        let err = provider.complete(request()).await.unwrap_err();
+        assert!(matches!(
+            err,
+            crate::HarnessError::ProviderStatus { status: 429, retry_after: Some(d), .. }
+                if d == std::time::Duration::from_secs(7)
+        ));
+    }
+
+    #[tokio::test]
+    async fn connection_failure_is_unreachable_not_a_status() {
+        // Nothing listens on the discard port.
+        let provider =
+            AnthropicProvider::new("sk-test", "claude-haiku-4-5-20251001").with_base_url("http://127.0.0.1:9");
+        let err = provider.complete(request()).await.unwrap_err();
+        assert!(matches!(err, crate::HarnessError::ProviderUnreachable(_)), "{err:?}");
+    }
+
     #[tokio::test]
     async fn sends_bearer_and_x_api_key_for_ppq_compat() {
         let server = MockServer::start().await;
@@ -211,6 +701,189 @@ mod tests {
         provider.complete(request()).await.unwrap();
     }
 
+    fn sse(events: &[serde_json::Value]) -> String {
+        events
+            .iter()
+            .map(|e| format!("event: {}\ndata: {e}\n\n", e["type"].as_str().unwrap()))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn stream_parses_sse_deltas_into_events_and_full_response() {
+        let server = MockServer::start().await;
+        let body = sse(&[
+            serde_json::json!({"type": "message_start", "message": {"usage": {"input_tokens": 42, "output_tokens": 1}}}),
+            serde_json::json!({"type": "content_block_start", "index": 0, "content_block": {"type": "text", "text": ""}}),
+            serde_json::json!({"type": "ping"}),
+            serde_json::json!({"type": "content_block_delta", "index": 0, "delta": {"type": "text_delta", "text": "hi "}}),
+            serde_json::json!({"type": "content_block_delta", "index": 0, "delta": {"type": "text_delta", "text": "there"}}),
+            serde_json::json!({"type": "content_block_stop", "index": 0}),
+            serde_json::json!({"type": "content_block_start", "index": 1, "content_block": {"type": "tool_use", "id": "tu_9", "name": "echo", "input": {}}}),
+            serde_json::json!({"type": "content_block_delta", "index": 1, "delta": {"type": "input_json_delta", "partial_json": "{\"text\": "}}),
+            serde_json::json!({"type": "content_block_delta", "index": 1, "delta": {"type": "input_json_delta", "partial_json": "\"x\"}"}}),
+            serde_json::json!({"type": "content_block_stop", "index": 1}),
+            serde_json::json!({"type": "message_delta", "delta": {"stop_reason": "tool_use"}, "usage": {"output_tokens": 7}}),
+            serde_json::json!({"type": "message_stop"}),
+        ]);
+        Mock::given(method("POST"))
+            .and(path("/v1/messages"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(body, "text/event-stream"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let provider = AnthropicProvider::new("sk-test", "claude-haiku-4-5-20251001")
+            .with_base_url(server.uri());
+        let events = std::sync::Mutex::new(Vec::new());
+        let resp = provider
+            .stream(request(), &|e| events.lock().unwrap().push(e))
+            .await
+            .unwrap();
+
+        assert_eq!(resp.stop_reason, StopReason::ToolUse);
+        assert_eq!(resp.usage, Usage { input_tokens: 42, output_tokens: 7, ..Default::default() });
+        assert_eq!(resp.content[0], ContentBlock::Text { text: "hi there".into() });
+        assert_eq!(
+            resp.content[1],
+            ContentBlock::ToolUse {
+                id: "tu_9".into(),
+                name: "echo".into(),
+                input: serde_json::json!({"text": "x"}),
+            }
+        );
+        let events = events.into_inner().unwrap();
+        assert_eq!(events[0], StreamEvent::TextDelta { index: 0, text: "hi ".into() });
+        assert_eq!(
+            events[2],
+            StreamEvent::ToolUseStart { index: 1, id: "tu_9".into(), name: "echo".into() }
+        );
+        assert_eq!(
+            events.last().unwrap(),
+            &StreamEvent::Usage(Usage { input_tokens: 42, output_tokens: 7, ..Default::default() })
+        );
+
+        let received = &server.received_requests().await.unwrap()[0];
+        let body: serde_json::Value = serde_json::from_slice(&received.body).unwrap();
+        assert_eq!(body["stream"], true);
+        assert_eq!(body["system"], "sys");
+    }
+
+    #[test]
+    fn thinking_is_sent_only_when_the_api_would_accept_it() {
+        let provider = AnthropicProvider::new("sk-test", "claude-sonnet-4-5");
+        let mut req = CompletionRequest { max_tokens: 8000, thinking: Some(4000), ..request() };
+        assert_eq!(
+            provider.request_body(&req)["thinking"],
+            serde_json::json!({"type": "enabled", "budget_tokens": 4000})
+        );
+        // A budget cap lowered max_tokens under the budget: trimmed to fit.
+        req.max_tokens = 3000;
+        assert_eq!(provider.request_body(&req)["thinking"]["budget_tokens"], 2999);
+        // Too little left to think with, or a forced tool: off for the call.
+        req.max_tokens = 1000;
+        assert!(provider.request_body(&req).get("thinking").is_none());
+        let forced = CompletionRequest { max_tokens: 8000, thinking: Some(4000), tool_choice: Some("echo".into()), ..request() };
+        assert!(provider.request_body(&forced).get("thinking").is_none());
+        assert!(provider.request_body(&request()).get("thinking").is_none());
+    }
+
+    #[tokio::test]
+    async fn thinking_blocks_parse_from_json_and_stream_with_estimated_tokens() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(wiremock::matchers::body_partial_json(serde_json::json!({"stream": true})))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(
+                sse(&[
+                    serde_json::json!({"type": "message_start", "message": {"usage": {"input_tokens": 42, "output_tokens": 1}}}),
+                    serde_json::json!({"type": "content_block_start", "index": 0, "content_block": {"type": "thinking", "thinking": "", "signature": ""}}),
+                    serde_json::json!({"type": "content_block_delta", "index": 0, "delta": {"type": "thinking_delta", "thinking": "two todos, "}}),
+                    serde_json::json!({"type": "content_block_delta", "index": 0, "delta": {"type": "thinking_delta", "thinking": "one price"}}),
+                    serde_json::json!({"type": "content_block_delta", "index": 0, "delta": {"type": "signature_delta", "signature": "EqQBCgIYAhIM"}}),
+                    serde_json::json!({"type": "content_block_start", "index": 1, "content_block": {"type": "redacted_thinking", "data": "opaque"}}),
+                    serde_json::json!({"type": "content_block_start", "index": 2, "content_block": {"type": "server_tool_use", "id": "srv_1", "name": "web_search", "input": {}}}),
+                    serde_json::json!({"type": "content_block_delta", "index": 2, "delta": {"type": "input_json_delta", "partial_json": "{\"query\": \"mulch\"}"}}),
+                    serde_json::json!({"type": "message_delta", "delta": {"stop_reason": "end_turn"}, "usage": {"output_tokens": 30}}),
+                ]),
+                "text/event-stream",
+            ))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "content": [
+                    {"type": "thinking", "thinking": "two todos, one price", "signature": "EqQBCgIYAhIM"},
+                    {"type": "redacted_thinking", "data": "opaque"},
+                    {"type": "server_tool_use", "id": "srv_1", "name": "web_search", "input": {"query": "mulch"}}
+                ],
+                "stop_reason": "end_turn",
+                "usage": {"input_tokens": 42, "output_tokens": 30}
+            })))
+            .mount(&server)
+            .await;
+
+        let provider = AnthropicProvider::new("sk-test", "claude-sonnet-4-5").with_base_url(server.uri());
+        let expected = vec![
+            ContentBlock::Thinking { thinking: "two todos, one price".into(), signature: "EqQBCgIYAhIM".into() },
+            ContentBlock::RedactedThinking { data: "opaque".into() },
+            ContentBlock::Unknown(serde_json::json!(
+                {"type": "server_tool_use", "id": "srv_1", "name": "web_search", "input": {"query": "mulch"}}
+            )),
+        ];
+        let usage = Usage { input_tokens: 42, output_tokens: 30, thinking_tokens: 5, ..Default::default() };
+        let json = provider.complete(request()).await.unwrap();
+        assert_eq!((&json.content, json.usage), (&expected, usage));
+        let streamed = provider.stream(request(), &|_| {}).await.unwrap();
+        assert_eq!((&streamed.content, streamed.usage), (&expected, usage));
+    }
+
+    #[tokio::test]
+    async fn stream_error_event_and_truncated_stream_are_provider_errors() {
+        let server = MockServer::start().await;
+        let overloaded = sse(&[
+            serde_json::json!({"type": "message_start", "message": {"usage": {"input_tokens": 5, "output_tokens": 1}}}),
+            serde_json::json!({"type": "error", "error": {"type": "overloaded_error", "message": "Overloaded"}}),
+        ]);
+        let truncated = sse(&[
+            serde_json::json!({"type": "message_start", "message": {"usage": {"input_tokens": 5, "output_tokens": 1}}}),
+            serde_json::json!({"type": "content_block_start", "index": 0, "content_block": {"type": "text", "text": ""}}),
+        ]);
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(overloaded, "text/event-stream"))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(truncated, "text/event-stream"))
+            .mount(&server)
+            .await;
+
+        let provider = AnthropicProvider::new("sk-test", "claude-haiku-4-5")
+            .with_base_url(server.uri());
+        let err = provider.stream(request(), &|_| {}).await.unwrap_err();
+        assert!(matches!(
+            err,
+            HarnessError::ProviderStatus { status: 529, body, .. } if body.contains("overloaded_error")
+        ));
+        let err = provider.stream(request(), &|_| {}).await.unwrap_err();
+        assert!(matches!(err, HarnessError::Provider(msg) if msg.contains("before the message completed")));
+    }
+
+    #[tokio::test]
+    async fn stream_http_error_maps_to_provider_error_with_body() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(401).set_body_json(serde_json::json!({
+                "type": "error",
+                "error": {"type": "authentication_error", "message": "invalid x-api-key"}
+            })))
+            .mount(&server)
+            .await;
+        let provider = AnthropicProvider::new("bad-key", "claude-haiku-4-5")
+            .with_base_url(server.uri());
+        let err = provider.stream(request(), &|_| {}).await.unwrap_err();
+        assert!(matches!(err, HarnessError::ProviderStatus { status: 401, .. }));
+    }
+
     #[tokio::test]
     async fn forced_tool_choice_is_serialized_and_absent_when_none() {
         let server = MockServer::start().await;
@@ -241,4 +914,212 @@ mod tests {
         let body1: serde_json::Value = serde_json::from_slice(&received[1].body).unwrap();
         assert!(body1.get("tool_choice").is_none());
     }
+
+    #[test]
+    fn cache_hints_become_cache_control_breakpoints() {
+        let provider = AnthropicProvider::new("sk-test", "claude-haiku-4-5-20251001");
+        let mut req = request();
+        req.system = "fixed rules\n\nmemory".into();
+        // 11 = end of "fixed rules"; 100 is out of range, 0 is empty.
+        req.cache = CacheHints { tools: true, system_prefixes: vec![11, 100, 0, 11] };
+        let body = provider.request_body(&req);
+
+        assert_eq!(body["tools"][0]["cache_control"], serde_json::json!({"type": "ephemeral"}));
+        assert_eq!(
+            body["system"],
+            serde_json::json!([
+                {"type": "text", "text": "fixed rules", "cache_control": {"type": "ephemeral"}},
+                {"type": "text", "text": "\n\nmemory"},
+            ])
+        );
+
+        // No hints: the plain-string form, no markers anywhere.
+        let body = provider.request_body(&request());
+        assert_eq!(body["system"], "sys");
+        assert!(body["tools"][0].get("cache_control").is_none());
+    }
+
+    #[test]
+    fn system_breakpoints_are_capped_keeping_the_longest_prefixes() {
+        let blocks = system_blocks("abcdef", &[1, 2, 3, 4, 5], 3);
+        let marked: Vec<&str> = blocks
+            .iter()
+            .filter(|b| b.get("cache_control").is_some())
+            .map(|b| b["text"].as_str().unwrap())
+            .collect();
+        // The first block covers everything up to the earliest kept end.
+        assert_eq!(marked, ["abc", "d", "e"]);
+        assert_eq!(blocks.last().unwrap()["text"], "f");
+        assert!(system_blocks("héllo", &[2], 4).is_empty(), "mid-char offset is dropped");
+    }
+
+    #[tokio::test]
+    async fn image_blocks_are_sent_as_base64_sources() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/messages"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "content": [{"type": "text", "text": "a ladder"}],
+                "stop_reason": "end_turn",
+                "usage": {"input_tokens": 1500, "output_tokens": 4}
+            })))
+            .mount(&server)
+            .await;
+
+        let provider = AnthropicProvider::new("sk-test", "claude-haiku-4-5-20251001")
+            .with_base_url(server.uri());
+        let mut req = request();
+        req.messages[0].content.push(ContentBlock::image("image/jpeg", b"jpeg"));
+        provider.complete(req).await.unwrap();
+
+        let received = &server.received_requests().await.unwrap()[0];
+        let body: serde_json::Value = serde_json::from_slice(&received.body).unwrap();
+        assert_eq!(
+            body["messages"][0]["content"][1],
+            serde_json::json!({
+                "type": "image",
+                "source": {"type": "base64", "media_type": "image/jpeg", "data": "anBlZw=="},
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn batch_lifecycle_submits_polls_and_fetches_results() {
+        let server = MockServer::start().await;
+        let batch = |status: &str, processing: u32, succeeded: u32| {
+            serde_json::json!({
+                "id": "msgbatch_1",
+                "type": "message_batch",
+                "processing_status": status,
+                "request_counts": {"processing": processing, "succeeded": succeeded, "errored": 1, "canceled": 0, "expired": 1},
+            })
+        };
+        Mock::given(method("POST"))
+            .and(path("/v1/messages/batches"))
+            .and(header("x-api-key", "sk-test"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(batch("in_progress", 3, 0)))
+            .expect(1)
+            .mount(&server)
+            .await;
+        // First poll: still running; every later one: ended.
+        Mock::given(method("GET"))
+            .and(path("/v1/messages/batches/msgbatch_1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(batch("in_progress", 1, 1)))
+            .up_to_n_times(1)
+            .with_priority(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/v1/messages/batches/msgbatch_1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(batch("ended", 0, 1)))
+            .mount(&server)
+            .await;
+        let results = [
+            serde_json::json!({"custom_id": "a", "result": {"type": "succeeded", "message": {
+                "model": "claude-haiku-4-5-20251001",
+                "content": [{"type": "text", "text": "done"}],
+                "stop_reason": "end_turn",
+                "usage": {"input_tokens": 30, "output_tokens": 2},
+            }}}),
+            serde_json::json!({"custom_id": "b", "result": {"type": "errored", "error": {
+                "type": "error", "error": {"type": "invalid_request_error", "message": "max_tokens too large"},
+            }}}),
+            serde_json::json!({"custom_id": "c", "result": {"type": "expired"}}),
+        ];
+        let jsonl: String = results.iter().map(|r| format!("{r}\n")).collect();
+        Mock::given(method("GET"))
+            .and(path("/v1/messages/batches/msgbatch_1/results"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(jsonl))
+            .mount(&server)
+            .await;
+
+        let provider = AnthropicProvider::new("sk-test", "claude-haiku-4-5-20251001")
+            .with_base_url(server.uri());
+        let requests = ["a", "b", "c"].map(|id| (id.to_string(), request())).to_vec();
+        let submitted = provider.submit_batch(requests).await.unwrap();
+        assert_eq!((submitted.id.as_str(), submitted.state), ("msgbatch_1", BatchState::InProgress));
+        assert_eq!(provider.batch_status("msgbatch_1").await.unwrap().state, BatchState::InProgress);
+        let ended = provider.batch_status("msgbatch_1").await.unwrap();
+        assert_eq!(ended.state, BatchState::Ended);
+        assert_eq!(ended.counts, BatchCounts { processing: 0, succeeded: 1, errored: 1, canceled: 0, expired: 1 });
+
+        let mut results = provider.batch_results("msgbatch_1").await.unwrap();
+        results.sort_by(|x, y| x.custom_id.cmp(&y.custom_id));
+        let done = results[0].response.as_ref().unwrap();
+        assert_eq!(done.content, vec![ContentBlock::Text { text: "done".into() }]);
+        assert_eq!(done.usage, Usage { input_tokens: 30, output_tokens: 2, ..Default::default() });
+        assert_eq!(done.model.as_deref(), Some("claude-haiku-4-5-20251001"));
+        assert!(matches!(&results[1].response, Err(HarnessError::Provider(m)) if m.contains("max_tokens too large")));
+        assert!(matches!(&results[2].response, Err(HarnessError::Provider(m)) if m.contains("expired")));
+
+        let body: serde_json::Value =
+            serde_json::from_slice(&server.received_requests().await.unwrap()[0].body).unwrap();
+        assert_eq!(body["requests"][1]["custom_id"], "b");
+        assert_eq!(body["requests"][1]["params"]["model"], "claude-haiku-4-5-20251001");
+        assert_eq!(body["requests"][1]["params"]["messages"][0]["role"], "user");
+        assert!(body["requests"][1]["params"].get("stream").is_none());
+    }
+
+    #[tokio::test]
+    async fn batch_http_errors_keep_their_status() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v1/messages/batches/msgbatch_gone"))
+            .respond_with(ResponseTemplate::new(404).set_body_string("not found"))
+            .mount(&server)
+            .await;
+        let provider = AnthropicProvider::new("sk-test", "m").with_base_url(server.uri());
+        let err = provider.batch_status("msgbatch_gone").await.unwrap_err();
+        assert!(matches!(err, HarnessError::ProviderStatus { status: 404, .. }));
+    }
+
+    #[tokio::test]
+    async fn cache_usage_is_parsed_from_json_and_stream() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/messages"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "content": [{"type": "text", "text": "ok"}],
+                "stop_reason": "end_turn",
+                "usage": {
+                    "input_tokens": 12,
+                    "output_tokens": 3,
+                    "cache_creation_input_tokens": 0,
+                    "cache_read_input_tokens": 1800
+                }
+            })))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        let body = sse(&[
+            serde_json::json!({"type": "message_start", "message": {"usage": {
+                "input_tokens": 12, "output_tokens": 1,
+                "cache_creation_input_tokens": 1800, "cache_read_input_tokens": 0
+            }}}),
+            serde_json::json!({"type": "content_block_start", "index": 0, "content_block": {"type": "text", "text": "ok"}}),
+            serde_json::json!({"type": "message_delta", "delta": {"stop_reason": "end_turn"}, "usage": {"output_tokens": 3}}),
+            serde_json::json!({"type": "message_stop"}),
+        ]);
+        Mock::given(method("POST"))
+            .and(path("/v1/messages"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(body, "text/event-stream"))
+            .mount(&server)
+            .await;
+
+        let provider = AnthropicProvider::new("sk-test", "claude-haiku-4-5-20251001")
+            .with_base_url(server.uri());
+        let read = provider.complete(request()).await.unwrap().usage;
+        assert_eq!(
+            read,
+            Usage { input_tokens: 12, output_tokens: 3, cache_creation_input_tokens: 0, cache_read_input_tokens: 1800, thinking_tokens: 0 }
+        );
+        let created = provider.stream(request(), &|_| {}).await.unwrap().usage;
+        assert_eq!(
+            created,
+            Usage { input_tokens: 12, output_tokens: 3, cache_creation_input_tokens: 1800, cache_read_input_tokens: 0, thinking_tokens: 0 }
+        );
+        // A cache read bills at a tenth, a write at a quarter more.
+        assert_eq!(read.billable_input_tokens(), 12 + 180);
+        assert_eq!(created.billable_input_tokens(), 12 + 2250);
+    }
 }
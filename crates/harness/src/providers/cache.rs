@@ -0,0 +1,297 @@
+//! A content-addressed response cache for idempotent calls. Reprocessing
+//! the same transcript, rebuilding the same document or re-running an eval
+//! sends byte-identical requests; `CachingProvider` answers a repeat from a
+//! `ResponseCache` instead of paying for it again. Entries are keyed by a
+//! hash of the model and the whole request — system, messages, tools,
+//! `max_tokens`, `tool_choice`, thinking — so any prompt edit is a miss.
+//! Cache hints are left out: they change billing, not the answer.
+//!
+//! A hit reports zero usage, since nothing was billed. `bypass_cache` scopes
+//! a future whose calls skip the lookup (a retry that wants a fresh answer);
+//! what they get back still replaces the stored entry. Only successful calls
+//! are stored.
+
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::HarnessError;
+use crate::llm::{
+    emit_response_events, CompletionRequest, CompletionResponse, ContentBlock, LlmProvider,
+    StopReason, StreamEvent, Usage,
+};
+
+tokio::task_local! {
+    static BYPASS: ();
+}
+
+/// Runs `fut` with its calls going to the provider instead of the cache.
+pub async fn bypass_cache<F: Future>(fut: F) -> F::Output {
+    BYPASS.scope((), fut).await
+}
+
+fn bypassed() -> bool {
+    BYPASS.try_with(|_| ()).is_ok()
+}
+
+/// What a cache stores for one call: the answer, not what it cost.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CachedResponse {
+    pub content: Vec<ContentBlock>,
+    pub stop_reason: StopReason,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+}
+
+impl CachedResponse {
+    fn from_response(response: &CompletionResponse) -> Self {
+        CachedResponse {
+            content: response.content.clone(),
+            stop_reason: response.stop_reason,
+            model: response.model.clone(),
+        }
+    }
+
+    fn into_response(self) -> CompletionResponse {
+        CompletionResponse {
+            content: self.content,
+            stop_reason: self.stop_reason,
+            usage: Usage::default(),
+            model: self.model,
+        }
+    }
+}
+
+/// Where a `CachingProvider` keeps its entries. Each backend enforces its
+/// own `CacheBounds`: an expired entry reads as a miss, and `put` evicts
+/// to stay under the size bound.
+pub trait ResponseCache: Send + Sync {
+    fn get(&self, key: &str) -> Result<Option<CachedResponse>, HarnessError>;
+    fn put(&self, key: &str, response: &CachedResponse) -> Result<(), HarnessError>;
+}
+
+/// Limits for a `ResponseCache` backend; `None` leaves that one unbounded.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CacheBounds {
+    /// How long an entry is served after it was stored.
+    pub ttl: Option<Duration>,
+    /// Total size of the stored entries; the oldest go first.
+    pub max_bytes: Option<u64>,
+}
+
+pub struct CachingProvider {
+    inner: Arc<dyn LlmProvider>,
+    model: String,
+    cache: Arc<dyn ResponseCache>,
+}
+
+impl CachingProvider {
+    /// Caches `inner`'s answers in `cache`. `model` is what `inner` is
+    /// configured to call — part of the key, so two models sharing a cache
+    /// never answer for each other.
+    pub fn new(inner: Arc<dyn LlmProvider>, model: impl Into<String>, cache: Arc<dyn ResponseCache>) -> Self {
+        CachingProvider { inner, model: model.into(), cache }
+    }
+
+    /// The stored answer, unless bypassed. A cache that can't be read is a
+    /// miss, never a failed call.
+    fn lookup(&self, key: &str) -> Option<CompletionResponse> {
+        if bypassed() {
+            return None;
+        }
+        self.cache.get(key).ok().flatten().map(CachedResponse::into_response)
+    }
+
+    /// Best effort: the provider already answered, so a write that fails
+    /// only costs the next call a miss.
+    fn store(&self, key: &str, response: &CompletionResponse) {
+        let _ = self.cache.put(key, &CachedResponse::from_response(response));
+    }
+}
+
+#[async_trait::async_trait]
+impl LlmProvider for CachingProvider {
+    async fn complete(&self, req: CompletionRequest) -> Result<CompletionResponse, HarnessError> {
+        let key = cache_key(&self.model, &req);
+        if let Some(hit) = self.lookup(&key) {
+            return Ok(hit);
+        }
+        let response = self.inner.complete(req).await?;
+        self.store(&key, &response);
+        Ok(response)
+    }
+
+    async fn stream(
+        &self,
+        req: CompletionRequest,
+        sink: &(dyn Fn(StreamEvent) + Send + Sync),
+    ) -> Result<CompletionResponse, HarnessError> {
+        let key = cache_key(&self.model, &req);
+        if let Some(hit) = self.lookup(&key) {
+            emit_response_events(&hit, None, sink);
+            return Ok(hit);
+        }
+        let response = self.inner.stream(req, sink).await?;
+        self.store(&key, &response);
+        Ok(response)
+    }
+}
+
+/// FNV-1a (128-bit) over the canonical JSON of the model and request —
+/// stable across Rust versions and platforms, and `serde_json` maps keep
+/// keys sorted. `thinking` only appears when set.
+fn cache_key(model: &str, request: &CompletionRequest) -> String {
+    let mut canonical = serde_json::json!({
+        "model": model,
+        "system": request.system,
+        "messages": request.messages,
+        "tools": request.tools,
+        "max_tokens": request.max_tokens,
+        "tool_choice": request.tool_choice,
+    });
+    if let Some(budget) = request.thinking {
+        canonical["thinking"] = budget.into();
+    }
+    let mut hash: u128 = 0x6c62_272e_07bb_0142_62b8_2175_6295_c58d;
+    for byte in canonical.to_string().bytes() {
+        hash ^= u128::from(byte);
+        hash = hash.wrapping_mul(0x0000_0000_0100_0000_0000_0000_0000_013b);
+    }
+    format!("{hash:032x}")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::llm::{CacheHints, Message};
+    use crate::mock::MockProvider;
+
+    #[derive(Default)]
+    struct MapCache(Mutex<HashMap<String, CachedResponse>>);
+
+    impl ResponseCache for MapCache {
+        fn get(&self, key: &str) -> Result<Option<CachedResponse>, HarnessError> {
+            Ok(self.0.lock().unwrap().get(key).cloned())
+        }
+        fn put(&self, key: &str, response: &CachedResponse) -> Result<(), HarnessError> {
+            self.0.lock().unwrap().insert(key.to_string(), response.clone());
+            Ok(())
+        }
+    }
+
+    struct BrokenCache;
+
+    impl ResponseCache for BrokenCache {
+        fn get(&self, _: &str) -> Result<Option<CachedResponse>, HarnessError> {
+            Err(HarnessError::Storage("disk gone".into()))
+        }
+        fn put(&self, _: &str, _: &CachedResponse) -> Result<(), HarnessError> {
+            Err(HarnessError::Storage("disk gone".into()))
+        }
+    }
+
+    fn request(text: &str) -> CompletionRequest {
+        CompletionRequest {
+            system: "system".into(),
+            messages: vec![Message::user_text(text)],
+            tools: vec![],
+            max_tokens: 100,
+            tool_choice: None,
+            cache: CacheHints::default(),
+            thinking: None,
+        }
+    }
+
+    fn answer(text: &str) -> CompletionResponse {
+        CompletionResponse {
+            content: vec![ContentBlock::Text { text: text.into() }],
+            stop_reason: StopReason::EndTurn,
+            usage: Usage { input_tokens: 10, output_tokens: 5, ..Default::default() },
+            model: Some("model-a".into()),
+        }
+    }
+
+    #[tokio::test]
+    async fn a_repeated_request_is_served_from_the_cache_for_free() {
+        let inner = Arc::new(MockProvider::new(vec![answer("first")]));
+        let cache = Arc::new(MapCache::default());
+        let provider = CachingProvider::new(inner.clone(), "model-a", cache);
+
+        let fresh = provider.complete(request("hello")).await.unwrap();
+        assert_eq!(fresh.usage.output_tokens, 5);
+
+        // Cache hints don't change the answer, so they don't change the key.
+        let mut hinted = request("hello");
+        hinted.cache.tools = true;
+        let hit = provider.complete(hinted).await.unwrap();
+        assert_eq!(hit.content, fresh.content);
+        assert_eq!(hit.model.as_deref(), Some("model-a"));
+        assert_eq!(hit.usage, Usage::default(), "nothing was billed");
+        assert_eq!(inner.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn another_model_or_request_is_a_miss() {
+        let cache: Arc<MapCache> = Arc::new(MapCache::default());
+        let inner = Arc::new(MockProvider::new(vec![answer("a"), answer("b"), answer("c")]));
+        let a = CachingProvider::new(inner.clone(), "model-a", cache.clone());
+        let b = CachingProvider::new(inner.clone(), "model-b", cache.clone());
+
+        a.complete(request("hello")).await.unwrap();
+        b.complete(request("hello")).await.unwrap();
+        let mut longer = request("hello");
+        longer.max_tokens = 200;
+        a.complete(longer).await.unwrap();
+        assert_eq!(inner.requests().len(), 3);
+        assert_eq!(cache.0.lock().unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn a_bypassed_call_goes_to_the_provider_and_refreshes_the_entry() {
+        let inner = Arc::new(MockProvider::new(vec![answer("stale"), answer("fresh")]));
+        let provider = CachingProvider::new(inner.clone(), "model-a", Arc::new(MapCache::default()));
+
+        provider.complete(request("hello")).await.unwrap();
+        let bypassed = bypass_cache(provider.complete(request("hello"))).await.unwrap();
+        assert_eq!(bypassed.content, answer("fresh").content);
+        let hit = provider.complete(request("hello")).await.unwrap();
+        assert_eq!(hit.content, answer("fresh").content);
+        assert_eq!(inner.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn a_streamed_hit_replays_its_events() {
+        let inner = Arc::new(MockProvider::new(vec![answer("hello there")]));
+        let provider = CachingProvider::new(inner, "model-a", Arc::new(MapCache::default()));
+        provider.complete(request("hello")).await.unwrap();
+
+        let events = Mutex::new(Vec::new());
+        let sink = |e: StreamEvent| events.lock().unwrap().push(e);
+        provider.stream(request("hello"), &sink).await.unwrap();
+        assert_eq!(
+            events.into_inner().unwrap(),
+            vec![
+                StreamEvent::TextDelta { index: 0, text: "hello there".into() },
+                StreamEvent::Usage(Usage::default()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn failures_are_not_stored_and_a_broken_cache_never_fails_a_call() {
+        let inner = Arc::new(MockProvider::new(vec![]));
+        let cache = Arc::new(MapCache::default());
+        let provider = CachingProvider::new(inner, "model-a", cache.clone());
+        assert!(provider.complete(request("hello")).await.is_err());
+        assert!(cache.0.lock().unwrap().is_empty());
+
+        let inner = Arc::new(MockProvider::new(vec![answer("ok")]));
+        let provider = CachingProvider::new(inner, "model-a", Arc::new(BrokenCache));
+        assert_eq!(provider.complete(request("hello")).await.unwrap().content, answer("ok").content);
+    }
+}
@@ -0,0 +1,414 @@
+//! A rate and concurrency limiter shared by every provider that spends
+//! against one API key: requests per minute, input tokens per minute, and
+//! calls in flight. Callers queue in priority lanes, so when the key is
+//! saturated a call someone is waiting on goes ahead of background work.
+//! Lanes follow the task — `with_priority` scopes a future, and every call
+//! made under it takes that lane (`Priority::Normal` outside any scope) —
+//! so one provider shared by several purposes still serves each at its own
+//! priority.
+
+use std::collections::VecDeque;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::sync::Notify;
+use tokio::time::Instant;
+
+use crate::budget::estimate_prompt_tokens;
+use crate::error::HarnessError;
+use crate::llm::{CompletionRequest, CompletionResponse, LlmProvider, StreamEvent};
+
+/// The span `RateLimits`' per-minute limits are counted over, sliding.
+const WINDOW: Duration = Duration::from_secs(60);
+
+/// Limits for one `RateLimiter`; `None` leaves that dimension unlimited.
+/// Set them at or a little under the key's tier so the API's own 429s stay
+/// rare.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct RateLimits {
+    pub requests_per_minute: Option<u32>,
+    /// Counted on the estimated prompt (chars/4) when a call is admitted,
+    /// corrected to the uncached input the provider reports once it answers.
+    pub input_tokens_per_minute: Option<u64>,
+    pub max_in_flight: Option<usize>,
+}
+
+/// A call's lane. Waiting calls are admitted highest lane first, in arrival
+/// order within a lane.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Priority {
+    /// Nobody is waiting: reflection, retries, reprocessing.
+    Background,
+    #[default]
+    Normal,
+    /// Someone is looking at a spinner: building a document, finishing a walk.
+    Interactive,
+}
+
+impl Priority {
+    const LANES: usize = 3;
+
+    fn lane(self) -> usize {
+        self as usize
+    }
+}
+
+tokio::task_local! {
+    static PRIORITY: Priority;
+}
+
+/// Runs `fut` with its provider calls in `priority`'s lane.
+pub async fn with_priority<F: Future>(priority: Priority, fut: F) -> F::Output {
+    PRIORITY.scope(priority, fut).await
+}
+
+/// The lane calls made here take.
+pub fn current_priority() -> Priority {
+    PRIORITY.try_with(|p| *p).unwrap_or_default()
+}
+
+#[derive(Default)]
+struct State {
+    in_flight: usize,
+    /// Admission times within the window.
+    requests: VecDeque<Instant>,
+    /// `(admitted at, ticket, input tokens)` within the window.
+    tokens: VecDeque<(Instant, u64, u64)>,
+    /// Waiting tickets per lane, oldest first.
+    waiting: [VecDeque<u64>; Priority::LANES],
+    next_ticket: u64,
+}
+
+impl State {
+    fn prune(&mut self, now: Instant) {
+        while self.requests.front().is_some_and(|at| now.duration_since(*at) >= WINDOW) {
+            self.requests.pop_front();
+        }
+        while self.tokens.front().is_some_and(|(at, _, _)| now.duration_since(*at) >= WINDOW) {
+            self.tokens.pop_front();
+        }
+    }
+
+    /// Whether `ticket` is the next call due: first in its lane, with
+    /// every higher lane empty.
+    fn is_next(&self, lane: usize, ticket: u64) -> bool {
+        self.waiting[lane].front() == Some(&ticket) && self.waiting[lane + 1..].iter().all(VecDeque::is_empty)
+    }
+}
+
+/// Why a call can't be admitted yet.
+enum Blocked {
+    /// Until a call in flight finishes.
+    InFlight,
+    /// Until the window has moved on (or something else changes).
+    Until(Instant),
+}
+
+/// Shared state behind every `LimitedProvider` on one key.
+pub struct RateLimiter {
+    limits: RateLimits,
+    state: Mutex<State>,
+    /// Woken whenever a ticket leaves a queue or a call finishes.
+    changed: Notify,
+}
+
+impl RateLimiter {
+    pub fn new(limits: RateLimits) -> Self {
+        RateLimiter { limits, state: Mutex::new(State::default()), changed: Notify::new() }
+    }
+
+    pub fn limits(&self) -> RateLimits {
+        self.limits
+    }
+
+    /// Waits for the call's turn and capacity, then holds a slot until the
+    /// returned permit is dropped. Cancel-safe: a dropped wait gives up its
+    /// place in line.
+    async fn acquire(self: &Arc<Self>, priority: Priority, tokens: u64) -> Permit {
+        let lane = priority.lane();
+        let ticket = {
+            let mut state = self.state.lock().unwrap();
+            let ticket = state.next_ticket;
+            state.next_ticket += 1;
+            state.waiting[lane].push_back(ticket);
+            ticket
+        };
+        let mut queued = Queued { limiter: self, lane, ticket, admitted: false };
+        loop {
+            let changed = self.changed.notified();
+            tokio::pin!(changed);
+            changed.as_mut().enable();
+            let blocked = {
+                let mut state = self.state.lock().unwrap();
+                let now = Instant::now();
+                state.prune(now);
+                if state.is_next(lane, ticket) {
+                    match self.blocked(&state, tokens) {
+                        None => {
+                            state.waiting[lane].pop_front();
+                            state.in_flight += 1;
+                            state.requests.push_back(now);
+                            state.tokens.push_back((now, ticket, tokens));
+                            queued.admitted = true;
+                            // The next in line may fit too.
+                            self.changed.notify_waiters();
+                            return Permit { limiter: self.clone(), ticket };
+                        }
+                        Some(blocked) => Some(blocked),
+                    }
+                } else {
+                    None
+                }
+            };
+            match blocked {
+                Some(Blocked::Until(at)) => {
+                    let _ = tokio::time::timeout_at(at, changed).await;
+                }
+                Some(Blocked::InFlight) | None => changed.await,
+            }
+        }
+    }
+
+    fn blocked(&self, state: &State, tokens: u64) -> Option<Blocked> {
+        if self.limits.max_in_flight.is_some_and(|max| state.in_flight >= max.max(1)) {
+            return Some(Blocked::InFlight);
+        }
+        if let Some(rpm) = self.limits.requests_per_minute {
+            if state.requests.len() >= rpm.max(1) as usize {
+                return state.requests.front().map(|at| Blocked::Until(*at + WINDOW));
+            }
+        }
+        if let Some(tpm) = self.limits.input_tokens_per_minute {
+            // A call bigger than the whole allowance still goes, alone.
+            let used: u64 = state.tokens.iter().map(|(_, _, t)| t).sum();
+            if used + tokens > tpm {
+                return state.tokens.front().map(|(at, _, _)| Blocked::Until(*at + WINDOW));
+            }
+        }
+        None
+    }
+}
+
+/// A place in line; leaving it unadmitted (a dropped `acquire`) hands the
+/// turn on.
+struct Queued<'a> {
+    limiter: &'a RateLimiter,
+    lane: usize,
+    ticket: u64,
+    admitted: bool,
+}
+
+impl Drop for Queued<'_> {
+    fn drop(&mut self) {
+        if self.admitted {
+            return;
+        }
+        let mut state = self.limiter.state.lock().unwrap();
+        state.waiting[self.lane].retain(|t| *t != self.ticket);
+        self.limiter.changed.notify_waiters();
+    }
+}
+
+/// An admitted call's slot, released on drop.
+struct Permit {
+    limiter: Arc<RateLimiter>,
+    ticket: u64,
+}
+
+impl Permit {
+    /// Replaces the admission estimate with what the call really used.
+    fn settle(&self, input_tokens: u64) {
+        let mut state = self.limiter.state.lock().unwrap();
+        if let Some(entry) = state.tokens.iter_mut().find(|(_, ticket, _)| *ticket == self.ticket) {
+            entry.2 = input_tokens;
+        }
+    }
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        let mut state = self.limiter.state.lock().unwrap();
+        state.in_flight -= 1;
+        self.limiter.changed.notify_waiters();
+    }
+}
+
+/// `LlmProvider` decorator that admits each call through a shared
+/// `RateLimiter`, in the caller's lane (`with_priority`). Wrap it inside a
+/// `RetryingProvider` so every attempt is counted.
+pub struct LimitedProvider {
+    inner: Arc<dyn LlmProvider>,
+    limiter: Arc<RateLimiter>,
+}
+
+impl LimitedProvider {
+    pub fn new(inner: Arc<dyn LlmProvider>, limiter: Arc<RateLimiter>) -> Self {
+        LimitedProvider { inner, limiter }
+    }
+
+    async fn run<Fut>(&self, req: &CompletionRequest, call: Fut) -> Result<CompletionResponse, HarnessError>
+    where
+        Fut: Future<Output = Result<CompletionResponse, HarnessError>>,
+    {
+        let permit = self.limiter.acquire(current_priority(), estimate_prompt_tokens(req)).await;
+        let result = call.await;
+        if let Ok(response) = &result {
+            permit.settle(response.usage.input_tokens + response.usage.cache_creation_input_tokens);
+        }
+        result
+    }
+}
+
+#[async_trait::async_trait]
+impl LlmProvider for LimitedProvider {
+    async fn complete(&self, req: CompletionRequest) -> Result<CompletionResponse, HarnessError> {
+        self.run(&req, self.inner.complete(req.clone())).await
+    }
+
+    async fn stream(
+        &self,
+        req: CompletionRequest,
+        sink: &(dyn Fn(StreamEvent) + Send + Sync),
+    ) -> Result<CompletionResponse, HarnessError> {
+        self.run(&req, self.inner.stream(req.clone(), sink)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::*;
+
+    fn request(prompt_chars: usize) -> CompletionRequest {
+        CompletionRequest {
+            system: String::new(),
+            messages: vec![Message::user_text("x".repeat(prompt_chars))],
+            tools: vec![],
+            max_tokens: 64,
+            tool_choice: None,
+            cache: CacheHints::default(),
+            thinking: None,
+        }
+    }
+
+    /// Answers after `delay`, recording the order calls started in by their
+    /// prompt length.
+    struct Slow {
+        delay: Duration,
+        started: Mutex<Vec<usize>>,
+        input_tokens: u64,
+    }
+
+    #[async_trait::async_trait]
+    impl LlmProvider for Slow {
+        async fn complete(&self, req: CompletionRequest) -> Result<CompletionResponse, HarnessError> {
+            let ContentBlock::Text { text } = &req.messages[0].content[0] else { unreachable!() };
+            self.started.lock().unwrap().push(text.len());
+            tokio::time::sleep(self.delay).await;
+            Ok(CompletionResponse {
+                content: vec![],
+                stop_reason: StopReason::EndTurn,
+                usage: Usage { input_tokens: self.input_tokens, ..Default::default() },
+                model: None,
+            })
+        }
+    }
+
+    fn limited(limits: RateLimits, delay: Duration) -> (Arc<LimitedProvider>, Arc<Slow>) {
+        let slow = Arc::new(Slow { delay, started: Mutex::new(Vec::new()), input_tokens: 10 });
+        let provider = LimitedProvider::new(slow.clone(), Arc::new(RateLimiter::new(limits)));
+        (Arc::new(provider), slow)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn requests_per_minute_spreads_calls_over_the_window() {
+        let limits = RateLimits { requests_per_minute: Some(2), ..Default::default() };
+        let (provider, _) = limited(limits, Duration::ZERO);
+        let started = Instant::now();
+        for _ in 0..3 {
+            provider.complete(request(4)).await.unwrap();
+        }
+        assert_eq!(started.elapsed(), WINDOW, "the third call waits for the first to age out");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn max_in_flight_caps_concurrent_calls() {
+        let limits = RateLimits { max_in_flight: Some(2), ..Default::default() };
+        let (provider, _) = limited(limits, Duration::from_secs(1));
+        let started = Instant::now();
+        let calls = (0..4).map(|_| provider.complete(request(4)));
+        futures::future::join_all(calls).await;
+        assert_eq!(started.elapsed(), Duration::from_secs(2));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn token_allowance_uses_reported_usage_once_a_call_answers() {
+        // Each prompt estimates at 250 tokens but reports 10, so after the
+        // first call settles the rest fit in the same minute.
+        let limits = RateLimits { input_tokens_per_minute: Some(300), ..Default::default() };
+        let (provider, _) = limited(limits, Duration::ZERO);
+        let started = Instant::now();
+        for _ in 0..3 {
+            provider.complete(request(1000)).await.unwrap();
+        }
+        assert_eq!(started.elapsed(), Duration::ZERO);
+
+        // Two at once can't both be admitted on estimates: the second waits
+        // for the first to answer and settle.
+        let (provider, _) = limited(limits, Duration::from_secs(1));
+        let started = Instant::now();
+        let (a, b) = futures::future::join(provider.complete(request(1000)), provider.complete(request(1000))).await;
+        assert!(a.is_ok() && b.is_ok());
+        assert_eq!(started.elapsed(), Duration::from_secs(2));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn interactive_calls_jump_the_background_queue() {
+        let limits = RateLimits { max_in_flight: Some(1), ..Default::default() };
+        let (provider, slow) = limited(limits, Duration::from_secs(1));
+        let call = |priority: Priority, chars: usize| {
+            let provider = provider.clone();
+            tokio::spawn(with_priority(priority, async move { provider.complete(request(chars)).await }))
+        };
+        // One call holds the only slot; two background calls queue, then an
+        // interactive one arrives last.
+        let first = call(Priority::Normal, 1);
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        let background = [call(Priority::Background, 2), call(Priority::Background, 3)];
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        let interactive = call(Priority::Interactive, 4);
+
+        first.await.unwrap().unwrap();
+        interactive.await.unwrap().unwrap();
+        for handle in background {
+            handle.await.unwrap().unwrap();
+        }
+        assert_eq!(*slow.started.lock().unwrap(), vec![1, 4, 2, 3]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn a_dropped_wait_gives_up_its_place() {
+        let limits = RateLimits { max_in_flight: Some(1), ..Default::default() };
+        let (provider, slow) = limited(limits, Duration::from_secs(1));
+        let first = tokio::spawn({
+            let provider = provider.clone();
+            async move { provider.complete(request(1)).await }
+        });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        let abandoned = tokio::time::timeout(
+            Duration::from_millis(10),
+            with_priority(Priority::Interactive, provider.complete(request(2))),
+        )
+        .await;
+        assert!(abandoned.is_err());
+        provider.complete(request(3)).await.unwrap();
+        first.await.unwrap().unwrap();
+        assert_eq!(*slow.started.lock().unwrap(), vec![1, 3]);
+    }
+
+    #[test]
+    fn calls_outside_any_scope_are_normal() {
+        assert_eq!(current_priority(), Priority::Normal);
+    }
+}
@@ -0,0 +1,752 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use serde::Deserialize;
+
+use crate::error::HarnessError;
+use crate::llm::{
+    CompletionRequest, CompletionResponse, ContentBlock, ImageSource, LlmProvider, Message, Role, StopReason,
+    ToolSpec, Usage,
+};
+use crate::providers::{openai, status_error};
+
+/// How tools reach a local model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LocalToolMode {
+    /// The server's own `tools` / `tool_calls` support (recent Ollama
+    /// builds with a tool-trained model).
+    Native,
+    /// No tools on the wire: the tool list goes into the system prompt and
+    /// the reply is constrained (`format`) to a JSON object naming the calls
+    /// to make. Works with any model the server can run.
+    Emulated,
+}
+
+/// Which HTTP API the local server speaks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LocalApi {
+    /// Ollama's `/api/chat`.
+    Ollama,
+    /// The OpenAI-compatible `/v1/chat/completions` (llama.cpp-server, or
+    /// Ollama's own compatibility route).
+    OpenAiCompatible,
+}
+
+/// A local inference server — Ollama's `/api/chat`, or the OpenAI-compatible
+/// route llama.cpp-server speaks — the on-device path for walks with no
+/// connectivity. No auth, no cost; a refused connection is
+/// `ProviderUnreachable` like any other provider.
+pub struct LocalProvider {
+    client: reqwest::Client,
+    model: String,
+    base_url: String,
+    api: LocalApi,
+    tool_mode: LocalToolMode,
+    /// Emulated calls carry no ids; mint run-unique ones so tool results
+    /// pair up with their calls.
+    next_call: AtomicU64,
+}
+
+impl LocalProvider {
+    pub fn new(model: impl Into<String>) -> Self {
+        LocalProvider {
+            client: reqwest::Client::builder()
+                .connect_timeout(std::time::Duration::from_secs(2))
+                // On-device inference is slow; a full extraction turn on a
+                // phone-class CPU can take minutes.
+                .timeout(std::time::Duration::from_secs(300))
+                .build()
+                .expect("reqwest client with static config cannot fail"),
+            model: model.into(),
+            base_url: "http://127.0.0.1:11434".into(),
+            api: LocalApi::Ollama,
+            tool_mode: LocalToolMode::Emulated,
+            next_call: AtomicU64::new(1),
+        }
+    }
+
+    /// The server root (no `/api`), e.g. `http://127.0.0.1:8080`.
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        let base_url = base_url.into();
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Defaults to `Ollama`.
+    pub fn with_api(mut self, api: LocalApi) -> Self {
+        self.api = api;
+        self
+    }
+
+    /// Defaults to `Emulated`, which every server supports.
+    pub fn with_tool_mode(mut self, tool_mode: LocalToolMode) -> Self {
+        self.tool_mode = tool_mode;
+        self
+    }
+
+    fn request_body(&self, req: &CompletionRequest) -> serde_json::Value {
+        let emulate = self.tool_mode == LocalToolMode::Emulated && !req.tools.is_empty();
+        let system = if emulate {
+            format!("{}\n\n{}", req.system, emulated_tool_prompt(&req.tools, req.tool_choice.as_deref()))
+        } else {
+            req.system.clone()
+        };
+        let mut body = match self.api {
+            LocalApi::Ollama => serde_json::json!({
+                "model": self.model,
+                "stream": false,
+                "options": {"num_predict": req.max_tokens},
+                "messages": wire_messages(&system, &req.messages, emulate, self.api),
+            }),
+            LocalApi::OpenAiCompatible => serde_json::json!({
+                "model": self.model,
+                "max_tokens": req.max_tokens,
+                // Native calls carry ids on this wire; replay them as OpenAI does.
+                "messages": if emulate {
+                    wire_messages(&system, &req.messages, true, self.api)
+                } else {
+                    openai::wire_messages(&system, &req.messages)
+                },
+            }),
+        };
+        if emulate {
+            let schema = emulated_reply_schema(&req.tools, req.tool_choice.as_deref());
+            match self.api {
+                LocalApi::Ollama => body["format"] = schema,
+                LocalApi::OpenAiCompatible => {
+                    body["response_format"] =
+                        serde_json::json!({"type": "json_schema", "json_schema": {"name": "reply", "schema": schema}});
+                }
+            }
+        } else if !req.tools.is_empty() {
+            // Native mode has no forced-choice knob; a forced call is asked
+            // for by offering only that tool.
+            body["tools"] = req
+                .tools
+                .iter()
+                .filter(|t| req.tool_choice.as_deref().is_none_or(|name| name == t.name))
+                .map(|t| {
+                    serde_json::json!({
+                        "type": "function",
+                        "function": {
+                            "name": t.name,
+                            "description": t.description,
+                            "parameters": t.input_schema,
+                        }
+                    })
+                })
+                .collect();
+        }
+        body
+    }
+
+    fn endpoint(&self) -> String {
+        match self.api {
+            LocalApi::Ollama => format!("{}/api/chat", self.base_url),
+            LocalApi::OpenAiCompatible => format!("{}/v1/chat/completions", self.base_url),
+        }
+    }
+
+    fn next_call_id(&self) -> String {
+        format!("local_{}", self.next_call.fetch_add(1, Ordering::Relaxed))
+    }
+
+    fn parse_response(&self, text: &str, emulated: bool) -> Result<CompletionResponse, HarnessError> {
+        let response = match self.api {
+            LocalApi::Ollama => self.parse_ollama(text)?,
+            LocalApi::OpenAiCompatible => {
+                CompletionResponse { model: Some(self.model.clone()), ..openai::parse_response(text)? }
+            }
+        };
+        if emulated {
+            self.unwrap_emulated(response)
+        } else {
+            Ok(response)
+        }
+    }
+
+    fn parse_ollama(&self, text: &str) -> Result<CompletionResponse, HarnessError> {
+        let parsed: ApiResponse = serde_json::from_str(text)
+            .map_err(|e| HarnessError::Provider(format!("bad response body: {e}: {text}")))?;
+        let usage = Usage { input_tokens: parsed.prompt_eval_count, output_tokens: parsed.eval_count, ..Default::default() };
+
+        let mut content = Vec::new();
+        if !parsed.message.content.is_empty() {
+            content.push(ContentBlock::Text { text: parsed.message.content });
+        }
+        for call in parsed.message.tool_calls {
+            let input = match call.function.arguments {
+                serde_json::Value::Null => serde_json::json!({}),
+                args => args,
+            };
+            content.push(ContentBlock::ToolUse { id: self.next_call_id(), name: call.function.name, input });
+        }
+
+        let has_tool_calls = content.iter().any(|b| matches!(b, ContentBlock::ToolUse { .. }));
+        let stop_reason = match parsed.done_reason.as_deref() {
+            _ if has_tool_calls => StopReason::ToolUse,
+            Some("length") => StopReason::MaxTokens,
+            Some("stop") | None => StopReason::EndTurn,
+            _ => StopReason::Unknown,
+        };
+        Ok(CompletionResponse { content, stop_reason, usage, model: Some(self.model.clone()) })
+    }
+
+    /// An emulated turn's text is the constrained JSON reply; unpack it into
+    /// text and tool calls, whichever wire it came over.
+    fn unwrap_emulated(&self, response: CompletionResponse) -> Result<CompletionResponse, HarnessError> {
+        // A truncated reply is cut-off JSON; hand back what there is and
+        // let the caller see MaxTokens rather than a parse failure.
+        if response.stop_reason == StopReason::MaxTokens {
+            return Ok(response);
+        }
+        let raw: String = response
+            .content
+            .iter()
+            .filter_map(|b| match b {
+                ContentBlock::Text { text } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect();
+        let reply: EmulatedReply = serde_json::from_str(&raw)
+            .map_err(|e| HarnessError::Provider(format!("bad emulated tool reply: {e}: {raw}")))?;
+        let mut content = Vec::new();
+        if !reply.text.is_empty() {
+            content.push(ContentBlock::Text { text: reply.text });
+        }
+        for call in reply.tool_calls {
+            content.push(ContentBlock::ToolUse { id: self.next_call_id(), name: call.name, input: call.input });
+        }
+        let stop_reason = if content.iter().any(|b| matches!(b, ContentBlock::ToolUse { .. })) {
+            StopReason::ToolUse
+        } else {
+            response.stop_reason
+        };
+        Ok(CompletionResponse { content, stop_reason, ..response })
+    }
+}
+
+/// The system-prompt half of emulation: what the tools are and how to ask
+/// for them. The `format` schema enforces the shape; this explains it.
+fn emulated_tool_prompt(tools: &[ToolSpec], forced: Option<&str>) -> String {
+    let mut out = String::from(
+        "You can call tools. Reply with ONLY a JSON object: \
+         {\"text\": <string>, \"tool_calls\": [{\"name\": <tool name>, \"input\": <object matching the tool's input schema>}]}. \
+         Use an empty tool_calls list when you are done.\n\nTools:\n",
+    );
+    for tool in tools {
+        out.push_str(&format!("- {}: {}\n  input schema: {}\n", tool.name, tool.description, tool.input_schema));
+    }
+    if let Some(name) = forced {
+        out.push_str(&format!("\nYou must call `{name}` in this reply.\n"));
+    }
+    out
+}
+
+/// The constrained-output schema for an emulated turn. A forced choice
+/// narrows the name to that one tool and requires at least one call.
+fn emulated_reply_schema(tools: &[ToolSpec], forced: Option<&str>) -> serde_json::Value {
+    let names: Vec<&str> = match forced {
+        Some(name) => vec![name],
+        None => tools.iter().map(|t| t.name.as_str()).collect(),
+    };
+    let mut calls = serde_json::json!({
+        "type": "array",
+        "items": {
+            "type": "object",
+            "properties": {
+                "name": {"type": "string", "enum": names},
+                "input": {"type": "object"},
+            },
+            "required": ["name", "input"],
+        },
+    });
+    if forced.is_some() {
+        calls["minItems"] = 1.into();
+    }
+    serde_json::json!({
+        "type": "object",
+        "properties": {"text": {"type": "string"}, "tool_calls": calls},
+        "required": ["text", "tool_calls"],
+    })
+}
+
+/// Our transcript in `/api/chat` messages. Native mode mirrors the OpenAI
+/// layout (object arguments, `role: "tool"` results). Emulated mode replays
+/// assistant turns in the same JSON shape the model is constrained to, and
+/// tool results as plain user text naming the tool — over either API, which
+/// then differ only in how images ride along.
+fn wire_messages(system: &str, messages: &[Message], emulated: bool, api: LocalApi) -> Vec<serde_json::Value> {
+    let mut out = vec![serde_json::json!({"role": "system", "content": system})];
+    let mut names = std::collections::HashMap::new();
+    for message in messages {
+        let text: Vec<&str> = message
+            .content
+            .iter()
+            .filter_map(|b| match b {
+                ContentBlock::Text { text } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect();
+        match message.role {
+            Role::Assistant => {
+                let calls: Vec<(&str, &serde_json::Value)> = message
+                    .content
+                    .iter()
+                    .filter_map(|b| match b {
+                        ContentBlock::ToolUse { id, name, input } => {
+                            names.insert(id.as_str(), name.as_str());
+                            Some((name.as_str(), input))
+                        }
+                        _ => None,
+                    })
+                    .collect();
+                if emulated {
+                    let reply = serde_json::json!({
+                        "text": text.join("\n"),
+                        "tool_calls": calls
+                            .iter()
+                            .map(|(name, input)| serde_json::json!({"name": name, "input": input}))
+                            .collect::<Vec<_>>(),
+                    });
+                    out.push(serde_json::json!({"role": "assistant", "content": reply.to_string()}));
+                } else {
+                    let mut wire = serde_json::json!({"role": "assistant", "content": text.join("\n")});
+                    if !calls.is_empty() {
+                        wire["tool_calls"] = calls
+                            .iter()
+                            .map(|(name, input)| serde_json::json!({"function": {"name": name, "arguments": input}}))
+                            .collect();
+                    }
+                    out.push(wire);
+                }
+            }
+            Role::User => {
+                let mut results = Vec::new();
+                for block in &message.content {
+                    if let ContentBlock::ToolResult { tool_use_id, content, is_error } = block {
+                        let name = names.get(tool_use_id.as_str()).copied().unwrap_or("tool");
+                        if emulated {
+                            let outcome = if *is_error { "failed" } else { "returned" };
+                            results.push(format!("Tool `{name}` {outcome}: {content}"));
+                        } else {
+                            let content =
+                                if *is_error { format!("Error: {content}") } else { content.clone() };
+                            out.push(serde_json::json!({"role": "tool", "tool_name": name, "content": content}));
+                        }
+                    }
+                }
+                results.extend(text.iter().map(|t| t.to_string()));
+                let images: Vec<(&str, &str)> = message
+                    .content
+                    .iter()
+                    .filter_map(|b| match b {
+                        ContentBlock::Image { source: ImageSource::Base64 { media_type, data } } => {
+                            Some((media_type.as_str(), data.as_str()))
+                        }
+                        _ => None,
+                    })
+                    .collect();
+                if results.is_empty() && images.is_empty() {
+                    continue;
+                }
+                let text = results.join("\n\n");
+                let wire = match api {
+                    _ if images.is_empty() => serde_json::json!({"role": "user", "content": text}),
+                    // Ollama takes bare base64 beside the text; the media type is sniffed.
+                    LocalApi::Ollama => serde_json::json!({
+                        "role": "user",
+                        "content": text,
+                        "images": images.iter().map(|(_, data)| *data).collect::<Vec<_>>(),
+                    }),
+                    LocalApi::OpenAiCompatible => {
+                        let mut parts = Vec::new();
+                        if !text.is_empty() {
+                            parts.push(serde_json::json!({"type": "text", "text": text}));
+                        }
+                        parts.extend(images.iter().map(|(media_type, data)| {
+                            serde_json::json!({
+                                "type": "image_url",
+                                "image_url": {"url": format!("data:{media_type};base64,{data}")},
+                            })
+                        }));
+                        serde_json::json!({"role": "user", "content": parts})
+                    }
+                };
+                out.push(wire);
+            }
+        }
+    }
+    out
+}
+
+#[derive(Deserialize)]
+struct ApiResponse {
+    message: ApiMessage,
+    #[serde(default)]
+    done_reason: Option<String>,
+    #[serde(default)]
+    prompt_eval_count: u64,
+    #[serde(default)]
+    eval_count: u64,
+}
+
+#[derive(Deserialize)]
+struct ApiMessage {
+    #[serde(default)]
+    content: String,
+    #[serde(default)]
+    tool_calls: Vec<ApiToolCall>,
+}
+
+#[derive(Deserialize)]
+struct ApiToolCall {
+    function: ApiFunction,
+}
+
+#[derive(Deserialize)]
+struct ApiFunction {
+    name: String,
+    /// An object here — unlike the OpenAI wire, not a JSON string.
+    #[serde(default)]
+    arguments: serde_json::Value,
+}
+
+#[derive(Deserialize)]
+struct EmulatedReply {
+    #[serde(default)]
+    text: String,
+    #[serde(default)]
+    tool_calls: Vec<EmulatedCall>,
+}
+
+#[derive(Deserialize)]
+struct EmulatedCall {
+    name: String,
+    #[serde(default = "empty_object")]
+    input: serde_json::Value,
+}
+
+fn empty_object() -> serde_json::Value {
+    serde_json::json!({})
+}
+
+#[async_trait::async_trait]
+impl LlmProvider for LocalProvider {
+    async fn complete(&self, req: CompletionRequest) -> Result<CompletionResponse, HarnessError> {
+        let emulated = self.tool_mode == LocalToolMode::Emulated && !req.tools.is_empty();
+        let resp = self
+            .client
+            .post(self.endpoint())
+            .json(&self.request_body(&req))
+            .send()
+            .await
+            .map_err(|e| HarnessError::ProviderUnreachable(e.to_string()))?;
+
+        if !resp.status().is_success() {
+            return Err(status_error(resp).await);
+        }
+        let text = resp
+            .text()
+            .await
+            .map_err(|e| HarnessError::ProviderUnreachable(e.to_string()))?;
+        self.parse_response(&text, emulated)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::*;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn request() -> CompletionRequest {
+        CompletionRequest {
+            system: "sys".into(),
+            messages: vec![Message::user_text("hello")],
+            tools: vec![ToolSpec {
+                name: "echo".into(),
+                description: "repeat text".into(),
+                input_schema: serde_json::json!({"type": "object"}),
+            }],
+            max_tokens: 256,
+            tool_choice: None,
+            cache: CacheHints::default(),
+            thinking: None,
+        }
+    }
+
+    fn chat_reply(content: &str, done_reason: &str) -> ResponseTemplate {
+        ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "model": "llama3.2",
+            "message": {"role": "assistant", "content": content},
+            "done": true,
+            "done_reason": done_reason,
+            "prompt_eval_count": 30,
+            "eval_count": 12
+        }))
+    }
+
+    #[test]
+    fn image_blocks_go_on_the_wire_beside_the_text() {
+        let messages = vec![Message {
+            role: Role::User,
+            content: vec![ContentBlock::Text { text: "what is this?".into() }, ContentBlock::image("image/png", b"png!")],
+        }];
+        let wire = wire_messages("sys", &messages, false, LocalApi::Ollama);
+        assert_eq!(
+            wire[1],
+            serde_json::json!({"role": "user", "content": "what is this?", "images": ["cG5nIQ=="]})
+        );
+    }
+
+    #[tokio::test]
+    async fn emulated_tools_constrain_the_reply_and_parse_into_tool_use() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/chat"))
+            .respond_with(chat_reply(
+                r#"{"text": "on it", "tool_calls": [{"name": "echo", "input": {"text": "x"}}]}"#,
+                "stop",
+            ))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let provider = LocalProvider::new("llama3.2").with_base_url(server.uri());
+        let resp = provider.complete(request()).await.unwrap();
+        assert_eq!(resp.stop_reason, StopReason::ToolUse);
+        assert_eq!(resp.usage, Usage { input_tokens: 30, output_tokens: 12, ..Default::default() });
+        assert_eq!(
+            resp.content,
+            vec![
+                ContentBlock::Text { text: "on it".into() },
+                ContentBlock::ToolUse {
+                    id: "local_1".into(),
+                    name: "echo".into(),
+                    input: serde_json::json!({"text": "x"}),
+                },
+            ]
+        );
+
+        let received = &server.received_requests().await.unwrap()[0];
+        let body: serde_json::Value = serde_json::from_slice(&received.body).unwrap();
+        assert_eq!(body["model"], "llama3.2");
+        assert_eq!(body["stream"], false);
+        assert_eq!(body["options"]["num_predict"], 256);
+        assert!(body.get("tools").is_none(), "emulated mode sends no native tools");
+        assert_eq!(body["format"]["properties"]["tool_calls"]["items"]["properties"]["name"]["enum"], serde_json::json!(["echo"]));
+        let system = body["messages"][0]["content"].as_str().unwrap();
+        assert!(system.starts_with("sys\n\n"));
+        assert!(system.contains("- echo: repeat text"));
+    }
+
+    #[tokio::test]
+    async fn emulated_round_trip_replays_calls_and_results_as_text() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(chat_reply(r#"{"text": "done", "tool_calls": []}"#, "stop"))
+            .mount(&server)
+            .await;
+
+        let mut req = request();
+        req.tool_choice = Some("echo".into());
+        req.messages.push(Message {
+            role: Role::Assistant,
+            content: vec![ContentBlock::ToolUse {
+                id: "local_7".into(),
+                name: "echo".into(),
+                input: serde_json::json!({"text": "x"}),
+            }],
+        });
+        req.messages.push(Message {
+            role: Role::User,
+            content: vec![ContentBlock::ToolResult {
+                tool_use_id: "local_7".into(),
+                content: "boom".into(),
+                is_error: true,
+            }],
+        });
+        let provider = LocalProvider::new("llama3.2").with_base_url(server.uri());
+        let resp = provider.complete(req).await.unwrap();
+        assert_eq!(resp.stop_reason, StopReason::EndTurn);
+        assert_eq!(resp.content, vec![ContentBlock::Text { text: "done".into() }]);
+
+        let received = &server.received_requests().await.unwrap()[0];
+        let body: serde_json::Value = serde_json::from_slice(&received.body).unwrap();
+        assert_eq!(body["format"]["properties"]["tool_calls"]["minItems"], 1);
+        let messages = body["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 4);
+        let replayed: serde_json::Value =
+            serde_json::from_str(messages[2]["content"].as_str().unwrap()).unwrap();
+        assert_eq!(
+            replayed,
+            serde_json::json!({"text": "", "tool_calls": [{"name": "echo", "input": {"text": "x"}}]})
+        );
+        assert_eq!(messages[3], serde_json::json!({"role": "user", "content": "Tool `echo` failed: boom"}));
+    }
+
+    #[tokio::test]
+    async fn native_mode_sends_tools_and_reads_object_arguments() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "message": {
+                    "role": "assistant",
+                    "content": "",
+                    "tool_calls": [{"function": {"name": "echo", "arguments": {"text": "x"}}}]
+                },
+                "done": true,
+                "done_reason": "stop"
+            })))
+            .mount(&server)
+            .await;
+
+        let provider = LocalProvider::new("qwen2.5")
+            .with_base_url(server.uri())
+            .with_tool_mode(LocalToolMode::Native);
+        let resp = provider.complete(request()).await.unwrap();
+        assert_eq!(resp.stop_reason, StopReason::ToolUse);
+        assert_eq!(
+            resp.content,
+            vec![ContentBlock::ToolUse {
+                id: "local_1".into(),
+                name: "echo".into(),
+                input: serde_json::json!({"text": "x"}),
+            }]
+        );
+        assert_eq!(resp.usage, Usage::default());
+
+        let received = &server.received_requests().await.unwrap()[0];
+        let body: serde_json::Value = serde_json::from_slice(&received.body).unwrap();
+        assert_eq!(body["tools"][0]["function"]["name"], "echo");
+        assert!(body.get("format").is_none());
+        assert_eq!(body["messages"][0]["content"], "sys");
+    }
+
+    #[tokio::test]
+    async fn unparseable_emulated_reply_is_an_invalid_response_and_truncation_is_max_tokens() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(chat_reply("sure, calling echo now", "stop"))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(chat_reply(r#"{"text": "cut of"#, "length"))
+            .mount(&server)
+            .await;
+
+        let provider = LocalProvider::new("llama3.2").with_base_url(server.uri());
+        let err = provider.complete(request()).await.unwrap_err();
+        assert_eq!(err.provider_kind(), Some(crate::ProviderErrorKind::InvalidResponse));
+
+        let resp = provider.complete(request()).await.unwrap();
+        assert_eq!(resp.stop_reason, StopReason::MaxTokens);
+    }
+
+    #[tokio::test]
+    async fn openai_compatible_route_emulates_tools_through_response_format() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "choices": [{
+                    "message": {
+                        "role": "assistant",
+                        "content": r#"{"text": "on it", "tool_calls": [{"name": "echo", "input": {"text": "x"}}]}"#
+                    },
+                    "finish_reason": "stop"
+                }],
+                "usage": {"prompt_tokens": 30, "completion_tokens": 12}
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let provider =
+            LocalProvider::new("qwen2.5").with_base_url(server.uri()).with_api(LocalApi::OpenAiCompatible);
+        let mut req = request();
+        req.messages = vec![Message {
+            role: Role::User,
+            content: vec![ContentBlock::Text { text: "what is this?".into() }, ContentBlock::image("image/png", b"png!")],
+        }];
+        let resp = provider.complete(req).await.unwrap();
+        assert_eq!(resp.stop_reason, StopReason::ToolUse);
+        assert_eq!(resp.usage, Usage { input_tokens: 30, output_tokens: 12, ..Default::default() });
+        assert_eq!(resp.model.as_deref(), Some("qwen2.5"));
+        assert_eq!(
+            resp.content,
+            vec![
+                ContentBlock::Text { text: "on it".into() },
+                ContentBlock::ToolUse {
+                    id: "local_1".into(),
+                    name: "echo".into(),
+                    input: serde_json::json!({"text": "x"}),
+                },
+            ]
+        );
+
+        let received = &server.received_requests().await.unwrap()[0];
+        let body: serde_json::Value = serde_json::from_slice(&received.body).unwrap();
+        assert_eq!(body["max_tokens"], 256);
+        assert!(body.get("tools").is_none() && body.get("format").is_none());
+        assert_eq!(
+            body["response_format"]["json_schema"]["schema"]["properties"]["tool_calls"]["items"]["properties"]["name"]
+                ["enum"],
+            serde_json::json!(["echo"])
+        );
+        assert_eq!(body["messages"][1]["content"][0], serde_json::json!({"type": "text", "text": "what is this?"}));
+        assert_eq!(body["messages"][1]["content"][1]["image_url"]["url"], "data:image/png;base64,cG5nIQ==");
+    }
+
+    #[tokio::test]
+    async fn openai_compatible_route_in_native_mode_keeps_the_servers_call_ids() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "choices": [{
+                    "message": {
+                        "role": "assistant",
+                        "content": null,
+                        "tool_calls": [{
+                            "id": "call_1",
+                            "type": "function",
+                            "function": {"name": "echo", "arguments": "{\"text\": \"x\"}"}
+                        }]
+                    },
+                    "finish_reason": "tool_calls"
+                }]
+            })))
+            .mount(&server)
+            .await;
+
+        let provider = LocalProvider::new("qwen2.5")
+            .with_base_url(server.uri())
+            .with_api(LocalApi::OpenAiCompatible)
+            .with_tool_mode(LocalToolMode::Native);
+        let resp = provider.complete(request()).await.unwrap();
+        assert_eq!(resp.stop_reason, StopReason::ToolUse);
+        assert_eq!(
+            resp.content,
+            vec![ContentBlock::ToolUse {
+                id: "call_1".into(),
+                name: "echo".into(),
+                input: serde_json::json!({"text": "x"}),
+            }]
+        );
+
+        let received = &server.received_requests().await.unwrap()[0];
+        let body: serde_json::Value = serde_json::from_slice(&received.body).unwrap();
+        assert_eq!(body["tools"][0]["function"]["name"], "echo");
+        assert!(body.get("response_format").is_none());
+    }
+
+    #[tokio::test]
+    async fn no_server_listening_is_unreachable() {
+        let provider = LocalProvider::new("llama3.2").with_base_url("http://127.0.0.1:9");
+        let err = provider.complete(request()).await.unwrap_err();
+        assert!(matches!(err, HarnessError::ProviderUnreachable(_)));
+    }
+}
@@ -1,2 +1,29 @@
 pub mod anthropic;
+pub mod cache;
+pub mod limit;
+pub mod local;
+pub mod openai;
+pub mod retry;
+pub mod router;
 pub use anthropic::AnthropicProvider;
+pub use cache::{bypass_cache, CacheBounds, CachedResponse, CachingProvider, ResponseCache};
+pub use limit::{with_priority, LimitedProvider, Priority, RateLimiter, RateLimits};
+pub use local::{LocalApi, LocalProvider, LocalToolMode};
+pub use openai::OpenAiProvider;
+pub use retry::{RetryPolicy, RetryStats, RetryingProvider};
+pub use router::{RouteRule, RoutingProvider};
+
+use crate::error::HarnessError;
+
+/// Maps a non-success response to `ProviderStatus`, keeping the server's
+/// `retry-after` hint for the retry layer. Shared by every HTTP provider.
+pub(crate) async fn status_error(resp: reqwest::Response) -> HarnessError {
+    let status = resp.status().as_u16();
+    let retry_after = resp
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(retry::parse_retry_after);
+    let body = resp.text().await.unwrap_or_else(|e| format!("<unreadable body: {e}>"));
+    HarnessError::ProviderStatus { status, retry_after, body }
+}
@@ -0,0 +1,544 @@
+use serde::Deserialize;
+
+use crate::error::HarnessError;
+use crate::llm::{
+    CompletionRequest, CompletionResponse, ContentBlock, ImageSource, LlmProvider, Message, Role, StopReason,
+    Usage,
+};
+use crate::providers::status_error;
+
+/// An OpenAI-style `/v1/chat/completions` endpoint (OpenAI itself, or any
+/// compatible gateway). Tool use maps onto function calling; streaming uses
+/// the trait's replay default.
+pub struct OpenAiProvider {
+    client: reqwest::Client,
+    api_key: String,
+    model: String,
+    base_url: String,
+}
+
+impl OpenAiProvider {
+    pub fn new(api_key: impl Into<String>, model: impl Into<String>) -> Self {
+        OpenAiProvider {
+            client: reqwest::Client::builder()
+                .connect_timeout(std::time::Duration::from_secs(5))
+                .timeout(std::time::Duration::from_secs(60))
+                .build()
+                .expect("reqwest client with static config cannot fail"),
+            api_key: api_key.into(),
+            model: model.into(),
+            base_url: "https://api.openai.com".into(),
+        }
+    }
+
+    /// The API root WITHOUT the `/v1` segment, same convention as
+    /// `AnthropicProvider::with_base_url`.
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        let base_url = base_url.into();
+        self.base_url = base_url.trim_end_matches('/').to_string();
+        self
+    }
+
+    fn request_body(&self, req: &CompletionRequest) -> serde_json::Value {
+        let mut body = serde_json::json!({
+            "model": self.model,
+            "max_tokens": req.max_tokens,
+            "messages": wire_messages(&req.system, &req.messages),
+        });
+        // Some compatible servers reject an empty `tools` array outright.
+        if !req.tools.is_empty() {
+            body["tools"] = req
+                .tools
+                .iter()
+                .map(|t| {
+                    serde_json::json!({
+                        "type": "function",
+                        "function": {
+                            "name": t.name,
+                            "description": t.description,
+                            "parameters": t.input_schema,
+                        }
+                    })
+                })
+                .collect();
+        }
+        if let Some(name) = &req.tool_choice {
+            body["tool_choice"] = serde_json::json!({"type": "function", "function": {"name": name}});
+        }
+        body
+    }
+}
+
+/// Flattens our block-structured transcript into chat messages: the system
+/// prompt leads, an assistant turn carries its tool calls as `tool_calls`,
+/// and each tool result becomes its own `role: "tool"` message (placed
+/// before any text in the same user turn, so it directly follows the
+/// assistant message that asked for it).
+pub(super) fn wire_messages(system: &str, messages: &[Message]) -> Vec<serde_json::Value> {
+    let mut out = vec![serde_json::json!({"role": "system", "content": system})];
+    for message in messages {
+        let text: Vec<&str> = message
+            .content
+            .iter()
+            .filter_map(|b| match b {
+                ContentBlock::Text { text } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect();
+        match message.role {
+            Role::Assistant => {
+                let calls: Vec<serde_json::Value> = message
+                    .content
+                    .iter()
+                    .filter_map(|b| match b {
+                        ContentBlock::ToolUse { id, name, input } => Some(serde_json::json!({
+                            "id": id,
+                            "type": "function",
+                            "function": {"name": name, "arguments": input.to_string()},
+                        })),
+                        _ => None,
+                    })
+                    .collect();
+                let mut wire = serde_json::json!({
+                    "role": "assistant",
+                    "content": if text.is_empty() { serde_json::Value::Null } else { text.join("\n").into() },
+                });
+                if !calls.is_empty() {
+                    wire["tool_calls"] = calls.into();
+                }
+                out.push(wire);
+            }
+            Role::User => {
+                for block in &message.content {
+                    if let ContentBlock::ToolResult { tool_use_id, content, is_error } = block {
+                        // No is_error flag on the wire; say so in the content.
+                        let content =
+                            if *is_error { format!("Error: {content}") } else { content.clone() };
+                        out.push(serde_json::json!({
+                            "role": "tool",
+                            "tool_call_id": tool_use_id,
+                            "content": content,
+                        }));
+                    }
+                }
+                // Images need the content-parts form; text-only turns keep
+                // the plain string.
+                let images: Vec<serde_json::Value> = message
+                    .content
+                    .iter()
+                    .filter_map(|b| match b {
+                        ContentBlock::Image { source: ImageSource::Base64 { media_type, data } } => {
+                            Some(serde_json::json!({
+                                "type": "image_url",
+                                "image_url": {"url": format!("data:{media_type};base64,{data}")},
+                            }))
+                        }
+                        _ => None,
+                    })
+                    .collect();
+                if !images.is_empty() {
+                    let mut parts = Vec::new();
+                    if !text.is_empty() {
+                        parts.push(serde_json::json!({"type": "text", "text": text.join("\n")}));
+                    }
+                    parts.extend(images);
+                    out.push(serde_json::json!({"role": "user", "content": parts}));
+                } else if !text.is_empty() {
+                    out.push(serde_json::json!({"role": "user", "content": text.join("\n")}));
+                }
+            }
+        }
+    }
+    out
+}
+
+#[derive(Deserialize)]
+struct ApiResponse {
+    choices: Vec<ApiChoice>,
+    #[serde(default)]
+    usage: Option<ApiUsage>,
+}
+
+#[derive(Deserialize)]
+struct ApiChoice {
+    message: ApiMessage,
+    #[serde(default)]
+    finish_reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ApiMessage {
+    #[serde(default)]
+    content: Option<String>,
+    #[serde(default)]
+    tool_calls: Option<Vec<ApiToolCall>>,
+}
+
+#[derive(Deserialize)]
+struct ApiToolCall {
+    id: String,
+    function: ApiFunction,
+}
+
+#[derive(Deserialize)]
+struct ApiFunction {
+    name: String,
+    /// A JSON document encoded as a string — NOT an object.
+    #[serde(default)]
+    arguments: String,
+}
+
+#[derive(Deserialize)]
+struct ApiUsage {
+    #[serde(default)]
+    prompt_tokens: u64,
+    #[serde(default)]
+    completion_tokens: u64,
+    #[serde(default)]
+    prompt_tokens_details: Option<PromptTokensDetails>,
+    #[serde(default)]
+    completion_tokens_details: Option<CompletionTokensDetails>,
+}
+
+/// OpenAI caches long prompt prefixes automatically; the hit count is
+/// reported here as part of (not on top of) `prompt_tokens`.
+#[derive(Deserialize)]
+struct PromptTokensDetails {
+    #[serde(default)]
+    cached_tokens: u64,
+}
+
+/// Reasoning models report their hidden reasoning here, as part of
+/// `completion_tokens`.
+#[derive(Deserialize)]
+struct CompletionTokensDetails {
+    #[serde(default)]
+    reasoning_tokens: u64,
+}
+
+pub(super) fn parse_response(text: &str) -> Result<CompletionResponse, HarnessError> {
+    let parsed: ApiResponse = serde_json::from_str(text)
+        .map_err(|e| HarnessError::Provider(format!("bad response body: {e}: {text}")))?;
+    let choice = parsed
+        .choices
+        .into_iter()
+        .next()
+        .ok_or_else(|| HarnessError::Provider(format!("response has no choices: {text}")))?;
+
+    let mut content = Vec::new();
+    if let Some(text) = choice.message.content.filter(|t| !t.is_empty()) {
+        content.push(ContentBlock::Text { text });
+    }
+    for call in choice.message.tool_calls.unwrap_or_default() {
+        // A tool with no parameters may come back with empty arguments.
+        let input = if call.function.arguments.trim().is_empty() {
+            serde_json::json!({})
+        } else {
+            serde_json::from_str(&call.function.arguments).map_err(|e| {
+                HarnessError::Provider(format!("bad tool arguments json: {e}: {}", call.function.arguments))
+            })?
+        };
+        content.push(ContentBlock::ToolUse { id: call.id, name: call.function.name, input });
+    }
+
+    // Some compatible servers report "stop" alongside tool calls; the calls
+    // are what the agent loop must act on.
+    let has_tool_calls = content.iter().any(|b| matches!(b, ContentBlock::ToolUse { .. }));
+    let stop_reason = match choice.finish_reason.as_deref() {
+        _ if has_tool_calls => StopReason::ToolUse,
+        Some("stop") => StopReason::EndTurn,
+        Some("length") => StopReason::MaxTokens,
+        _ => StopReason::Unknown,
+    };
+    let usage = parsed
+        .usage
+        .map(|u| {
+            let cached = u.prompt_tokens_details.map_or(0, |d| d.cached_tokens).min(u.prompt_tokens);
+            Usage {
+                input_tokens: u.prompt_tokens - cached,
+                output_tokens: u.completion_tokens,
+                cache_creation_input_tokens: 0,
+                cache_read_input_tokens: cached,
+                thinking_tokens: u.completion_tokens_details.map_or(0, |d| d.reasoning_tokens),
+            }
+        })
+        .unwrap_or_default();
+    Ok(CompletionResponse { content, stop_reason, usage, model: None })
+}
+
+#[async_trait::async_trait]
+impl LlmProvider for OpenAiProvider {
+    async fn complete(&self, req: CompletionRequest) -> Result<CompletionResponse, HarnessError> {
+        let resp = self
+            .client
+            .post(format!("{}/v1/chat/completions", self.base_url))
+            .bearer_auth(&self.api_key)
+            .json(&self.request_body(&req))
+            .send()
+            .await
+            .map_err(|e| HarnessError::ProviderUnreachable(e.to_string()))?;
+
+        if !resp.status().is_success() {
+            return Err(status_error(resp).await);
+        }
+        let text = resp
+            .text()
+            .await
+            .map_err(|e| HarnessError::ProviderUnreachable(e.to_string()))?;
+        let response = parse_response(&text)?;
+        Ok(CompletionResponse { model: Some(self.model.clone()), ..response })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::*;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn request() -> CompletionRequest {
+        CompletionRequest {
+            system: "sys".into(),
+            messages: vec![Message::user_text("hello")],
+            tools: vec![ToolSpec {
+                name: "echo".into(),
+                description: "d".into(),
+                input_schema: serde_json::json!({"type": "object"}),
+            }],
+            max_tokens: 256,
+            tool_choice: None,
+            cache: CacheHints::default(),
+            thinking: None,
+        }
+    }
+
+    #[test]
+    fn image_blocks_go_on_the_wire_beside_the_text() {
+        let messages = vec![Message {
+            role: Role::User,
+            content: vec![ContentBlock::Text { text: "what is this?".into() }, ContentBlock::image("image/png", b"png!")],
+        }];
+        let wire = wire_messages("sys", &messages);
+        assert_eq!(
+            wire[1],
+            serde_json::json!({"role": "user", "content": [
+                {"type": "text", "text": "what is this?"},
+                {"type": "image_url", "image_url": {"url": "data:image/png;base64,cG5nIQ=="}},
+            ]})
+        );
+    }
+
+    #[tokio::test]
+    async fn sends_correct_request_and_parses_response() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .and(header("authorization", "Bearer sk-test"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "choices": [{
+                    "message": {
+                        "role": "assistant",
+                        "content": "hi there",
+                        "tool_calls": [{
+                            "id": "call_9",
+                            "type": "function",
+                            "function": {"name": "echo", "arguments": "{\"text\":\"x\"}"}
+                        }]
+                    },
+                    "finish_reason": "tool_calls"
+                }],
+                "usage": {"prompt_tokens": 42, "completion_tokens": 7, "total_tokens": 49}
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let provider = OpenAiProvider::new("sk-test", "gpt-4o-mini").with_base_url(server.uri());
+        let resp = provider.complete(request()).await.unwrap();
+
+        assert_eq!(resp.stop_reason, StopReason::ToolUse);
+        assert_eq!(resp.usage, Usage { input_tokens: 42, output_tokens: 7, ..Default::default() });
+        assert_eq!(
+            resp.content,
+            vec![
+                ContentBlock::Text { text: "hi there".into() },
+                ContentBlock::ToolUse {
+                    id: "call_9".into(),
+                    name: "echo".into(),
+                    input: serde_json::json!({"text": "x"}),
+                },
+            ]
+        );
+
+        // verify body shape
+        let received = &server.received_requests().await.unwrap()[0];
+        let body: serde_json::Value = serde_json::from_slice(&received.body).unwrap();
+        assert_eq!(body["model"], "gpt-4o-mini");
+        assert_eq!(body["max_tokens"], 256);
+        assert_eq!(body["messages"][0], serde_json::json!({"role": "system", "content": "sys"}));
+        assert_eq!(body["messages"][1], serde_json::json!({"role": "user", "content": "hello"}));
+        assert_eq!(body["tools"][0]["type"], "function");
+        assert_eq!(body["tools"][0]["function"]["name"], "echo");
+        assert_eq!(body["tools"][0]["function"]["parameters"], serde_json::json!({"type": "object"}));
+        assert!(body.get("tool_choice").is_none());
+    }
+
+    #[tokio::test]
+    async fn tool_round_trip_and_forced_choice_map_to_the_wire() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "choices": [{"message": {"role": "assistant", "content": "done"}, "finish_reason": "stop"}],
+                "usage": {"prompt_tokens": 1, "completion_tokens": 1}
+            })))
+            .mount(&server)
+            .await;
+
+        let mut req = request();
+        req.tool_choice = Some("echo".into());
+        req.messages.push(Message {
+            role: Role::Assistant,
+            content: vec![ContentBlock::ToolUse {
+                id: "call_1".into(),
+                name: "echo".into(),
+                input: serde_json::json!({"text": "x"}),
+            }],
+        });
+        req.messages.push(Message {
+            role: Role::User,
+            content: vec![
+                ContentBlock::ToolResult { tool_use_id: "call_1".into(), content: "x".into(), is_error: false },
+                ContentBlock::ToolResult { tool_use_id: "call_2".into(), content: "boom".into(), is_error: true },
+            ],
+        });
+        let provider = OpenAiProvider::new("sk-test", "gpt-4o-mini").with_base_url(server.uri());
+        let resp = provider.complete(req).await.unwrap();
+        assert_eq!(resp.stop_reason, StopReason::EndTurn);
+
+        let received = &server.received_requests().await.unwrap()[0];
+        let body: serde_json::Value = serde_json::from_slice(&received.body).unwrap();
+        assert_eq!(
+            body["tool_choice"],
+            serde_json::json!({"type": "function", "function": {"name": "echo"}})
+        );
+        let messages = body["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 5);
+        assert_eq!(
+            messages[2],
+            serde_json::json!({
+                "role": "assistant",
+                "content": null,
+                "tool_calls": [{
+                    "id": "call_1",
+                    "type": "function",
+                    "function": {"name": "echo", "arguments": "{\"text\":\"x\"}"}
+                }]
+            })
+        );
+        assert_eq!(messages[3], serde_json::json!({"role": "tool", "tool_call_id": "call_1", "content": "x"}));
+        assert_eq!(messages[4]["content"], "Error: boom");
+    }
+
+    #[tokio::test]
+    async fn api_error_maps_to_provider_status_with_body() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .respond_with(ResponseTemplate::new(401).set_body_json(serde_json::json!({
+                "error": {"message": "Incorrect API key provided", "type": "invalid_request_error"}
+            })))
+            .mount(&server)
+            .await;
+
+        let provider = OpenAiProvider::new("bad-key", "gpt-4o-mini").with_base_url(server.uri());
+        let err = provider.complete(request()).await.unwrap_err();
+        assert!(matches!(
+            &err,
+            HarnessError::ProviderStatus { status: 401, body, .. } if body.contains("Incorrect API key")
+        ));
+        assert_eq!(err.provider_kind(), Some(crate::ProviderErrorKind::Auth));
+    }
+
+    #[tokio::test]
+    async fn bad_tool_arguments_are_an_invalid_response() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "choices": [{
+                    "message": {"role": "assistant", "content": null, "tool_calls": [{
+                        "id": "call_1", "type": "function",
+                        "function": {"name": "echo", "arguments": "{\"text\": "}
+                    }]},
+                    "finish_reason": "tool_calls"
+                }]
+            })))
+            .mount(&server)
+            .await;
+
+        let provider = OpenAiProvider::new("sk-test", "gpt-4o-mini").with_base_url(server.uri());
+        let err = provider.complete(request()).await.unwrap_err();
+        assert_eq!(err.provider_kind(), Some(crate::ProviderErrorKind::InvalidResponse));
+    }
+
+    #[test]
+    fn tool_calls_win_over_a_stop_finish_reason_and_missing_usage_is_zero() {
+        let resp = parse_response(
+            &serde_json::json!({
+                "choices": [{
+                    "message": {"content": "", "tool_calls": [{
+                        "id": "call_1", "type": "function",
+                        "function": {"name": "ping", "arguments": ""}
+                    }]},
+                    "finish_reason": "stop"
+                }]
+            })
+            .to_string(),
+        )
+        .unwrap();
+        assert_eq!(resp.stop_reason, StopReason::ToolUse);
+        assert_eq!(
+            resp.content,
+            vec![ContentBlock::ToolUse { id: "call_1".into(), name: "ping".into(), input: serde_json::json!({}) }]
+        );
+        assert_eq!(resp.usage, Usage::default());
+    }
+
+    #[test]
+    fn cached_prompt_tokens_are_split_out_of_input() {
+        let resp = parse_response(
+            &serde_json::json!({
+                "choices": [{"message": {"content": "ok"}, "finish_reason": "stop"}],
+                "usage": {
+                    "prompt_tokens": 2000,
+                    "completion_tokens": 9,
+                    "prompt_tokens_details": {"cached_tokens": 1792}
+                }
+            })
+            .to_string(),
+        )
+        .unwrap();
+        assert_eq!(
+            resp.usage,
+            Usage { input_tokens: 208, output_tokens: 9, cache_creation_input_tokens: 0, cache_read_input_tokens: 1792, thinking_tokens: 0 }
+        );
+    }
+
+    #[tokio::test]
+    async fn base_url_with_trailing_slash_is_normalized() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "choices": [{"message": {"content": "ok"}, "finish_reason": "length"}],
+                "usage": {"prompt_tokens": 1, "completion_tokens": 1}
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let provider =
+            OpenAiProvider::new("sk-test", "gpt-4o-mini").with_base_url(format!("{}/", server.uri()));
+        let resp = provider.complete(request()).await.unwrap();
+        assert_eq!(resp.stop_reason, StopReason::MaxTokens);
+    }
+}
@@ -0,0 +1,424 @@
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use tokio::time::Instant;
+
+use crate::error::HarnessError;
+use crate::llm::{CompletionRequest, CompletionResponse, LlmProvider, StreamEvent, Usage};
+
+/// How hard `RetryingProvider` tries before handing the error back.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt (0 = never retry).
+    pub max_retries: u32,
+    /// Backoff before the first retry; doubles each retry, then jittered.
+    pub base_delay: Duration,
+    /// Cap on a computed backoff. A server `retry-after` is NOT capped — it
+    /// is honored as long as it fits in the deadline.
+    pub max_delay: Duration,
+    /// Wall-clock budget for the whole call, attempts and sleeps included.
+    pub deadline: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_retries: 4,
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(30),
+            // Matches the provider's streamed-call timeout, so retries never
+            // cut short a long summary that is still making progress.
+            deadline: Duration::from_secs(300),
+        }
+    }
+}
+
+/// Running totals across every call made through a `RetryingProvider`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RetryStats {
+    pub calls: u64,
+    pub retries: u64,
+    /// Usage of the calls that succeeded. A failed attempt returns no usage.
+    pub usage: Usage,
+}
+
+/// `LlmProvider` decorator that retries transient failures — rate limiting,
+/// overload, 5xx, and requests that never got an answer — with jittered
+/// exponential backoff, honoring the server's `retry-after`. Anything else
+/// (bad request, auth, unparseable body) comes straight back.
+pub struct RetryingProvider {
+    inner: Arc<dyn LlmProvider>,
+    policy: RetryPolicy,
+    stats: Mutex<RetryStats>,
+}
+
+impl RetryingProvider {
+    pub fn new(inner: Arc<dyn LlmProvider>) -> Self {
+        RetryingProvider { inner, policy: RetryPolicy::default(), stats: Mutex::new(RetryStats::default()) }
+    }
+
+    pub fn with_policy(mut self, policy: RetryPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub fn stats(&self) -> RetryStats {
+        self.stats.lock().unwrap().clone()
+    }
+
+    /// Runs `attempt` until it succeeds, fails fatally, or the policy runs
+    /// out. `may_retry` is checked before each retry — a stream that already
+    /// reached the sink must not be replayed into it.
+    async fn run<F, Fut>(
+        &self,
+        mut attempt: F,
+        may_retry: impl Fn() -> bool,
+    ) -> Result<CompletionResponse, HarnessError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<CompletionResponse, HarnessError>>,
+    {
+        let deadline = Instant::now() + self.policy.deadline;
+        let mut retries = 0u32;
+        let result = loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let err = match tokio::time::timeout(remaining, attempt()).await {
+                Ok(Ok(response)) => break Ok(response),
+                Ok(Err(err)) => err,
+                Err(_) => {
+                    break Err(HarnessError::ProviderUnreachable(format!(
+                        "gave up after {:?} ({retries} retries): deadline exceeded",
+                        self.policy.deadline
+                    )))
+                }
+            };
+            if retries >= self.policy.max_retries || !may_retry() {
+                break Err(err);
+            }
+            let Some(hint) = retry_hint(&err) else { break Err(err) };
+            let delay = hint.unwrap_or_else(|| self.backoff(retries));
+            if Instant::now() + delay >= deadline {
+                break Err(err);
+            }
+            tokio::time::sleep(delay).await;
+            retries += 1;
+        };
+
+        let mut stats = self.stats.lock().unwrap();
+        stats.calls += 1;
+        stats.retries += u64::from(retries);
+        if let Ok(response) = &result {
+            stats.usage.add(&response.usage);
+        }
+        result
+    }
+
+    /// `base_delay * 2^retry`, capped, then jittered into `[delay/2, delay]`
+    /// so clients that failed together don't retry together.
+    fn backoff(&self, retry: u32) -> Duration {
+        let exp = self.policy.base_delay.saturating_mul(1u32 << retry.min(16));
+        let delay = exp.min(self.policy.max_delay);
+        delay.mul_f64(0.5 + 0.5 * jitter())
+    }
+}
+
+/// `None` = fatal. `Some(hint)` = retryable, after the server's
+/// `retry-after` when it sent one.
+fn retry_hint(err: &HarnessError) -> Option<Option<Duration>> {
+    match err {
+        // 408 timeout, 409 lock conflict, 429 rate limited, 5xx incl. 529 overloaded.
+        HarnessError::ProviderStatus { status: 408 | 409 | 429 | 500..=599, retry_after, .. } => {
+            Some(*retry_after)
+        }
+        HarnessError::ProviderUnreachable(_) => Some(None),
+        _ => None,
+    }
+}
+
+/// Whether `RetryingProvider` would retry this error.
+pub fn is_retryable(err: &HarnessError) -> bool {
+    retry_hint(err).is_some()
+}
+
+/// Parses a `retry-after` header value: delay-seconds (fractions tolerated)
+/// or an HTTP-date. A date in the past means "now".
+pub fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<f64>() {
+        return Duration::try_from_secs_f64(secs).ok();
+    }
+    let at = httpdate::parse_http_date(value).ok()?;
+    Some(at.duration_since(SystemTime::now()).unwrap_or(Duration::ZERO))
+}
+
+/// Uniform in `[0, 1)`. `RandomState` is seeded per instance, which is all
+/// the randomness backoff jitter needs.
+fn jitter() -> f64 {
+    use std::hash::{BuildHasher, Hasher};
+    let bits = std::collections::hash_map::RandomState::new().build_hasher().finish();
+    (bits >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[async_trait::async_trait]
+impl LlmProvider for RetryingProvider {
+    async fn complete(&self, req: CompletionRequest) -> Result<CompletionResponse, HarnessError> {
+        self.run(|| self.inner.complete(req.clone()), || true).await
+    }
+
+    async fn stream(
+        &self,
+        req: CompletionRequest,
+        sink: &(dyn Fn(StreamEvent) + Send + Sync),
+    ) -> Result<CompletionResponse, HarnessError> {
+        let emitted = std::sync::atomic::AtomicBool::new(false);
+        let tracking = |event: StreamEvent| {
+            emitted.store(true, std::sync::atomic::Ordering::Relaxed);
+            sink(event);
+        };
+        self.run(
+            || self.inner.stream(req.clone(), &tracking),
+            || !emitted.load(std::sync::atomic::Ordering::Relaxed),
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::*;
+    use crate::providers::AnthropicProvider;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn request() -> CompletionRequest {
+        CompletionRequest {
+            system: "sys".into(),
+            messages: vec![Message::user_text("hi")],
+            tools: vec![],
+            max_tokens: 64,
+            tool_choice: None,
+            cache: CacheHints::default(),
+            thinking: None,
+        }
+    }
+
+    fn fast() -> RetryPolicy {
+        RetryPolicy {
+            max_retries: 3,
+            base_delay: Duration::from_millis(5),
+            max_delay: Duration::from_millis(20),
+            deadline: Duration::from_secs(10),
+        }
+    }
+
+    fn ok_body() -> serde_json::Value {
+        serde_json::json!({
+            "content": [{"type": "text", "text": "ok"}],
+            "stop_reason": "end_turn",
+            "usage": {"input_tokens": 12, "output_tokens": 3}
+        })
+    }
+
+    fn retrying(server: &MockServer, policy: RetryPolicy) -> RetryingProvider {
+        let inner = AnthropicProvider::new("sk-test", "claude-haiku-4-5").with_base_url(server.uri());
+        RetryingProvider::new(Arc::new(inner)).with_policy(policy)
+    }
+
+    #[tokio::test]
+    async fn overloaded_then_ok_is_retried_and_reports_retries_and_usage() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/messages"))
+            .respond_with(ResponseTemplate::new(529).set_body_string("overloaded"))
+            .up_to_n_times(2)
+            .expect(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/v1/messages"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(ok_body()))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let provider = retrying(&server, fast());
+        let resp = provider.complete(request()).await.unwrap();
+        assert_eq!(resp.content, vec![ContentBlock::Text { text: "ok".into() }]);
+        assert_eq!(
+            provider.stats(),
+            RetryStats { calls: 1, retries: 2, usage: Usage { input_tokens: 12, output_tokens: 3, ..Default::default() } }
+        );
+    }
+
+    #[tokio::test]
+    async fn bad_request_is_fatal_and_not_retried() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(400).set_body_string("max_tokens too large"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let provider = retrying(&server, fast());
+        let err = provider.complete(request()).await.unwrap_err();
+        assert!(matches!(err, HarnessError::ProviderStatus { status: 400, .. }));
+        assert_eq!(provider.stats(), RetryStats { calls: 1, retries: 0, usage: Usage::default() });
+    }
+
+    #[tokio::test]
+    async fn retry_after_header_is_honored_over_the_backoff() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(429).insert_header("retry-after", "1"))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(ok_body()))
+            .mount(&server)
+            .await;
+
+        let provider = retrying(&server, fast());
+        let started = std::time::Instant::now();
+        provider.complete(request()).await.unwrap();
+        assert!(started.elapsed() >= Duration::from_secs(1), "slept only {:?}", started.elapsed());
+        assert_eq!(provider.stats().retries, 1);
+    }
+
+    #[tokio::test]
+    async fn gives_up_with_the_last_error_when_retries_run_out() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(503).set_body_string("unavailable"))
+            .expect(4)
+            .mount(&server)
+            .await;
+
+        let provider = retrying(&server, fast());
+        let err = provider.complete(request()).await.unwrap_err();
+        assert!(matches!(err, HarnessError::ProviderStatus { status: 503, .. }));
+        assert_eq!(provider.stats().retries, 3);
+    }
+
+    #[tokio::test]
+    async fn retry_after_beyond_the_deadline_gives_up_without_sleeping() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(429).insert_header("retry-after", "60"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let provider = retrying(&server, RetryPolicy { deadline: Duration::from_secs(5), ..fast() });
+        let started = std::time::Instant::now();
+        let err = provider.complete(request()).await.unwrap_err();
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert!(matches!(err, HarnessError::ProviderStatus { status: 429, .. }));
+    }
+
+    #[tokio::test]
+    async fn a_hung_attempt_is_cut_off_at_the_deadline() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(ok_body()).set_delay(Duration::from_secs(5)))
+            .mount(&server)
+            .await;
+
+        let provider =
+            retrying(&server, RetryPolicy { deadline: Duration::from_millis(200), ..fast() });
+        let err = provider.complete(request()).await.unwrap_err();
+        assert!(matches!(err, HarnessError::ProviderUnreachable(msg) if msg.contains("deadline")));
+    }
+
+    #[tokio::test]
+    async fn stream_is_retried_before_output_but_never_after() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(529))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        let sse = concat!(
+            "event: message_start\ndata: {\"type\":\"message_start\",\"message\":{\"usage\":{\"input_tokens\":5,\"output_tokens\":0}}}\n\n",
+            "event: content_block_start\ndata: {\"type\":\"content_block_start\",\"index\":0,\"content_block\":{\"type\":\"text\",\"text\":\"\"}}\n\n",
+            "event: content_block_delta\ndata: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"ok\"}}\n\n",
+            "event: message_delta\ndata: {\"type\":\"message_delta\",\"delta\":{\"stop_reason\":\"end_turn\"},\"usage\":{\"output_tokens\":1}}\n\n",
+        );
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(sse, "text/event-stream"))
+            .mount(&server)
+            .await;
+
+        let provider = retrying(&server, fast());
+        let events = Mutex::new(Vec::new());
+        let resp = provider.stream(request(), &|e| events.lock().unwrap().push(e)).await.unwrap();
+        assert_eq!(resp.usage, Usage { input_tokens: 5, output_tokens: 1, ..Default::default() });
+        assert_eq!(provider.stats().retries, 1);
+        assert_eq!(events.lock().unwrap().len(), 2, "one text delta + usage, not replayed");
+
+        // A failure after deltas reached the sink comes straight back.
+        struct DropsMidStream;
+        #[async_trait::async_trait]
+        impl LlmProvider for DropsMidStream {
+            async fn complete(&self, _: CompletionRequest) -> Result<CompletionResponse, HarnessError> {
+                unreachable!()
+            }
+            async fn stream(
+                &self,
+                _: CompletionRequest,
+                sink: &(dyn Fn(StreamEvent) + Send + Sync),
+            ) -> Result<CompletionResponse, HarnessError> {
+                sink(StreamEvent::TextDelta { index: 0, text: "par".into() });
+                Err(HarnessError::ProviderUnreachable("connection reset".into()))
+            }
+        }
+        let provider = RetryingProvider::new(Arc::new(DropsMidStream)).with_policy(fast());
+        let err = provider.stream(request(), &|_| {}).await.unwrap_err();
+        assert!(matches!(err, HarnessError::ProviderUnreachable(_)));
+        assert_eq!(provider.stats().retries, 0);
+    }
+
+    #[test]
+    fn classifies_transient_failures_as_retryable() {
+        let status = |status| HarnessError::ProviderStatus { status, retry_after: None, body: String::new() };
+        for code in [408, 409, 429, 500, 502, 503, 529] {
+            assert!(is_retryable(&status(code)), "{code}");
+        }
+        for code in [400, 401, 403, 404, 413] {
+            assert!(!is_retryable(&status(code)), "{code}");
+        }
+        assert!(is_retryable(&HarnessError::ProviderUnreachable("timed out".into())));
+        assert!(!is_retryable(&HarnessError::Provider("bad response body".into())));
+        assert!(!is_retryable(&HarnessError::MaxTurns(3)));
+    }
+
+    #[test]
+    fn parses_retry_after_seconds_and_http_dates() {
+        assert_eq!(parse_retry_after("7"), Some(Duration::from_secs(7)));
+        assert_eq!(parse_retry_after(" 1.5 "), Some(Duration::from_millis(1500)));
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"), Some(Duration::ZERO));
+        let soon = httpdate::fmt_http_date(SystemTime::now() + Duration::from_secs(30));
+        let parsed = parse_retry_after(&soon).unwrap();
+        assert!(parsed > Duration::from_secs(25) && parsed <= Duration::from_secs(30), "{parsed:?}");
+        assert_eq!(parse_retry_after("-1"), None);
+        assert_eq!(parse_retry_after("soon"), None);
+    }
+
+    #[test]
+    fn backoff_doubles_within_jitter_and_respects_the_cap() {
+        let provider = RetryingProvider::new(Arc::new(crate::MockProvider::new(vec![]))).with_policy(RetryPolicy {
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(300),
+            ..RetryPolicy::default()
+        });
+        for _ in 0..20 {
+            let first = provider.backoff(0);
+            assert!(first >= Duration::from_millis(50) && first <= Duration::from_millis(100));
+            let second = provider.backoff(1);
+            assert!(second >= Duration::from_millis(100) && second <= Duration::from_millis(200));
+            assert!(provider.backoff(10) <= Duration::from_millis(300));
+        }
+    }
+}
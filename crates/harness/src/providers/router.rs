@@ -0,0 +1,261 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use crate::budget::estimate_prompt_tokens;
+use crate::error::HarnessError;
+use crate::llm::{CompletionRequest, CompletionResponse, LlmProvider, StreamEvent};
+use crate::providers::retry::is_retryable;
+
+/// Sends requests small enough to `model` first — e.g. a short transcript
+/// with few tools to a cheaper model. Every limit is optional; a rule with
+/// none matches everything.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RouteRule {
+    pub model: String,
+    /// Estimated prompt size: system, messages and tool specs as sent.
+    pub max_prompt_tokens: Option<u64>,
+    pub max_tools: Option<usize>,
+}
+
+impl RouteRule {
+    fn matches(&self, request: &CompletionRequest) -> bool {
+        self.max_tools.is_none_or(|max| request.tools.len() <= max)
+            && self.max_prompt_tokens.is_none_or(|max| estimate_prompt_tokens(request) <= max)
+    }
+}
+
+/// `LlmProvider` over an ordered chain of models. Each call starts at the
+/// model the first matching `RouteRule` picks (the head of the chain when
+/// none does) and falls through the rest of the chain, in order, on errors
+/// `RetryingProvider` would retry — so wrap each route in one to retry a
+/// model before giving up on it. Anything else comes straight back, as does
+/// the last route's error. Responses name the model that served them.
+pub struct RoutingProvider {
+    routes: Vec<(String, Arc<dyn LlmProvider>)>,
+    rules: Vec<RouteRule>,
+}
+
+impl RoutingProvider {
+    pub fn new(model: impl Into<String>, provider: Arc<dyn LlmProvider>) -> Self {
+        RoutingProvider { routes: vec![(model.into(), provider)], rules: Vec::new() }
+    }
+
+    /// Appends `model` to the chain, tried after every route before it.
+    pub fn with_fallback(mut self, model: impl Into<String>, provider: Arc<dyn LlmProvider>) -> Self {
+        self.routes.push((model.into(), provider));
+        self
+    }
+
+    /// Rules are checked in the order added. A rule naming a model that
+    /// isn't in the chain never matches.
+    pub fn with_rule(mut self, rule: RouteRule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    /// The chain for `request`: the picked route first, the rest in order.
+    fn order(&self, request: &CompletionRequest) -> Vec<&(String, Arc<dyn LlmProvider>)> {
+        let first = self
+            .rules
+            .iter()
+            .filter(|rule| rule.matches(request))
+            .find_map(|rule| self.routes.iter().position(|(model, _)| *model == rule.model))
+            .unwrap_or(0);
+        let mut order = vec![&self.routes[first]];
+        order.extend(self.routes.iter().enumerate().filter(|(i, _)| *i != first).map(|(_, route)| route));
+        order
+    }
+}
+
+/// Stamps the route's model on a response whose provider didn't name one.
+fn served(model: &str, mut response: CompletionResponse) -> CompletionResponse {
+    response.model.get_or_insert_with(|| model.to_string());
+    response
+}
+
+#[async_trait::async_trait]
+impl LlmProvider for RoutingProvider {
+    async fn complete(&self, req: CompletionRequest) -> Result<CompletionResponse, HarnessError> {
+        let order = self.order(&req);
+        let (last, fallbacks) = order.split_last().expect("a routing chain is never empty");
+        for (model, provider) in fallbacks {
+            match provider.complete(req.clone()).await {
+                Ok(response) => return Ok(served(model, response)),
+                Err(err) if is_retryable(&err) => continue,
+                Err(err) => return Err(err),
+            }
+        }
+        let (model, provider) = last;
+        provider.complete(req).await.map(|response| served(model, response))
+    }
+
+    async fn stream(
+        &self,
+        req: CompletionRequest,
+        sink: &(dyn Fn(StreamEvent) + Send + Sync),
+    ) -> Result<CompletionResponse, HarnessError> {
+        // A stream that already reached the sink can't be restarted on
+        // another model without the sink seeing both.
+        let emitted = AtomicBool::new(false);
+        let tracking = |event: StreamEvent| {
+            emitted.store(true, Ordering::Relaxed);
+            sink(event);
+        };
+        let order = self.order(&req);
+        let (last, fallbacks) = order.split_last().expect("a routing chain is never empty");
+        for (model, provider) in fallbacks {
+            match provider.stream(req.clone(), &tracking).await {
+                Ok(response) => return Ok(served(model, response)),
+                Err(err) if is_retryable(&err) && !emitted.load(Ordering::Relaxed) => continue,
+                Err(err) => return Err(err),
+            }
+        }
+        let (model, provider) = last;
+        provider.stream(req, &tracking).await.map(|response| served(model, response))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::llm::*;
+    use crate::mock::MockProvider;
+
+    fn request(tools: usize, text: &str) -> CompletionRequest {
+        CompletionRequest {
+            system: "sys".into(),
+            messages: vec![Message::user_text(text)],
+            tools: (0..tools)
+                .map(|i| ToolSpec {
+                    name: format!("tool_{i}"),
+                    description: "d".into(),
+                    input_schema: serde_json::json!({"type": "object"}),
+                })
+                .collect(),
+            max_tokens: 64,
+            tool_choice: None,
+            cache: CacheHints::default(),
+            thinking: None,
+        }
+    }
+
+    fn text(t: &str) -> CompletionResponse {
+        CompletionResponse {
+            content: vec![ContentBlock::Text { text: t.into() }],
+            stop_reason: StopReason::EndTurn,
+            usage: Usage { input_tokens: 5, output_tokens: 1, ..Default::default() },
+            model: None,
+        }
+    }
+
+    /// Fails every call with `status`, optionally after streaming a delta.
+    struct Failing {
+        status: u16,
+        emit_first: bool,
+        calls: Mutex<usize>,
+    }
+
+    impl Failing {
+        fn new(status: u16) -> Arc<Self> {
+            Arc::new(Failing { status, emit_first: false, calls: Mutex::new(0) })
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl LlmProvider for Failing {
+        async fn complete(&self, _: CompletionRequest) -> Result<CompletionResponse, HarnessError> {
+            *self.calls.lock().unwrap() += 1;
+            Err(HarnessError::ProviderStatus { status: self.status, retry_after: None, body: "nope".into() })
+        }
+
+        async fn stream(
+            &self,
+            req: CompletionRequest,
+            sink: &(dyn Fn(StreamEvent) + Send + Sync),
+        ) -> Result<CompletionResponse, HarnessError> {
+            if self.emit_first {
+                sink(StreamEvent::TextDelta { index: 0, text: "par".into() });
+            }
+            self.complete(req).await
+        }
+    }
+
+    #[tokio::test]
+    async fn an_overloaded_model_falls_through_to_the_next_and_names_it() {
+        let sonnet = Failing::new(529);
+        let haiku = Arc::new(MockProvider::new(vec![text("from haiku")]));
+        let router = RoutingProvider::new("claude-sonnet-4-5", sonnet.clone())
+            .with_fallback("claude-haiku-4-5", haiku.clone());
+
+        let response = router.complete(request(1, "hi")).await.unwrap();
+        assert_eq!(response.content, vec![ContentBlock::Text { text: "from haiku".into() }]);
+        assert_eq!(response.model.as_deref(), Some("claude-haiku-4-5"));
+        assert_eq!((*sonnet.calls.lock().unwrap(), haiku.requests().len()), (1, 1));
+    }
+
+    #[tokio::test]
+    async fn fatal_errors_and_the_last_routes_error_come_straight_back() {
+        let haiku = Arc::new(MockProvider::new(vec![text("unused")]));
+        let router = RoutingProvider::new("claude-sonnet-4-5", Failing::new(400))
+            .with_fallback("claude-haiku-4-5", haiku.clone());
+        let err = router.complete(request(0, "hi")).await.unwrap_err();
+        assert!(matches!(err, HarnessError::ProviderStatus { status: 400, .. }));
+        assert!(haiku.requests().is_empty(), "a bad request would fail on every model");
+
+        let router = RoutingProvider::new("claude-sonnet-4-5", Failing::new(529))
+            .with_fallback("claude-haiku-4-5", Failing::new(503));
+        let err = router.complete(request(0, "hi")).await.unwrap_err();
+        assert!(matches!(err, HarnessError::ProviderStatus { status: 503, .. }));
+    }
+
+    #[tokio::test]
+    async fn rules_pick_the_starting_model_from_prompt_size_and_tool_count() {
+        let sonnet = Arc::new(MockProvider::new(vec![text("big"), text("many tools")]));
+        let haiku = Arc::new(MockProvider::new(vec![text("small")]));
+        let router = RoutingProvider::new("claude-sonnet-4-5", sonnet.clone())
+            .with_fallback("claude-haiku-4-5", haiku.clone())
+            .with_rule(RouteRule {
+                model: "claude-haiku-4-5".into(),
+                max_prompt_tokens: Some(200),
+                max_tools: Some(2),
+            });
+
+        let small = router.complete(request(2, "short walk")).await.unwrap();
+        assert_eq!(small.model.as_deref(), Some("claude-haiku-4-5"));
+        let big = router.complete(request(2, &"long walk ".repeat(200))).await.unwrap();
+        assert_eq!(big.model.as_deref(), Some("claude-sonnet-4-5"));
+        let tools = router.complete(request(3, "short walk")).await.unwrap();
+        assert_eq!(tools.model.as_deref(), Some("claude-sonnet-4-5"));
+        assert_eq!((sonnet.requests().len(), haiku.requests().len()), (2, 1));
+    }
+
+    #[tokio::test]
+    async fn a_picked_model_that_fails_falls_back_to_the_head_of_the_chain() {
+        let sonnet = Arc::new(MockProvider::new(vec![text("from sonnet")]));
+        let router = RoutingProvider::new("claude-sonnet-4-5", sonnet)
+            .with_fallback("claude-haiku-4-5", Failing::new(429))
+            .with_rule(RouteRule { model: "claude-haiku-4-5".into(), ..Default::default() });
+        let response = router.complete(request(0, "hi")).await.unwrap();
+        assert_eq!(response.model.as_deref(), Some("claude-sonnet-4-5"));
+    }
+
+    #[tokio::test]
+    async fn a_stream_that_reached_the_sink_never_falls_through() {
+        let events = Mutex::new(Vec::new());
+        let sink = |e: StreamEvent| events.lock().unwrap().push(e);
+
+        let router = RoutingProvider::new("claude-sonnet-4-5", Failing::new(529))
+            .with_fallback("claude-haiku-4-5", Arc::new(MockProvider::new(vec![text("ok")])));
+        let response = router.stream(request(0, "hi"), &sink).await.unwrap();
+        assert_eq!(response.model.as_deref(), Some("claude-haiku-4-5"));
+
+        let midway = Arc::new(Failing { status: 529, emit_first: true, calls: Mutex::new(0) });
+        let haiku = Arc::new(MockProvider::new(vec![text("ok")]));
+        let router = RoutingProvider::new("claude-sonnet-4-5", midway).with_fallback("claude-haiku-4-5", haiku.clone());
+        let err = router.stream(request(0, "hi"), &sink).await.unwrap_err();
+        assert!(matches!(err, HarnessError::ProviderStatus { status: 529, .. }));
+        assert!(haiku.requests().is_empty());
+    }
+}
@@ -6,12 +6,15 @@
 use std::collections::BTreeSet;
 use std::sync::Arc;
 
-use crate::agent::RunError;
+use crate::agent::{RunError, ServedBy};
+use crate::cancel::CancelToken;
 use crate::error::HarnessError;
 use crate::llm::{
-    CompletionRequest, ContentBlock, LlmProvider, Message, ToolSpec, Usage,
+    CacheHints, CompletionRequest, ContentBlock, LlmProvider, Message, ToolSpec, Usage,
 };
-use crate::memory::{is_internal_section, FactSource, Memory, DEFAULT_WORD_CAP};
+use crate::memory::fact::TypedFact;
+use crate::memory::{is_internal_section, FactSource, Memory, MemoryEntry, DEFAULT_WORD_CAP};
+use crate::observer::{observed_call, SharedObserver};
 
 const WRITE_MEMORY: &str = "write_memory";
 
@@ -22,17 +25,41 @@ pub struct ReflectionOutcome {
     /// Measured after word-cap clamping — actual-memory churn, not LLM-intent churn.
     pub churn: f32,
     pub usage: Usage,
+    /// The model that served the reflection call, with its spend.
+    pub model: ServedBy,
 }
 
 pub struct ReflectionEngine {
     provider: Arc<dyn LlmProvider>,
     pub word_cap: usize,
     pub max_tokens: u32,
+    observer: Option<SharedObserver>,
+    cancel: CancelToken,
 }
 
 impl ReflectionEngine {
     pub fn new(provider: Arc<dyn LlmProvider>) -> Self {
-        ReflectionEngine { provider, word_cap: DEFAULT_WORD_CAP, max_tokens: 2048 }
+        ReflectionEngine {
+            provider,
+            word_cap: DEFAULT_WORD_CAP,
+            max_tokens: 2048,
+            observer: None,
+            cancel: CancelToken::default(),
+        }
+    }
+
+    /// Reports each reflection's provider call to `observer` as a
+    /// "reflection" run.
+    pub fn with_observer(mut self, observer: SharedObserver) -> Self {
+        self.observer = Some(observer);
+        self
+    }
+
+    /// Abandons the reflection call when `cancel` fires; `reflect` then
+    /// fails with `HarnessError::Cancelled` and zero usage.
+    pub fn with_cancel(mut self, cancel: CancelToken) -> Self {
+        self.cancel = cancel;
+        self
     }
 
     fn tool_spec(&self) -> ToolSpec {
@@ -45,8 +72,11 @@ impl ReflectionEngine {
                 "properties": {
                     "sections": {
                         "type": "object",
-                        "description": "section name -> list of short fact strings",
-                        "additionalProperties": { "type": "array", "items": { "type": "string" } }
+                        "description": "section name -> list of short fact strings, or typed facts",
+                        "additionalProperties": {
+                            "type": "array",
+                            "items": { "anyOf": [{ "type": "string" }, TypedFact::schema()] }
+                        }
                     }
                 },
                 "required": ["sections"]
@@ -64,7 +94,9 @@ impl ReflectionEngine {
              them. When recent activity contradicts an existing fact, drop the stale fact \
              and write the corrected one; never merge the two into a blended claim. Facts \
              marked [corrected] are user corrections and outrank everything else — do not \
-             drop or alter them. Typical sections: vocabulary, people, projects, \
+             drop or alter them. Lines tagged [person], [price] or [preference] are typed \
+             facts: keep them verbatim, tag included, or write the changed fact as an \
+             object with its kind and fields. Typical sections: vocabulary, people, projects, \
              preferences. Vocabulary terms are domain jargon that improve transcription \
              accuracy — preserve them verbatim and drop a vocabulary term only if it is \
              clearly a transcription artifact, not a real term. Call {} exactly once with \
@@ -115,21 +147,27 @@ impl ReflectionEngine {
             self.memory_block(current)
         );
 
+        let request = CompletionRequest {
+            system: self.system_prompt(),
+            messages: vec![Message::user_text(user)],
+            tools: vec![self.tool_spec()],
+            max_tokens: self.max_tokens,
+            tool_choice: Some(WRITE_MEMORY.into()),
+            cache: CacheHints::default(),
+            thinking: None,
+        };
+        let call = observed_call(self.provider.as_ref(), request, None, self.observer.as_deref(), "reflection");
         let response = self
-            .provider
-            .complete(CompletionRequest {
-                system: self.system_prompt(),
-                messages: vec![Message::user_text(user)],
-                tools: vec![self.tool_spec()],
-                max_tokens: self.max_tokens,
-                tool_choice: Some(WRITE_MEMORY.into()),
-            })
+            .cancel
+            .guard(Usage::default(), call)
             .await
-            .map_err(|e| RunError { source: e, usage: Usage::default() })?;
+            .map_err(|e| RunError { source: e, usage: Usage::default(), model: ServedBy::default() })?;
 
         // Capture usage now: every post-completion error path below carries it
         // so the coordinator can log what was burned even on content failure.
         let response_usage = response.usage;
+        let mut model = ServedBy::default();
+        model.add(response.model.as_deref(), &response_usage);
 
         let input = response
             .content
@@ -143,6 +181,7 @@ impl ReflectionEngine {
                     "reflection response missing write_memory call".into(),
                 ),
                 usage: response_usage,
+                model: model.clone(),
             })?;
         let sections = input
             .get("sections")
@@ -153,23 +192,51 @@ impl ReflectionEngine {
                     "write_memory call had malformed sections".into(),
                 ),
                 usage: response_usage,
+                model: model.clone(),
             })?;
 
         let mut memory = Memory::default();
         for (section, texts) in &sections {
             // Non-array section values drop the section; next reflection repopulates.
             let Some(texts) = texts.as_array() else { continue };
-            for text in texts.iter().filter_map(|t| t.as_str()) {
+            for item in texts {
+                let prior_of = |text: &str| -> Option<MemoryEntry> {
+                    current.sections.get(section)?.iter().find(|e| e.text == text || e.prompt_text() == text).cloned()
+                };
+                if let Some(text) = item.as_str() {
+                    match prior_of(text) {
+                        Some(e) => match e.fact {
+                            Some(fact) => memory.remember_fact(section, fact, e.last_touched, e.source, e.session),
+                            None => memory.remember_from(section, &e.text, e.last_touched, e.source, e.session),
+                        },
+                        // A typed line the model edited as text has lost its
+                        // fields; keep the text, not the tag.
+                        None => memory.remember_from(section, untagged(text), now, FactSource::Inferred, None),
+                    }
+                    continue;
+                }
+                // Anything else must be a typed fact; one that isn't is dropped.
+                let Some(fact) = serde_json::from_value::<TypedFact>(item.clone()).ok().filter(TypedFact::is_valid)
+                else {
+                    continue;
+                };
+                // The prior entry about the same subject keeps its provenance
+                // through a changed detail (a phone added, a price raised);
+                // only an unchanged fact keeps its age too.
                 let prior = current
                     .sections
                     .get(section)
-                    .and_then(|es| es.iter().find(|e| e.text == text))
-                    .cloned();
+                    .and_then(|entries| {
+                        entries.iter().find(|e| e.fact.as_ref().is_some_and(|prior| prior.same_subject(&fact)))
+                    })
+                    .cloned()
+                    .or_else(|| prior_of(&fact.summary()));
                 match prior {
                     Some(e) => {
-                        memory.remember_from(section, text, e.last_touched, e.source, e.session)
+                        let touched = if e.text == fact.summary() { e.last_touched } else { now };
+                        memory.remember_fact(section, fact, touched, e.source, e.session)
                     }
-                    None => memory.remember_from(section, text, now, FactSource::Inferred, None),
+                    None => memory.remember_fact(section, fact, now, FactSource::Inferred, None),
                 }
             }
         }
@@ -194,15 +261,21 @@ impl ReflectionEngine {
                     "reflection produced empty memory from non-empty input".into(),
                 ),
                 usage: response_usage,
+                model: model.clone(),
             });
         }
         memory.clamp_to_cap(self.word_cap);
 
         let churn = churn_between(current, &memory);
-        Ok(ReflectionOutcome { memory, churn, usage: response_usage })
+        Ok(ReflectionOutcome { memory, churn, usage: response_usage, model })
     }
 }
 
+/// `text` without a leading typed-fact tag (`TypedFact::prompt_line`).
+fn untagged(text: &str) -> &str {
+    ["[person] ", "[price] ", "[preference] "].iter().find_map(|tag| text.strip_prefix(tag)).unwrap_or(text)
+}
+
 /// Whether `m` holds any non-internal, non-empty section — the "real content"
 /// predicate shared by the empty-wipe guard and `memory_block` (Plan 15 D5-15).
 fn has_non_internal_content(m: &Memory) -> bool {
@@ -247,7 +320,8 @@ mod tests {
                 input: serde_json::json!({ "sections": sections }),
             }],
             stop_reason: StopReason::ToolUse,
-            usage: Usage { input_tokens: 100, output_tokens: 50 },
+            usage: Usage { input_tokens: 100, output_tokens: 50, ..Default::default() },
+            model: None,
         }
     }
 
@@ -280,6 +354,7 @@ mod tests {
                 last_touched: 111,
                 source: FactSource::Corrected,
                 session: Some("s1".into()),
+                fact: None,
             }
         );
         // new fact: Inferred, no session, touched now
@@ -290,9 +365,10 @@ mod tests {
                 last_touched: 999,
                 source: FactSource::Inferred,
                 session: None,
+                fact: None,
             }
         );
-        assert_eq!(out.usage, Usage { input_tokens: 100, output_tokens: 50 });
+        assert_eq!(out.usage, Usage { input_tokens: 100, output_tokens: 50, ..Default::default() });
 
         // request shape: forced tool, memory + activity present, corrected marker rendered
         let reqs = provider.requests();
@@ -306,6 +382,57 @@ mod tests {
         assert!(text.contains("walked the Johnson site"));
     }
 
+    #[tokio::test]
+    async fn typed_facts_survive_reflection_verbatim_or_as_objects() {
+        let sod = TypedFact::Price { item: "sod".into(), unit: Some("sq ft".into()), amount_cents: 110 };
+        let dave = TypedFact::Person { name: "Dave".into(), role: Some("irrigation sub".into()), phone: None };
+        let mut current = Memory::default();
+        current.remember_fact("pricing", sod.clone(), 111, FactSource::Stated, Some("s1".into()));
+        current.remember_fact("people", dave, 222, FactSource::Stated, Some("s2".into()));
+        current.remember_fact(
+            "preferences",
+            TypedFact::Preference { topic: "mulch".into(), value: "dark".into() },
+            333,
+            FactSource::Inferred,
+            None,
+        );
+        let dave_now = serde_json::json!({
+            "kind": "person", "name": "Dave", "role": "irrigation sub", "phone": "555-0142"
+        });
+        let provider = Arc::new(MockProvider::new(vec![write_memory_response(serde_json::json!({
+            "pricing": ["[price] sod — $1.10/sq ft", {"kind": "price", "item": "", "amount_cents": 5}],
+            "people": [dave_now],
+            "preferences": ["[preference] mulch: dark brown"]
+        }))]));
+        let out = ReflectionEngine::new(provider.clone()).reflect(&current, &[], 999).await.unwrap();
+
+        // echoed verbatim: the whole prior entry, fact and provenance, survives
+        assert_eq!(out.memory.sections["pricing"], current.sections["pricing"]);
+        let dave = &out.memory.sections["people"][0];
+        assert_eq!(dave.text, "Dave — irrigation sub — 555-0142");
+        assert_eq!(serde_json::to_value(dave.fact.as_ref().unwrap()).unwrap(), dave_now);
+        // a changed detail keeps the source and session, touched now
+        assert_eq!((dave.source, dave.session.as_deref(), dave.last_touched), (FactSource::Stated, Some("s2"), 999));
+        let mulch = &out.memory.sections["preferences"][0];
+        assert_eq!((mulch.text.as_str(), mulch.fact.as_ref()), ("mulch: dark brown", None));
+
+        let ContentBlock::Text { text } = &provider.requests()[0].messages[0].content[0] else {
+            panic!("expected text block")
+        };
+        assert!(text.contains("- [price] sod — $1.10/sq ft\n"));
+    }
+
+    #[tokio::test]
+    async fn a_cancelled_reflection_never_calls_the_provider() {
+        let provider = Arc::new(MockProvider::new(vec![]));
+        let cancel = CancelToken::new();
+        cancel.cancel();
+        let engine = ReflectionEngine::new(provider.clone()).with_cancel(cancel);
+        let err = engine.reflect(&current_memory(), &["walked".into()], 999).await.unwrap_err();
+        assert!(matches!(err.source, HarnessError::Cancelled { usage } if usage == Usage::default()));
+        assert!(provider.requests().is_empty());
+    }
+
     #[tokio::test]
     async fn churn_measures_added_plus_removed() {
         // old: {Dev, Dave}; new: {Dev, Sara} → added 1, removed 1, sizes 2+2 → churn 0.5
@@ -354,6 +481,7 @@ mod tests {
             content: vec![ContentBlock::Text { text: "I decline".into() }],
             stop_reason: StopReason::EndTurn,
             usage: Usage::default(),
+            model: None,
         }]));
         let engine = ReflectionEngine::new(provider);
         let err = engine.reflect(&Memory::default(), &[], 999).await.unwrap_err();
@@ -374,7 +502,8 @@ mod tests {
                 input: serde_json::json!({ "sections": "not an object" }),
             }],
             stop_reason: StopReason::ToolUse,
-            usage: Usage { input_tokens: 77, output_tokens: 11 },
+            usage: Usage { input_tokens: 77, output_tokens: 11, ..Default::default() },
+            model: None,
         }]));
         let engine = ReflectionEngine::new(provider);
         let err = engine.reflect(&Memory::default(), &[], 999).await.unwrap_err();
@@ -382,7 +511,7 @@ mod tests {
             matches!(&err.source, HarnessError::Provider(msg) if msg.contains("malformed sections"))
         );
         // post-completion failure: usage from the completed response is preserved
-        assert_eq!(err.usage, Usage { input_tokens: 77, output_tokens: 11 });
+        assert_eq!(err.usage, Usage { input_tokens: 77, output_tokens: 11, ..Default::default() });
     }
 
     #[tokio::test]
@@ -395,8 +524,8 @@ mod tests {
         assert!(
             matches!(&err.source, HarnessError::Provider(msg) if msg.contains("empty memory"))
         );
-        // write_memory_response uses Usage { input_tokens: 100, output_tokens: 50 }
-        assert_eq!(err.usage, Usage { input_tokens: 100, output_tokens: 50 });
+        // write_memory_response uses Usage { input_tokens: 100, output_tokens: 50, ..Default::default() }
+        assert_eq!(err.usage, Usage { input_tokens: 100, output_tokens: 50, ..Default::default() });
     }
 
     #[test]
@@ -0,0 +1,426 @@
+//! Record/replay cassettes: run a pipeline once against a real provider with
+//! a recording `ReplayProvider`, commit the cassette, then re-run it offline
+//! (CI, evals) with a replaying one — real model behavior, no key, no network.
+//!
+//! Interactions are keyed by a hash of the normalized request. Normalization
+//! drops what legitimately differs between runs and can't change the answer:
+//! cache hints, and UUIDs (store ids), which are renumbered by first
+//! appearance. Everything else must match exactly — a prompt edit is a new
+//! request, and replay fails loudly instead of serving a stale answer.
+//! Ids the model echoes back (a priced `item_id`) are stored as the same
+//! placeholders and replayed as this run's ids.
+
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::HarnessError;
+use crate::llm::{
+    emit_response_events, CompletionRequest, CompletionResponse, ContentBlock, LlmProvider,
+    StopReason, StreamEvent, Usage,
+};
+
+/// Set to `1` to re-record cassettes against the real provider.
+pub const RECORD_ENV: &str = "MURMUR_RECORD_CASSETTES";
+
+const CASSETTE_VERSION: u32 = 1;
+
+#[derive(Default, Serialize, Deserialize)]
+struct Cassette {
+    version: u32,
+    interactions: Vec<Interaction>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Interaction {
+    key: String,
+    /// The normalized request, kept for reviewing and diffing cassettes.
+    request: serde_json::Value,
+    response: RecordedResponse,
+}
+
+#[derive(Serialize, Deserialize)]
+struct RecordedResponse {
+    content: Vec<ContentBlock>,
+    stop_reason: StopReason,
+    usage: Usage,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    model: Option<String>,
+}
+
+enum Mode {
+    Record(Arc<dyn LlmProvider>),
+    /// One flag per interaction: already served.
+    Replay(Mutex<Vec<bool>>),
+}
+
+pub struct ReplayProvider {
+    mode: Mode,
+    path: PathBuf,
+    cassette: Mutex<Cassette>,
+}
+
+impl ReplayProvider {
+    /// Forwards every call to `inner` and records it, rewriting the cassette
+    /// at `path` after each call (an aborted run keeps what it captured).
+    /// Failed calls aren't recorded.
+    pub fn recording(inner: Arc<dyn LlmProvider>, path: impl Into<PathBuf>) -> Self {
+        ReplayProvider {
+            mode: Mode::Record(inner),
+            path: path.into(),
+            cassette: Mutex::new(Cassette { version: CASSETTE_VERSION, interactions: Vec::new() }),
+        }
+    }
+
+    /// Serves the cassette at `path`. Identical requests are answered in
+    /// recorded order; a request with no unplayed match is an error.
+    pub fn replaying(path: impl Into<PathBuf>) -> Result<Self, HarnessError> {
+        let path = path.into();
+        let text = std::fs::read_to_string(&path)
+            .map_err(|e| HarnessError::Storage(format!("cannot read cassette {}: {e}", path.display())))?;
+        let cassette: Cassette = serde_json::from_str(&text)
+            .map_err(|e| HarnessError::Storage(format!("bad cassette {}: {e}", path.display())))?;
+        if cassette.version != CASSETTE_VERSION {
+            return Err(HarnessError::Storage(format!(
+                "cassette {} is version {}, expected {CASSETTE_VERSION}; re-record it",
+                path.display(),
+                cassette.version
+            )));
+        }
+        let played = vec![false; cassette.interactions.len()];
+        Ok(ReplayProvider { mode: Mode::Replay(Mutex::new(played)), path, cassette: Mutex::new(cassette) })
+    }
+
+    /// Records through `inner` when `RECORD_ENV` is `1`, else replays.
+    /// `inner` is only built when recording, so replay needs no key.
+    pub fn from_env(
+        path: impl Into<PathBuf>,
+        inner: impl FnOnce() -> Arc<dyn LlmProvider>,
+    ) -> Result<Self, HarnessError> {
+        if std::env::var(RECORD_ENV).is_ok_and(|v| v == "1") {
+            Ok(Self::recording(inner(), path))
+        } else {
+            Self::replaying(path)
+        }
+    }
+
+    /// Interactions not yet served (replay), or recorded so far (record).
+    /// A replayed test can assert this is 0 to catch a run that now makes
+    /// fewer calls than it did when recorded.
+    pub fn unplayed(&self) -> usize {
+        match &self.mode {
+            Mode::Record(_) => self.cassette.lock().unwrap().interactions.len(),
+            Mode::Replay(played) => played.lock().unwrap().iter().filter(|p| !**p).count(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn replay(&self, request: &CompletionRequest, played: &Mutex<Vec<bool>>) -> Result<CompletionResponse, HarnessError> {
+        let (normalized, ids) = normalize_request(request);
+        let key = request_key(&normalized);
+        let cassette = self.cassette.lock().unwrap();
+        let mut played = played.lock().unwrap();
+        let index = cassette
+            .interactions
+            .iter()
+            .enumerate()
+            .position(|(i, rec)| !played[i] && rec.key == key)
+            .ok_or_else(|| {
+                HarnessError::Provider(format!(
+                    "cassette {} has no unplayed response for request {key} (re-record with {RECORD_ENV}=1): {}",
+                    self.path.display(),
+                    truncate(&normalized.to_string(), 400)
+                ))
+            })?;
+        played[index] = true;
+        let recorded = &cassette.interactions[index].response;
+        Ok(CompletionResponse {
+            content: map_content(&recorded.content, |s| restore_ids(s, &ids))?,
+            stop_reason: recorded.stop_reason,
+            usage: recorded.usage,
+            model: recorded.model.clone(),
+        })
+    }
+
+    fn record(&self, request: &CompletionRequest, response: &CompletionResponse) -> Result<(), HarnessError> {
+        let (normalized, mut ids) = normalize_request(request);
+        // Only ids the request carried: anything else the model wrote has no
+        // counterpart to map to on replay.
+        let content = map_content(&response.content, |s| renumber_uuids(s, &mut ids, false))?;
+        let mut cassette = self.cassette.lock().unwrap();
+        cassette.interactions.push(Interaction {
+            key: request_key(&normalized),
+            request: normalized,
+            response: RecordedResponse {
+                content,
+                stop_reason: response.stop_reason,
+                usage: response.usage,
+                model: response.model.clone(),
+            },
+        });
+        let text = serde_json::to_string_pretty(&*cassette)
+            .map_err(|e| HarnessError::Storage(format!("cannot encode cassette: {e}")))?;
+        if let Some(dir) = self.path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| HarnessError::Storage(format!("cannot create {}: {e}", dir.display())))?;
+        }
+        std::fs::write(&self.path, text)
+            .map_err(|e| HarnessError::Storage(format!("cannot write cassette {}: {e}", self.path.display())))
+    }
+}
+
+#[async_trait::async_trait]
+impl LlmProvider for ReplayProvider {
+    async fn complete(&self, req: CompletionRequest) -> Result<CompletionResponse, HarnessError> {
+        match &self.mode {
+            Mode::Replay(played) => self.replay(&req, played),
+            Mode::Record(inner) => {
+                let response = inner.complete(req.clone()).await?;
+                self.record(&req, &response)?;
+                Ok(response)
+            }
+        }
+    }
+
+    async fn stream(
+        &self,
+        req: CompletionRequest,
+        sink: &(dyn Fn(StreamEvent) + Send + Sync),
+    ) -> Result<CompletionResponse, HarnessError> {
+        match &self.mode {
+            Mode::Replay(played) => {
+                let response = self.replay(&req, played)?;
+                emit_response_events(&response, None, sink);
+                Ok(response)
+            }
+            Mode::Record(inner) => {
+                let response = inner.stream(req.clone(), sink).await?;
+                self.record(&req, &response)?;
+                Ok(response)
+            }
+        }
+    }
+}
+
+/// The request as matched and stored: everything but the cache hints, with
+/// UUIDs renumbered `<id1>`, `<id2>`, ... by first appearance. Also returns
+/// the ids in placeholder order. `thinking` only appears when set, so
+/// cassettes recorded before it existed still match.
+fn normalize_request(request: &CompletionRequest) -> (serde_json::Value, Vec<String>) {
+    let mut raw = serde_json::json!({
+        "system": request.system,
+        "messages": request.messages,
+        "tools": request.tools,
+        "max_tokens": request.max_tokens,
+        "tool_choice": request.tool_choice,
+    });
+    if let Some(budget) = request.thinking {
+        raw["thinking"] = budget.into();
+    }
+    let mut ids = Vec::new();
+    let normalized = map_strings(raw, &mut |s| renumber_uuids(s, &mut ids, true));
+    (normalized, ids)
+}
+
+/// Rewrites every string in `value`, depth-first in document order.
+fn map_strings(value: serde_json::Value, f: &mut impl FnMut(&str) -> String) -> serde_json::Value {
+    use serde_json::Value;
+    match value {
+        Value::String(s) => Value::String(f(&s)),
+        Value::Array(items) => Value::Array(items.into_iter().map(|v| map_strings(v, f)).collect()),
+        Value::Object(map) => Value::Object(map.into_iter().map(|(k, v)| (k, map_strings(v, f))).collect()),
+        other => other,
+    }
+}
+
+fn map_content(
+    content: &[ContentBlock],
+    mut f: impl FnMut(&str) -> String,
+) -> Result<Vec<ContentBlock>, HarnessError> {
+    let value = serde_json::to_value(content)
+        .map_err(|e| HarnessError::Storage(format!("cannot encode response content: {e}")))?;
+    serde_json::from_value(map_strings(value, &mut f))
+        .map_err(|e| HarnessError::Storage(format!("bad recorded response content: {e}")))
+}
+
+/// Replaces each UUID with its `<idN>` placeholder. An id not yet numbered
+/// gets the next number when `extend`, and is left as is otherwise.
+fn renumber_uuids(text: &str, ids: &mut Vec<String>, extend: bool) -> String {
+    const UUID_LEN: usize = 36;
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while !rest.is_empty() {
+        if let Some(candidate) = rest.get(..UUID_LEN).filter(|c| is_uuid(c)) {
+            let n = match ids.iter().position(|id| id == candidate) {
+                Some(i) => Some(i + 1),
+                None if extend => {
+                    ids.push(candidate.to_string());
+                    Some(ids.len())
+                }
+                None => None,
+            };
+            match n {
+                Some(n) => out.push_str(&format!("<id{n}>")),
+                None => out.push_str(candidate),
+            }
+            rest = &rest[UUID_LEN..];
+        } else {
+            let ch = rest.chars().next().unwrap();
+            out.push(ch);
+            rest = &rest[ch.len_utf8()..];
+        }
+    }
+    out
+}
+
+/// Inverse of `renumber_uuids` against this run's ids. A placeholder past
+/// the end of `ids` is left as is.
+fn restore_ids(text: &str, ids: &[String]) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("<id") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 3..];
+        let digits = after.bytes().take_while(u8::is_ascii_digit).count();
+        let id = after[..digits]
+            .parse::<usize>()
+            .ok()
+            .filter(|_| after[digits..].starts_with('>'))
+            .and_then(|n| ids.get(n.checked_sub(1)?));
+        match id {
+            Some(id) => {
+                out.push_str(id);
+                rest = &after[digits + 1..];
+            }
+            None => {
+                out.push_str("<id");
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn is_uuid(s: &str) -> bool {
+    s.len() == 36
+        && s.bytes().enumerate().all(|(i, b)| match i {
+            8 | 13 | 18 | 23 => b == b'-',
+            _ => b.is_ascii_hexdigit(),
+        })
+}
+
+/// FNV-1a over the canonical JSON — stable across Rust versions and
+/// platforms, unlike `DefaultHasher`. `serde_json` maps keep keys sorted.
+fn request_key(normalized: &serde_json::Value) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in normalized.to_string().bytes() {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    format!("{hash:016x}")
+}
+
+fn truncate(s: &str, max_chars: usize) -> String {
+    match s.char_indices().nth(max_chars) {
+        Some((end, _)) => format!("{}…", &s[..end]),
+        None => s.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::{CacheHints, Message};
+    use crate::mock::MockProvider;
+
+    fn cassette_path(tag: &str) -> PathBuf {
+        let nanos = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).unwrap().as_nanos();
+        std::env::temp_dir().join(format!("harness-cassette-{tag}-{nanos}.json"))
+    }
+
+    fn request(text: &str) -> CompletionRequest {
+        CompletionRequest {
+            system: "sys".into(),
+            messages: vec![Message::user_text(text)],
+            tools: vec![],
+            max_tokens: 64,
+            tool_choice: None,
+            cache: CacheHints::default(),
+            thinking: None,
+        }
+    }
+
+    fn text_response(text: &str) -> CompletionResponse {
+        CompletionResponse {
+            content: vec![ContentBlock::Text { text: text.into() }],
+            stop_reason: StopReason::EndTurn,
+            usage: Usage { input_tokens: 5, output_tokens: 2, ..Default::default() },
+            model: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn recorded_calls_replay_offline_in_order() {
+        let path = cassette_path("order");
+        let live = Arc::new(MockProvider::new(vec![text_response("one"), text_response("two"), text_response("other")]));
+        let recorder = ReplayProvider::recording(live, &path);
+        for text in ["same", "same", "different"] {
+            recorder.complete(request(text)).await.unwrap();
+        }
+        assert_eq!(recorder.unplayed(), 3);
+
+        let replay = ReplayProvider::replaying(&path).unwrap();
+        // Identical requests come back in recorded order, whatever else interleaves.
+        assert_eq!(replay.complete(request("different")).await.unwrap(), text_response("other"));
+        let mut cached = request("same");
+        cached.cache = CacheHints { tools: true, system_prefixes: vec![3] };
+        assert_eq!(replay.complete(cached).await.unwrap(), text_response("one"));
+        let events = Mutex::new(Vec::new());
+        let second = replay.stream(request("same"), &|e| events.lock().unwrap().push(e)).await.unwrap();
+        assert_eq!(second, text_response("two"));
+        assert_eq!(events.lock().unwrap().first(), Some(&StreamEvent::TextDelta { index: 0, text: "two".into() }));
+        assert_eq!(replay.unplayed(), 0);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn an_unrecorded_or_exhausted_request_fails_loudly() {
+        let path = cassette_path("miss");
+        let recorder = ReplayProvider::recording(Arc::new(MockProvider::new(vec![text_response("ok")])), &path);
+        recorder.complete(request("hello")).await.unwrap();
+
+        let replay = ReplayProvider::replaying(&path).unwrap();
+        let err = replay.complete(request("hello, edited")).await.unwrap_err().to_string();
+        assert!(err.contains("no unplayed response") && err.contains(RECORD_ENV), "{err}");
+        replay.complete(request("hello")).await.unwrap();
+        assert!(replay.complete(request("hello")).await.is_err(), "each recording plays once");
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(matches!(ReplayProvider::replaying(&path), Err(HarnessError::Storage(_))));
+    }
+
+    #[test]
+    fn uuids_are_renumbered_by_first_appearance() {
+        let a = "0190f5d2-8c4e-7b3a-9f21-5a6b7c8d9e0f";
+        let b = "0190f5d2-8c4e-7b3a-9f21-5a6b7c8d9eAA";
+        let mut ids = Vec::new();
+        let renumbered = renumber_uuids(&format!("- [{b}] x\n- [{a}] y, again {b}"), &mut ids, true);
+        assert_eq!(renumbered, "- [<id1>] x\n- [<id2>] y, again <id1>");
+        assert_eq!(restore_ids("<id2> priced, <id3> unknown, <idx>", &ids), format!("{a} priced, <id3> unknown, <idx>"));
+        // Same shape, different ids: same key.
+        let other = "11111111-2222-3333-4444-555555555555";
+        assert_eq!(
+            request_key(&normalize_request(&request(&format!("price {a}"))).0),
+            request_key(&normalize_request(&request(&format!("price {other}"))).0)
+        );
+        assert_ne!(
+            request_key(&normalize_request(&request("price it")).0),
+            request_key(&normalize_request(&request("price that")).0)
+        );
+    }
+}
@@ -0,0 +1,243 @@
+//! Validation of tool inputs against the JSON schema each tool advertises.
+//! Covers the subset tool schemas here actually use — `type`, `properties`,
+//! `required`, `items`, `enum`, `minLength`/`maxLength`, `minItems`/
+//! `maxItems` and `minimum`/`maximum`; any other keyword is ignored.
+//!
+//! One deliberate departure from the spec: `minLength` counts characters
+//! after trimming, so a blank string is as empty as `""`. Every tool here
+//! means "non-empty" by `minLength: 1`, and a model that sends `" "` should
+//! hear so. Another: an explicit `null` on a property not in `required` counts
+//! as leaving it out, as the tools' `Option` fields already read it.
+
+use serde_json::Value;
+
+/// Checks `value` against `schema`. `Err` lists every violation, each led by
+/// the offending path (`lines[2].title`), joined with `"; "` — phrased for
+/// the model to correct its next call from.
+pub fn validate(schema: &Value, value: &Value) -> Result<(), String> {
+    let mut errors = Vec::new();
+    check(schema, value, "", &mut errors);
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.join("; "))
+    }
+}
+
+fn check(schema: &Value, value: &Value, path: &str, errors: &mut Vec<String>) {
+    let at = if path.is_empty() { "input".to_string() } else { format!("`{path}`") };
+
+    if let Some(expected) = schema.get("type") {
+        let allowed: Vec<&str> = match expected {
+            Value::String(t) => vec![t.as_str()],
+            Value::Array(ts) => ts.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !allowed.is_empty() && !allowed.iter().any(|t| is_type(value, t)) {
+            errors.push(format!("{at} must be {}, got {}", article_list(&allowed), with_article(type_of(value))));
+            // Nothing below means anything for the wrong type.
+            return;
+        }
+    }
+
+    if let Some(options) = schema.get("enum").and_then(Value::as_array) {
+        if !options.contains(value) {
+            let listed = options.iter().map(Value::to_string).collect::<Vec<_>>().join(", ");
+            errors.push(format!("{at} must be one of {listed}, got {value}"));
+        }
+    }
+
+    match value {
+        Value::String(s) => {
+            let len = s.trim().chars().count() as u64;
+            if let Some(min) = schema.get("minLength").and_then(Value::as_u64) {
+                if len < min {
+                    errors.push(if min == 1 {
+                        format!("{at} must not be empty")
+                    } else {
+                        format!("{at} must be at least {min} characters, got {len}")
+                    });
+                }
+            }
+            if let Some(max) = schema.get("maxLength").and_then(Value::as_u64) {
+                if s.chars().count() as u64 > max {
+                    errors.push(format!("{at} must be at most {max} characters, got {}", s.chars().count()));
+                }
+            }
+        }
+        Value::Number(n) => {
+            let n = n.as_f64().unwrap_or_default();
+            if let Some(min) = schema.get("minimum").and_then(Value::as_f64) {
+                if n < min {
+                    errors.push(format!("{at} must be at least {min}, got {n}"));
+                }
+            }
+            if let Some(max) = schema.get("maximum").and_then(Value::as_f64) {
+                if n > max {
+                    errors.push(format!("{at} must be at most {max}, got {n}"));
+                }
+            }
+        }
+        Value::Array(elements) => {
+            let len = elements.len() as u64;
+            if let Some(min) = schema.get("minItems").and_then(Value::as_u64) {
+                if len < min {
+                    errors.push(format!("{at} must have at least {min} entries, got {len}"));
+                }
+            }
+            if let Some(max) = schema.get("maxItems").and_then(Value::as_u64) {
+                if len > max {
+                    errors.push(format!("{at} must have at most {max} entries, got {len}"));
+                }
+            }
+            if let Some(items) = schema.get("items") {
+                for (i, element) in elements.iter().enumerate() {
+                    check(items, element, &format!("{path}[{i}]"), errors);
+                }
+            }
+        }
+        Value::Object(fields) => {
+            let required: Vec<&str> = schema
+                .get("required")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+                .collect();
+            for key in &required {
+                if !fields.contains_key(*key) {
+                    errors.push(format!("`{}` is required", join(path, key)));
+                }
+            }
+            if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+                for (key, field) in fields {
+                    if field.is_null() && !required.contains(&key.as_str()) {
+                        continue;
+                    }
+                    if let Some(property) = properties.get(key) {
+                        check(property, field, &join(path, key), errors);
+                    }
+                }
+            }
+        }
+        Value::Null | Value::Bool(_) => {}
+    }
+}
+
+fn join(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{path}.{key}")
+    }
+}
+
+fn is_type(value: &Value, expected: &str) -> bool {
+    match expected {
+        "integer" => value.is_i64() || value.is_u64(),
+        // An integer is a number too.
+        "number" => value.is_number(),
+        other => type_of(value) == other,
+    }
+}
+
+fn type_of(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_i64() || n.is_u64() => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn with_article(type_name: &str) -> String {
+    match type_name {
+        "null" => "null".to_string(),
+        t if t.starts_with(['a', 'e', 'i', 'o', 'u']) => format!("an {t}"),
+        t => format!("a {t}"),
+    }
+}
+
+fn article_list(types: &[&str]) -> String {
+    types.iter().map(|t| with_article(t)).collect::<Vec<_>>().join(" or ")
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "kind": { "type": "string", "enum": ["todo", "price"] },
+                "text": { "type": "string", "minLength": 1 },
+                "lines": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "title": { "type": "string", "minLength": 1 },
+                            "amount_cents": { "type": "integer", "minimum": 0 }
+                        },
+                        "required": ["title"]
+                    }
+                }
+            },
+            "required": ["kind", "text"]
+        })
+    }
+
+    #[test]
+    fn valid_input_passes_and_unknown_keywords_are_ignored() {
+        let input = json!({"kind": "todo", "text": "order lumber", "lines": [{"title": "Mulch", "amount_cents": 34500}]});
+        assert_eq!(validate(&schema(), &input), Ok(()));
+        assert_eq!(validate(&json!({"type": "number", "description": "x"}), &json!(3)), Ok(()));
+    }
+
+    #[test]
+    fn every_violation_is_listed_with_its_path() {
+        let input = json!({"kind": "vibe", "lines": [{"amount_cents": 1.5}, {"title": " ", "amount_cents": -1}]});
+        assert_eq!(
+            validate(&schema(), &input).unwrap_err(),
+            "`text` is required; \
+             `kind` must be one of \"todo\", \"price\", got \"vibe\"; \
+             `lines[0].title` is required; \
+             `lines[0].amount_cents` must be an integer, got a number; \
+             `lines[1].amount_cents` must be at least 0, got -1; \
+             `lines[1].title` must not be empty"
+        );
+    }
+
+    #[test]
+    fn null_counts_as_absent_only_for_optional_properties() {
+        let contact = json!({
+            "type": "object",
+            "properties": { "name": { "type": "string" }, "phone": { "type": "string" } },
+            "required": ["name"]
+        });
+        assert_eq!(validate(&contact, &json!({"name": "Dev", "phone": null})), Ok(()));
+        assert_eq!(
+            validate(&contact, &json!({"name": null})).unwrap_err(),
+            "`name` must be a string, got null"
+        );
+    }
+
+    #[test]
+    fn the_wrong_type_stops_at_that_value() {
+        assert_eq!(validate(&schema(), &json!([])).unwrap_err(), "input must be an object, got an array");
+        assert_eq!(
+            validate(&schema(), &json!({"kind": 42, "text": "x"})).unwrap_err(),
+            "`kind` must be a string, got an integer"
+        );
+        assert_eq!(
+            validate(&json!({"type": ["string", "null"]}), &json!(true)).unwrap_err(),
+            "input must be a string or null, got a boolean"
+        );
+    }
+}
@@ -0,0 +1,222 @@
+//! Structured output through a forced tool call: the request names a tool
+//! in `tool_choice`, and the tool's input — deserialized into the caller's
+//! type — is the answer. A reply that doesn't parse gets one repair
+//! round-trip carrying the parse error; after that the call fails.
+
+use serde::de::DeserializeOwned;
+
+use crate::agent::{RunError, ServedBy};
+use crate::cancel::CancelToken;
+use crate::error::HarnessError;
+use crate::llm::{CompletionRequest, CompletionResponse, ContentBlock, LlmProvider, Message, Role, StreamSink, Usage};
+use crate::observer::{observed_call, AgentObserver};
+
+/// A parsed forced-tool answer, with what the call (and its repair, if one
+/// was needed) spent.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ForcedOutput<T> {
+    pub value: T,
+    pub usage: Usage,
+    /// See `TurnOutcome::model`.
+    pub model: ServedBy,
+}
+
+/// Sends `request`, whose `tool_choice` names the tool to force, and parses
+/// that tool's input as `T`. A reply without the tool call, or with input
+/// `T` rejects, is answered once with the error — as the tool's error
+/// result, or as a reminder when the call was skipped — and the retry's
+/// answer stands. Both calls go to `observer` as `purpose`, stream through
+/// `sink` when given, and race `cancel`.
+///
+/// Never loses spend: `RunError::usage` covers every completed call, a
+/// reply that couldn't be parsed included.
+pub async fn forced_tool_call<T: DeserializeOwned>(
+    provider: &dyn LlmProvider,
+    mut request: CompletionRequest,
+    sink: Option<&StreamSink>,
+    observer: Option<&dyn AgentObserver>,
+    cancel: &CancelToken,
+    purpose: &str,
+) -> Result<ForcedOutput<T>, RunError> {
+    let Some(tool) = request.tool_choice.clone() else {
+        return Err(RunError {
+            source: HarnessError::Provider(format!("{purpose}: forced-tool call without a tool_choice")),
+            usage: Usage::default(),
+            model: ServedBy::default(),
+        });
+    };
+    let mut usage = Usage::default();
+    let mut model = ServedBy::default();
+    let mut repaired = false;
+    loop {
+        let call = observed_call(provider, request.clone(), sink, observer, purpose);
+        let response = cancel
+            .guard(usage, call)
+            .await
+            .map_err(|source| RunError { source, usage, model: model.clone() })?;
+        usage.add(&response.usage);
+        model.add(response.model.as_deref(), &response.usage);
+
+        let tool_use = response.content.iter().find_map(|block| match block {
+            ContentBlock::ToolUse { id, name, input } if *name == tool => Some((id.clone(), input.clone())),
+            _ => None,
+        });
+        let (error, reply) = match tool_use {
+            Some((id, input)) => match serde_json::from_value::<T>(input) {
+                Ok(value) => return Ok(ForcedOutput { value, usage, model }),
+                Err(e) => (
+                    format!("{tool} input invalid: {e}"),
+                    ContentBlock::ToolResult {
+                        tool_use_id: id,
+                        content: format!("invalid input: {e}. Call {tool} again with corrected input."),
+                        is_error: true,
+                    },
+                ),
+            },
+            None => (
+                format!("{purpose} response missing {tool} call"),
+                ContentBlock::Text { text: format!("Respond by calling the {tool} tool.") },
+            ),
+        };
+        if repaired {
+            return Err(RunError { source: HarnessError::Provider(error), usage, model });
+        }
+        repaired = true;
+        if !response.content.is_empty() {
+            request.messages.push(Message { role: Role::Assistant, content: response.content });
+        }
+        request.messages.push(Message { role: Role::User, content: vec![reply] });
+    }
+}
+
+/// Parses `tool`'s input out of a reply that was forced to call it but
+/// can't take a repair round-trip — a batch result, answered long after the
+/// request went out. Errors as `forced_tool_call` would after its repair.
+pub fn forced_tool_input<T: DeserializeOwned>(
+    response: &CompletionResponse,
+    tool: &str,
+    purpose: &str,
+) -> Result<T, HarnessError> {
+    let input = response.content.iter().find_map(|block| match block {
+        ContentBlock::ToolUse { name, input, .. } if name == tool => Some(input.clone()),
+        _ => None,
+    });
+    let input = input.ok_or_else(|| HarnessError::Provider(format!("{purpose} response missing {tool} call")))?;
+    serde_json::from_value(input).map_err(|e| HarnessError::Provider(format!("{tool} input invalid: {e}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+    use crate::llm::{CacheHints, StopReason, ToolSpec};
+    use crate::mock::MockProvider;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Verdict {
+        score: u8,
+    }
+
+    fn request() -> CompletionRequest {
+        CompletionRequest {
+            system: "grade it".into(),
+            messages: vec![Message::user_text("the walk")],
+            tools: vec![ToolSpec {
+                name: "grade".into(),
+                description: "d".into(),
+                input_schema: serde_json::json!({"type": "object"}),
+            }],
+            max_tokens: 64,
+            tool_choice: Some("grade".into()),
+            cache: CacheHints::default(),
+            thinking: None,
+        }
+    }
+
+    fn grade(input: serde_json::Value) -> CompletionResponse {
+        CompletionResponse {
+            content: vec![ContentBlock::ToolUse { id: "tu_1".into(), name: "grade".into(), input }],
+            stop_reason: StopReason::ToolUse,
+            usage: Usage { input_tokens: 40, output_tokens: 10, ..Default::default() },
+            model: Some("claude-haiku-4-5".into()),
+        }
+    }
+
+    fn prose() -> CompletionResponse {
+        CompletionResponse {
+            content: vec![ContentBlock::Text { text: "looks fine".into() }],
+            stop_reason: StopReason::EndTurn,
+            usage: Usage { input_tokens: 40, output_tokens: 5, ..Default::default() },
+            model: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn parses_the_forced_tools_input() {
+        let provider = MockProvider::new(vec![grade(serde_json::json!({"score": 7}))]);
+        let out: ForcedOutput<Verdict> =
+            forced_tool_call(&provider, request(), None, None, &CancelToken::new(), "grading").await.unwrap();
+        assert_eq!(out.value, Verdict { score: 7 });
+        assert_eq!(out.usage, Usage { input_tokens: 40, output_tokens: 10, ..Default::default() });
+        assert_eq!(out.model.label().as_deref(), Some("claude-haiku-4-5"));
+        assert_eq!(provider.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn a_bad_input_is_repaired_once_with_the_parse_error() {
+        let provider = MockProvider::new(vec![
+            grade(serde_json::json!({"score": "seven"})),
+            grade(serde_json::json!({"score": 7})),
+        ]);
+        let out: ForcedOutput<Verdict> =
+            forced_tool_call(&provider, request(), None, None, &CancelToken::new(), "grading").await.unwrap();
+        assert_eq!(out.value, Verdict { score: 7 });
+        assert_eq!(out.usage.input_tokens, 80, "both calls counted");
+
+        let repair = &provider.requests()[1];
+        assert_eq!(repair.tool_choice.as_deref(), Some("grade"));
+        assert_eq!(repair.messages.len(), 3);
+        assert_eq!(repair.messages[1].role, Role::Assistant);
+        let ContentBlock::ToolResult { tool_use_id, content, is_error } = &repair.messages[2].content[0] else {
+            panic!("expected the error as the tool's result")
+        };
+        assert_eq!(tool_use_id, "tu_1");
+        assert!(*is_error);
+        assert!(content.contains("invalid type: string \"seven\""), "{content}");
+    }
+
+    #[tokio::test]
+    async fn a_skipped_call_is_reminded_then_fails_with_all_the_spend() {
+        let provider = MockProvider::new(vec![prose(), prose()]);
+        let err = forced_tool_call::<Verdict>(&provider, request(), None, None, &CancelToken::new(), "grading")
+            .await
+            .unwrap_err();
+        assert!(matches!(&err.source, HarnessError::Provider(m) if m == "grading response missing grade call"));
+        assert_eq!(err.usage, Usage { input_tokens: 80, output_tokens: 10, ..Default::default() });
+        assert_eq!(
+            provider.requests()[1].messages[2].content,
+            vec![ContentBlock::Text { text: "Respond by calling the grade tool.".into() }]
+        );
+    }
+
+    #[tokio::test]
+    async fn a_failed_repair_call_keeps_the_first_calls_spend() {
+        let provider = MockProvider::new(vec![prose()]);
+        let err = forced_tool_call::<Verdict>(&provider, request(), None, None, &CancelToken::new(), "grading")
+            .await
+            .unwrap_err();
+        assert!(matches!(err.source, HarnessError::Provider(m) if m.contains("exhausted")));
+        assert_eq!(err.usage.input_tokens, 40);
+    }
+
+    #[test]
+    fn forced_tool_input_parses_a_reply_without_repairing_it() {
+        let verdict: Verdict = forced_tool_input(&grade(serde_json::json!({"score": 3})), "grade", "grading").unwrap();
+        assert_eq!(verdict, Verdict { score: 3 });
+        let err = forced_tool_input::<Verdict>(&prose(), "grade", "grading").unwrap_err();
+        assert!(matches!(err, HarnessError::Provider(m) if m == "grading response missing grade call"));
+        let err = forced_tool_input::<Verdict>(&grade(serde_json::json!({})), "grade", "grading").unwrap_err();
+        assert!(matches!(err, HarnessError::Provider(m) if m.starts_with("grade input invalid")));
+    }
+}
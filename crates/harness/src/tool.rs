@@ -1,16 +1,30 @@
 use std::collections::BTreeMap;
 use std::sync::Arc;
 
+use serde::de::DeserializeOwned;
+
 use crate::error::HarnessError;
 use crate::llm::ToolSpec;
+use crate::schema;
 
 /// A capability the agent can invoke. Implementations convert their own errors into HarnessError at this boundary.
 #[async_trait::async_trait]
 pub trait Tool: Send + Sync + 'static {
     fn name(&self) -> &str;
     fn description(&self) -> &str;
+    /// Enforced: `ToolRegistry::execute` rejects input that doesn't
+    /// validate (`schema::validate`) before `execute` sees it, so a tool
+    /// checks only what a schema can't express.
     fn input_schema(&self) -> serde_json::Value;
     async fn execute(&self, input: serde_json::Value) -> Result<String, HarnessError>;
+
+    /// Whether calls to this tool may overlap with each other and with other
+    /// concurrency-safe calls in the same turn. Opt-in: a tool whose effect
+    /// depends on what an earlier call in the turn did (or that mutates
+    /// shared in-memory state) keeps the default and runs alone.
+    fn is_concurrency_safe(&self) -> bool {
+        false
+    }
 }
 
 #[derive(Default, Clone)]
@@ -40,7 +54,15 @@ impl ToolRegistry {
             .collect()
     }
 
+    /// False for unknown names — their (error) result is produced in turn
+    /// order like any other exclusive call.
+    pub fn is_concurrency_safe(&self, name: &str) -> bool {
+        self.tools.get(name).is_some_and(|t| t.is_concurrency_safe())
+    }
+
     /// Dispatches to the named tool, or returns HarnessError::UnknownTool.
+    /// Input that fails the tool's schema is a `HarnessError::Tool` naming
+    /// every violation; the tool never runs.
     pub async fn execute(
         &self,
         name: &str,
@@ -50,10 +72,22 @@ impl ToolRegistry {
             .tools
             .get(name)
             .ok_or_else(|| HarnessError::UnknownTool(name.to_string()))?;
+        schema::validate(&tool.input_schema(), &input).map_err(|violations| HarnessError::Tool {
+            name: name.to_string(),
+            message: format!("invalid input: {violations}"),
+        })?;
         tool.execute(input).await
     }
 }
 
+/// Deserializes a tool's (already validated) input into its typed form. A
+/// failure here means the type disagrees with the advertised schema, or
+/// the tool was called around the registry.
+pub fn parse_input<T: DeserializeOwned>(tool: &str, input: serde_json::Value) -> Result<T, HarnessError> {
+    serde_json::from_value(input)
+        .map_err(|e| HarnessError::Tool { name: tool.to_string(), message: format!("invalid input: {e}") })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -91,6 +125,16 @@ mod tests {
         assert_eq!(out, "hi");
     }
 
+    #[tokio::test]
+    async fn input_that_fails_the_schema_never_reaches_the_tool() {
+        let mut reg = ToolRegistry::new();
+        reg.register(Echo);
+        let err = reg.execute("echo", serde_json::json!({"text": 7})).await.unwrap_err();
+        assert_eq!(err.to_string(), "tool 'echo' failed: invalid input: `text` must be a string, got an integer");
+        let err = reg.execute("echo", serde_json::json!({})).await.unwrap_err();
+        assert!(matches!(err, HarnessError::Tool { message, .. } if message == "invalid input: `text` is required"));
+    }
+
     #[tokio::test]
     async fn unknown_tool_is_an_error() {
         let reg = ToolRegistry::new();
@@ -98,6 +142,14 @@ mod tests {
         assert!(matches!(err, HarnessError::UnknownTool(n) if n == "nope"));
     }
 
+    #[test]
+    fn tools_are_exclusive_unless_they_opt_in() {
+        let mut reg = ToolRegistry::new();
+        reg.register(Echo);
+        assert!(!reg.is_concurrency_safe("echo"));
+        assert!(!reg.is_concurrency_safe("nope"));
+    }
+
     #[test]
     fn specs_lists_registered_tools() {
         let mut reg = ToolRegistry::new();
@@ -81,6 +81,7 @@ async fn full_round_trip_through_real_provider_wire_format() {
             system_prompt: "extract items from field transcripts".into(),
             max_turns: 4,
             max_tokens: 512,
+            thinking: None,
         },
     );
 
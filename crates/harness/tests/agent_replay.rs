@@ -0,0 +1,69 @@
+use std::sync::Arc;
+
+use harness::replay::RECORD_ENV;
+use harness::{
+    Agent, AgentConfig, AnthropicProvider, ContentBlock, HarnessError, LlmProvider, Message, ReplayProvider, Tool,
+    ToolRegistry,
+};
+
+/// Committed cassette; re-record with `MURMUR_RECORD_CASSETTES=1` and
+/// `ANTHROPIC_API_KEY` set.
+const CASSETTE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/cassettes/agent_save_item.json");
+
+struct SaveItem;
+
+#[async_trait::async_trait]
+impl Tool for SaveItem {
+    fn name(&self) -> &str {
+        "save_item"
+    }
+    fn description(&self) -> &str {
+        "saves a captured item"
+    }
+    fn input_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {"title": {"type": "string"}},
+            "required": ["title"]
+        })
+    }
+    async fn execute(&self, input: serde_json::Value) -> Result<String, HarnessError> {
+        Ok(format!("saved: {}", input["title"].as_str().unwrap_or("?")))
+    }
+}
+
+#[tokio::test]
+async fn the_agent_loop_replays_a_committed_cassette() {
+    let provider = Arc::new(
+        ReplayProvider::from_env(CASSETTE, || -> Arc<dyn LlmProvider> {
+            let key = std::env::var("ANTHROPIC_API_KEY").expect("recording needs ANTHROPIC_API_KEY");
+            Arc::new(AnthropicProvider::new(key, "claude-haiku-4-5-20251001"))
+        })
+        .unwrap(),
+    );
+    let mut tools = ToolRegistry::new();
+    tools.register(SaveItem);
+    let agent = Agent::new(
+        provider.clone(),
+        tools,
+        AgentConfig {
+            system_prompt: "extract items from field transcripts; save each with save_item, then reply briefly"
+                .into(),
+            max_turns: 4,
+            max_tokens: 512,
+            thinking: None,
+        },
+    );
+
+    let out = agent.run(vec![Message::user_text("front beds need mulch, call it three yards")]).await.unwrap();
+
+    // Loose on purpose: a re-recorded cassette holds whatever the model said.
+    assert!(!out.text.is_empty());
+    assert!(out.messages.iter().any(|m| m
+        .content
+        .iter()
+        .any(|b| matches!(b, ContentBlock::ToolResult { content, .. } if content.starts_with("saved: ")))));
+    if std::env::var(RECORD_ENV).is_err() {
+        assert_eq!(provider.unplayed(), 0, "the run made fewer calls than were recorded");
+    }
+}
@@ -17,8 +17,8 @@ use std::io::Read;
 use std::process::ExitCode;
 use std::sync::{Arc, Mutex};
 
-use harness::{AnthropicProvider, FileMemoryStore, Memory, MemoryStore};
-use murmur_core::{NewJob, ReflectionCoordinator, SessionProcessor, Store};
+use harness::{AnthropicProvider, Memory};
+use murmur_core::{NewJob, ReflectionCoordinator, SessionProcessor, SqliteMemoryStore, Store};
 
 const MODEL: &str = "claude-haiku-4-5";
 
@@ -114,9 +114,12 @@ async fn run() -> Result<(), String> {
     let db_is_fresh = !std::path::Path::new(&db_path).exists();
     let store = Store::open(&db_path, "walk-cli").map_err(|e| format!("cannot open db: {e}"))?;
 
-    // Memory lives next to the db so it persists across runs with --db.
-    let memory_store = Arc::new(FileMemoryStore::new(format!("{db_path}.memory.json")));
-    let memory = memory_store.load().map_err(|e| format!("cannot load memory: {e}"))?;
+    // Memory lives in the db, so it persists across runs with --db; a
+    // memory file left by an older run moves in on first open.
+    store
+        .import_memory_file(format!("{db_path}.memory.json"))
+        .map_err(|e| format!("cannot import memory: {e}"))?;
+    let memory = store.load_memory().map_err(|e| format!("cannot load memory: {e}"))?;
 
     if db_is_fresh {
         let job = store
@@ -151,12 +154,13 @@ async fn run() -> Result<(), String> {
     }
     let provider = Arc::new(provider);
     let store = Arc::new(Mutex::new(store));
+    let memory_store = Arc::new(SqliteMemoryStore::new(store.clone()));
     let memory = Arc::new(Mutex::new(memory));
     let processor = SessionProcessor::new(
         provider.clone(),
         store.clone(),
         memory.clone(),
-        memory_store.clone(),
+        memory_store,
     );
     println!("processing with {MODEL}...\n");
     let outcome = processor
@@ -194,15 +198,22 @@ async fn run() -> Result<(), String> {
         "\nsummary: {}",
         outcome.session.summary.as_deref().unwrap_or("(none)")
     );
-    let (input_total, output_total) = store
+    let totals = store
         .lock()
         .map_err(|_| "store lock poisoned".to_string())?
-        .usage_totals()
+        .usage_breakdown()
         .map_err(|e| format!("cannot read usage totals: {e}"))?;
-    println!("usage totals: {input_total} input / {output_total} output tokens");
+    println!(
+        "usage totals: {} input ({} cache write, {} cache read; {} billable) / {} output tokens",
+        totals.input_tokens,
+        totals.cache_creation_input_tokens,
+        totals.cache_read_input_tokens,
+        totals.billable_input_tokens(),
+        totals.output_tokens
+    );
 
     // Reflection: runs only when cadence + activity warrant it.
-    let coordinator = ReflectionCoordinator::new(provider, store, memory.clone(), memory_store);
+    let coordinator = ReflectionCoordinator::in_store(provider, store, memory.clone());
     match coordinator.maybe_reflect().await {
         Ok(Some(churn)) => {
             println!("\nreflection ran (churn {churn:.2}); memory is now:");
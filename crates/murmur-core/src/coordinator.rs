@@ -5,14 +5,18 @@
 //! `ReflectionEngine::reflect`).
 //!
 //! Sequence: policy gate -> activity gate -> PRE-reflection snapshot save ->
-//! engine reflect -> swap + persist -> record signals + cost.
+//! engine reflect -> swap + persist -> record signals + cost -> the same
+//! reflect + persist for each scoped memory (`MemoryScope`) whose job or
+//! client had sessions of its own.
 
 use std::sync::{Arc, Mutex};
 
 use harness::{
-    Clock, LlmProvider, Memory, MemoryStore, ReflectionEngine, ReflectionPolicy, Usage,
+    with_priority, CancelToken, Clock, HarnessError, LlmProvider, Memory, MemoryChange, MemoryStore, Priority,
+    ReflectionEngine, ReflectionPolicy, SharedObserver, Usage,
 };
 
+use crate::domain::MemoryScope;
 use crate::error::CoreError;
 use crate::store::Store;
 
@@ -28,7 +32,8 @@ pub struct ReflectionCoordinator {
     pub policy: ReflectionPolicy,
     store: Arc<Mutex<Store>>,
     memory: Arc<Mutex<Memory>>,
-    memory_store: Arc<dyn MemoryStore>,
+    /// `None`: memory lives in `store` itself (`in_store`).
+    memory_store: Option<Arc<dyn MemoryStore>>,
     clock: Clock,
     /// Most-recent sessions fed to one reflection.
     pub max_activity_sessions: usize,
@@ -40,6 +45,23 @@ impl ReflectionCoordinator {
         store: Arc<Mutex<Store>>,
         memory: Arc<Mutex<Memory>>,
         memory_store: Arc<dyn MemoryStore>,
+    ) -> Self {
+        Self::build(provider, store, memory, Some(memory_store))
+    }
+
+    /// For memory kept in `store` (`SqliteMemoryStore`): the rewritten
+    /// memory commits in the same transaction as the reflection's signals
+    /// and cost, and needs no pre-reflection snapshot — replaced entries
+    /// stay behind as tombstones.
+    pub fn in_store(provider: Arc<dyn LlmProvider>, store: Arc<Mutex<Store>>, memory: Arc<Mutex<Memory>>) -> Self {
+        Self::build(provider, store, memory, None)
+    }
+
+    fn build(
+        provider: Arc<dyn LlmProvider>,
+        store: Arc<Mutex<Store>>,
+        memory: Arc<Mutex<Memory>>,
+        memory_store: Option<Arc<dyn MemoryStore>>,
     ) -> Self {
         ReflectionCoordinator {
             engine: ReflectionEngine::new(provider),
@@ -52,6 +74,19 @@ impl ReflectionCoordinator {
         }
     }
 
+    /// Traces each reflection's provider call into `observer`.
+    pub fn with_observer(mut self, observer: SharedObserver) -> Self {
+        self.engine = self.engine.with_observer(observer);
+        self
+    }
+
+    /// Abandons a reflection in flight when `cancel` fires; `maybe_reflect`
+    /// then returns `HarnessError::Cancelled` with memory untouched.
+    pub fn with_cancel(mut self, cancel: CancelToken) -> Self {
+        self.engine = self.engine.with_cancel(cancel);
+        self
+    }
+
     /// Replaces the clock (tests inject deterministic time).
     pub fn with_clock(mut self, clock: Clock) -> Self {
         self.clock = clock;
@@ -75,11 +110,13 @@ impl ReflectionCoordinator {
     /// snapshot). Signals are not reset, so the next `maybe_reflect` will
     /// fire again; a restart silently loads the OLD memory until the next
     /// successful reflection persists.
+    /// `in_store` has no such gap on disk: memory, signals and cost commit
+    /// together or not at all.
     pub async fn maybe_reflect(&self) -> Result<Option<f32>, CoreError> {
         // Store guard: policy + activity gates — drop before taking memory guard
         // (no overlapping locks; Batch C review: never hold store guard across
         // an await and never hold store + memory together).
-        let activity = {
+        let (activity, scoped) = {
             let store = self.locked_store()?;
             let signals = store.reflection_signals()?;
             if !self.policy.should_reflect(&signals) {
@@ -89,7 +126,15 @@ impl ReflectionCoordinator {
             if activity.is_empty() {
                 return Ok(None);
             }
-            activity
+            // Read before `finish_reflection` moves the activity window on.
+            let mut scoped = Vec::new();
+            for scope in store.memory_scopes()? {
+                let activity = store.scoped_activity_for_reflection(&scope, self.max_activity_sessions)?;
+                if !activity.is_empty() {
+                    scoped.push((store.load_scoped_memory(&scope)?, scope, activity));
+                }
+            }
+            (activity, scoped)
         }; // store guard dropped here
 
         // Memory guard in its own scope — no overlap with the store guard above.
@@ -103,13 +148,14 @@ impl ReflectionCoordinator {
         // Pre-reflection snapshot: saving the CURRENT memory rotates it into
         // the store's snapshot slots, guaranteeing a rollback point that this
         // reflection cannot erode (Plan 02 final-review note).
-        self.memory_store.save(&current_memory).map_err(CoreError::Agent)?;
+        if let Some(memory_store) = &self.memory_store {
+            memory_store.save(&current_memory).map_err(CoreError::Agent)?;
+        }
 
-        let outcome = match self
-            .engine
-            .reflect(&current_memory, &activity, (self.clock)())
-            .await
-        {
+        // Nobody waits on a reflection: it queues behind user-facing calls
+        // on a shared rate limiter.
+        let reflection = self.engine.reflect(&current_memory, &activity, (self.clock)());
+        let outcome = match with_priority(Priority::Background, reflection).await {
             Ok(o) => o,
             Err(run_err) => {
                 // Zero usage means the provider call itself failed (network, auth, etc.)
@@ -118,7 +164,7 @@ impl ReflectionCoordinator {
                     // best-effort: a store failure here must not mask the original
                     // engine error (same precedence pattern as finish_session_failed).
                     if let Ok(store) = self.locked_store() {
-                        let _ = store.record_llm_usage(None, "reflection", &run_err.usage);
+                        let _ = store.record_served_usage(None, "reflection", &run_err.usage, &run_err.model);
                     }
                 }
                 return Err(CoreError::Agent(run_err.source));
@@ -132,11 +178,51 @@ impl ReflectionCoordinator {
                 .map_err(|_| CoreError::InvalidState("memory lock poisoned".into()))?;
             *memory = outcome.memory.clone();
         }
-        self.memory_store.save(&outcome.memory).map_err(CoreError::Agent)?;
+        let in_store = match &self.memory_store {
+            Some(memory_store) => {
+                let change = MemoryChange::Reflection { churn: outcome.churn };
+                memory_store.save_change(&outcome.memory, &change).map_err(CoreError::Agent)?;
+                None
+            }
+            None => Some(&outcome.memory),
+        };
 
-        self.locked_store()?.finish_reflection(outcome.churn, &outcome.usage)?;
+        self.locked_store()?.finish_reflection(outcome.churn, &outcome.usage, &outcome.model, in_store)?;
+        self.reflect_scopes(scoped).await?;
         Ok(Some(outcome.churn))
     }
+
+    /// Compresses each scoped memory against its own sessions, separately
+    /// from the user's memory and under its own word cap. Scoped memory
+    /// always lives in the store, so each commits with its cost. Best
+    /// effort after the global reflection has committed: a scope whose
+    /// reflection fails keeps its memory until its job sees new sessions.
+    /// Cancellation still stops the rest.
+    async fn reflect_scopes(&self, scoped: Vec<(Memory, MemoryScope, Vec<String>)>) -> Result<(), CoreError> {
+        for (memory, scope, activity) in scoped {
+            let reflection = self.engine.reflect(&memory, &activity, (self.clock)());
+            match with_priority(Priority::Background, reflection).await {
+                Ok(outcome) => self.locked_store()?.finish_scoped_reflection(
+                    &scope,
+                    &outcome.memory,
+                    outcome.churn,
+                    &outcome.usage,
+                    &outcome.model,
+                )?,
+                Err(run_err) => {
+                    if run_err.usage != Usage::default() {
+                        if let Ok(store) = self.locked_store() {
+                            let _ = store.record_served_usage(None, "reflection", &run_err.usage, &run_err.model);
+                        }
+                    }
+                    if matches!(run_err.source, HarnessError::Cancelled { .. }) {
+                        return Err(CoreError::Agent(run_err.source));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -182,7 +268,8 @@ mod tests {
                 input: serde_json::json!({"sections": sections}),
             }],
             stop_reason: StopReason::ToolUse,
-            usage: Usage { input_tokens: 200, output_tokens: 40 },
+            usage: Usage { input_tokens: 200, output_tokens: 40, ..Default::default() },
+            model: None,
         }
     }
 
@@ -283,6 +370,7 @@ mod tests {
             content: vec![ContentBlock::Text { text: "refused".into() }],
             stop_reason: StopReason::EndTurn,
             usage: Usage::default(),
+            model: None,
         };
         let (coordinator, memory, memory_store, store) =
             coordinator_with(vec![bad], store_with_ended_session());
@@ -295,6 +383,65 @@ mod tests {
         assert_eq!(signals.completed_reflections, 0, "failed reflection is not recorded");
     }
 
+    #[tokio::test]
+    async fn in_store_memory_commits_with_the_reflection() {
+        let store = Arc::new(Mutex::new(store_with_ended_session()));
+        let memory = Arc::new(Mutex::new(Memory::default()));
+        let coordinator = ReflectionCoordinator::in_store(
+            Arc::new(MockProvider::new(vec![write_memory_response(
+                serde_json::json!({"people": ["Dev — framer"]}),
+            )])),
+            store.clone(),
+            memory.clone(),
+        )
+        .with_clock(Arc::new(|| 1000));
+        assert!(coordinator.maybe_reflect().await.unwrap().is_some());
+
+        let store = store.lock().unwrap();
+        let persisted = store.load_memory().unwrap();
+        assert_eq!(persisted.section_texts("people"), vec!["Dev — framer"]);
+        assert_eq!(persisted, *memory.lock().unwrap());
+        assert_eq!(store.reflection_signals().unwrap().completed_reflections, 1);
+    }
+
+    #[tokio::test]
+    async fn a_job_memory_is_reflected_separately_against_its_own_sessions() {
+        let s = Store::open_in_memory("device-a").unwrap().with_clock(Arc::new(|| 1000));
+        let job = s.create_job(crate::domain::NewJob { name: "Alvarez yard".into(), ..Default::default() }).unwrap();
+        let other = s.start_session(None).unwrap();
+        s.append_transcript(&other.id, "walked the deck with Dev").unwrap();
+        s.end_and_record_session(&other.id).unwrap();
+        let linked = s.start_session(Some(&job.id)).unwrap();
+        s.append_transcript(&linked.id, "gate code changed to 5521").unwrap();
+        s.end_and_record_session(&linked.id).unwrap();
+        let scope = MemoryScope::Job(job.id.clone());
+        let mut job_memory = Memory::default();
+        job_memory.remember("access", "gate code 4412", 900);
+        s.save_scoped_memory(&scope, &job_memory, &MemoryChange::Other).unwrap();
+
+        let provider = Arc::new(MockProvider::new(vec![
+            write_memory_response(serde_json::json!({"people": ["Dev — framer"]})),
+            write_memory_response(serde_json::json!({"access": ["gate code 5521"]})),
+        ]));
+        let store = Arc::new(Mutex::new(s));
+        let memory = Arc::new(Mutex::new(Memory::default()));
+        let coordinator =
+            ReflectionCoordinator::in_store(provider.clone(), store.clone(), memory).with_clock(Arc::new(|| 1000));
+        assert!(coordinator.maybe_reflect().await.unwrap().is_some());
+
+        let requests = provider.requests();
+        assert_eq!(requests.len(), 2, "one reflection for the user, one for the job");
+        let scoped_prompt = serde_json::to_string(&requests[1].messages[0]).unwrap();
+        assert!(scoped_prompt.contains("gate code changed") && !scoped_prompt.contains("walked the deck"));
+
+        let store = store.lock().unwrap();
+        assert_eq!(store.load_scoped_memory(&scope).unwrap().section_texts("access"), vec!["gate code 5521"]);
+        assert_eq!(store.load_memory().unwrap().section_texts("people"), vec!["Dev — framer"]);
+        assert!(store.load_memory().unwrap().section_texts("access").is_empty());
+        assert_eq!(store.usage_totals().unwrap(), (400, 80), "both reflections' cost logged");
+        assert_eq!(store.reflection_signals().unwrap().completed_reflections, 1, "cadence follows the user's memory");
+    }
+
     /// A content failure (post-completion — write_memory has malformed sections)
     /// returns an error, leaves memory and signals untouched, AND records a
     /// "reflection" usage row for the tokens that were burned (R9).
@@ -308,7 +455,8 @@ mod tests {
                 input: serde_json::json!({ "sections": "not an object" }),
             }],
             stop_reason: StopReason::ToolUse,
-            usage: Usage { input_tokens: 200, output_tokens: 40 },
+            usage: Usage { input_tokens: 200, output_tokens: 40, ..Default::default() },
+            model: None,
         };
         let (coordinator, memory, _memory_store, store) =
             coordinator_with(vec![malformed], store_with_ended_session());
@@ -106,6 +106,29 @@ pub struct Session {
     pub created_at: u64,
     pub updated_at: u64,
     pub device_id: String,
+    /// Why the last processing attempt failed; `None` unless `status` is
+    /// `Failed` by way of a provider error.
+    pub failure: Option<SessionFailure>,
+    /// `Processed` by the on-device fallback model (cloud unreachable); the
+    /// outputs stand until a cloud reprocess replaces them. Always `false`
+    /// for any other status.
+    pub provisional: bool,
+}
+
+/// The provider failure that left a session `Failed` — persisted so the app
+/// can say "API key rejected" instead of a generic failure. Storage, tool and
+/// max-turns failures aren't the provider's and leave no record.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionFailure {
+    pub kind: harness::ProviderErrorKind,
+    /// The HTTP status, when the provider answered with one.
+    pub http_status: Option<u16>,
+}
+
+impl SessionFailure {
+    pub fn from_error(err: &harness::HarnessError) -> Option<Self> {
+        Some(SessionFailure { kind: err.provider_kind()?, http_status: err.http_status() })
+    }
 }
 
 /// Where a captured item came from. Drives the end-of-session swap
@@ -218,6 +241,9 @@ pub struct Photo {
     pub item_id: Option<String>,
     /// Shell-owned, opaque to core: a relative filename in `<Documents>/photos/`.
     pub filename: String,
+    /// One line on what the photo shows, from the vision pass; `None` until
+    /// a pass has looked at it.
+    pub caption: Option<String>,
     pub captured_at: u64,
     pub created_at: u64,
     pub updated_at: u64,
@@ -370,11 +396,95 @@ pub struct LlmUsageRow {
     pub id: String,
     pub session_id: Option<String>,
     /// What the tokens bought: "processing" (extraction agent + summary call are
-    /// folded into a single row per session by design), "reflection", or future
-    /// pipeline phases. "summary" never appears as a standalone purpose.
+    /// folded into a single row per serving model by design), "processing_local"
+    /// (the same, on the on-device fallback model — free, excluded from
+    /// `usage_totals`), "reflection", or future pipeline phases. "summary"
+    /// never appears as a standalone purpose.
     pub purpose: String,
     pub input_tokens: u64,
     pub output_tokens: u64,
+    /// Prompt-cache write and read tokens, on top of `input_tokens`.
+    pub cache_creation_input_tokens: u64,
+    pub cache_read_input_tokens: u64,
+    /// The model that served these calls — a run a fallback answered in
+    /// part logs a row per model (`harness::ServedBy`). None when unknown.
+    pub model: Option<String>,
+    pub created_at: u64,
+    pub device_id: String,
+}
+
+/// A pipeline call waiting in a provider message batch (`harness::BatchProvider`).
+/// `kind` names the pipeline step that submitted it ("notes", "document") and
+/// owns `state`, the JSON it needs to finish the job once the batch ends.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BatchJob {
+    pub id: String,
+    pub batch_id: String,
+    pub session_id: String,
+    pub kind: String,
+    pub state: String,
+    pub created_at: u64,
+    pub device_id: String,
+}
+
+/// Whose memory: the user's own, or one kept for a single job or client —
+/// "gate code is 4412" belongs to a job, not to the user. Each scope has its
+/// own rows, revision log, word cap and reflection.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum MemoryScope {
+    Global,
+    Job(String),
+    /// Keyed by client name as written on the job, compared case- and
+    /// whitespace-insensitively (there is no client entity).
+    Client(String),
+}
+
+impl MemoryScope {
+    /// The client scope for `name`, normalized so "Mrs. Alvarez" and
+    /// "mrs.  alvarez" share one memory.
+    pub fn client(name: &str) -> Self {
+        MemoryScope::Client(name.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase())
+    }
+
+    /// The scopes a session linked to `job` reads besides the global one:
+    /// the job's, then its client's when it names one.
+    pub fn for_job(job: &Job) -> Vec<MemoryScope> {
+        let mut scopes = vec![MemoryScope::Job(job.id.clone())];
+        if let Some(client) = job.client.as_deref().filter(|c| !c.trim().is_empty()) {
+            scopes.push(MemoryScope::client(client));
+        }
+        scopes
+    }
+
+    /// Stored form: `""`, `job:{id}` or `client:{name}`.
+    pub fn key(&self) -> String {
+        match self {
+            MemoryScope::Global => String::new(),
+            MemoryScope::Job(id) => format!("job:{id}"),
+            MemoryScope::Client(name) => format!("client:{name}"),
+        }
+    }
+
+    pub fn parse(key: &str) -> Result<Self, CoreError> {
+        if key.is_empty() {
+            return Ok(MemoryScope::Global);
+        }
+        match key.split_once(':') {
+            Some(("job", id)) => Ok(MemoryScope::Job(id.to_string())),
+            Some(("client", name)) => Ok(MemoryScope::Client(name.to_string())),
+            _ => Err(CoreError::Corrupt(format!("unknown memory scope: {key}"))),
+        }
+    }
+}
+
+/// One entry in the memory revision log: the whole memory after a save
+/// that changed it, and why it changed.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MemoryRevision {
+    pub id: String,
+    pub scope: MemoryScope,
+    pub change: harness::MemoryChange,
+    pub memory: harness::Memory,
     pub created_at: u64,
     pub device_id: String,
 }
@@ -408,6 +518,10 @@ pub struct WalkSummary {
     pub ended_at: Option<u64>,
     pub item_count: u64,
     pub has_document: bool,
+    /// See `Session::failure`.
+    pub failure: Option<SessionFailure>,
+    /// See `Session::provisional`.
+    pub provisional: bool,
 }
 
 #[cfg(test)]
@@ -0,0 +1,126 @@
+//! Shared plumbing for pipeline steps that can go through a provider message
+//! batch (`harness::BatchProvider`) instead of calling synchronously. A step
+//! stages its single-shot requests as jobs; `submit` sends them as one batch
+//! and records each job in the store; `ended_jobs`, on a later pass — a
+//! later app launch, even — hands back every job whose batch has ended with
+//! its answers, and the step `claim`s each one in the transaction that
+//! applies it.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use harness::{BatchProvider, BatchState, CompletionRequest, CompletionResponse, HarnessError};
+
+use crate::domain::BatchJob;
+use crate::error::CoreError;
+use crate::store::Store;
+
+/// One job, ready to submit.
+#[derive(Clone)]
+pub(crate) struct StagedJob {
+    pub id: String,
+    pub session_id: String,
+    /// What the step needs to finish the job, as JSON (`BatchJob::state`).
+    pub state: String,
+    /// The job's requests, each under a phase name unique within the job.
+    pub requests: Vec<(&'static str, CompletionRequest)>,
+}
+
+/// A job whose batch has ended, not yet claimed.
+pub(crate) struct EndedJob {
+    pub job: BatchJob,
+    responses: HashMap<String, Result<CompletionResponse, HarnessError>>,
+}
+
+impl EndedJob {
+    /// `phase`'s answer. One the batch no longer has is an error like any
+    /// other failed request.
+    pub fn take(&mut self, phase: &str) -> Result<CompletionResponse, HarnessError> {
+        self.responses
+            .remove(phase)
+            .unwrap_or_else(|| Err(HarnessError::Provider(format!("batch returned no {phase} result"))))
+    }
+}
+
+/// `custom_id` for a job's phase. Job ids are UUIDs, so `_` splits them
+/// back apart unambiguously.
+fn custom_id(job_id: &str, phase: &str) -> String {
+    format!("{job_id}_{phase}")
+}
+
+fn locked(store: &Mutex<Store>) -> Result<std::sync::MutexGuard<'_, Store>, CoreError> {
+    store.lock().map_err(|_| CoreError::InvalidState("store lock poisoned".into()))
+}
+
+/// Submits `jobs` as one batch and records them under `kind`. Returns the
+/// batch id; nothing is recorded if submission fails.
+pub(crate) async fn submit(
+    batches: &dyn BatchProvider,
+    store: &Mutex<Store>,
+    kind: &str,
+    jobs: &[StagedJob],
+) -> Result<String, HarnessError> {
+    let requests = jobs
+        .iter()
+        .flat_map(|job| job.requests.iter().map(|(phase, request)| (custom_id(&job.id, phase), request.clone())))
+        .collect();
+    let batch = batches.submit_batch(requests).await?;
+    let store = locked(store).map_err(|e| HarnessError::Storage(e.to_string()))?;
+    for job in jobs {
+        store
+            .add_batch_job(&job.id, &batch.id, &job.session_id, kind, &job.state)
+            .map_err(|e| HarnessError::Storage(e.to_string()))?;
+    }
+    Ok(batch.id)
+}
+
+/// Claims an ended job: opens the transaction its result is applied in and
+/// takes the job inside it. Committing lands the claim with the result; an
+/// apply that fails midway rolls both back, leaving the job for the next
+/// `ended_jobs` pass. A job another caller already claimed is an error.
+pub(crate) fn claim<'a>(store: &'a Store, job_id: &str) -> Result<rusqlite::Transaction<'a>, CoreError> {
+    let tx = store.conn.unchecked_transaction()?;
+    if !store.take_batch_job(job_id)? {
+        return Err(CoreError::InvalidState(format!("batch job {job_id} already claimed")));
+    }
+    Ok(tx)
+}
+
+/// Polls every batch holding a `kind` job and returns the jobs of each one
+/// that has ended, with their answers; the caller `claim`s each. Jobs of a
+/// batch still running stay for the next call. A batch the provider no
+/// longer knows (results are kept for a limited time) ends its jobs with no
+/// answers.
+pub(crate) async fn ended_jobs(
+    batches: &dyn BatchProvider,
+    store: &Mutex<Store>,
+    kind: &str,
+) -> Result<Vec<EndedJob>, CoreError> {
+    let jobs = locked(store)?.list_batch_jobs(kind)?;
+    let mut batch_ids: Vec<&str> = jobs.iter().map(|j| j.batch_id.as_str()).collect();
+    batch_ids.sort_unstable();
+    batch_ids.dedup();
+
+    let mut ended = Vec::new();
+    for batch_id in batch_ids {
+        let results = match batches.batch_status(batch_id).await {
+            Ok(status) if status.state == BatchState::Ended => batches.batch_results(batch_id).await?,
+            Ok(_) => continue,
+            Err(HarnessError::ProviderStatus { status: 404, .. }) => Vec::new(),
+            Err(e) => return Err(e.into()),
+        };
+        let mut by_id: HashMap<String, Result<CompletionResponse, HarnessError>> =
+            results.into_iter().map(|r| (r.custom_id, r.response)).collect();
+        for job in jobs.iter().filter(|j| j.batch_id == batch_id) {
+            let prefix = custom_id(&job.id, "");
+            let keys: Vec<String> = by_id.keys().filter(|k| k.starts_with(&prefix)).cloned().collect();
+            let responses = keys
+                .into_iter()
+                .filter_map(|k| by_id.remove_entry(&k))
+                .map(|(k, r)| (k[prefix.len()..].to_string(), r))
+                .collect();
+            ended.push(EndedJob { job: job.clone(), responses });
+        }
+    }
+    Ok(ended)
+}
@@ -3,18 +3,22 @@
 //! items (no LLM), then for pricing kinds runs one focused items-only
 //! pricing pass (R6). A document always lands — pricing failure degrades to
 //! an unpriced structure-only document, never a hard failure (R7).
+//! `queue` is the same build with its calls sent as a message batch.
 
 use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, Mutex};
 
 use harness::{
-    CompletionRequest, ContentBlock, HarnessError, LlmProvider, Memory, MemoryStore, Message,
-    ToolSpec, Usage,
+    forced_tool_call, forced_tool_input, AgentObserver, BatchProvider, CacheHints, CancelToken,
+    CompletionRequest, ForcedOutput, HarnessError, LlmProvider, Memory, MemoryStore, Message, SharedObserver,
+    ServedBy, ToolSpec, Usage, DEFAULT_MEMORY_BUDGET_TOKENS,
 };
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
 
-use crate::domain::{Artifact, CapturedItem, DocumentSchema, SchemaField, SessionStatus};
+use crate::domain::{Artifact, BatchJob, CapturedItem, DocumentSchema, SchemaField, Session, SessionStatus};
 use crate::error::CoreError;
-use crate::pipeline::{doc_kinds_for_template, is_pricing_kind};
+use crate::pipeline::{batch, doc_kinds_for_template, is_pricing_kind};
 use crate::store::Store;
 
 /// C1: whether a rendered line defaults to `is_gap: true`. `PerPricingKind`
@@ -130,6 +134,90 @@ fn price_items_tool_spec() -> ToolSpec {
     }
 }
 
+/// `price_items` input. Its rows (like `fill_fields`') are parsed one at a
+/// time, so one bad row is dropped instead of failing the pass.
+#[derive(Deserialize)]
+struct PricesInput {
+    prices: Vec<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct PriceRow {
+    item_id: String,
+    amount_cents: i64,
+}
+
+#[derive(Deserialize)]
+struct FieldsInput {
+    fields: Vec<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct FieldRow {
+    key: String,
+    value: String,
+}
+
+/// Runs a forced document pass, adding its spend (repair included) to
+/// `usage`/`model` whether or not it produced a parseable answer (R9).
+async fn forced_pass<T: DeserializeOwned>(
+    provider: &Arc<dyn LlmProvider>,
+    request: CompletionRequest,
+    usage: &mut Usage,
+    model: &mut ServedBy,
+    observer: Option<&dyn AgentObserver>,
+    cancel: &CancelToken,
+    purpose: &str,
+) -> Result<T, HarnessError> {
+    match forced_tool_call(provider.as_ref(), request, None, observer, cancel, purpose).await {
+        Ok(ForcedOutput { value, usage: spent, model: served }) => {
+            usage.add(&spent);
+            model.merge(&served);
+            Ok(value)
+        }
+        Err(run_err) => {
+            usage.add(&run_err.usage);
+            model.merge(&run_err.model);
+            Err(run_err.source)
+        }
+    }
+}
+
+/// `forced_pass` for a pass that went through a message batch: its answer
+/// is parsed the same way, minus the repair round-trip a batch can't take.
+fn batched_pass<T: DeserializeOwned>(
+    ended: &mut batch::EndedJob,
+    phase: &str,
+    tool: &str,
+    purpose: &str,
+    usage: &mut Usage,
+    model: &mut ServedBy,
+) -> Result<T, HarnessError> {
+    let response = ended.take(phase)?;
+    usage.add(&response.usage);
+    model.add(response.model.as_deref(), &response.usage);
+    forced_tool_input(&response, tool, purpose)
+}
+
+/// First-wins dedup of `(key, value)` rows; rows that don't parse as `R` or
+/// name a key outside `valid` are dropped — never fail the whole pass over
+/// one bad row.
+fn first_wins<R: DeserializeOwned, V>(
+    rows: Vec<serde_json::Value>,
+    valid: &HashSet<&str>,
+    split: impl Fn(R) -> (String, V),
+) -> HashMap<String, V> {
+    let mut map = HashMap::new();
+    for row in rows {
+        let Ok(row) = serde_json::from_value::<R>(row) else { continue };
+        let (key, value) = split(row);
+        if valid.contains(key.as_str()) && !map.contains_key(&key) {
+            map.insert(key, value);
+        }
+    }
+    map
+}
+
 fn format_pricing_items(items: &[CapturedItem]) -> String {
     items
         .iter()
@@ -146,15 +234,15 @@ fn format_pricing_items(items: &[CapturedItem]) -> String {
 /// structure render, only amounts can move. Echo-and-validate + first-wins
 /// dedup mirror Plan 12's `item_id` pattern.
 ///
-/// Usage is accumulated into `usage` as soon as a response arrives (R9: a
-/// response that fails to parse into a valid tool call still cost tokens) —
-/// BEFORE this function's own success/failure is decided, matching
-/// `run_build_document`/`summarize`'s pattern elsewhere in this pipeline.
+/// A reply without a parseable `price_items` call gets one repair
+/// round-trip (`forced_tool_call`). Usage — both calls' when repaired — is
+/// accumulated into `usage` whether the pass succeeds or not (R9: a
+/// response that fails to parse into a valid tool call still cost tokens).
 /// (This is a small, deliberate signature addition vs the plan's literal
 /// `-> Result<HashMap<...>, HarnessError>` — an out-param is needed so a
 /// degrade path that reached the API but got an unparseable response still
-/// logs its cost; a `provider.complete` `Err` itself carries no usage, so the
-/// zero-token case is unaffected.)
+/// logs its cost.) `cancel` abandons the call in flight.
+#[allow(clippy::too_many_arguments)]
 pub(crate) async fn price_items(
     provider: &Arc<dyn LlmProvider>,
     items: &[CapturedItem],
@@ -162,7 +250,23 @@ pub(crate) async fn price_items(
     memory_prompt: &str,
     max_tokens: u32,
     usage: &mut Usage,
+    model: &mut ServedBy,
+    observer: Option<&dyn AgentObserver>,
+    cancel: &CancelToken,
 ) -> Result<HashMap<String, i64>, HarnessError> {
+    let request = pricing_request(items, spoken_total_cents, memory_prompt, max_tokens);
+    let input: PricesInput =
+        forced_pass(provider, request, usage, model, observer, cancel, "document_pricing").await?;
+    Ok(valid_prices(input, items))
+}
+
+/// The forced `price_items` request `price_items` sends.
+fn pricing_request(
+    items: &[CapturedItem],
+    spoken_total_cents: Option<i64>,
+    memory_prompt: &str,
+    max_tokens: u32,
+) -> CompletionRequest {
     let memory_block = if memory_prompt.trim().is_empty() {
         String::new()
     } else {
@@ -185,43 +289,21 @@ pub(crate) async fn price_items(
     let items_block = format_pricing_items(items);
     let user_message = format!("Price these items.\n\n{items_block}{hint_block}");
 
-    let response = provider
-        .complete(CompletionRequest {
-            system,
-            messages: vec![Message::user_text(user_message)],
-            tools: vec![price_items_tool_spec()],
-            max_tokens,
-            tool_choice: Some(PRICE_ITEMS.to_string()),
-        })
-        .await?;
-    usage.add(&response.usage);
-
-    let input = response.content.iter().find_map(|b| match b {
-        ContentBlock::ToolUse { name, input, .. } if name == PRICE_ITEMS => Some(input.clone()),
-        _ => None,
-    });
-    let input = input.ok_or_else(|| {
-        HarnessError::Provider("price_items response missing price_items call".into())
-    })?;
+    CompletionRequest {
+        system,
+        messages: vec![Message::user_text(user_message)],
+        tools: vec![price_items_tool_spec()],
+        max_tokens,
+        tool_choice: Some(PRICE_ITEMS.to_string()),
+        cache: CacheHints::default(),
+        thinking: None,
+    }
+}
 
+/// Echo-and-validate: keeps the first price per `item_id` in `items`.
+fn valid_prices(input: PricesInput, items: &[CapturedItem]) -> HashMap<String, i64> {
     let valid_ids: HashSet<&str> = items.iter().map(|i| i.id.as_str()).collect();
-    let mut map: HashMap<String, i64> = HashMap::new();
-    if let Some(prices) = input.get("prices").and_then(|v| v.as_array()) {
-        for p in prices {
-            let (Some(id), Some(amount)) = (
-                p.get("item_id").and_then(|v| v.as_str()),
-                p.get("amount_cents").and_then(|v| v.as_i64()),
-            ) else {
-                continue;
-            };
-            // First-wins dedup; unknown/hallucinated ids are dropped — never
-            // fail the whole pass over one bad row.
-            if valid_ids.contains(id) && !map.contains_key(id) {
-                map.insert(id.to_string(), amount);
-            }
-        }
-    }
-    Ok(map)
+    first_wins(input.prices, &valid_ids, |row: PriceRow| (row.item_id, row.amount_cents))
 }
 
 const FILL_FIELDS: &str = "fill_fields";
@@ -258,12 +340,12 @@ fn fill_fields_tool_spec() -> ToolSpec {
 /// (never the transcript, R6); the items block reuses `format_pricing_items`
 /// (ONE item-formatting helper, no divergent shape). Echo-and-validate
 /// against the offered field keys, first-wins dedup, drop unknown keys.
-/// Usage is accumulated as soon as a response arrives (R9: an unparseable
-/// response still cost tokens) — BEFORE success/failure is decided. A
-/// provider `Err` carries no usage; a completed response whose tool block is
-/// missing/unparseable is `Err(HarnessError::Provider(..))` after
-/// `usage.add`, exactly like `price_items`. A tool block that IS present but
+/// Usage is accumulated whether or not the pass succeeds (R9: an
+/// unparseable response still cost tokens); a tool block still
+/// missing/unparseable after the repair round-trip is
+/// `Err(HarnessError::Provider(..))`, exactly like `price_items`. A tool block that IS present but
 /// simply omits a field is NOT an error — that field is a truthful gap.
+#[allow(clippy::too_many_arguments)]
 pub(crate) async fn fill_fields(
     provider: &Arc<dyn LlmProvider>,
     fields: &[SchemaField],
@@ -271,7 +353,18 @@ pub(crate) async fn fill_fields(
     summary: &str,
     max_tokens: u32,
     usage: &mut Usage,
+    model: &mut ServedBy,
+    observer: Option<&dyn AgentObserver>,
+    cancel: &CancelToken,
 ) -> Result<HashMap<String, String>, HarnessError> {
+    let request = fill_request(fields, items, summary, max_tokens);
+    let input: FieldsInput =
+        forced_pass(provider, request, usage, model, observer, cancel, "document_fill").await?;
+    Ok(valid_fields(input, fields))
+}
+
+/// The forced `fill_fields` request `fill_fields` sends.
+fn fill_request(fields: &[SchemaField], items: &[CapturedItem], summary: &str, max_tokens: u32) -> CompletionRequest {
     let system = "You fill named fields of a field-work document for a tradesperson. Put a \
                   value only on a field whose answer was clearly stated in the session — never \
                   guess. You may fill only fields from the given list, by their exact key.";
@@ -296,42 +389,21 @@ pub(crate) async fn fill_fields(
          Session summary:\n{summary}"
     );
 
-    let response = provider
-        .complete(CompletionRequest {
-            system: system.to_string(),
-            messages: vec![Message::user_text(user_message)],
-            tools: vec![fill_fields_tool_spec()],
-            max_tokens,
-            tool_choice: Some(FILL_FIELDS.to_string()),
-        })
-        .await?;
-    usage.add(&response.usage);
-
-    let input = response.content.iter().find_map(|b| match b {
-        ContentBlock::ToolUse { name, input, .. } if name == FILL_FIELDS => Some(input.clone()),
-        _ => None,
-    });
-    let input = input.ok_or_else(|| {
-        HarnessError::Provider("fill_fields response missing fill_fields call".into())
-    })?;
+    CompletionRequest {
+        system: system.to_string(),
+        messages: vec![Message::user_text(user_message)],
+        tools: vec![fill_fields_tool_spec()],
+        max_tokens,
+        tool_choice: Some(FILL_FIELDS.to_string()),
+        cache: CacheHints::default(),
+        thinking: None,
+    }
+}
 
+/// Echo-and-validate: keeps the first value per offered field key.
+fn valid_fields(input: FieldsInput, fields: &[SchemaField]) -> HashMap<String, String> {
     let valid_keys: HashSet<&str> = fields.iter().map(|f| f.key.as_str()).collect();
-    let mut map: HashMap<String, String> = HashMap::new();
-    if let Some(entries) = input.get("fields").and_then(|v| v.as_array()) {
-        for e in entries {
-            let (Some(key), Some(value)) =
-                (e.get("key").and_then(|v| v.as_str()), e.get("value").and_then(|v| v.as_str()))
-            else {
-                continue;
-            };
-            // First-wins dedup; unknown/hallucinated keys are dropped — never
-            // fail the whole pass over one bad row.
-            if valid_keys.contains(key) && !map.contains_key(key) {
-                map.insert(key.to_string(), value.to_string());
-            }
-        }
-    }
-    Ok(map)
+    first_wins(input.fields, &valid_keys, |row: FieldRow| (row.key, row.value))
 }
 
 /// Assembles the payload `fields[]` (Plan 19 Stage 5): one entry per
@@ -406,6 +478,40 @@ pub struct BuildDocumentOutcome {
     pub queued: bool,
 }
 
+/// What `DocumentBuilder::queue` did with a build.
+#[derive(Debug)]
+pub enum QueuedDocument {
+    /// No call was needed, so the document was built at once.
+    Built(BuildDocumentOutcome),
+    /// Its calls went out in this batch; `resume_batches` mints it.
+    Batched { batch_id: String },
+}
+
+/// `BatchJob::kind` of a queued build, and the phases of its requests.
+const DOCUMENT_JOB: &str = "document";
+const PRICING_PHASE: &str = "pricing";
+const FILL_PHASE: &str = "fill";
+
+/// What one build renders from, resolved and validated.
+struct DocumentPlan {
+    session: Session,
+    items: Vec<CapturedItem>,
+    schema: DocumentSchema,
+    priced: bool,
+    walk_fields: Vec<SchemaField>,
+}
+
+impl DocumentPlan {
+    /// D5: the pricing pass runs for a priced schema with something to price.
+    fn needs_pricing(&self) -> bool {
+        self.priced && !self.items.is_empty()
+    }
+
+    fn summary(&self) -> &str {
+        self.session.summary.as_deref().unwrap_or("")
+    }
+}
+
 /// On-demand document builder (D1/D8/D9), engine-keyed by the caller (FFI
 /// `MurmurEngine::build_document`, not `WalkSession`-scoped — the walk may
 /// already be over and its `WalkSession` handle dropped).
@@ -414,11 +520,18 @@ pub struct DocumentBuilder {
     store: Arc<Mutex<Store>>,
     memory: Arc<Mutex<Memory>>,
     /// Reserved: a future price-book seam (D6) may consult saved facts
-    /// directly rather than only the rendered `to_prompt()` text.
+    /// directly rather than only the rendered memory text.
     #[allow(dead_code)]
     memory_store: Arc<dyn MemoryStore>,
     /// Pricing-call output budget.
     pub max_tokens: u32,
+    /// Budget for the memory entries most relevant to the priced items.
+    pub memory_budget_tokens: usize,
+    /// Sees the pricing ("document_pricing") and fill ("document_fill") calls.
+    observer: Option<SharedObserver>,
+    cancel: CancelToken,
+    /// Where `queue` sends its calls.
+    batches: Option<Arc<dyn BatchProvider>>,
 }
 
 impl DocumentBuilder {
@@ -428,7 +541,36 @@ impl DocumentBuilder {
         memory: Arc<Mutex<Memory>>,
         memory_store: Arc<dyn MemoryStore>,
     ) -> Self {
-        DocumentBuilder { provider, store, memory, memory_store, max_tokens: 1024 }
+        DocumentBuilder {
+            provider,
+            store,
+            memory,
+            memory_store,
+            max_tokens: 1024,
+            memory_budget_tokens: DEFAULT_MEMORY_BUDGET_TOKENS,
+            observer: None,
+            cancel: CancelToken::default(),
+            batches: None,
+        }
+    }
+
+    pub fn with_observer(mut self, observer: SharedObserver) -> Self {
+        self.observer = Some(observer);
+        self
+    }
+
+    /// Abandons the build when `cancel` fires mid-call. Unlike a failed
+    /// call this is no degrade: nothing is minted, the spend so far is
+    /// logged, and `build` returns `HarnessError::Cancelled` with it.
+    pub fn with_cancel(mut self, cancel: CancelToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// Enables `queue`/`resume_batches`. `build` is unaffected.
+    pub fn with_batches(mut self, batches: Arc<dyn BatchProvider>) -> Self {
+        self.batches = Some(batches);
+        self
     }
 
     fn locked(&self) -> Result<std::sync::MutexGuard<'_, Store>, CoreError> {
@@ -440,11 +582,176 @@ impl DocumentBuilder {
     /// D8 validation, D4 structure render, D5/D5a pricing pass, D7 mint +
     /// persist. Never a hard failure on a pricing LLM error (R7) — degrades
     /// to `queued: true` with an unpriced structure-only document instead.
+    /// Cancellation is the one exception (`with_cancel`).
     pub async fn build(
         &self,
         session_id: &str,
         doc_kind: &str,
     ) -> Result<BuildDocumentOutcome, CoreError> {
+        let plan = self.plan(session_id, doc_kind)?;
+        let mut lines = render_lines(&plan.items, GapPolicy::PerPricingKind, plan.priced);
+        let mut usage = Usage::default();
+        let mut model = ServedBy::default();
+        let mut queued = false;
+
+        if plan.needs_pricing() {
+            let hint = self.session_spoken_total(session_id)?;
+            let memory_prompt = self.memory_prompt(&plan.items)?;
+            match price_items(
+                &self.provider,
+                &plan.items,
+                hint,
+                &memory_prompt,
+                self.max_tokens,
+                &mut usage,
+                &mut model,
+                self.observer.as_deref(),
+                &self.cancel,
+            )
+            .await
+            {
+                Ok(map) => apply_prices(&map, &mut lines),
+                Err(HarnessError::Cancelled { .. }) => {
+                    return self.cancelled(session_id, usage, &model)
+                }
+                // R7: never a hard failure — the structure-only document
+                // still lands, just unpriced and flagged queued (D5 degrade).
+                Err(_) => queued = true,
+            }
+        }
+
+        let mut fill_values: HashMap<String, String> = HashMap::new();
+        if !plan.walk_fields.is_empty() {
+            match fill_fields(
+                &self.provider,
+                &plan.walk_fields,
+                &plan.items,
+                plan.summary(),
+                self.max_tokens,
+                &mut usage,
+                &mut model,
+                self.observer.as_deref(),
+                &self.cancel,
+            )
+            .await
+            {
+                Ok(map) => fill_values = map,
+                Err(HarnessError::Cancelled { .. }) => {
+                    return self.cancelled(session_id, usage, &model)
+                }
+                // Mirrors the pricing degrade exactly (R7): a model call this
+                // build needed didn't complete — regenerate to retry. Every
+                // walk field then falls to a truthful gap below.
+                Err(_) => queued = true,
+            }
+        }
+
+        self.mint(session_id, doc_kind, &plan, lines, &fill_values, queued, usage, model, None)
+    }
+
+    /// `build` for a document nobody is waiting on (`with_batches`): the
+    /// pricing and fill calls go out as one message batch at half price, and
+    /// `resume_batches` mints the document once it has ended. A document that
+    /// needs no call is built at once. Validation errors surface here, not
+    /// at resume.
+    pub async fn queue(&self, session_id: &str, doc_kind: &str) -> Result<QueuedDocument, CoreError> {
+        let Some(batches) = &self.batches else {
+            return Err(CoreError::InvalidState("queue needs a batch provider".into()));
+        };
+        let plan = self.plan(session_id, doc_kind)?;
+        let mut requests = Vec::new();
+        if plan.needs_pricing() {
+            let hint = self.session_spoken_total(session_id)?;
+            let memory_prompt = self.memory_prompt(&plan.items)?;
+            requests.push((PRICING_PHASE, pricing_request(&plan.items, hint, &memory_prompt, self.max_tokens)));
+        }
+        if !plan.walk_fields.is_empty() {
+            requests.push((
+                FILL_PHASE,
+                fill_request(&plan.walk_fields, &plan.items, plan.summary(), self.max_tokens),
+            ));
+        }
+        if requests.is_empty() {
+            return self.build(session_id, doc_kind).await.map(QueuedDocument::Built);
+        }
+        let job = batch::StagedJob {
+            id: crate::ids::new_id(),
+            session_id: session_id.to_string(),
+            state: serde_json::json!({ "doc_kind": doc_kind }).to_string(),
+            requests,
+        };
+        let batch_id = batch::submit(batches.as_ref(), &self.store, DOCUMENT_JOB, &[job]).await?;
+        Ok(QueuedDocument::Batched { batch_id })
+    }
+
+    /// Mints the document of every `queue`d build whose batch has ended,
+    /// from the session's items as they are now. A pass whose answer didn't
+    /// come back usable degrades exactly as in `build` (`queued: true`); a
+    /// session that can no longer take the document (reprocessing, or the
+    /// schema was removed) is that job's error. Jobs of batches still
+    /// running are left for a later call.
+    pub async fn resume_batches(
+        &self,
+    ) -> Result<Vec<(String, Result<BuildDocumentOutcome, CoreError>)>, CoreError> {
+        let Some(batches) = &self.batches else {
+            return Err(CoreError::InvalidState("resume_batches needs a batch provider".into()));
+        };
+        let mut results = Vec::new();
+        for mut ended in batch::ended_jobs(batches.as_ref(), &self.store, DOCUMENT_JOB).await? {
+            let session_id = ended.job.session_id.clone();
+            let outcome = self.finish_queued(&mut ended);
+            results.push((session_id, outcome));
+        }
+        Ok(results)
+    }
+
+    fn finish_queued(&self, ended: &mut batch::EndedJob) -> Result<BuildDocumentOutcome, CoreError> {
+        let session_id = ended.job.session_id.clone();
+        let (doc_kind, plan) = match self.queued_plan(&ended.job) {
+            Ok(planned) => planned,
+            Err(e) => {
+                // Nothing to apply: claim the job so it isn't retried forever.
+                self.locked()?.take_batch_job(&ended.job.id)?;
+                return Err(e);
+            }
+        };
+        let doc_kind = doc_kind.as_str();
+        let mut lines = render_lines(&plan.items, GapPolicy::PerPricingKind, plan.priced);
+        let mut usage = Usage::default();
+        let mut model = ServedBy::default();
+        let mut queued = false;
+
+        if plan.needs_pricing() {
+            let answer = batched_pass(ended, PRICING_PHASE, PRICE_ITEMS, "document_pricing", &mut usage, &mut model);
+            match answer {
+                Ok(input) => apply_prices(&valid_prices(input, &plan.items), &mut lines),
+                Err(_) => queued = true,
+            }
+        }
+        let mut fill_values: HashMap<String, String> = HashMap::new();
+        if !plan.walk_fields.is_empty() {
+            let answer = batched_pass(ended, FILL_PHASE, FILL_FIELDS, "document_fill", &mut usage, &mut model);
+            match answer {
+                Ok(input) => fill_values = valid_fields(input, &plan.walk_fields),
+                Err(_) => queued = true,
+            }
+        }
+        self.mint(&session_id, doc_kind, &plan, lines, &fill_values, queued, usage, model, Some(&ended.job.id))
+    }
+
+    /// A queued job's doc kind and its plan — an error when the job is
+    /// unreadable or the session can no longer take the document.
+    fn queued_plan(&self, job: &BatchJob) -> Result<(String, DocumentPlan), CoreError> {
+        let state: serde_json::Value = serde_json::from_str(&job.state)?;
+        let doc_kind = state["doc_kind"]
+            .as_str()
+            .ok_or_else(|| CoreError::Corrupt(format!("document batch job {} has no doc_kind", job.id)))?;
+        Ok((doc_kind.to_string(), self.plan(&job.session_id, doc_kind)?))
+    }
+
+    /// D8 validation and §4 steps 1-3/5: what a build of `doc_kind` renders
+    /// from and which calls it needs.
+    fn plan(&self, session_id: &str, doc_kind: &str) -> Result<DocumentPlan, CoreError> {
         let (session, items, schema) = {
             let store = self.locked()?;
             let session = store.get_session(session_id)?;
@@ -488,33 +795,6 @@ impl DocumentBuilder {
             .iter()
             .find(|s| s.kind == "line_items")
             .is_some_and(|s| s.priced);
-        let mut lines = render_lines(&items, GapPolicy::PerPricingKind, priced);
-        let mut usage = Usage::default();
-        let mut queued = false;
-
-        if priced && !items.is_empty() {
-            let hint = self.session_spoken_total(session_id)?;
-            let memory_prompt = self
-                .memory
-                .lock()
-                .map_err(|_| CoreError::InvalidState("memory lock poisoned".into()))?
-                .to_prompt();
-            match price_items(
-                &self.provider,
-                &items,
-                hint,
-                &memory_prompt,
-                self.max_tokens,
-                &mut usage,
-            )
-            .await
-            {
-                Ok(map) => apply_prices(&map, &mut lines),
-                // R7: never a hard failure — the structure-only document
-                // still lands, just unpriced and flagged queued (D5 degrade).
-                Err(_) => queued = true,
-            }
-        }
 
         // §4 step 5 — the fill pass: ONE focused call iff the schema has ≥1
         // LLM-fillable (`fill: "walk"`) field. Built-ins have none → zero
@@ -526,32 +806,33 @@ impl DocumentBuilder {
             .filter(|s| s.kind == "filled")
             .flat_map(|s| s.fields.iter().filter(|f| f.fill == "walk").cloned())
             .collect();
-        let mut fill_values: HashMap<String, String> = HashMap::new();
-        if !walk_fields.is_empty() {
-            match fill_fields(
-                &self.provider,
-                &walk_fields,
-                &items,
-                session.summary.as_deref().unwrap_or(""),
-                self.max_tokens,
-                &mut usage,
-            )
-            .await
-            {
-                Ok(map) => fill_values = map,
-                // Mirrors the pricing degrade exactly (R7): a model call this
-                // build needed didn't complete — regenerate to retry. Every
-                // walk field then falls to a truthful gap below.
-                Err(_) => queued = true,
-            }
-        }
-        let fields = assemble_fields(&schema, &fill_values);
+        Ok(DocumentPlan { session, items, schema, priced, walk_fields })
+    }
+
+    /// §4 steps 6-7 and D7/D9: assembles the payload, mints the number, and
+    /// logs the spend — in one transaction with `claim`, the batch job the
+    /// answers came back in.
+    #[allow(clippy::too_many_arguments)]
+    fn mint(
+        &self,
+        session_id: &str,
+        doc_kind: &str,
+        plan: &DocumentPlan,
+        lines: Vec<serde_json::Value>,
+        fill_values: &HashMap<String, String>,
+        queued: bool,
+        usage: Usage,
+        model: ServedBy,
+        claim: Option<&str>,
+    ) -> Result<BuildDocumentOutcome, CoreError> {
+        let schema = &plan.schema;
+        let fields = assemble_fields(schema, fill_values);
 
         // §4 step 6 — the total shape comes from the schema envelope (for
         // every built-in this equals the old total_shape(doc_kind) exactly).
         let payload = serde_json::json!({
             "doc_kind": doc_kind,
-            "job_date_unix": session.started_at,
+            "job_date_unix": plan.session.started_at,
             "total_kind": schema.total_kind,
             "total_label_key": schema.total_label_key,
             "static_total_cents": serde_json::Value::Null,
@@ -568,19 +849,47 @@ impl DocumentBuilder {
 
         // D7: always mint a fresh number and write a new snapshot artifact —
         // burn per tap, never reuse (regenerate leaves prior snapshots intact).
-        let artifact = self
-            .locked()?
-            .mint_document_number_and_add_artifact(session_id, doc_kind, None, payload)?;
+        let store = self.locked()?;
+        let claimed = claim.map(|job_id| batch::claim(&store, job_id)).transpose()?;
+        let artifact = store.mint_document_number_and_add_artifact(session_id, doc_kind, None, payload)?;
 
         // D9: log a "document"-purpose usage row only if a call was actually
         // made (non-pricing kinds and the empty-items skip make zero calls).
         if usage != Usage::default() {
-            self.locked()?.record_llm_usage(Some(session_id), "document", &usage)?;
+            store.record_served_usage(Some(session_id), "document", &usage, &model)?;
+        }
+        if let Some(tx) = claimed {
+            tx.commit()?;
         }
 
         Ok(BuildDocumentOutcome { document_artifact_id: artifact.id, usage, queued })
     }
 
+    /// The memory entries most relevant to `items` (what the pricing pass
+    /// is about). Store guard released before the memory guard is taken.
+    fn memory_prompt(&self, items: &[CapturedItem]) -> Result<String, CoreError> {
+        let now = self.locked()?.now();
+        Ok(self
+            .memory
+            .lock()
+            .map_err(|_| CoreError::InvalidState("memory lock poisoned".into()))?
+            .to_prompt_for(&format_pricing_items(items), now, self.memory_budget_tokens))
+    }
+
+    /// A cancelled build's exit: the usage row (when a call got that far)
+    /// and the error carrying the build's whole spend.
+    fn cancelled(
+        &self,
+        session_id: &str,
+        usage: Usage,
+        model: &ServedBy,
+    ) -> Result<BuildDocumentOutcome, CoreError> {
+        if usage != Usage::default() {
+            self.locked()?.record_served_usage(Some(session_id), "document", &usage, model)?;
+        }
+        Err(HarnessError::Cancelled { usage }.into())
+    }
+
     /// D5a: reads the `session_meta` artifact (if any) written by `process()`
     /// on success and returns its `spoken_total_cents` scalar. `None` when no
     /// meta artifact exists, or it exists but the field is absent (no total
@@ -617,7 +926,8 @@ mod tests {
         harness::CompletionResponse {
             content: vec![ContentBlock::ToolUse { id: "tu_1".into(), name: name.into(), input }],
             stop_reason: StopReason::ToolUse,
-            usage: Usage { input_tokens: 80, output_tokens: 15 },
+            usage: Usage { input_tokens: 80, output_tokens: 15, ..Default::default() },
+            model: None,
         }
     }
 
@@ -733,12 +1043,57 @@ mod tests {
         )]));
 
         let mut usage = Usage::default();
-        let map = price_items(&provider, &its, None, "", 512, &mut usage).await.unwrap();
+        let map = price_items(
+            &provider,
+            &its,
+            None,
+            "",
+            512,
+            &mut usage,
+            &mut ServedBy::default(),
+            None,
+            &CancelToken::default(),
+        )
+        .await
+        .unwrap();
         assert_eq!(map.get(a1.as_str()), Some(&28500));
         assert_eq!(map.get(a2.as_str()), Some(&31000), "first-wins on the duplicate a2");
         assert_eq!(map.get("bogus"), None, "hallucinated id dropped");
         assert_eq!(map.len(), 2);
-        assert_eq!(usage, Usage { input_tokens: 80, output_tokens: 15 });
+        assert_eq!(usage, Usage { input_tokens: 80, output_tokens: 15, ..Default::default() });
+    }
+
+    #[tokio::test]
+    async fn price_items_repairs_a_malformed_call_and_counts_both() {
+        let store = Store::open_in_memory("device-a").unwrap();
+        let session = store.start_session(None).unwrap();
+        let its = items(&store, &session.id, &[("todo", "mulch")]);
+        let a1 = its[0].id.clone();
+
+        let provider = Arc::new(MockProvider::new(vec![
+            tool_use("price_items", serde_json::json!({"prices": "28500"})),
+            tool_use("price_items", serde_json::json!({"prices": [{"item_id": a1, "amount_cents": 28500}]})),
+        ]));
+        let dyn_provider: Arc<dyn LlmProvider> = provider.clone();
+        let mut usage = Usage::default();
+        let map = price_items(
+            &dyn_provider,
+            &its,
+            None,
+            "",
+            512,
+            &mut usage,
+            &mut ServedBy::default(),
+            None,
+            &CancelToken::default(),
+        )
+            .await
+            .unwrap();
+        assert_eq!(map.get(a1.as_str()), Some(&28500));
+        assert_eq!(usage, Usage { input_tokens: 160, output_tokens: 30, ..Default::default() });
+        let ContentBlock::ToolResult { is_error: true, .. } = &provider.requests()[1].messages[2].content[0] else {
+            panic!("the repair carries the parse error as the tool's result");
+        };
     }
 
     #[tokio::test]
@@ -754,7 +1109,19 @@ mod tests {
         )]));
         let dyn_provider: Arc<dyn LlmProvider> = provider.clone();
         let mut usage = Usage::default();
-        price_items(&dyn_provider, &its, Some(120000), "", 512, &mut usage).await.unwrap();
+        price_items(
+            &dyn_provider,
+            &its,
+            Some(120000),
+            "",
+            512,
+            &mut usage,
+            &mut ServedBy::default(),
+            None,
+            &CancelToken::default(),
+        )
+        .await
+        .unwrap();
 
         let reqs = provider.requests();
         let ContentBlock::Text { text } = &reqs[0].messages[0].content[0] else {
@@ -784,6 +1151,7 @@ mod tests {
                 &session.id,
                 "Walked the site.",
                 &Usage::default(),
+                &ServedBy::default(),
                 &run_item_ids,
             )
             .unwrap();
@@ -812,7 +1180,7 @@ mod tests {
 
         let outcome = b.build(&sid, "estimate").await.unwrap();
         assert!(!outcome.queued);
-        assert_eq!(outcome.usage, Usage { input_tokens: 80, output_tokens: 15 });
+        assert_eq!(outcome.usage, Usage { input_tokens: 80, output_tokens: 15, ..Default::default() });
 
         let store = store.lock().unwrap();
         let art = store.get_artifact(&outcome.document_artifact_id).unwrap();
@@ -857,6 +1225,23 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn a_cancelled_build_mints_nothing() {
+        let (store, sid) = processed_session_with_items(&[("todo", "mulch")]);
+        let store = Arc::new(Mutex::new(store));
+        let provider = Arc::new(MockProvider::new(vec![]));
+        let cancel = CancelToken::new();
+        cancel.cancel();
+        let b = builder(store.clone(), provider.clone()).with_cancel(cancel);
+
+        let err = b.build(&sid, "estimate").await.unwrap_err();
+        assert!(matches!(err, CoreError::Agent(HarnessError::Cancelled { .. })), "not degraded to queued");
+        assert!(provider.requests().is_empty());
+        let store = store.lock().unwrap();
+        assert!(store.list_artifacts_for_session(&sid).unwrap().iter().all(|a| a.kind != "document"));
+        assert!(store.list_llm_usage_for_session(&sid).unwrap().iter().all(|r| r.purpose != "document"));
+    }
+
     #[tokio::test]
     async fn build_non_pricing_kind_makes_zero_calls() {
         let (store, sid) = processed_session_with_items(&[("todo", "mulch")]);
@@ -979,7 +1364,13 @@ mod tests {
         store.append_transcript(&session.id, "site walk").unwrap();
         store.end_and_record_session(&session.id).unwrap();
         store
-            .finish_session_processed(&session.id, "Walked the site.", &Usage::default(), &run_item_ids)
+            .finish_session_processed(
+                &session.id,
+                "Walked the site.",
+                &Usage::default(),
+                &ServedBy::default(),
+                &run_item_ids,
+            )
             .unwrap();
         (store, session.id)
     }
@@ -1204,6 +1595,7 @@ mod tests {
                 &session.id,
                 "Walked the front yard; HOA approval 41827 on file.",
                 &Usage::default(),
+                &ServedBy::default(),
                 &[a.id.clone(), b.id.clone()],
             )
             .unwrap();
@@ -1236,12 +1628,24 @@ mod tests {
             ]),
         )]));
         let mut usage = Usage::default();
-        let map = fill_fields(&provider, &fields, &[], "summary", 512, &mut usage).await.unwrap();
+        let map = fill_fields(
+            &provider,
+            &fields,
+            &[],
+            "summary",
+            512,
+            &mut usage,
+            &mut ServedBy::default(),
+            None,
+            &CancelToken::default(),
+        )
+        .await
+        .unwrap();
         assert_eq!(map.get("hoa_no").map(String::as_str), Some("41827"), "first-wins dedup");
         assert_eq!(map.get("reviewed_by").map(String::as_str), Some("Dana"));
         assert_eq!(map.get("gate_code"), None, "hallucinated key dropped");
         assert_eq!(map.len(), 2);
-        assert_eq!(usage, Usage { input_tokens: 80, output_tokens: 15 }, "R9: usage accumulated");
+        assert_eq!(usage, Usage { input_tokens: 80, output_tokens: 15, ..Default::default() }, "R9: usage accumulated");
     }
 
     /// R6 + the WE-B exact prompt: items (via `format_pricing_items`
@@ -1280,6 +1684,9 @@ mod tests {
             "Walked the front yard; HOA approval 41827 on file.",
             512,
             &mut usage,
+            &mut ServedBy::default(),
+            None,
+            &CancelToken::default(),
         )
         .await
         .unwrap();
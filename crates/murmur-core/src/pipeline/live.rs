@@ -23,13 +23,14 @@
 //! Failure posture: a failed pass never disrupts recording. Non-zero usage is
 //! logged (R9), the cursor is NOT advanced, and the next tick retries the same
 //! window. Items a failed pass already wrote stay on the board and are
-//! de-duplicated by the "already captured" list on the retry.
+//! de-duplicated by the "already captured" list on the retry. A cancelled
+//! pass (`with_cancel`) is logged the same way but reported as `Cancelled`.
 
 use std::sync::{Arc, Mutex};
 
 use harness::{
-    Agent, AgentConfig, ContextAssembler, ContextSection, LlmProvider, Memory, Message,
-    ToolRegistry, Usage,
+    Agent, AgentConfig, CancelToken, ContextAssembler, ContextSection, LlmProvider, Memory, Message,
+    SharedObserver, ToolRegistry, Usage,
 };
 
 use crate::domain::SessionStatus;
@@ -53,6 +54,9 @@ pub enum LiveExtractOutcome {
     /// The pass failed and was swallowed to protect recording. Non-zero usage is
     /// logged; the cursor is unchanged so the next tick retries.
     Failed { usage: Usage },
+    /// The pass was cancelled between turns or mid-call. Logged like a
+    /// failure, but not one: nobody should retry it.
+    Cancelled { usage: Usage },
 }
 
 /// Drives incremental extraction for ONE recording session. One instance per
@@ -80,8 +84,14 @@ pub struct LiveExtractor {
     pub transcript_window_tokens: usize,
     /// Budget for the already-captured dedup list (newest-first; oldest cut).
     pub already_captured_budget_tokens: usize,
+    /// Budget for the memory entries most relevant to the window (vocabulary
+    /// and corrections always ride along). Re-picked every pass and sent in
+    /// the user turn, so the system prompt stays one cached prefix.
+    pub memory_budget_tokens: usize,
     pub max_turns: usize,
     pub max_tokens: u32,
+    observer: Option<SharedObserver>,
+    cancel: CancelToken,
 }
 
 impl LiveExtractor {
@@ -100,11 +110,26 @@ impl LiveExtractor {
             min_new_chars: 120,
             transcript_window_tokens: 2_000,
             already_captured_budget_tokens: 400,
+            memory_budget_tokens: 250,
             max_turns: 8,
             max_tokens: 1_024,
+            observer: None,
+            cancel: CancelToken::default(),
         }
     }
 
+    /// Traces every pass's agent run into `observer` ("live_extraction").
+    pub fn with_observer(mut self, observer: SharedObserver) -> Self {
+        self.observer = Some(observer);
+        self
+    }
+
+    /// Stops the pass in flight, and every later one, when `cancel` fires.
+    pub fn with_cancel(mut self, cancel: CancelToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
     /// Chars of transcript covered by the last successful pass.
     pub fn cursor(&self) -> usize {
         self.cursor
@@ -122,7 +147,7 @@ impl LiveExtractor {
     pub async fn maybe_extract(&mut self) -> Result<LiveExtractOutcome, CoreError> {
         // Gate + snapshot under a scoped store guard (never held across an await,
         // never overlapping the memory guard).
-        let (window, already_captured, items_before, seen_chars) = {
+        let (window, already_captured, items_before, seen_chars, now) = {
             let store = self.locked()?;
             let session = store.get_session(&self.session_id)?;
             if session.status != SessionStatus::Recording {
@@ -146,6 +171,7 @@ impl LiveExtractor {
                 prompts::format_already_captured(&items),
                 items.len(),
                 self.cursor + window_chars_included,
+                store.now(),
             )
         };
 
@@ -154,9 +180,15 @@ impl LiveExtractor {
             .memory
             .lock()
             .map_err(|_| CoreError::InvalidState("memory lock poisoned".into()))?
-            .to_prompt();
+            .to_prompt_for(&window, now, self.memory_budget_tokens);
 
         let assembled = ContextAssembler::assemble(&[
+            ContextSection {
+                title: "what you know about this user".into(),
+                // Selection already fit the budget; the assembler only lays out.
+                budget_tokens: harness::approx_tokens(&memory_prompt),
+                content: memory_prompt,
+            },
             ContextSection {
                 title: "already captured".into(),
                 content: already_captured,
@@ -171,25 +203,29 @@ impl LiveExtractor {
 
         let mut registry = ToolRegistry::new();
         registry.register(AddItemTool::live(self.store.clone(), &self.session_id));
-        let agent = Agent::new(
+        let system_prompt = prompts::live_extraction_system_prompt();
+        let cache = prompts::agent_cache_hints(&system_prompt);
+        let mut agent = Agent::new(
             self.provider.clone(),
             registry,
-            AgentConfig {
-                system_prompt: prompts::live_extraction_system_prompt(&memory_prompt),
-                max_turns: self.max_turns,
-                max_tokens: self.max_tokens,
-            },
-        );
+            AgentConfig { system_prompt, max_turns: self.max_turns, max_tokens: self.max_tokens, thinking: None },
+        )
+        .with_cache_hints(cache)
+        .with_cancel(self.cancel.clone());
+        if let Some(observer) = &self.observer {
+            agent = agent.with_observer(observer.clone(), "live_extraction");
+        }
 
         match agent.run(vec![Message::user_text(assembled.text)]).await {
             Ok(outcome) => {
                 let items_after = {
                     let store = self.locked()?;
                     // Cost first (R9), then read the new count.
-                    store.record_llm_usage(
+                    store.record_served_usage(
                         Some(&self.session_id),
                         "live_extraction",
                         &outcome.usage,
+                        &outcome.model,
                     )?;
                     store.list_items_for_session(&self.session_id)?.len()
                 };
@@ -206,13 +242,17 @@ impl LiveExtractor {
                 // store failure here must not mask the swallow — best-effort.
                 if run_err.usage != Usage::default() {
                     if let Ok(store) = self.locked() {
-                        let _ = store.record_llm_usage(
+                        let _ = store.record_served_usage(
                             Some(&self.session_id),
                             "live_extraction",
                             &run_err.usage,
+                            &run_err.model,
                         );
                     }
                 }
+                if matches!(run_err.source, harness::HarnessError::Cancelled { .. }) {
+                    return Ok(LiveExtractOutcome::Cancelled { usage: run_err.usage });
+                }
                 Ok(LiveExtractOutcome::Failed { usage: run_err.usage })
             }
         }
@@ -265,7 +305,8 @@ mod tests {
         CompletionResponse {
             content: vec![ContentBlock::ToolUse { id: "tu_1".into(), name: name.into(), input }],
             stop_reason: StopReason::ToolUse,
-            usage: Usage { input_tokens: 30, output_tokens: 8 },
+            usage: Usage { input_tokens: 30, output_tokens: 8, ..Default::default() },
+            model: None,
         }
     }
 
@@ -273,7 +314,8 @@ mod tests {
         CompletionResponse {
             content: vec![ContentBlock::Text { text: text.into() }],
             stop_reason: StopReason::EndTurn,
-            usage: Usage { input_tokens: 10, output_tokens: 2 },
+            usage: Usage { input_tokens: 10, output_tokens: 2, ..Default::default() },
+            model: None,
         }
     }
 
@@ -316,7 +358,7 @@ mod tests {
             outcome,
             LiveExtractOutcome::Extracted {
                 items_added: 1,
-                usage: Usage { input_tokens: 40, output_tokens: 10 },
+                usage: Usage { input_tokens: 40, output_tokens: 10, ..Default::default() },
             }
         );
         // cursor advanced to the transcript length in chars
@@ -407,7 +449,7 @@ mod tests {
         let outcome = extractor.maybe_extract().await.unwrap();
         assert_eq!(
             outcome,
-            LiveExtractOutcome::Failed { usage: Usage { input_tokens: 30, output_tokens: 8 } }
+            LiveExtractOutcome::Failed { usage: Usage { input_tokens: 30, output_tokens: 8, ..Default::default() } }
         );
         assert_eq!(extractor.cursor(), 0, "cursor held so the window retries");
 
@@ -421,6 +463,38 @@ mod tests {
         assert_eq!(usage[0].input_tokens, 30);
     }
 
+    #[tokio::test]
+    async fn cancelled_pass_logs_its_spend_and_holds_cursor() {
+        /// Cancels the pass as its first response comes back.
+        struct CancelsOnReply(MockProvider, CancelToken);
+        #[async_trait::async_trait]
+        impl LlmProvider for CancelsOnReply {
+            async fn complete(&self, req: CompletionRequest) -> Result<CompletionResponse, HarnessError> {
+                self.1.cancel();
+                self.0.complete(req).await
+            }
+        }
+        let (extractor, store, _mem, sid) = extractor_with(vec![], "order lumber for the deck today");
+        let cancel = CancelToken::new();
+        let provider = CancelsOnReply(
+            MockProvider::new(vec![
+                tool_use("add_item", serde_json::json!({"kind": "todo", "text": "order lumber"})),
+                end_turn("unreached"),
+            ]),
+            cancel.clone(),
+        );
+        let mut extractor = LiveExtractor { provider: Arc::new(provider), ..extractor }.with_cancel(cancel);
+
+        let spent = Usage { input_tokens: 30, output_tokens: 8, ..Default::default() };
+        assert_eq!(extractor.maybe_extract().await.unwrap(), LiveExtractOutcome::Cancelled { usage: spent });
+        assert_eq!(extractor.cursor(), 0);
+        let store = store.lock().unwrap();
+        // The turn's tool call ran to completion before the run stopped.
+        assert_eq!(store.list_items_for_session(&sid).unwrap().len(), 1);
+        let rows = store.list_llm_usage_for_session(&sid).unwrap();
+        assert_eq!((rows.len(), rows[0].input_tokens), (1, 30));
+    }
+
     #[tokio::test]
     async fn gated_add_item_blocks_a_stale_write_when_processing_races_ahead() {
         // The session is Recording when maybe_extract snapshots it and starts
@@ -459,7 +533,7 @@ mod tests {
             outcome,
             LiveExtractOutcome::Extracted {
                 items_added: 0,
-                usage: Usage { input_tokens: 40, output_tokens: 10 },
+                usage: Usage { input_tokens: 40, output_tokens: 10, ..Default::default() },
             }
         );
 
@@ -536,7 +610,7 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn memory_reaches_the_live_system_prompt() {
+    async fn memory_reaches_the_live_user_turn() {
         let provider = Arc::new(MockProvider::new(vec![end_turn("nothing new")]));
         let store = Store::open_in_memory("device-a").unwrap();
         let session = store.start_session(None).unwrap();
@@ -553,11 +627,47 @@ mod tests {
         extractor.min_new_chars = 1;
         extractor.maybe_extract().await.unwrap();
         let reqs = provider.requests();
-        assert!(reqs[0].system.contains("french drain"));
-        // and the new transcript reached the user message
+        assert!(!reqs[0].system.contains("french drain"), "the system prompt stays memory-free");
         assert!(matches!(
             &reqs[0].messages[0].content[0],
-            ContentBlock::Text { text } if text.contains("french drain regrade")
+            ContentBlock::Text { text } if text.contains("## what you know about this user")
+                && text.contains("- french drain\n")
+                && text.contains("french drain regrade")
         ));
     }
+
+    #[tokio::test]
+    async fn ticks_resend_the_same_system_prompt_and_repick_memory_per_window() {
+        let provider = Arc::new(MockProvider::new(vec![end_turn("a"), end_turn("b"), end_turn("c")]));
+        let store = Store::open_in_memory("device-a").unwrap();
+        let session = store.start_session(None).unwrap();
+        store.append_transcript(&session.id, "the french drain along the fence").unwrap();
+        let sid = session.id;
+        let store = Arc::new(Mutex::new(store));
+        let mut memory = Memory::default();
+        memory.remember("pricing", "french drains run $45 per linear foot", 1);
+        memory.remember("pricing", "sod is $1.10 per square foot installed", 1);
+        let memory = Arc::new(Mutex::new(memory));
+        let mut extractor = LiveExtractor::new(provider.clone(), store.clone(), memory.clone(), &sid);
+        extractor.min_new_chars = 1;
+        // Room for one pricing entry: the window decides which.
+        extractor.memory_budget_tokens = 12;
+
+        extractor.maybe_extract().await.unwrap();
+        store.lock().unwrap().append_transcript(&sid, " then new sod out front, square foot pricing").unwrap();
+        extractor.maybe_extract().await.unwrap();
+        memory.lock().unwrap().remember("people", "Dev — framer", 2);
+        store.lock().unwrap().append_transcript(&sid, " and call Dev").unwrap();
+        extractor.maybe_extract().await.unwrap();
+
+        let reqs = provider.requests();
+        let user_turn = |i: usize| match &reqs[i].messages[0].content[0] {
+            ContentBlock::Text { text } => text.clone(),
+            other => panic!("{other:?}"),
+        };
+        assert!(user_turn(0).contains("french drains") && !user_turn(0).contains("sod is"));
+        assert!(user_turn(1).contains("sod is $1.10"), "a moving window re-picks memory");
+        assert!(user_turn(2).contains("Dev — framer"), "a memory change reaches the next tick");
+        assert!(reqs.iter().all(|r| r.system == reqs[0].system), "the cached system prompt never moves");
+    }
 }
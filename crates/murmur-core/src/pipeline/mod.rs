@@ -5,24 +5,30 @@
 
 pub mod tools;
 
+pub(crate) mod batch;
+
 pub mod live;
 
 pub mod document;
 
 pub mod notes;
 
+pub mod vision;
+
 pub(crate) mod prompts;
 
 use std::sync::{Arc, Mutex};
 
 use harness::{
-    Agent, AgentConfig, ContextAssembler, ContextSection, LlmProvider, Memory, MemoryStore,
-    Message, ToolRegistry, UpdateMemoryTool, Usage,
+    bypass_cache, with_priority, Agent, AgentConfig, BatchProvider, Budget, CancelToken, Compaction, ContextAssembler,
+    ContextSection, DEFAULT_MEMORY_BUDGET_TOKENS, LlmProvider, Memory, MemoryStore, Message, Priority, ServedBy,
+    SharedObserver, StreamSink, ToolRegistry, UpdateMemoryTool, Usage,
 };
+use serde::{Deserialize, Serialize};
 
-use crate::domain::{Session, SessionStatus};
+use crate::domain::{MemoryScope, Session, SessionFailure, SessionStatus, SessionSummary};
 use crate::error::CoreError;
-use crate::store::Store;
+use crate::store::{SqliteMemoryStore, Store};
 use tools::{AddItemTool, UpsertContactTool, WriteReportTool};
 
 /// Plan 13 D8: the legal `doc_kind` vocabulary for a session's template, in
@@ -80,6 +86,93 @@ pub fn doc_kind_for_template(template: Option<&str>) -> &'static str {
 pub struct ProcessOutcome {
     pub session: Session,
     pub usage: Usage,
+    /// Set when the session's notes call went out in this message batch
+    /// (`SessionProcessor::with_batches`): the session keeps its status, and
+    /// `usage` covers only the extraction, until `resume_batches` finishes it.
+    pub batch_id: Option<String>,
+}
+
+/// `BatchJob::kind` (and phase) of a session's notes call in a batch.
+const NOTES_JOB: &str = "notes";
+
+/// What a notes job carries to `resume_batches`: the extraction's spend and
+/// the items it created, for the finish swap.
+#[derive(Serialize, Deserialize)]
+struct NotesJobState {
+    usage: Usage,
+    model: ServedBy,
+    created_ids: Vec<String>,
+}
+
+enum Staged {
+    Job { job: batch::StagedJob, extraction: NotesJobState },
+    Done(Result<ProcessOutcome, CoreError>),
+}
+
+/// A memory kept for the session's job or its client, loaded next to the
+/// user's own: rendered into the extraction prompt, and a scope the
+/// `update_memory` tool can write.
+struct ScopedMemory {
+    scope: MemoryScope,
+    /// The tool's name for the scope: "job" or "client".
+    name: &'static str,
+    /// Which job or client, for the prompt and the tool schema.
+    about: String,
+    memory: Arc<Mutex<Memory>>,
+}
+
+/// What `begin` hands the LLM phases.
+struct Begun {
+    transcript: String,
+    memory_prompt: String,
+    scoped: Vec<ScopedMemory>,
+    /// Set when reprocessing a provisional walk: the artifacts of its
+    /// on-device result. They stay until this run succeeds and replaces
+    /// them, so a failed attempt leaves the walk as it was.
+    superseded_artifacts: Option<Vec<String>>,
+}
+
+impl ScopedMemory {
+    /// The memories of `job_id` and its client. None for an unlinked
+    /// session, or one whose job has since been deleted.
+    fn load(store: &Store, job_id: Option<&str>) -> Result<Vec<ScopedMemory>, CoreError> {
+        let Some(job_id) = job_id else { return Ok(Vec::new()) };
+        let job = match store.get_job(job_id) {
+            Ok(job) => job,
+            Err(CoreError::NotFound { .. }) => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        MemoryScope::for_job(&job)
+            .into_iter()
+            .map(|scope| {
+                let (name, about) = match &scope {
+                    MemoryScope::Client(_) => {
+                        ("client", format!("client {}", job.client.as_deref().unwrap_or_default()))
+                    }
+                    _ => ("job", format!("job \"{}\"", job.name)),
+                };
+                let memory = store.load_scoped_memory(&scope)?;
+                Ok(ScopedMemory { scope, name, about, memory: Arc::new(Mutex::new(memory)) })
+            })
+            .collect()
+    }
+
+    /// Like the user's memory prompt, each section titled with whose it is.
+    fn prompt(&self, query: &str, now: u64, budget_tokens: usize) -> Result<String, CoreError> {
+        let selected = self
+            .memory
+            .lock()
+            .map_err(|_| CoreError::InvalidState("memory lock poisoned".into()))?
+            .relevant_to(query, now, budget_tokens);
+        let titled = Memory {
+            sections: selected
+                .sections
+                .into_iter()
+                .map(|(section, entries)| (format!("{section} — {}", self.about), entries))
+                .collect(),
+        };
+        Ok(titled.to_prompt_for(query, now, usize::MAX))
+    }
 }
 
 pub struct SessionProcessor {
@@ -90,10 +183,37 @@ pub struct SessionProcessor {
     /// Extraction-pass agent budget.
     pub max_turns: usize,
     pub max_tokens: u32,
+    /// Cumulative token/cost ceiling for the extraction run (default: none).
+    /// Crossing it fails the session like any other aborted run — partial
+    /// usage logged, status Failed.
+    pub budget: Budget,
+    /// Extended-thinking budget for the extraction pass (default: off), so
+    /// the model can reason through a messy transcript before extracting.
+    /// Part of `max_tokens`; ignored by providers without thinking.
+    pub thinking: Option<u32>,
+    /// Keeps the extraction's resent history under a size (default: off):
+    /// old tool exchanges collapse into a ledger after the transcript.
+    pub compaction: Option<Compaction>,
     /// Transcript token budget for both passes (chars/4 approximation).
     pub transcript_budget_tokens: usize,
+    /// Memory budget for both passes: the entries most relevant to the
+    /// transcript, plus vocabulary and corrections whatever their size.
+    /// Shared evenly by the user's memory and the job's and client's when
+    /// they hold anything, so a linked session costs no more.
+    pub memory_budget_tokens: usize,
     /// Summary-call output budget.
     pub summary_max_tokens: u32,
+    /// When set, both passes stream and forward their deltas here (the FFI
+    /// previews the notes while `process()` is still running).
+    stream_sink: Option<StreamSink>,
+    /// On-device model used when `provider` is unreachable. Its result is
+    /// provisional until a cloud reprocess replaces it.
+    local_fallback: Option<Arc<dyn LlmProvider>>,
+    /// Sees the extraction run ("processing") and the notes call ("summary").
+    observer: Option<SharedObserver>,
+    cancel: CancelToken,
+    /// When set, the drains send their notes calls as one message batch.
+    batches: Option<Arc<dyn BatchProvider>>,
 }
 
 impl SessionProcessor {
@@ -110,42 +230,160 @@ impl SessionProcessor {
             memory_store,
             max_turns: 16,
             max_tokens: 4096,
+            budget: Budget::default(),
+            thinking: None,
+            compaction: None,
             transcript_budget_tokens: 12_000,
+            memory_budget_tokens: DEFAULT_MEMORY_BUDGET_TOKENS,
             // C2: 512 -> 1024 so the narrative summary + up to 12 notes
             // entries fit in one write_notes response without truncation.
             // No new call (D1-14) — this is an output-token budget bump on
             // the one pass that already ran.
             summary_max_tokens: 1024,
+            stream_sink: None,
+            local_fallback: None,
+            observer: None,
+            cancel: CancelToken::default(),
+            batches: None,
         }
     }
 
+    /// Streams the extraction and notes calls into `sink`. Progress display
+    /// only — the outcome, the swap, and the usage accounting are unchanged.
+    pub fn with_stream_sink(mut self, sink: StreamSink) -> Self {
+        self.stream_sink = Some(sink);
+        self
+    }
+
+    /// Falls back to `local` when the cloud provider can't be reached at all
+    /// (no signal on site). Any other cloud failure — a rejected key, a
+    /// malformed reply — still fails the session: the on-device model is a
+    /// stand-in for connectivity, not a second opinion.
+    pub fn with_local_fallback(mut self, local: Arc<dyn LlmProvider>) -> Self {
+        self.local_fallback = Some(local);
+        self
+    }
+
+    /// Traces both passes of every `process()` call into `observer`.
+    pub fn with_observer(mut self, observer: SharedObserver) -> Self {
+        self.observer = Some(observer);
+        self
+    }
+
+    /// Stops `process()` when `cancel` fires — between agent turns or mid
+    /// call. A cancelled run is not a failure: the session keeps its status
+    /// (whoever cancelled decides its fate) and the spend so far is logged.
+    pub fn with_cancel(mut self, cancel: CancelToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// Batch mode for the drains (`process_pending`, `retry_failed_sessions`):
+    /// each session's extraction still runs at once, but the notes calls go
+    /// out together as one message batch at half price, and the sessions
+    /// finish on a later `resume_batches`. `process()` stays synchronous — a
+    /// session the user is waiting on — and supersedes a pending batch job.
+    /// Batched calls aren't streamed, traced, or repaired, and there is no
+    /// on-device fallback for them.
+    pub fn with_batches(mut self, batches: Arc<dyn BatchProvider>) -> Self {
+        self.batches = Some(batches);
+        self
+    }
+
     fn locked(&self) -> Result<std::sync::MutexGuard<'_, Store>, CoreError> {
         self.store
             .lock()
             .map_err(|_| CoreError::InvalidState("store lock poisoned".into()))
     }
 
-    /// Processes one ended session. Valid from AwaitingProcessing or Failed
-    /// (retry). On success: outputs written, summary set, status Processed.
-    /// On LLM failure: status Failed, cost still logged (R9), error returned.
+    /// Processes one ended session. Valid from AwaitingProcessing, Failed
+    /// (retry), or a provisional Processed (the cloud reprocess). On success:
+    /// outputs written, summary set, status Processed. On LLM failure: status
+    /// Failed, cost still logged (R9), error returned — except that a
+    /// provisional walk keeps its on-device result and stays provisional, so
+    /// a later reprocess can try again.
+    ///
+    /// With a local fallback, an unreachable cloud provider isn't the end:
+    /// the failed attempt is recorded as usual, then the session is processed
+    /// again on-device and lands Processed + provisional.
+    ///
+    /// Cancelled (`with_cancel`): `HarnessError::Cancelled` carrying the
+    /// partial usage, which is logged without touching the session's status.
+    /// Once cancelled, every later call returns at once with zero usage.
     ///
     /// The app shell must not delete or mutate a session while it is being
     /// processed — status is re-validated only at the exit write, so a
     /// concurrent tombstone would produce a silent no-op or a store error.
     pub async fn process(&self, session_id: &str) -> Result<ProcessOutcome, CoreError> {
-        // Phase 0: validate, sweep prior FAILED-run authoritative leftovers
-        // (never the live board), and snapshot the transcript. Plan 13 Stage
-        // 2 dropped phase B (the forced build_document call), so the
-        // template/existing-doc-number snapshot that fed it is gone too —
-        // documents are now built on demand (`DocumentBuilder::build`,
+        if self.cancel.is_cancelled() {
+            return Err(harness::HarnessError::Cancelled { usage: Usage::default() }.into());
+        }
+        let result = self.process_on(&self.provider, session_id, false).await;
+        match (&self.local_fallback, result) {
+            (Some(local), Err(CoreError::Agent(e)))
+                if e.provider_kind() == Some(harness::ProviderErrorKind::Unreachable) =>
+            {
+                self.process_on(local, session_id, true).await
+            }
+            (_, result) => result,
+        }
+    }
+
+    async fn process_on(
+        &self,
+        provider: &Arc<dyn LlmProvider>,
+        session_id: &str,
+        provisional: bool,
+    ) -> Result<ProcessOutcome, CoreError> {
+        let Some(Begun { transcript, memory_prompt, scoped, superseded_artifacts }) = self.begin(session_id)? else {
+            return self.finish_empty(session_id);
+        };
+
+        // Phase 1+2: extraction agent pass, forced summary (D5a: the summary
+        // call may also return an optional spoken grand-total scalar). The id
+        // sink records which items THIS run created, for the finish swap.
+        let mut usage = Usage::default();
+        let mut model = ServedBy::default();
+        let created_ids = Arc::new(Mutex::new(Vec::<String>::new()));
+        let result = self
+            .run_llm_phases(
+                provider,
+                session_id,
+                &transcript,
+                &memory_prompt,
+                &scoped,
+                &mut usage,
+                &mut model,
+                created_ids.clone(),
+            )
+            .await;
+        let ids = created_ids
+            .lock()
+            .map_err(|_| CoreError::InvalidState("created-ids lock poisoned".into()))?
+            .clone();
+        self.finish(session_id, result, usage, model, &ids, provisional, superseded_artifacts.as_deref(), None)
+    }
+
+    /// Phase 0: validate, sweep prior FAILED-run authoritative leftovers
+    /// (never the live board), and snapshot the transcript. Returns the
+    /// budgeted transcript, the memory prompt, and the linked job's scoped
+    /// memories — `None` for an empty transcript, which `finish_empty`
+    /// closes out without a call. A provisional walk keeps its outputs until
+    /// the finish: the swap replaces its items, and `finish` its artifacts.
+    fn begin(&self, session_id: &str) -> Result<Option<Begun>, CoreError> {
+        // Plan 13 Stage 2 dropped phase B (the forced build_document call),
+        // so the template/existing-doc-number snapshot that fed it is gone
+        // too — documents are now built on demand (`DocumentBuilder::build`,
         // engine-keyed, not part of `process()`).
-        let transcript = {
+        let (transcript, now, scoped, superseded_artifacts) = {
             let store = self.locked()?;
             let session = store.get_session(session_id)?;
-            if !matches!(
-                session.status,
-                SessionStatus::AwaitingProcessing | SessionStatus::Failed
-            ) {
+            let reprocessable = match session.status {
+                SessionStatus::AwaitingProcessing | SessionStatus::Failed => true,
+                SessionStatus::Processed => session.provisional,
+                SessionStatus::Recording => false,
+            };
+            if !reprocessable {
                 return Err(CoreError::InvalidState(format!(
                     "cannot process a {} session",
                     session.status.as_str()
@@ -155,61 +393,112 @@ impl SessionProcessor {
             // including any `session_meta` spoken-total artifact) so repeated
             // retries can't accumulate duplicate todos or a stale hint. Never
             // touches the live board (the safety net) or manual items.
-            store.clear_authoritative_outputs(session_id)?;
-            session.transcript
+            let superseded_artifacts = if session.provisional {
+                Some(store.list_artifacts_for_session(session_id)?.into_iter().map(|a| a.id).collect())
+            } else {
+                store.clear_authoritative_outputs(session_id)?;
+                None
+            };
+            // A notes call still out in a batch is superseded by this run;
+            // dropping its job keeps `resume_batches` from finishing the
+            // session a second time with stale notes. The job's extraction
+            // already ran, so its spend is logged as it goes (R9).
+            let tx = store.conn.unchecked_transaction()?;
+            for job in store.drop_batch_jobs_for_session(session_id, NOTES_JOB)? {
+                if let Ok(NotesJobState { usage, model, .. }) = serde_json::from_str(&job.state) {
+                    store.record_served_usage(Some(session_id), "processing", &usage, &model)?;
+                }
+            }
+            tx.commit()?;
+            let scoped = ScopedMemory::load(&store, session.job_id.as_deref())?;
+            (session.transcript, store.now(), scoped, superseded_artifacts)
         };
 
         // Empty guard: an empty/whitespace-only transcript would send empty
-        // content blocks to the real API (rejected). Skip the LLM phase and
-        // process with a placeholder summary; zero usage is correct — no call
-        // was made, and the tx helper's contract is status+usage together.
+        // content blocks to the real API (rejected).
         if transcript.trim().is_empty() {
-            let usage = Usage::default();
-            let session = self.locked()?.finish_session_processed(
-                session_id,
-                "(empty session)",
-                &usage,
-                &[],
-            )?;
-            return Ok(ProcessOutcome { session, usage });
+            return Ok(None);
         }
 
-        // Memory lock in its own scope — never held alongside the store guard
-        // (no store→memory lock ordering for a second caller to deadlock on).
-        let memory_prompt = self
-            .memory
-            .lock()
-            .map_err(|_| CoreError::InvalidState("memory lock poisoned".into()))?
-            .to_prompt();
-
         let assembled = ContextAssembler::assemble(&[ContextSection {
             title: "transcript".into(),
             content: transcript,
             budget_tokens: self.transcript_budget_tokens,
         }]);
 
-        // Phase 1+2: extraction agent pass, forced summary (D5a: the summary
-        // call may also return an optional spoken grand-total scalar). The id
-        // sink records which items THIS run created, for the finish swap.
-        let mut usage = Usage::default();
-        let created_ids = Arc::new(Mutex::new(Vec::<String>::new()));
-        let result = self
-            .run_llm_phases(session_id, &assembled.text, &memory_prompt, &mut usage, created_ids.clone())
-            .await;
+        // Memory lock in its own scope — never held alongside the store guard
+        // (no store→memory lock ordering for a second caller to deadlock on).
+        let mut sharing = 1;
+        for s in &scoped {
+            let memory = s.memory.lock().map_err(|_| CoreError::InvalidState("memory lock poisoned".into()))?;
+            if !memory.sections.is_empty() {
+                sharing += 1;
+            }
+        }
+        let share = self.memory_budget_tokens / sharing;
+        let mut prompts = vec![self
+            .memory
+            .lock()
+            .map_err(|_| CoreError::InvalidState("memory lock poisoned".into()))?
+            .to_prompt_for(&assembled.text, now, share)];
+        for s in &scoped {
+            prompts.push(s.prompt(&assembled.text, now, share)?);
+        }
+        prompts.retain(|p| !p.is_empty());
+        Ok(Some(Begun {
+            transcript: assembled.text,
+            memory_prompt: prompts.join("\n\n"),
+            scoped,
+            superseded_artifacts,
+        }))
+    }
 
-        // Exit: persist outcome + cost atomically, success or not.
+    /// Skips the LLM phase and processes with a placeholder summary; zero
+    /// usage is correct — no call was made, and the tx helper's contract is
+    /// status+usage together.
+    fn finish_empty(&self, session_id: &str) -> Result<ProcessOutcome, CoreError> {
+        let usage = Usage::default();
+        let session = self.locked()?.finish_session_processed(
+            session_id,
+            "(empty session)",
+            &usage,
+            &ServedBy::default(),
+            &[],
+        )?;
+        Ok(ProcessOutcome { session, usage, batch_id: None })
+    }
+
+    /// Exit: persist outcome + cost atomically, success or not. `ids` are the
+    /// items this run created — the finish swap keeps exactly those.
+    /// `superseded_artifacts` (a provisional walk's reprocess, see `Begun`)
+    /// are replaced on success; on failure the walk keeps its on-device
+    /// result and stays provisional for a later retry. `claim` is the batch
+    /// job the result came back in, claimed in the same transaction.
+    #[allow(clippy::too_many_arguments)]
+    fn finish(
+        &self,
+        session_id: &str,
+        result: Result<prompts::SessionNotes, harness::HarnessError>,
+        usage: Usage,
+        model: ServedBy,
+        ids: &[String],
+        provisional: bool,
+        superseded_artifacts: Option<&[String]>,
+        claim: Option<&str>,
+    ) -> Result<ProcessOutcome, CoreError> {
         let store = self.locked()?;
-        match result {
-            Ok((summary, spoken_total_cents, buckets)) => {
-                let ids = created_ids
-                    .lock()
-                    .map_err(|_| CoreError::InvalidState("created-ids lock poisoned".into()))?
-                    .clone();
+        let claimed = claim.map(|job_id| batch::claim(&store, job_id)).transpose()?;
+        let purpose = if provisional { "processing_local" } else { "processing" };
+        let outcome = match result {
+            Ok(notes) => {
+                for id in superseded_artifacts.into_iter().flatten() {
+                    store.delete_artifact(id)?;
+                }
                 // D5a: persist the spoken grand-total scalar (if any) as a tiny
                 // per-session artifact BEFORE the finish swap — no migration,
                 // `kind` is free-form (artifacts.rs:24). Absent unless the
                 // model clearly heard a stated total (R6).
-                if let Some(cents) = spoken_total_cents {
+                if let Some(cents) = notes.spoken_total_cents {
                     store.add_artifact(
                         session_id,
                         "session_meta",
@@ -220,34 +509,118 @@ impl SessionProcessor {
                 // Plan 14 D5-14: persist buckets as a notes artifact BEFORE the
                 // finish swap, only when non-empty (mirrors session_meta above).
                 // clear_authoritative_outputs already sweeps it on reprocess.
-                if !buckets.is_empty() {
+                if !notes.buckets.is_empty() {
                     store.add_artifact(
                         session_id,
                         "notes",
                         "notes",
-                        &notes::serialize_buckets(&buckets),
+                        &notes::serialize_buckets(&notes.buckets),
                     )?;
                 }
-                let session = store.finish_session_processed(session_id, &summary, &usage, &ids)?;
-                Ok(ProcessOutcome { session, usage })
+                let session = if provisional {
+                    store.finish_session_provisional(session_id, &notes.summary, &usage, &model, ids)?
+                } else {
+                    store.finish_session_processed(session_id, &notes.summary, &usage, &model, ids)?
+                };
+                Ok(ProcessOutcome { session, usage, batch_id: None })
+            }
+            Err(e) if superseded_artifacts.is_some() => {
+                // A failed reprocess leaves the provisional walk as it was
+                // (still Processed, retried later); only this attempt's items
+                // and cost are settled. Bookkeeping errors are secondary, as below.
+                let kept = superseded_artifacts.unwrap_or_default();
+                let _ = store.abandon_provisional_reprocess(session_id, purpose, &usage, &model, ids, kept);
+                Err(match e {
+                    harness::HarnessError::Cancelled { .. } => harness::HarnessError::Cancelled { usage }.into(),
+                    e => e.into(),
+                })
+            }
+            Err(harness::HarnessError::Cancelled { .. }) => {
+                // The error carries only the interrupted call's share; the
+                // caller gets (and R9 logs) the whole run's. Nothing spent,
+                // nothing logged. Best effort: a failed usage row must not
+                // replace the cancellation.
+                if usage != Usage::default() {
+                    let _ = store.record_served_usage(Some(session_id), purpose, &usage, &model);
+                }
+                Err(harness::HarnessError::Cancelled { usage }.into())
             }
             Err(e) => {
                 // Bookkeeping errors are secondary: the original LLM error is
                 // what the caller must see — never mask it with a DB failure.
-                let _ = store.finish_session_failed(session_id, &usage);
+                let failure = SessionFailure::from_error(&e);
+                let _ = store.finish_session_failed(session_id, &usage, &model, failure);
                 Err(e.into())
             }
+        };
+        if let Some(tx) = claimed {
+            tx.commit()?;
         }
+        outcome
     }
 
+    #[allow(clippy::too_many_arguments)]
     async fn run_llm_phases(
         &self,
+        provider: &Arc<dyn LlmProvider>,
+        session_id: &str,
+        assembled_transcript: &str,
+        memory_prompt: &str,
+        scoped: &[ScopedMemory],
+        usage: &mut Usage,
+        model: &mut ServedBy,
+        created_ids: Arc<Mutex<Vec<String>>>,
+    ) -> Result<prompts::SessionNotes, harness::HarnessError> {
+        self.run_extraction(
+            provider,
+            session_id,
+            assembled_transcript,
+            memory_prompt,
+            scoped,
+            usage,
+            model,
+            created_ids,
+        )
+        .await?;
+
+        let notes = match prompts::summarize(
+            provider.clone(),
+            assembled_transcript,
+            self.summary_max_tokens,
+            self.stream_sink.as_ref(),
+            self.observer.as_deref(),
+            &self.cancel,
+        )
+        .await
+        {
+            Ok(notes) => notes,
+            Err(run_err) => {
+                // R9: a model that never produced usable notes still cost
+                // us the calls.
+                usage.add(&run_err.usage);
+                model.merge(&run_err.model);
+                return Err(run_err.source);
+            }
+        };
+        usage.add(&notes.usage);
+        model.merge(&notes.model);
+        Ok(notes.value)
+    }
+
+    /// Phase 1: the extraction agent pass, writing items, contacts, the
+    /// report, and memory through its tools.
+    #[allow(clippy::too_many_arguments)]
+    async fn run_extraction(
+        &self,
+        provider: &Arc<dyn LlmProvider>,
         session_id: &str,
         assembled_transcript: &str,
         memory_prompt: &str,
+        scoped: &[ScopedMemory],
         usage: &mut Usage,
+        model: &mut ServedBy,
         created_ids: Arc<Mutex<Vec<String>>>,
-    ) -> Result<(String, Option<i64>, Vec<notes::NotesEntry>), harness::HarnessError> {
+    ) -> Result<(), harness::HarnessError> {
         let mut registry = ToolRegistry::new();
         registry.register(AddItemTool::authoritative(
             self.store.clone(),
@@ -256,50 +629,203 @@ impl SessionProcessor {
         ));
         registry.register(UpsertContactTool::new(self.store.clone()));
         registry.register(WriteReportTool::new(self.store.clone(), session_id));
-        registry.register(
-            UpdateMemoryTool::new(self.memory.clone(), self.memory_store.clone())
-                .for_session(session_id),
+        // Scoped memories live in the store whatever `memory_store` is.
+        let memory_tool = scoped.iter().fold(
+            UpdateMemoryTool::new(self.memory.clone(), self.memory_store.clone()).for_session(session_id),
+            |tool, s| {
+                let store = SqliteMemoryStore::new(self.store.clone()).with_scope(s.scope.clone());
+                tool.with_scope(s.name, &s.about, s.memory.clone(), Arc::new(store))
+            },
         );
+        registry.register(memory_tool);
 
-        let agent = Agent::new(
-            self.provider.clone(),
+        let system_prompt = prompts::extraction_system_prompt(memory_prompt);
+        let cache = prompts::agent_cache_hints(&system_prompt);
+        let mut agent = Agent::new(
+            provider.clone(),
             registry,
             AgentConfig {
-                system_prompt: prompts::extraction_system_prompt(memory_prompt),
+                system_prompt,
                 max_turns: self.max_turns,
                 max_tokens: self.max_tokens,
+                thinking: self.thinking,
             },
-        );
-        let outcome = match agent
+        )
+        .with_cache_hints(cache)
+        .with_budget(self.budget)
+        .with_cancel(self.cancel.clone());
+        if let Some(sink) = &self.stream_sink {
+            agent = agent.with_stream_sink(sink.clone());
+        }
+        if let Some(observer) = &self.observer {
+            agent = agent.with_observer(observer.clone(), "processing");
+        }
+        if let Some(compaction) = self.compaction {
+            agent = agent.with_compaction(compaction);
+        }
+        match agent
             .run(vec![Message::user_text(format!(
                 "Process this session.\n\n{assembled_transcript}"
             ))])
             .await
         {
-            Ok(o) => o,
+            Ok(outcome) => {
+                usage.add(&outcome.usage);
+                model.merge(&outcome.model);
+                Ok(())
+            }
             Err(run_err) => {
                 // Accumulate partial usage before propagating (R9: cost is measured
                 // from day one, even when the agent aborts mid-run).
                 usage.add(&run_err.usage);
-                return Err(run_err.source);
+                model.merge(&run_err.model);
+                Err(run_err.source)
             }
+        }
+    }
+
+    /// Batch mode's `process_on`: runs the extraction now and stages the
+    /// notes call as a job. A session that finishes without one — empty, or
+    /// its extraction failed or was cancelled — comes back finished.
+    async fn stage(&self, session_id: &str) -> Staged {
+        // The batch drains take AwaitingProcessing and Failed sessions, never
+        // a provisional walk, so there are no superseded artifacts here.
+        let Begun { transcript, memory_prompt, scoped, .. } = match self.begin(session_id) {
+            Ok(Some(begun)) => begun,
+            Ok(None) => return Staged::Done(self.finish_empty(session_id)),
+            Err(e) => return Staged::Done(Err(e)),
+        };
+        let mut usage = Usage::default();
+        let mut model = ServedBy::default();
+        let created_ids = Arc::new(Mutex::new(Vec::<String>::new()));
+        let extracted = self
+            .run_extraction(
+                &self.provider,
+                session_id,
+                &transcript,
+                &memory_prompt,
+                &scoped,
+                &mut usage,
+                &mut model,
+                created_ids.clone(),
+            )
+            .await;
+        let created_ids = match created_ids.lock() {
+            Ok(ids) => ids.clone(),
+            Err(_) => return Staged::Done(Err(CoreError::InvalidState("created-ids lock poisoned".into()))),
+        };
+        if let Err(e) = extracted {
+            return Staged::Done(self.finish(session_id, Err(e), usage, model, &created_ids, false, None, None));
+        }
+        let extraction = NotesJobState { usage, model, created_ids };
+        let state = match serde_json::to_string(&extraction) {
+            Ok(state) => state,
+            Err(e) => return Staged::Done(Err(e.into())),
+        };
+        let job = batch::StagedJob {
+            id: crate::ids::new_id(),
+            session_id: session_id.to_string(),
+            state,
+            requests: vec![(NOTES_JOB, prompts::notes_request(&transcript, self.summary_max_tokens))],
         };
-        usage.add(&outcome.usage);
+        Staged::Job { job, extraction }
+    }
 
-        let (summary, spoken_total_cents, buckets, summary_usage) = prompts::summarize(
-            self.provider.clone(),
-            assembled_transcript,
-            self.summary_max_tokens,
-        )
-        .await?;
-        // Count the summary/notes call's tokens BEFORE judging its content
-        // (R9: a model that skipped the tool still cost us the call).
-        usage.add(&summary_usage);
-        let summary = summary.ok_or_else(|| {
-            harness::HarnessError::Provider("summary response missing write_notes call".into())
-        })?;
+    /// Batch mode's drain: stages every session, then submits all the notes
+    /// calls as one batch. A staged session keeps its status until
+    /// `resume_batches` finishes it (`ProcessOutcome::batch_id` is set); if
+    /// the submission itself fails, each one is marked Failed with its
+    /// extraction spend, like any other failed run.
+    async fn process_batched(
+        &self,
+        batches: &dyn BatchProvider,
+        session_ids: Vec<String>,
+    ) -> Vec<(String, Result<ProcessOutcome, CoreError>)> {
+        let mut results = Vec::with_capacity(session_ids.len());
+        let mut staged = Vec::new();
+        for session_id in session_ids {
+            if self.cancel.is_cancelled() {
+                let cancelled = harness::HarnessError::Cancelled { usage: Usage::default() };
+                results.push((session_id, Err(cancelled.into())));
+                continue;
+            }
+            match self.stage(&session_id).await {
+                Staged::Job { job, extraction } => staged.push((job, extraction)),
+                Staged::Done(outcome) => results.push((session_id, outcome)),
+            }
+        }
+        if staged.is_empty() {
+            return results;
+        }
 
-        Ok((summary, spoken_total_cents, buckets))
+        let jobs: Vec<batch::StagedJob> = staged.iter().map(|(job, _)| job.clone()).collect();
+        let submitted = batch::submit(batches, &self.store, NOTES_JOB, &jobs).await;
+        for (job, extraction) in staged {
+            let outcome = match &submitted {
+                Ok(batch_id) => self.locked().and_then(|store| store.get_session(&job.session_id)).map(|session| {
+                    ProcessOutcome { session, usage: extraction.usage, batch_id: Some(batch_id.clone()) }
+                }),
+                Err(e) => {
+                    let e = harness::HarnessError::Provider(format!("batch submission failed: {e}"));
+                    self.finish(
+                        &job.session_id,
+                        Err(e),
+                        extraction.usage,
+                        extraction.model,
+                        &extraction.created_ids,
+                        false,
+                        None,
+                        None,
+                    )
+                }
+            };
+            results.push((job.session_id, outcome));
+        }
+        results
+    }
+
+    /// Finishes every session whose notes batch has ended — Processed, or
+    /// Failed (for `retry_failed_sessions`) when its notes didn't come back
+    /// usable — and leaves the rest for a later call. Meant for app launch
+    /// and a periodic poll while any batch is out.
+    pub async fn resume_batches(
+        &self,
+    ) -> Result<Vec<(String, Result<ProcessOutcome, CoreError>)>, CoreError> {
+        let Some(batches) = &self.batches else {
+            return Err(CoreError::InvalidState("resume_batches needs a batch provider".into()));
+        };
+        let mut results = Vec::new();
+        for mut ended in batch::ended_jobs(batches.as_ref(), &self.store, NOTES_JOB).await? {
+            let session_id = ended.job.session_id.clone();
+            let outcome = match serde_json::from_str::<NotesJobState>(&ended.job.state) {
+                Ok(NotesJobState { mut usage, mut model, created_ids }) => {
+                    let notes = ended.take(NOTES_JOB).and_then(|response| {
+                        usage.add(&response.usage);
+                        model.add(response.model.as_deref(), &response.usage);
+                        prompts::notes_from_response(&response)
+                    });
+                    self.finish(&session_id, notes, usage, model, &created_ids, false, None, Some(&ended.job.id))
+                }
+                // Unreadable state can't be applied; claim the job so it
+                // isn't retried forever.
+                Err(e) => self.locked().and_then(|store| store.take_batch_job(&ended.job.id)).and(Err(e.into())),
+            };
+            results.push((session_id, outcome));
+        }
+        Ok(results)
+    }
+
+    /// Drops sessions whose notes call is still out in a batch — the drains
+    /// leave those to `resume_batches`.
+    fn without_batched(&self, sessions: Vec<SessionSummary>) -> Result<Vec<SessionSummary>, CoreError> {
+        let store = self.locked()?;
+        let mut kept = Vec::with_capacity(sessions.len());
+        for summary in sessions {
+            if !store.has_batch_job(&summary.id, NOTES_JOB)? {
+                kept.push(summary);
+            }
+        }
+        Ok(kept)
     }
 
     /// Drains the awaiting_processing queue (spec §6: offline sessions queue
@@ -309,16 +835,23 @@ impl SessionProcessor {
     /// retry affordance, R7).
     ///
     /// Drain order: newest-first — the most recent session is what the user
-    /// is waiting on; a reconnect backlog processes LIFO.
+    /// is waiting on; a reconnect backlog processes LIFO. Like the other
+    /// drains, its calls take a shared rate limiter's background lane
+    /// (`harness::Priority`).
     pub async fn process_pending(
         &self,
     ) -> Result<Vec<(String, Result<ProcessOutcome, CoreError>)>, CoreError> {
         let queued = self
             .locked()?
             .list_session_summaries_by_status(SessionStatus::AwaitingProcessing)?;
+        let queued = self.without_batched(queued)?;
+        if let Some(batches) = &self.batches {
+            let ids = queued.into_iter().map(|summary| summary.id).collect();
+            return Ok(with_priority(Priority::Background, self.process_batched(batches.as_ref(), ids)).await);
+        }
         let mut results = Vec::with_capacity(queued.len());
         for summary in queued {
-            let outcome = self.process(&summary.id).await;
+            let outcome = with_priority(Priority::Background, self.process(&summary.id)).await;
             results.push((summary.id, outcome));
         }
         Ok(results)
@@ -342,6 +875,9 @@ impl SessionProcessor {
     /// calls blocking the UI), and R9 means a huge backlog isn't retried for
     /// free — the longest-waiting walk gets first crack, the rest wait for a
     /// future app-open.
+    ///
+    /// Retries skip any response cache (`harness::bypass_cache`): a walk
+    /// that failed on an answer it got must not be handed the same answer.
     pub async fn retry_failed_sessions(
         &self,
     ) -> Result<Vec<(String, Result<ProcessOutcome, CoreError>)>, CoreError> {
@@ -350,14 +886,45 @@ impl SessionProcessor {
         // `list_session_summaries_by_status` is newest-first (started_at DESC);
         // reverse to oldest-first before capping so the cap drops the NEWEST
         // stragglers, not the ones that have been waiting longest.
-        let mut failed = self.locked()?.list_session_summaries_by_status(SessionStatus::Failed)?;
+        let failed = self.locked()?.list_session_summaries_by_status(SessionStatus::Failed)?;
+        let mut failed = self.without_batched(failed)?;
         failed.reverse();
         failed.truncate(MAX_RETRIES_PER_CALL);
+        if let Some(batches) = &self.batches {
+            let ids = failed.into_iter().map(|summary| summary.id).collect();
+            return Ok(with_priority(Priority::Background, self.process_batched(batches.as_ref(), ids)).await);
+        }
 
         let mut results = Vec::with_capacity(failed.len());
         for summary in failed {
-            let outcome = self.process(&summary.id).await;
+            let outcome = with_priority(Priority::Background, bypass_cache(self.process(&summary.id))).await;
+            results.push((summary.id, outcome));
+        }
+        Ok(results)
+    }
+
+    /// The other half of the offline fallback: re-runs provisional
+    /// (on-device) results against the cloud model, oldest-first, same cap
+    /// as `retry_failed_sessions`. A session whose reprocess comes back
+    /// provisional again means the cloud is still out of reach — the loop
+    /// stops there rather than re-running every walk on-device for nothing.
+    pub async fn reprocess_provisional_sessions(
+        &self,
+    ) -> Result<Vec<(String, Result<ProcessOutcome, CoreError>)>, CoreError> {
+        const MAX_REPROCESS_PER_CALL: usize = 5;
+
+        let mut provisional = self.locked()?.list_provisional_session_summaries()?;
+        provisional.reverse();
+        provisional.truncate(MAX_REPROCESS_PER_CALL);
+
+        let mut results = Vec::with_capacity(provisional.len());
+        for summary in provisional {
+            let outcome = with_priority(Priority::Background, self.process(&summary.id)).await;
+            let still_offline = matches!(&outcome, Ok(o) if o.session.provisional);
             results.push((summary.id, outcome));
+            if still_offline {
+                break;
+            }
         }
         Ok(results)
     }
@@ -412,7 +979,8 @@ mod tests {
         CompletionResponse {
             content: vec![ContentBlock::ToolUse { id: "tu_1".into(), name: name.into(), input }],
             stop_reason: StopReason::ToolUse,
-            usage: Usage { input_tokens: 100, output_tokens: 20 },
+            usage: Usage { input_tokens: 100, output_tokens: 20, ..Default::default() },
+            model: None,
         }
     }
 
@@ -420,7 +988,8 @@ mod tests {
         CompletionResponse {
             content: vec![ContentBlock::Text { text: text.into() }],
             stop_reason: StopReason::EndTurn,
-            usage: Usage { input_tokens: 50, output_tokens: 10 },
+            usage: Usage { input_tokens: 50, output_tokens: 10, ..Default::default() },
+            model: None,
         }
     }
 
@@ -441,7 +1010,7 @@ mod tests {
         assert_eq!(outcome.session.summary.as_deref(), Some("Ordered lumber; Dev handles framing."));
         // usage: 100+20, 100+20, 50+10 agent + 100+20 summary — NO build_document
         // call (Plan 13 Stage 2 drops phase B): finish is strictly cheaper.
-        assert_eq!(outcome.usage, Usage { input_tokens: 350, output_tokens: 70 });
+        assert_eq!(outcome.usage, Usage { input_tokens: 350, output_tokens: 70, ..Default::default() });
 
         let store = store.lock().unwrap();
         assert_eq!(store.list_items_for_session(&sid).unwrap().len(), 1);
@@ -478,12 +1047,115 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn stream_sink_previews_the_notes_and_leaves_the_outcome_unchanged() {
+        let store = Store::open_in_memory("device-a").unwrap();
+        let session = store.start_session(None).unwrap();
+        store.append_transcript(&session.id, "we need lumber. call Dev the framer.").unwrap();
+        store.end_and_record_session(&session.id).unwrap();
+        let provider = Arc::new(
+            MockProvider::new(vec![
+                tool_use("add_item", serde_json::json!({"kind": "todo", "text": "order lumber"})),
+                end_turn("done"),
+                summary_response("Ordered lumber."),
+            ])
+            .chunked(4),
+        );
+        let fragments = Arc::new(Mutex::new(String::new()));
+        let sink_fragments = fragments.clone();
+        let processor = SessionProcessor::new(
+            provider,
+            Arc::new(Mutex::new(store)),
+            Arc::new(Mutex::new(Memory::default())),
+            Arc::new(NullMemoryStore),
+        )
+        .with_stream_sink(Arc::new(move |e| {
+            if let harness::StreamEvent::ToolInputDelta { partial_json, .. } = e {
+                sink_fragments.lock().unwrap().push_str(&partial_json);
+            }
+        }));
+        let outcome = processor.process(&session.id).await.unwrap();
+        assert_eq!(outcome.session.summary.as_deref(), Some("Ordered lumber."));
+        assert_eq!(outcome.usage, Usage { input_tokens: 250, output_tokens: 50, ..Default::default() });
+        // both passes streamed: the add_item input, then the write_notes input
+        let streamed = fragments.lock().unwrap().clone();
+        assert!(streamed.contains("order lumber"));
+        assert_eq!(
+            notes::partial_summary(&streamed[streamed.find("{\"summary\"").unwrap()..]).as_deref(),
+            Some("Ordered lumber.")
+        );
+    }
+
+    #[tokio::test]
+    async fn observer_traces_the_extraction_run_and_the_notes_call() {
+        #[derive(Default)]
+        struct Runs(Mutex<Vec<String>>);
+        impl harness::AgentObserver for Runs {
+            fn tool_finished(&self, run: &str, turn: usize, report: &harness::ToolCallReport<'_>) {
+                self.0.lock().unwrap().push(format!("{run}#{turn} {}", report.name));
+            }
+            fn run_finished(&self, run: &str, usage: &Usage) {
+                self.0.lock().unwrap().push(format!("{run} done {}", usage.input_tokens));
+            }
+        }
+
+        let (processor, _store, sid) = processor_with(vec![
+            tool_use("add_item", serde_json::json!({"kind": "todo", "text": "order lumber"})),
+            end_turn("done"),
+            summary_response("Ordered lumber."),
+        ]);
+        let runs = Arc::new(Runs::default());
+        let processor = processor.with_observer(runs.clone());
+        processor.process(&sid).await.unwrap();
+        assert_eq!(
+            *runs.0.lock().unwrap(),
+            ["processing#0 add_item", "processing done 150", "summary done 100"]
+        );
+    }
+
+    #[tokio::test]
+    async fn usage_rows_split_the_run_by_the_model_that_served_each_call() {
+        /// Serves its script, then answers overloaded once it runs out.
+        struct OverloadedWhenDone(MockProvider);
+        #[async_trait::async_trait]
+        impl LlmProvider for OverloadedWhenDone {
+            async fn complete(&self, req: harness::CompletionRequest) -> Result<CompletionResponse, HarnessError> {
+                self.0.complete(req).await.map_err(|_| HarnessError::ProviderStatus {
+                    status: 529,
+                    retry_after: None,
+                    body: "overloaded".into(),
+                })
+            }
+        }
+        // The strong model serves the agent run and is overloaded by the
+        // summary call, which the live model answers.
+        let strong = OverloadedWhenDone(MockProvider::new(vec![
+            tool_use("add_item", serde_json::json!({"kind": "todo", "text": "order lumber"})),
+            end_turn("done"),
+        ]));
+        let router = harness::RoutingProvider::new("claude-sonnet-4-5", Arc::new(strong))
+            .with_fallback("claude-haiku-4-5", Arc::new(MockProvider::new(vec![summary_response("Order lumber.")])));
+        let (mut processor, store, sid) = processor_with(vec![]);
+        processor.provider = Arc::new(router);
+
+        processor.process(&sid).await.unwrap();
+        let rows = store.lock().unwrap().list_llm_usage_for_session(&sid).unwrap();
+        let mut by_model: Vec<(Option<String>, u64)> = rows.into_iter().map(|r| (r.model, r.input_tokens)).collect();
+        by_model.sort();
+        assert_eq!(
+            by_model,
+            [(Some("claude-haiku-4-5".into()), 100), (Some("claude-sonnet-4-5".into()), 150)],
+            "one row per serving model, each with its own spend"
+        );
+    }
+
     #[tokio::test]
     async fn failure_marks_failed_and_still_logs_usage() {
         // agent pass succeeds, summary response has no tool call -> Provider error
         let (processor, store, sid) = processor_with(vec![
             end_turn("nothing to extract"),
             end_turn("I refuse to call tools"),
+            end_turn("still refusing"),
         ]);
         let err = processor.process(&sid).await.unwrap_err();
         assert!(matches!(err, CoreError::Agent(_)));
@@ -491,10 +1163,201 @@ mod tests {
         assert_eq!(store.get_session(&sid).unwrap().status, SessionStatus::Failed);
         let usage_rows = store.list_llm_usage_for_session(&sid).unwrap();
         assert_eq!(usage_rows.len(), 1, "cost is logged even on failure (R9)");
-        // agent pass (50) + summary call that skipped the tool (50) — the
-        // failed summary call still cost tokens and they must be counted
-        assert_eq!(usage_rows[0].input_tokens, 100);
-        assert_eq!(usage_rows[0].output_tokens, 20);
+        // agent pass (50) + summary call that skipped the tool (50) + its
+        // repair (50) — the failed summary calls still cost tokens and they
+        // must be counted
+        assert_eq!(usage_rows[0].input_tokens, 150);
+        assert_eq!(usage_rows[0].output_tokens, 30);
+        // a skipped forced tool call is the provider's failure: invalid response
+        assert_eq!(
+            store.get_session(&sid).unwrap().failure,
+            Some(SessionFailure { kind: harness::ProviderErrorKind::InvalidResponse, http_status: None })
+        );
+    }
+
+    #[tokio::test]
+    async fn rejected_api_key_is_persisted_as_an_auth_failure() {
+        struct RejectsKey;
+        #[async_trait::async_trait]
+        impl harness::LlmProvider for RejectsKey {
+            async fn complete(
+                &self,
+                _req: harness::CompletionRequest,
+            ) -> Result<CompletionResponse, harness::HarnessError> {
+                Err(harness::HarnessError::ProviderStatus {
+                    status: 401,
+                    retry_after: None,
+                    body: r#"{"type":"error","error":{"type":"authentication_error"}}"#.into(),
+                })
+            }
+        }
+        let (_, store, sid) = processor_with(vec![]);
+        let processor = SessionProcessor::new(
+            Arc::new(RejectsKey),
+            store.clone(),
+            Arc::new(Mutex::new(Memory::default())),
+            Arc::new(NullMemoryStore),
+        );
+        let err = processor.process(&sid).await.unwrap_err();
+        assert!(matches!(err, CoreError::Agent(e) if e.http_status() == Some(401)));
+        let session = store.lock().unwrap().get_session(&sid).unwrap();
+        assert_eq!(session.status, SessionStatus::Failed);
+        assert_eq!(
+            session.failure,
+            Some(SessionFailure { kind: harness::ProviderErrorKind::Auth, http_status: Some(401) })
+        );
+    }
+
+    /// The cloud side of a job site with no signal.
+    struct Offline;
+    #[async_trait::async_trait]
+    impl harness::LlmProvider for Offline {
+        async fn complete(
+            &self,
+            _req: harness::CompletionRequest,
+        ) -> Result<CompletionResponse, harness::HarnessError> {
+            Err(harness::HarnessError::ProviderUnreachable("no route to host".into()))
+        }
+    }
+
+    fn offline_with_local(store: &Arc<Mutex<Store>>, local: Arc<MockProvider>) -> SessionProcessor {
+        SessionProcessor::new(
+            Arc::new(Offline),
+            store.clone(),
+            Arc::new(Mutex::new(Memory::default())),
+            Arc::new(NullMemoryStore),
+        )
+        .with_local_fallback(local)
+    }
+
+    #[tokio::test]
+    async fn unreachable_cloud_falls_back_to_a_provisional_local_result() {
+        let local = Arc::new(MockProvider::new(vec![
+            tool_use("add_item", serde_json::json!({"kind": "todo", "text": "order lumber"})),
+            end_turn("done"),
+            summary_response("on-device notes"),
+        ]));
+        let (_, store, sid) = processor_with(vec![]);
+        let processor = offline_with_local(&store, local.clone());
+
+        let outcome = processor.process(&sid).await.unwrap();
+        assert_eq!(outcome.session.status, SessionStatus::Processed);
+        assert!(outcome.session.provisional);
+        assert_eq!(outcome.session.summary.as_deref(), Some("on-device notes"));
+        assert_eq!(outcome.session.failure, None, "the offline attempt's failure is superseded");
+        assert_eq!(local.requests().len(), 3);
+
+        let s = store.lock().unwrap();
+        assert_eq!(s.list_items_for_session(&sid).unwrap().len(), 1);
+        let purposes: Vec<String> =
+            s.list_llm_usage_for_session(&sid).unwrap().into_iter().map(|u| u.purpose).collect();
+        assert_eq!(purposes, ["processing", "processing_local"]);
+    }
+
+    #[tokio::test]
+    async fn only_an_unreachable_cloud_falls_back() {
+        let local = Arc::new(MockProvider::new(vec![]));
+        let (_, store, sid) = processor_with(vec![]);
+        // Reachable but unhelpful: no write_notes call → InvalidResponse.
+        let processor = SessionProcessor::new(
+            Arc::new(MockProvider::new(vec![end_turn("done"), end_turn("no summary tool")])),
+            store.clone(),
+            Arc::new(Mutex::new(Memory::default())),
+            Arc::new(NullMemoryStore),
+        )
+        .with_local_fallback(local.clone());
+
+        assert!(processor.process(&sid).await.is_err());
+        assert!(local.requests().is_empty());
+        let session = store.lock().unwrap().get_session(&sid).unwrap();
+        assert_eq!(session.status, SessionStatus::Failed);
+        assert!(!session.provisional);
+    }
+
+    #[tokio::test]
+    async fn reprocess_provisional_sessions_swaps_in_the_cloud_result() {
+        let local = Arc::new(MockProvider::new(vec![
+            tool_use("add_item", serde_json::json!({"kind": "todo", "text": "order lumbr"})),
+            end_turn("done"),
+            summary_response("on-device notes"),
+        ]));
+        let (_, store, sid) = processor_with(vec![]);
+        offline_with_local(&store, local).process(&sid).await.unwrap();
+
+        // Still offline at the next app-open: the attempt falls back again
+        // and the walk stays provisional.
+        let local_again = Arc::new(MockProvider::new(vec![
+            end_turn("done"),
+            summary_response("on-device notes, again"),
+        ]));
+        let results =
+            offline_with_local(&store, local_again).reprocess_provisional_sessions().await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert!(results[0].1.as_ref().unwrap().session.provisional);
+
+        let cloud = SessionProcessor::new(
+            Arc::new(MockProvider::new(vec![
+                tool_use("add_item", serde_json::json!({"kind": "todo", "text": "order lumber"})),
+                end_turn("done"),
+                summary_response("cloud notes"),
+            ])),
+            store.clone(),
+            Arc::new(Mutex::new(Memory::default())),
+            Arc::new(NullMemoryStore),
+        );
+        let results = cloud.reprocess_provisional_sessions().await.unwrap();
+        assert_eq!(results.len(), 1);
+        let session = &results[0].1.as_ref().unwrap().session;
+        assert!(!session.provisional);
+        assert_eq!(session.summary.as_deref(), Some("cloud notes"));
+
+        let items = store.lock().unwrap().list_items_for_session(&sid).unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].text, "order lumber");
+        assert!(cloud.reprocess_provisional_sessions().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn a_failed_cloud_reprocess_keeps_the_provisional_walk() {
+        let local = Arc::new(MockProvider::new(vec![
+            tool_use("add_item", serde_json::json!({"kind": "todo", "text": "order lumbr"})),
+            end_turn("done"),
+            summary_response("on-device notes"),
+        ]));
+        let (_, store, sid) = processor_with(vec![]);
+        offline_with_local(&store, local).process(&sid).await.unwrap();
+        let artifacts_before = store.lock().unwrap().list_artifacts_for_session(&sid).unwrap().len();
+
+        // Reachable but unhelpful: the attempt's item is extracted, then the
+        // notes call never comes — not a fallback case, and not a reason to
+        // throw the on-device result away.
+        let cloud = SessionProcessor::new(
+            Arc::new(MockProvider::new(vec![
+                tool_use("add_item", serde_json::json!({"kind": "todo", "text": "order lumber"})),
+                end_turn("done"),
+                end_turn("no summary tool"),
+            ])),
+            store.clone(),
+            Arc::new(Mutex::new(Memory::default())),
+            Arc::new(NullMemoryStore),
+        );
+        let results = cloud.reprocess_provisional_sessions().await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert!(results[0].1.is_err());
+
+        let s = store.lock().unwrap();
+        let session = s.get_session(&sid).unwrap();
+        assert_eq!(session.status, SessionStatus::Processed);
+        assert!(session.provisional, "still queued for the next reprocess");
+        assert_eq!(session.summary.as_deref(), Some("on-device notes"));
+        assert_eq!(session.failure, None);
+        let items = s.list_items_for_session(&sid).unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].text, "order lumbr");
+        assert_eq!(s.list_artifacts_for_session(&sid).unwrap().len(), artifacts_before);
+        let purposes: Vec<String> =
+            s.list_llm_usage_for_session(&sid).unwrap().into_iter().map(|u| u.purpose).collect();
+        assert_eq!(purposes, ["processing", "processing_local", "processing"]);
     }
 
     #[tokio::test]
@@ -504,6 +1367,7 @@ mod tests {
             tool_use("add_item", serde_json::json!({"kind": "todo", "text": "order lumber"})),
             end_turn("done"),
             end_turn("no summary tool"),
+            end_turn("still no summary tool"),
             // attempt 2: extracts the same item again, summary succeeds
             tool_use("add_item", serde_json::json!({"kind": "todo", "text": "order lumber"})),
             end_turn("done"),
@@ -532,7 +1396,7 @@ mod tests {
         // attempt 1 fails (summary returns no tool); attempt 2 succeeds.
         let processor = SessionProcessor::new(
             Arc::new(MockProvider::new(vec![
-                end_turn("no extraction"), end_turn("no summary tool"),
+                end_turn("no extraction"), end_turn("no summary tool"), end_turn("still none"),
                 tool_use("add_item", serde_json::json!({"kind":"todo","text":"order 12 2x10s"})),
                 end_turn("done"),
                 summary_response("Lumber ordered."),
@@ -570,9 +1434,9 @@ mod tests {
         let processor = SessionProcessor::new(
             Arc::new(MockProvider::new(vec![
                 tool_use("add_item", serde_json::json!({"kind":"todo","text":"order lumber"})),
-                end_turn("done"), end_turn("no summary tool"),          // attempt 1 fails
+                end_turn("done"), end_turn("no summary tool"), end_turn("still none"), // attempt 1 fails
                 tool_use("add_item", serde_json::json!({"kind":"todo","text":"order lumber"})),
-                end_turn("done"), end_turn("no summary tool"),          // attempt 2 fails
+                end_turn("done"), end_turn("no summary tool"), end_turn("still none"), // attempt 2 fails
                 tool_use("add_item", serde_json::json!({"kind":"todo","text":"order 12 2x10s"})),
                 end_turn("done"), summary_response("Lumber ordered."),            ])),
             store.clone(), Arc::new(Mutex::new(Memory::default())), Arc::new(NullMemoryStore),
@@ -633,6 +1497,126 @@ mod tests {
         assert!(reqs[0].system.contains("french drain"));
     }
 
+    #[tokio::test]
+    async fn a_job_linked_session_reads_and_writes_the_job_memory() {
+        let provider = Arc::new(MockProvider::new(vec![
+            tool_use(
+                "update_memory",
+                serde_json::json!({"op": "remember", "section": "preferences", "text": "darker mulch", "scope": "client"}),
+            ),
+            end_turn("done"),
+            summary_response("s"),
+        ]));
+        let store = Store::open_in_memory("device-a").unwrap();
+        let job = store
+            .create_job(crate::domain::NewJob {
+                name: "Alvarez yard".into(),
+                client: Some("Mrs. Alvarez".into()),
+                ..Default::default()
+            })
+            .unwrap();
+        let session = store.start_session(Some(&job.id)).unwrap();
+        store.append_transcript(&session.id, "mulch the front beds").unwrap();
+        store.end_and_record_session(&session.id).unwrap();
+        let mut gate = Memory::default();
+        gate.remember("access", "gate code 4412", 1);
+        store.save_scoped_memory(&MemoryScope::Job(job.id.clone()), &gate, &harness::MemoryChange::Other).unwrap();
+        let store = Arc::new(Mutex::new(store));
+        let processor = SessionProcessor::new(
+            provider.clone(),
+            store.clone(),
+            Arc::new(Mutex::new(Memory::default())),
+            Arc::new(NullMemoryStore),
+        );
+        processor.process(&session.id).await.unwrap();
+
+        let first = &provider.requests()[0];
+        assert!(first.system.contains("## access — job \"Alvarez yard\"\n- gate code 4412"));
+        let memory_tool = first.tools.iter().find(|t| t.name == "update_memory").unwrap();
+        let scopes = &memory_tool.input_schema["properties"]["scope"]["enum"];
+        assert_eq!(*scopes, serde_json::json!(["user", "job", "client"]));
+
+        let store = store.lock().unwrap();
+        let client = store.load_scoped_memory(&MemoryScope::client("Mrs. Alvarez")).unwrap();
+        assert_eq!(client.section_texts("preferences"), vec!["darker mulch"]);
+        assert!(store.load_memory().unwrap().sections.is_empty());
+    }
+
+    #[tokio::test]
+    async fn the_users_and_the_jobs_memories_share_one_budget() {
+        let provider = Arc::new(MockProvider::new(vec![end_turn("done"), summary_response("s")]));
+        let store = Store::open_in_memory("device-a").unwrap();
+        let job = store
+            .create_job(crate::domain::NewJob {
+                name: "Alvarez yard".into(),
+                client: Some("Mrs. Alvarez".into()),
+                ..Default::default()
+            })
+            .unwrap();
+        let session = store.start_session(Some(&job.id)).unwrap();
+        store.append_transcript(&session.id, "walked the beds").unwrap();
+        store.end_and_record_session(&session.id).unwrap();
+        let mut yard = Memory::default();
+        yard.remember("access", "gate code 4412, side entrance", 1);
+        yard.remember("access", "dog in the back yard, call ahead", 1);
+        let job_scope = MemoryScope::Job(job.id.clone());
+        store.save_scoped_memory(&job_scope, &yard, &harness::MemoryChange::Other).unwrap();
+        let mut memory = Memory::default();
+        memory.remember("people", "Dev — framer, prefers morning", 1);
+        memory.remember("people", "Ana — tile setter, weekends", 1);
+        let mut processor = SessionProcessor::new(
+            provider.clone(),
+            Arc::new(Mutex::new(store)),
+            Arc::new(Mutex::new(memory)),
+            Arc::new(NullMemoryStore),
+        );
+        // Room for two entries in all; the empty client memory takes no share.
+        processor.memory_budget_tokens = 24;
+        processor.process(&session.id).await.unwrap();
+
+        let system = &provider.requests()[0].system;
+        let facts = ["gate code", "dog in the back", "Dev —", "Ana —"];
+        let shown = facts.iter().filter(|f| system.contains(*f)).count();
+        assert_eq!(shown, 2, "{system}");
+        assert!(system.contains("## access — job \"Alvarez yard\""));
+        assert!(system.contains("## people\n"));
+    }
+
+    #[tokio::test]
+    async fn the_memory_budget_goes_to_facts_the_transcript_mentions() {
+        let provider = Arc::new(MockProvider::new(vec![end_turn("done"), summary_response("s")]));
+        let store = Store::open_in_memory("device-a").unwrap();
+        let session = store.start_session(None).unwrap();
+        store.append_transcript(&session.id, "the Johnson patio needs resealing").unwrap();
+        store.end_and_record_session(&session.id).unwrap();
+        let mut memory = Memory::default();
+        memory.remember("jobs", "Johnson patio — pavers laid in spring", 1);
+        memory.remember("people", "Dev — framer, prefers morning calls", 1);
+        let mut processor = SessionProcessor::new(
+            provider.clone(),
+            Arc::new(Mutex::new(store)),
+            Arc::new(Mutex::new(memory)),
+            Arc::new(NullMemoryStore),
+        );
+        processor.memory_budget_tokens = 12;
+        processor.process(&session.id).await.unwrap();
+        let system = &provider.requests()[0].system;
+        assert!(system.contains("Johnson patio"));
+        assert!(!system.contains("Dev — framer"), "an unmentioned fact is left out when the budget is tight");
+    }
+
+    #[tokio::test]
+    async fn thinking_is_budgeted_for_extraction_only() {
+        let provider = Arc::new(MockProvider::new(vec![end_turn("done"), summary_response("s")]));
+        let (mut processor, _store, sid) = processor_with(vec![]);
+        processor.provider = provider.clone();
+        processor.thinking = Some(2048);
+        processor.process(&sid).await.unwrap();
+        let reqs = provider.requests();
+        assert_eq!(reqs[0].thinking, Some(2048));
+        assert_eq!(reqs[1].thinking, None, "the forced notes call can't think");
+    }
+
     #[tokio::test]
     async fn unknown_session_is_not_found() {
         let (processor, _store, _sid) = processor_with(vec![]);
@@ -663,6 +1647,73 @@ mod tests {
         assert_eq!(usage_rows[0].output_tokens, 20);
     }
 
+    #[tokio::test]
+    async fn budget_exceeded_fails_the_session_with_exact_spend() {
+        let (mut processor, store, sid) = processor_with(vec![
+            tool_use("add_item", serde_json::json!({"kind": "todo", "text": "order lumber"})),
+            tool_use("add_item", serde_json::json!({"kind": "todo", "text": "order lumber"})),
+        ]);
+        // The first turn spends all 20 output tokens; the second isn't made.
+        processor.budget = Budget { max_output_tokens: Some(20), ..Default::default() };
+
+        let err = processor.process(&sid).await.unwrap_err();
+        assert!(matches!(
+            &err,
+            CoreError::Agent(harness::HarnessError::BudgetExceeded { usage, .. }) if usage.input_tokens == 100
+        ));
+
+        let store = store.lock().unwrap();
+        let session = store.get_session(&sid).unwrap();
+        assert_eq!(session.status, SessionStatus::Failed);
+        assert_eq!(session.failure, None, "not the provider's failure");
+        let usage_rows = store.list_llm_usage_for_session(&sid).unwrap();
+        assert_eq!((usage_rows[0].input_tokens, usage_rows[0].output_tokens), (100, 20));
+    }
+
+    #[tokio::test]
+    async fn cancel_mid_call_logs_the_partial_spend_and_leaves_the_status_alone() {
+        /// Serves its script, then cancels and hangs on the next call.
+        struct CancelsWhenDone(MockProvider, CancelToken);
+        #[async_trait::async_trait]
+        impl LlmProvider for CancelsWhenDone {
+            async fn complete(&self, req: harness::CompletionRequest) -> Result<CompletionResponse, HarnessError> {
+                if self.0.requests().len() == 2 {
+                    self.1.cancel();
+                    std::future::pending::<()>().await;
+                }
+                self.0.complete(req).await
+            }
+        }
+        let cancel = CancelToken::new();
+        let (processor, store, sid) = processor_with(vec![]);
+        let provider = CancelsWhenDone(
+            MockProvider::new(vec![
+                tool_use("add_item", serde_json::json!({"kind": "todo", "text": "order lumber"})),
+                end_turn("done"),
+            ]),
+            cancel.clone(),
+        );
+        let processor = SessionProcessor { provider: Arc::new(provider), ..processor }.with_cancel(cancel);
+
+        // The agent run completed; the summary call was abandoned.
+        let err = processor.process(&sid).await.unwrap_err();
+        let spent = Usage { input_tokens: 150, output_tokens: 30, ..Default::default() };
+        assert!(matches!(&err, CoreError::Agent(HarnessError::Cancelled { usage }) if *usage == spent));
+
+        {
+            let store = store.lock().unwrap();
+            let session = store.get_session(&sid).unwrap();
+            assert_eq!(session.status, SessionStatus::AwaitingProcessing, "cancelled, not failed");
+            let rows = store.list_llm_usage_for_session(&sid).unwrap();
+            assert_eq!((rows.len(), rows[0].input_tokens, rows[0].output_tokens), (1, 150, 30));
+        }
+
+        // Spent for good: the next call makes no call and logs nothing.
+        let err = processor.process(&sid).await.unwrap_err();
+        assert!(matches!(err, CoreError::Agent(HarnessError::Cancelled { usage }) if usage == Usage::default()));
+        assert_eq!(store.lock().unwrap().list_llm_usage_for_session(&sid).unwrap().len(), 1);
+    }
+
     /// A failed session stays Failed and process_pending does NOT re-pull it on
     /// a second call — only AwaitingProcessing sessions are drained.
     #[tokio::test]
@@ -59,6 +59,44 @@ pub fn serialize_buckets(entries: &[NotesEntry]) -> String {
     serde_json::json!({ "buckets": entries }).to_string()
 }
 
+/// Best-effort read of the `summary` string from a `write_notes` tool input
+/// that is still streaming in (a JSON prefix, not valid JSON). Returns the
+/// decoded text seen so far — `None` until the `summary` value has opened.
+/// A trailing half-received escape is held back until its next fragment.
+/// Display-only: the finished tool input is still parsed normally.
+pub fn partial_summary(partial_json: &str) -> Option<String> {
+    const KEY: &str = "\"summary\"";
+    let key = partial_json.find(KEY)?;
+    let rest = partial_json[key + KEY.len()..].trim_start();
+    let rest = rest.strip_prefix(':')?.trim_start();
+    let mut chars = rest.strip_prefix('"')?.chars();
+    let mut out = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => break,
+            '\\' => {
+                let decoded = match chars.next() {
+                    Some('n') => '\n',
+                    Some('t') => '\t',
+                    Some('r') => '\r',
+                    Some('u') => {
+                        let hex: String = chars.by_ref().take(4).collect();
+                        match u32::from_str_radix(&hex, 16).ok().filter(|_| hex.len() == 4) {
+                            Some(code) => char::from_u32(code).unwrap_or('\u{fffd}'),
+                            None => break,
+                        }
+                    }
+                    Some(other) => other,
+                    None => break,
+                };
+                out.push(decoded);
+            }
+            c => out.push(c),
+        }
+    }
+    Some(out)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -105,6 +143,22 @@ mod tests {
         assert_eq!(parsed, vec![entry("scope_of_work", "Mulch", "ok")]);
     }
 
+    #[test]
+    fn partial_summary_reads_an_unterminated_streaming_value() {
+        assert_eq!(partial_summary(""), None);
+        assert_eq!(partial_summary(r#"{"summ"#), None);
+        assert_eq!(partial_summary(r#"{"summary": "#), None);
+        assert_eq!(partial_summary(r#"{"summary": ""#).as_deref(), Some(""));
+        assert_eq!(partial_summary(r#"{"summary": "Walked the"#).as_deref(), Some("Walked the"));
+        assert_eq!(
+            partial_summary(r#"{"summary":"Said \"darker\" mulch","notes":["#).as_deref(),
+            Some(r#"Said "darker" mulch"#)
+        );
+        // a half-received escape is held back, not rendered as garbage
+        assert_eq!(partial_summary(r#"{"summary": "caf\u00"#).as_deref(), Some("caf"));
+        assert_eq!(partial_summary(r#"{"summary": "café"#).as_deref(), Some("café"));
+    }
+
     #[test]
     fn empty_or_absent_body_is_empty() {
         assert_eq!(parse_notes_artifact(""), Vec::new());
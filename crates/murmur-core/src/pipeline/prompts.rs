@@ -5,22 +5,47 @@
 use std::sync::Arc;
 
 use harness::{
-    CompletionRequest, ContentBlock, HarnessError, LlmProvider, Message, ToolSpec, Usage,
+    forced_tool_call, forced_tool_input, AgentObserver, CacheHints, CancelToken, CompletionRequest,
+    CompletionResponse, ForcedOutput, HarnessError, LlmProvider, Message, RunError, StreamSink, ToolSpec,
 };
+use serde::Deserialize;
 
 use crate::domain::CapturedItem;
 use crate::pipeline::notes::{parse_notes_value, NotesEntry};
 
 const WRITE_NOTES: &str = "write_notes";
 
-/// System prompt for the extraction pass. `memory_prompt` is
-/// `Memory::to_prompt()` output ("" when empty).
-pub(crate) fn extraction_system_prompt(memory_prompt: &str) -> String {
-    let memory_block = if memory_prompt.trim().is_empty() {
+/// Opens the memory block the extraction prompt ends with.
+const MEMORY_HEADING: &str = "\n\nWhat you know about this user:\n";
+
+fn memory_block(memory_prompt: &str) -> String {
+    if memory_prompt.trim().is_empty() {
         String::new()
     } else {
-        format!("\n\nWhat you know about this user:\n{memory_prompt}")
-    };
+        format!("{MEMORY_HEADING}{memory_prompt}")
+    }
+}
+
+/// Prompt-caching breakpoints for an agent pass whose system prompt came
+/// from `extraction_system_prompt` / `live_extraction_system_prompt`: the
+/// tools, the fixed instructions (stable across every call), and the
+/// instructions + memory block, when there is one. A live pass's memory
+/// rides in its user turn instead, re-picked for each window, so every tick
+/// resends the whole system prompt verbatim and only the user turn is
+/// billed at the full rate.
+pub(crate) fn agent_cache_hints(system_prompt: &str) -> CacheHints {
+    let mut system_prefixes = Vec::new();
+    if let Some(memory_start) = system_prompt.find(MEMORY_HEADING) {
+        system_prefixes.push(memory_start);
+    }
+    system_prefixes.push(system_prompt.len());
+    CacheHints { tools: true, system_prefixes }
+}
+
+/// System prompt for the extraction pass. `memory_prompt` is
+/// `Memory::to_prompt_for()` output ("" when empty).
+pub(crate) fn extraction_system_prompt(memory_prompt: &str) -> String {
+    let memory_block = memory_block(memory_prompt);
     format!(
         "You process one transcribed field-work session (site walk, inspection, \
          client meeting) for a tradesperson. Extract structured records with the \
@@ -84,13 +109,32 @@ fn notes_tool_spec() -> ToolSpec {
     }
 }
 
+/// What the notes pass returns.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct SessionNotes {
+    pub summary: String,
+    pub spoken_total_cents: Option<i64>,
+    pub buckets: Vec<NotesEntry>,
+}
+
+/// `write_notes` input as sent. `notes` stays loose: C2 degrades it rather
+/// than rejecting the call.
+#[derive(Deserialize)]
+struct NotesInput {
+    summary: String,
+    #[serde(default)]
+    spoken_total_cents: Option<i64>,
+    #[serde(default)]
+    notes: Option<serde_json::Value>,
+}
+
 /// One-shot forced notes call (the Plan 02 reflection-engine pattern; Plan
 /// 14 D1: the same pass that already returned `spoken_total_cents` now also
 /// returns the narrative summary's richer detail as `notes[]`).
 ///
-/// Provider errors stay `Err` (no tokens were incurred). A successful call
-/// that lacks a `write_notes` block returns `Ok((None, None, [], usage))` so
-/// the caller can log the spend (R9) before deciding it's a failure. The
+/// A reply that skips `write_notes` or sends no `summary` gets one repair
+/// round-trip (`forced_tool_call`); if that fails too the `RunError` still
+/// carries both calls' usage so the caller can log the spend (R9). The
 /// transcript excerpt is passed through as-is — it already carries its own
 /// `## transcript` header from the context assembler.
 ///
@@ -99,6 +143,10 @@ fn notes_tool_spec() -> ToolSpec {
 /// into the on-demand pricing pass (`DocumentBuilder::build`) later, so the
 /// pricing prompt itself never needs transcript access.
 ///
+/// With a `sink`, the call streams (`LlmProvider::stream`) so the shell can
+/// preview the notes while they're written; the result is the same either way.
+/// `cancel` abandons the call in flight (`HarnessError::Cancelled`).
+///
 /// C2 (R7): a `notes` value that's truncated (model hit `max_tokens` mid-array)
 /// or malformed (non-array, garbled entries) degrades to `buckets: []` — the
 /// parseable `summary` is still returned. `parse_notes_value` is the same
@@ -108,41 +156,55 @@ pub(crate) async fn summarize(
     provider: Arc<dyn LlmProvider>,
     transcript_excerpt: &str,
     max_tokens: u32,
-) -> Result<(Option<String>, Option<i64>, Vec<NotesEntry>, Usage), HarnessError> {
-    let response = provider
-        .complete(CompletionRequest {
-            system: "You are building a client/team coordination artifact from one transcribed \
-                     field-work session. Write a narrative summary (2-4 plain sentences: what, \
-                     why, when) AND comprehensive notes grouped into three buckets: \
-                     scope_of_work (directives with client detail baked in — \"darker mulch than \
-                     last year\"), constraints (budget, permits, deadline, site access/gate \
-                     codes, client preferences), and conditions_and_issues (site findings \
-                     affecting the work). At most 12 notes entries; prefer fewer, denser entries. \
-                     Capture only what was said; never invent a budget, deadline, or access \
-                     detail — a missed note is cheaper than a fabricated constraint."
-                .into(),
-            messages: vec![Message::user_text(transcript_excerpt)],
-            tools: vec![notes_tool_spec()],
-            max_tokens,
-            tool_choice: Some(WRITE_NOTES.into()),
-        })
-        .await?;
-
-    let tool_input = response.content.iter().find_map(|b| match b {
-        ContentBlock::ToolUse { name, input, .. } if name == WRITE_NOTES => Some(input),
-        _ => None,
-    });
-    let summary =
-        tool_input.and_then(|i| i.get("summary").and_then(|s| s.as_str()).map(str::to_string));
-    let spoken_total_cents =
-        tool_input.and_then(|i| i.get("spoken_total_cents").and_then(|s| s.as_i64()));
-    // C2: a missing/non-array/garbled `notes` field yields [] via
-    // parse_notes_value's tolerant walk — never a panic, never an Err.
-    let buckets = tool_input
-        .and_then(|i| i.get("notes"))
-        .map(parse_notes_value)
-        .unwrap_or_default();
-    Ok((summary, spoken_total_cents, buckets, response.usage))
+    sink: Option<&StreamSink>,
+    observer: Option<&dyn AgentObserver>,
+    cancel: &CancelToken,
+) -> Result<ForcedOutput<SessionNotes>, RunError> {
+    let request = notes_request(transcript_excerpt, max_tokens);
+    let out: ForcedOutput<NotesInput> =
+        forced_tool_call(provider.as_ref(), request, sink, observer, cancel, "summary").await?;
+    Ok(ForcedOutput { value: out.value.into(), usage: out.usage, model: out.model })
+}
+
+/// The notes call's answer when it went through a message batch: parsed
+/// as `summarize` parses it, minus the repair round-trip a batch can't take.
+pub(crate) fn notes_from_response(response: &CompletionResponse) -> Result<SessionNotes, HarnessError> {
+    forced_tool_input::<NotesInput>(response, WRITE_NOTES, "summary").map(SessionNotes::from)
+}
+
+impl From<NotesInput> for SessionNotes {
+    fn from(input: NotesInput) -> Self {
+        // C2: a missing/non-array/garbled `notes` field yields [] via
+        // parse_notes_value's tolerant walk — never a panic, never an Err.
+        let buckets = input.notes.as_ref().map(parse_notes_value).unwrap_or_default();
+        SessionNotes { summary: input.summary, spoken_total_cents: input.spoken_total_cents, buckets }
+    }
+}
+
+/// The forced `write_notes` request `summarize` sends.
+pub(crate) fn notes_request(transcript_excerpt: &str, max_tokens: u32) -> CompletionRequest {
+    let system: String = "You are building a client/team coordination artifact from one transcribed \
+                          field-work session. Write a narrative summary (2-4 plain sentences: what, \
+                          why, when) AND comprehensive notes grouped into three buckets: \
+                          scope_of_work (directives with client detail baked in — \"darker mulch than \
+                          last year\"), constraints (budget, permits, deadline, site access/gate \
+                          codes, client preferences), and conditions_and_issues (site findings \
+                          affecting the work). At most 12 notes entries; prefer fewer, denser entries. \
+                          Capture only what was said; never invent a budget, deadline, or access \
+                          detail — a missed note is cheaper than a fabricated constraint."
+        .into();
+    // Everything but the transcript is fixed text: cache through the system
+    // prompt (which, in Anthropic's prefix order, covers the tool too).
+    let cache = CacheHints { tools: true, system_prefixes: vec![system.len()] };
+    CompletionRequest {
+        system,
+        messages: vec![Message::user_text(transcript_excerpt)],
+        tools: vec![notes_tool_spec()],
+        max_tokens,
+        tool_choice: Some(WRITE_NOTES.into()),
+        cache,
+        thinking: None,
+    }
 }
 
 /// Formats a session's existing items as a newest-first dedup list for a live
@@ -161,15 +223,11 @@ pub(crate) fn format_already_captured(items: &[CapturedItem]) -> String {
 /// System prompt for a live in-session pass (spec Rev 2 §2). Even more
 /// conservative than `extraction_system_prompt`: the transcript is partial, so
 /// R6's under-extraction bias applies doubly. `add_item` is the only tool —
-/// reports, contacts, and memory are end-of-session concerns. `memory_prompt`
-/// is `Memory::to_prompt()` output ("" when empty).
-pub(crate) fn live_extraction_system_prompt(memory_prompt: &str) -> String {
-    let memory_block = if memory_prompt.trim().is_empty() {
-        String::new()
-    } else {
-        format!("\n\nWhat you know about this user:\n{memory_prompt}")
-    };
-    format!(
+/// reports, contacts, and memory are end-of-session concerns. Memory is not
+/// part of it: `LiveExtractor` sends the entries relevant to each window in
+/// the user turn.
+pub(crate) fn live_extraction_system_prompt() -> String {
+    String::from(
         "You extract items LIVE from an in-progress field-work session while the \
          tradesperson is still talking. You see only the newest slice of a running \
          transcript plus the items already captured so far.\n\
@@ -186,7 +244,7 @@ pub(crate) fn live_extraction_system_prompt(memory_prompt: &str) -> String {
          contacts. When nothing new is worth capturing, reply with a short \
          acknowledgement and call no tools.\n\
          - Transcripts are speech-to-text: expect misrecognized jargon and names; \
-         prefer terms from what you know about the user.{memory_block}"
+         prefer terms from 'what you know about this user'."
     )
 }
 
@@ -194,7 +252,7 @@ pub(crate) fn live_extraction_system_prompt(memory_prompt: &str) -> String {
 mod tests {
     use std::sync::Arc;
 
-    use harness::{CompletionResponse, ContentBlock, MockProvider, StopReason, Usage};
+    use harness::{CompletionResponse, ContentBlock, HarnessError, MockProvider, StopReason, Usage};
 
     use super::*;
 
@@ -213,6 +271,19 @@ mod tests {
         assert!(!p.contains("What you know about this user"));
     }
 
+    #[test]
+    fn cache_hints_split_the_fixed_rules_from_memory() {
+        let p = extraction_system_prompt("## vocabulary\n- french drain\n");
+        let hints = agent_cache_hints(&p);
+        assert!(hints.tools);
+        let [rules_end, end] = hints.system_prefixes[..] else { panic!("{hints:?}") };
+        assert_eq!(&p[..rules_end], extraction_system_prompt("").as_str(), "rules don't depend on memory");
+        assert_eq!(end, p.len());
+
+        let live = live_extraction_system_prompt();
+        assert_eq!(agent_cache_hints(&live).system_prefixes, vec![live.len()]);
+    }
+
     #[tokio::test]
     async fn summarize_forces_the_tool_and_returns_text() {
         let provider = Arc::new(MockProvider::new(vec![CompletionResponse {
@@ -222,14 +293,14 @@ mod tests {
                 input: serde_json::json!({"summary": "Walked the deck; two todos."}),
             }],
             stop_reason: StopReason::ToolUse,
-            usage: Usage { input_tokens: 40, output_tokens: 12 },
+            usage: Usage { input_tokens: 40, output_tokens: 12, ..Default::default() },
+            model: None,
         }]));
-        let (summary, spoken_total_cents, buckets, usage) =
-            summarize(provider.clone(), "transcript text", 512).await.unwrap();
-        assert_eq!(summary.as_deref(), Some("Walked the deck; two todos."));
-        assert_eq!(spoken_total_cents, None, "no total was stated");
-        assert_eq!(buckets, Vec::new(), "no notes array in the response -> []");
-        assert_eq!(usage, Usage { input_tokens: 40, output_tokens: 12 });
+        let out = summarize(provider.clone(), "transcript text", 512, None, None, &CancelToken::default()).await.unwrap();
+        assert_eq!(out.value.summary, "Walked the deck; two todos.");
+        assert_eq!(out.value.spoken_total_cents, None, "no total was stated");
+        assert_eq!(out.value.buckets, Vec::new(), "no notes array in the response -> []");
+        assert_eq!(out.usage, Usage { input_tokens: 40, output_tokens: 12, ..Default::default() });
         let reqs = provider.requests();
         assert_eq!(reqs[0].tool_choice.as_deref(), Some("write_notes"));
         assert!(reqs[0].max_tokens >= 1);
@@ -238,6 +309,7 @@ mod tests {
             reqs[0].messages[0].content,
             vec![ContentBlock::Text { text: "transcript text".into() }]
         );
+        assert_eq!(reqs[0].cache.system_prefixes, vec![reqs[0].system.len()], "only the transcript varies");
     }
 
     #[tokio::test]
@@ -255,10 +327,10 @@ mod tests {
                 }),
             }],
             stop_reason: StopReason::ToolUse,
-            usage: Usage { input_tokens: 40, output_tokens: 12 },
+            usage: Usage { input_tokens: 40, output_tokens: 12, ..Default::default() },
+            model: None,
         }]));
-        let (_summary, _spoken_total_cents, buckets, _usage) =
-            summarize(provider, "t", 512).await.unwrap();
+        let buckets = summarize(provider, "t", 512, None, None, &CancelToken::default()).await.unwrap().value.buckets;
         assert_eq!(
             buckets,
             vec![crate::pipeline::notes::NotesEntry {
@@ -282,12 +354,12 @@ mod tests {
                 }),
             }],
             stop_reason: StopReason::ToolUse,
-            usage: Usage { input_tokens: 40, output_tokens: 12 },
+            usage: Usage { input_tokens: 40, output_tokens: 12, ..Default::default() },
+            model: None,
         }]));
-        let (summary, _spoken_total_cents, buckets, _usage) =
-            summarize(provider, "t", 512).await.unwrap();
-        assert_eq!(summary.as_deref(), Some("Still a valid summary."), "summary is preserved (R7)");
-        assert_eq!(buckets, Vec::new(), "garbled notes -> [] not a hard failure");
+        let notes = summarize(provider, "t", 512, None, None, &CancelToken::default()).await.unwrap().value;
+        assert_eq!(notes.summary, "Still a valid summary.", "summary is preserved (R7)");
+        assert_eq!(notes.buckets, Vec::new(), "garbled notes -> [] not a hard failure");
     }
 
     #[test]
@@ -303,22 +375,15 @@ mod tests {
 
     #[test]
     fn live_prompt_is_conservative_and_add_item_only() {
-        let p = live_extraction_system_prompt("## vocabulary\n- french drain\n");
+        let p = live_extraction_system_prompt();
         assert!(p.contains("already captured"), "dedup instruction");
         assert!(p.contains("partial transcript"), "names the partial-transcript risk");
         assert!(p.contains("add_item is your only tool"));
         assert!(p.contains("Bias hard toward fewer items"), "R6 doubly");
-        assert!(p.contains("french drain"), "memory is injected");
         // live passes must NOT be told to write reports or save contacts
         assert!(p.contains("do not summarize, write reports, or save"));
     }
 
-    #[test]
-    fn live_prompt_without_memory_omits_the_block() {
-        let p = live_extraction_system_prompt("");
-        assert!(!p.contains("What you know about this user"));
-    }
-
     #[test]
     fn already_captured_is_newest_first_and_tagged() {
         let s = crate::store::Store::open_in_memory("device-a").unwrap();
@@ -337,17 +402,40 @@ mod tests {
     }
 
     #[tokio::test]
-    async fn summarize_without_tool_call_returns_no_summary_but_usage() {
-        let provider = Arc::new(MockProvider::new(vec![CompletionResponse {
+    async fn summarize_without_tool_call_is_repaired_once_then_errs_with_usage() {
+        let prose = || CompletionResponse {
             content: vec![ContentBlock::Text { text: "no tool".into() }],
             stop_reason: StopReason::EndTurn,
-            usage: Usage { input_tokens: 50, output_tokens: 10 },
-        }]));
-        let (summary, spoken_total_cents, buckets, usage) = summarize(provider, "t", 512).await.unwrap();
-        assert!(summary.is_none(), "missing tool call is not an Err — spend must be loggable");
-        assert_eq!(spoken_total_cents, None);
-        assert_eq!(buckets, Vec::new());
-        assert_eq!(usage, Usage { input_tokens: 50, output_tokens: 10 });
+            usage: Usage { input_tokens: 50, output_tokens: 10, ..Default::default() },
+            model: None,
+        };
+        let provider = Arc::new(MockProvider::new(vec![prose(), prose()]));
+        let err = summarize(provider.clone(), "t", 512, None, None, &CancelToken::default()).await.unwrap_err();
+        assert!(matches!(&err.source, HarnessError::Provider(m) if m == "summary response missing write_notes call"));
+        assert_eq!(err.usage, Usage { input_tokens: 100, output_tokens: 20, ..Default::default() }, "spend must be loggable");
+        assert_eq!(provider.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn summarize_repairs_a_missing_summary() {
+        let notes = |input: serde_json::Value| CompletionResponse {
+            content: vec![ContentBlock::ToolUse { id: "tu_1".into(), name: "write_notes".into(), input }],
+            stop_reason: StopReason::ToolUse,
+            usage: Usage { input_tokens: 40, output_tokens: 12, ..Default::default() },
+            model: None,
+        };
+        let provider = Arc::new(MockProvider::new(vec![
+            notes(serde_json::json!({"notes": []})),
+            notes(serde_json::json!({"summary": "Second try."})),
+        ]));
+        let out = summarize(provider.clone(), "t", 512, None, None, &CancelToken::default()).await.unwrap();
+        assert_eq!(out.value.summary, "Second try.");
+        assert_eq!(out.usage.input_tokens, 80);
+        let ContentBlock::ToolResult { content, is_error: true, .. } = &provider.requests()[1].messages[2].content[0]
+        else {
+            panic!("expected an error tool result")
+        };
+        assert!(content.contains("missing field `summary`"), "{content}");
     }
 
     #[tokio::test]
@@ -362,11 +450,11 @@ mod tests {
                 }),
             }],
             stop_reason: StopReason::ToolUse,
-            usage: Usage { input_tokens: 40, output_tokens: 12 },
+            usage: Usage { input_tokens: 40, output_tokens: 12, ..Default::default() },
+            model: None,
         }]));
-        let (summary, spoken_total_cents, _buckets, _usage) =
-            summarize(provider, "transcript text", 512).await.unwrap();
-        assert!(summary.is_some());
-        assert_eq!(spoken_total_cents, Some(120000));
+        let notes =
+            summarize(provider, "transcript text", 512, None, None, &CancelToken::default()).await.unwrap().value;
+        assert_eq!(notes.spoken_total_cents, Some(120000));
     }
 }
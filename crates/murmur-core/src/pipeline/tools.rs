@@ -1,11 +1,14 @@
 //! Vocational tools (spec §4): thin adapters from the harness Tool trait onto
 //! the Store's writer API. Tools capture their handles (Arc) — no context
 //! parameter on execute (Plan 01 review decision). The std Mutex is never
-//! held across an await.
+//! held across an await. Input shape (required fields, types, `kind`
+//! membership, non-empty strings) is the advertised schema's job, enforced
+//! by `ToolRegistry::execute`; `execute` just deserializes.
 
 use std::sync::{Arc, Mutex};
 
-use harness::{HarnessError, Tool};
+use harness::{parse_input, HarnessError, Tool};
+use serde::Deserialize;
 
 use crate::domain::SessionStatus;
 use crate::store::Store;
@@ -21,28 +24,6 @@ fn lock<'a>(
     store.lock().map_err(|_| tool_err(tool, "store lock poisoned"))
 }
 
-fn req_str<'a>(input: &'a serde_json::Value, key: &str, tool: &str) -> Result<&'a str, HarnessError> {
-    match input.get(key) {
-        None => Err(tool_err(tool, format!("missing '{key}'"))),
-        Some(v) => v
-            .as_str()
-            .ok_or_else(|| tool_err(tool, format!("'{key}' must be a string"))),
-    }
-}
-
-/// Required string that must also be non-empty after trimming.
-fn req_nonempty_str<'a>(
-    input: &'a serde_json::Value,
-    key: &str,
-    tool: &str,
-) -> Result<&'a str, HarnessError> {
-    let value = req_str(input, key, tool)?;
-    if value.trim().is_empty() {
-        return Err(tool_err(tool, format!("'{key}' must not be empty")));
-    }
-    Ok(value)
-}
-
 use crate::domain::VALID_ITEM_KINDS;
 
 pub struct AddItemTool {
@@ -115,7 +96,7 @@ impl Tool for AddItemTool {
             "type": "object",
             "properties": {
                 // Built from the shared const (Plan 16 Task 2) — the advertised
-                // enum can never drift from the `execute` validation below.
+                // enum IS the validation (`ToolRegistry::execute`).
                 "kind": { "type": "string", "enum": VALID_ITEM_KINDS, "minLength": 1 },
                 "text": { "type": "string", "minLength": 1, "description": "one short item, in the speaker's own terms" }
             },
@@ -124,21 +105,19 @@ impl Tool for AddItemTool {
     }
 
     async fn execute(&self, input: serde_json::Value) -> Result<String, HarnessError> {
-        let kind = req_str(&input, "kind", "add_item")?;
-        if !VALID_ITEM_KINDS.contains(&kind) {
-            return Err(tool_err(
-                "add_item",
-                format!("invalid kind '{kind}'; must be one of: {}", VALID_ITEM_KINDS.join(", ")),
-            ));
+        #[derive(Deserialize)]
+        struct Input {
+            kind: String,
+            text: String,
         }
-        let text = req_nonempty_str(&input, "text", "add_item")?;
+        let Input { kind, text } = parse_input("add_item", input)?;
         let guard = lock(&self.store, "add_item")?;
         let item = match self.required_status {
             None => guard
-                .add_item_with_source(&self.session_id, kind, text, self.source)
+                .add_item_with_source(&self.session_id, &kind, &text, self.source)
                 .map_err(|e| tool_err("add_item", e.to_string()))?,
             Some(required) => guard
-                .add_item_if_status(&self.session_id, kind, text, required, self.source)
+                .add_item_if_status(&self.session_id, &kind, &text, required, self.source)
                 .map_err(|e| tool_err("add_item", e.to_string()))?
                 .ok_or_else(|| tool_err("add_item", "session no longer recording"))?,
         };
@@ -149,6 +128,12 @@ impl Tool for AddItemTool {
         }
         Ok(format!("added {kind}: {text}"))
     }
+
+    /// Each call is one self-contained insert under the store lock, and the
+    /// body never awaits — so overlapping calls still land in call order.
+    fn is_concurrency_safe(&self) -> bool {
+        true
+    }
 }
 
 pub struct UpsertContactTool {
@@ -185,15 +170,23 @@ impl Tool for UpsertContactTool {
     }
 
     async fn execute(&self, input: serde_json::Value) -> Result<String, HarnessError> {
-        let name = req_nonempty_str(&input, "name", "upsert_contact")?;
-        let trade = input["trade"].as_str();
-        let phone = input["phone"].as_str();
-        let notes = input["notes"].as_str();
+        #[derive(Deserialize)]
+        struct Input {
+            name: String,
+            trade: Option<String>,
+            phone: Option<String>,
+            notes: Option<String>,
+        }
+        let Input { name, trade, phone, notes } = parse_input("upsert_contact", input)?;
         lock(&self.store, "upsert_contact")?
-            .upsert_contact(name, trade, phone, notes)
+            .upsert_contact(&name, trade.as_deref(), phone.as_deref(), notes.as_deref())
             .map_err(|e| tool_err("upsert_contact", e.to_string()))?;
         Ok(format!("contact saved: {name}"))
     }
+
+    fn is_concurrency_safe(&self) -> bool {
+        true
+    }
 }
 
 pub struct WriteReportTool {
@@ -229,10 +222,14 @@ impl Tool for WriteReportTool {
     }
 
     async fn execute(&self, input: serde_json::Value) -> Result<String, HarnessError> {
-        let title = req_nonempty_str(&input, "title", "write_report")?;
-        let body = req_str(&input, "body", "write_report")?;
+        #[derive(Deserialize)]
+        struct Input {
+            title: String,
+            body: String,
+        }
+        let Input { title, body } = parse_input("write_report", input)?;
         lock(&self.store, "write_report")?
-            .add_artifact(&self.session_id, "report", title, body)
+            .add_artifact(&self.session_id, "report", &title, &body)
             .map_err(|e| tool_err("write_report", e.to_string()))?;
         Ok(format!("report written: {title}"))
     }
@@ -338,40 +335,44 @@ impl Tool for BuildDocumentTool {
     }
 
     async fn execute(&self, input: serde_json::Value) -> Result<String, HarnessError> {
-        let total_kind = req_nonempty_str(&input, "total_kind", "build_document")?;
-        let total_label_key = req_nonempty_str(&input, "total_label_key", "build_document")?;
-        let static_total_cents = input.get("static_total_cents").and_then(|v| v.as_i64());
-        let lines_in = input
-            .get("lines")
-            .and_then(|v| v.as_array())
-            .ok_or_else(|| tool_err("build_document", "missing 'lines'"))?;
+        #[derive(Deserialize)]
+        struct Line {
+            title: String,
+            // Null reads as absent, as the schema check allows.
+            detail: Option<String>,
+            qty: Option<String>,
+            amount_cents: Option<i64>,
+            section: Option<String>,
+            is_gap: Option<bool>,
+            item_id: Option<String>,
+        }
+        #[derive(Deserialize)]
+        struct Input {
+            total_kind: String,
+            total_label_key: String,
+            static_total_cents: Option<i64>,
+            lines: Vec<Line>,
+        }
+        let Input { total_kind, total_label_key, static_total_cents, lines: lines_in } =
+            parse_input("build_document", input)?;
 
         // D1/D2: echo-and-validate, first-wins dedup. `claimed` tracks which
         // valid item ids have already been attached to an earlier line so a
         // duplicate echo degrades to None rather than double-claiming.
         let mut claimed: std::collections::HashSet<String> = std::collections::HashSet::new();
         let mut lines = Vec::with_capacity(lines_in.len());
-        for (idx, line) in lines_in.iter().enumerate() {
-            let title = line
-                .get("title")
-                .and_then(|v| v.as_str())
-                .ok_or_else(|| tool_err("build_document", format!("lines[{idx}] missing 'title'")))?;
-            let detail = line.get("detail").and_then(|v| v.as_str()).unwrap_or("");
-            let qty = line.get("qty").and_then(|v| v.as_str()).unwrap_or("");
-            let amount_cents = line.get("amount_cents").and_then(|v| v.as_i64());
-            let section = line.get("section").and_then(|v| v.as_str());
-            let is_gap = match line.get("is_gap").and_then(|v| v.as_bool()) {
+        for line in lines_in {
+            let is_gap = match line.is_gap {
                 Some(explicit) => explicit,
                 // D2a: only the dollar template auto-derives a gap from a missing
                 // amount. report/inspection lines default to NOT a gap — a normal
                 // "OK" row or a §-finding with no dollar figure is not a gap.
-                None => self.doc_kind == "estimate" && amount_cents.is_none(),
+                None => self.doc_kind == "estimate" && line.amount_cents.is_none(),
             };
-            let echoed_item_id = line.get("item_id").and_then(|v| v.as_str());
-            let item_id = match echoed_item_id {
-                Some(id) if self.valid_item_ids.contains(id) && !claimed.contains(id) => {
-                    claimed.insert(id.to_string());
-                    Some(id.to_string())
+            let item_id = match line.item_id {
+                Some(id) if self.valid_item_ids.contains(&id) && !claimed.contains(&id) => {
+                    claimed.insert(id.clone());
+                    Some(id)
                 }
                 // missing, not in the run's authoritative set, or already
                 // claimed by an earlier line — degrade to None, never fail.
@@ -379,11 +380,11 @@ impl Tool for BuildDocumentTool {
             };
             lines.push(serde_json::json!({
                 "id": crate::ids::new_id(),
-                "title": title,
-                "detail": detail,
-                "qty": qty,
-                "amount_cents": amount_cents,
-                "section": section,
+                "title": line.title,
+                "detail": line.detail.unwrap_or_default(),
+                "qty": line.qty.unwrap_or_default(),
+                "amount_cents": line.amount_cents,
+                "section": line.section,
                 "is_gap": is_gap,
                 "item_id": item_id,
             }));
@@ -421,7 +422,7 @@ impl Tool for BuildDocumentTool {
 mod tests {
     use std::sync::{Arc, Mutex};
 
-    use harness::{HarnessError, Tool};
+    use harness::{HarnessError, Tool, ToolRegistry};
 
     use crate::store::Store;
 
@@ -431,6 +432,15 @@ mod tests {
         (Arc::new(Mutex::new(store)), session.id)
     }
 
+    /// Input validation lives in the registry (the advertised schema), so
+    /// bad-input tests dispatch through one, as the agent does.
+    async fn via_registry(tool: impl Tool, input: serde_json::Value) -> Result<String, HarnessError> {
+        let name = tool.name().to_string();
+        let mut registry = ToolRegistry::new();
+        registry.register(tool);
+        registry.execute(&name, input).await
+    }
+
     #[tokio::test]
     async fn add_item_writes_through_store() {
         let (store, sid) = shared_store_with_session();
@@ -449,8 +459,8 @@ mod tests {
     async fn add_item_rejects_bad_input() {
         let (store, sid) = shared_store_with_session();
         let tool = super::AddItemTool::new(store, &sid);
-        let err = tool.execute(serde_json::json!({"kind": "todo"})).await.unwrap_err();
-        assert!(matches!(err, HarnessError::Tool { .. }));
+        let err = via_registry(tool, serde_json::json!({"kind": "todo"})).await.unwrap_err();
+        assert!(matches!(err, HarnessError::Tool { message, .. } if message == "invalid input: `text` is required"));
     }
 
     #[tokio::test]
@@ -496,12 +506,9 @@ mod tests {
     async fn wrong_typed_field_names_the_type_error() {
         let (store, sid) = shared_store_with_session();
         let tool = super::AddItemTool::new(store, &sid);
-        let err = tool
-            .execute(serde_json::json!({"kind": 42, "text": "x"}))
-            .await
-            .unwrap_err();
+        let err = via_registry(tool, serde_json::json!({"kind": 42, "text": "x"})).await.unwrap_err();
         assert!(
-            matches!(&err, HarnessError::Tool { message, .. } if message.contains("must be a string")),
+            matches!(&err, HarnessError::Tool { message, .. } if message.contains("`kind` must be a string")),
             "got: {err}"
         );
     }
@@ -510,10 +517,7 @@ mod tests {
     async fn invalid_kind_names_the_valid_kinds() {
         let (store, sid) = shared_store_with_session();
         let tool = super::AddItemTool::new(store.clone(), &sid);
-        let err = tool
-            .execute(serde_json::json!({"kind": "vibe", "text": "x"}))
-            .await
-            .unwrap_err();
+        let err = via_registry(tool, serde_json::json!({"kind": "vibe", "text": "x"})).await.unwrap_err();
         assert!(
             matches!(&err, HarnessError::Tool { message, .. }
                 if message.contains("todo") && message.contains("price")),
@@ -548,10 +552,7 @@ mod tests {
     async fn empty_text_is_rejected() {
         let (store, sid) = shared_store_with_session();
         let tool = super::AddItemTool::new(store.clone(), &sid);
-        let err = tool
-            .execute(serde_json::json!({"kind": "todo", "text": "  "}))
-            .await
-            .unwrap_err();
+        let err = via_registry(tool, serde_json::json!({"kind": "todo", "text": "  "})).await.unwrap_err();
         assert!(
             matches!(&err, HarnessError::Tool { message, .. } if message.contains("must not be empty")),
             "got: {err}"
@@ -618,8 +619,7 @@ mod tests {
         let (store, sid) = shared_store_with_session();
         let tool = super::BuildDocumentTool::new(store.clone(), &sid, "estimate", None, vec![]);
         // missing total_kind -> validation error, no mint
-        let err = tool
-            .execute(serde_json::json!({"total_label_key": "total", "lines": []}))
+        let err = via_registry(tool, serde_json::json!({"total_label_key": "total", "lines": []}))
             .await
             .unwrap_err();
         assert!(matches!(err, HarnessError::Tool { .. }));
@@ -668,6 +668,25 @@ mod tests {
         assert_eq!(v["lines"][1]["is_gap"], true, "unheard amount on a dollar template ⇒ gap");
     }
 
+    #[tokio::test]
+    async fn build_document_reads_null_line_fields_as_empty() {
+        let (store, sid) = shared_store_with_session();
+        let tool = super::BuildDocumentTool::new(store.clone(), &sid, "estimate", None, vec![]);
+        via_registry(
+            tool,
+            serde_json::json!({
+                "total_kind": "sum", "total_label_key": "total",
+                "lines": [{"title": "Mulch", "detail": null, "qty": null, "amount_cents": 28500}]
+            }),
+        )
+        .await
+        .unwrap();
+        let doc = store.lock().unwrap().latest_document_artifact(&sid).unwrap().unwrap();
+        let v: serde_json::Value = serde_json::from_str(&doc.body).unwrap();
+        assert_eq!(v["lines"][0]["detail"], "");
+        assert_eq!(v["lines"][0]["qty"], "");
+    }
+
     #[tokio::test]
     async fn inspection_findings_have_no_amount_but_are_not_gaps() {
         let (store, sid) = shared_store_with_session();
@@ -743,15 +762,12 @@ mod tests {
     async fn empty_name_and_title_are_rejected() {
         let (store, sid) = shared_store_with_session();
         let contact = super::UpsertContactTool::new(store.clone());
-        let err = contact.execute(serde_json::json!({"name": ""})).await.unwrap_err();
+        let err = via_registry(contact, serde_json::json!({"name": ""})).await.unwrap_err();
         assert!(
             matches!(&err, HarnessError::Tool { message, .. } if message.contains("must not be empty"))
         );
         let report = super::WriteReportTool::new(store, &sid);
-        let err = report
-            .execute(serde_json::json!({"title": " ", "body": "b"}))
-            .await
-            .unwrap_err();
+        let err = via_registry(report, serde_json::json!({"title": " ", "body": "b"})).await.unwrap_err();
         assert!(
             matches!(&err, HarnessError::Tool { message, .. } if message.contains("must not be empty"))
         );
@@ -0,0 +1,485 @@
+//! Photo vision pass: the shell hands over a processed session's photo
+//! bytes for one call — core never stores them, only what the model said
+//! about them — and the model captions each photo, matches it to the item
+//! it illustrates, and drafts items the walk missed (a safety finding only
+//! visible in the picture, say). Like pricing (R6), it may touch only the
+//! photos and items it was shown, by their exact ids.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use harness::{
+    forced_tool_call, CacheHints, CancelToken, CompletionRequest, ContentBlock, ForcedOutput, LlmProvider,
+    Message, Role, SharedObserver, ToolSpec, Usage,
+};
+use serde::Deserialize;
+
+use crate::domain::{CapturedItem, ItemSource, SessionStatus, VALID_ITEM_KINDS};
+use crate::error::CoreError;
+use crate::store::Store;
+
+/// The image formats the vision models accept.
+pub const VISION_MEDIA_TYPES: [&str; 4] = ["image/jpeg", "image/png", "image/gif", "image/webp"];
+
+/// One photo's bytes, read by the shell from its Documents dir for the
+/// length of one pass.
+#[derive(Clone, Debug)]
+pub struct PhotoBytes {
+    pub photo_id: String,
+    /// One of `VISION_MEDIA_TYPES`.
+    pub media_type: String,
+    pub bytes: Vec<u8>,
+}
+
+/// What one `PhotoVision::describe` call changed.
+#[derive(Debug, Default)]
+pub struct PhotoVisionOutcome {
+    /// Photos whose caption was written.
+    pub captioned: usize,
+    /// Session-level photos now attached to an item — a high-confidence
+    /// match, or the photo a drafted item came from.
+    pub attached: usize,
+    /// Items drafted from the photos, in the model's order.
+    pub new_item_ids: Vec<String>,
+    pub usage: Usage,
+}
+
+const DESCRIBE_PHOTOS: &str = "describe_photos";
+
+fn describe_photos_tool_spec() -> ToolSpec {
+    ToolSpec {
+        name: DESCRIBE_PHOTOS.into(),
+        description: "Describe each photo by its exact photo_id. Name the item a photo illustrates \
+                       only from the given list, by its exact item_id, and say how sure you are. \
+                       Draft a new item only for something clearly visible that no listed item \
+                       already covers."
+            .into(),
+        input_schema: serde_json::json!({
+            "type": "object",
+            "properties": {
+                "photos": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "photo_id": { "type": "string" },
+                            "caption": { "type": "string", "description": "one short line on what the photo shows" },
+                            "item_id": { "type": "string" },
+                            "match_confidence": { "type": "string", "enum": ["high", "medium", "low"] }
+                        },
+                        "required": ["photo_id", "caption"]
+                    }
+                },
+                "new_items": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "kind": { "type": "string", "enum": VALID_ITEM_KINDS },
+                            "text": { "type": "string", "minLength": 1 },
+                            "photo_id": { "type": "string" }
+                        },
+                        "required": ["kind", "text"]
+                    }
+                }
+            },
+            "required": ["photos"]
+        }),
+    }
+}
+
+/// `describe_photos` input. Rows are parsed one at a time (as in the
+/// document passes), so one bad row is dropped instead of failing the pass.
+#[derive(Deserialize)]
+struct VisionInput {
+    photos: Vec<serde_json::Value>,
+    #[serde(default)]
+    new_items: Vec<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct PhotoRow {
+    photo_id: String,
+    caption: String,
+    #[serde(default)]
+    item_id: Option<String>,
+    #[serde(default)]
+    match_confidence: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct NewItemRow {
+    kind: String,
+    text: String,
+    #[serde(default)]
+    photo_id: Option<String>,
+}
+
+fn format_items(items: &[CapturedItem]) -> String {
+    if items.is_empty() {
+        return "(none)".into();
+    }
+    items
+        .iter()
+        .map(|i| format!("- [{}] {} (item_id: {})", i.kind, i.text, i.id))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+pub struct PhotoVision {
+    provider: Arc<dyn LlmProvider>,
+    store: Arc<Mutex<Store>>,
+    /// Vision-call output budget.
+    pub max_tokens: u32,
+    /// Sees the one "photo_vision" call (and its repair, if any).
+    observer: Option<SharedObserver>,
+    cancel: CancelToken,
+}
+
+impl PhotoVision {
+    pub fn new(provider: Arc<dyn LlmProvider>, store: Arc<Mutex<Store>>) -> Self {
+        PhotoVision { provider, store, max_tokens: 1024, observer: None, cancel: CancelToken::default() }
+    }
+
+    pub fn with_observer(mut self, observer: SharedObserver) -> Self {
+        self.observer = Some(observer);
+        self
+    }
+
+    /// Abandons the pass when `cancel` fires mid-call: nothing is written
+    /// but the usage row, and `describe` returns `HarnessError::Cancelled`.
+    pub fn with_cancel(mut self, cancel: CancelToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    fn locked(&self) -> Result<std::sync::MutexGuard<'_, Store>, CoreError> {
+        self.store
+            .lock()
+            .map_err(|_| CoreError::InvalidState("store lock poisoned".into()))
+    }
+
+    /// Captions `photos` — every one a live photo of the PROCESSED session
+    /// `session_id` (`InvalidState` otherwise, before any call) — attaches
+    /// each session-level photo the model matches to an item with high
+    /// confidence, and adds its drafted items as `Authoritative`. A photo
+    /// the user already attached keeps its item. A failed call writes
+    /// nothing but the usage row; no photos is no call.
+    pub async fn describe(
+        &self,
+        session_id: &str,
+        photos: &[PhotoBytes],
+    ) -> Result<PhotoVisionOutcome, CoreError> {
+        let (items, mut attached_to) = {
+            let store = self.locked()?;
+            let session = store.get_session(session_id)?;
+            if session.status != SessionStatus::Processed {
+                return Err(CoreError::InvalidState(format!(
+                    "cannot describe photos of a {} session",
+                    session.status.as_str()
+                )));
+            }
+            let live: HashMap<String, Option<String>> = store
+                .list_photos_for_session(session_id)?
+                .into_iter()
+                .map(|p| (p.id, p.item_id))
+                .collect();
+            let mut attached_to = HashMap::new();
+            for photo in photos {
+                let Some(item_id) = live.get(&photo.photo_id) else {
+                    return Err(CoreError::InvalidState(format!(
+                        "photo {} is not a live photo of session {session_id}",
+                        photo.photo_id
+                    )));
+                };
+                if !VISION_MEDIA_TYPES.contains(&photo.media_type.as_str()) {
+                    return Err(CoreError::InvalidState(format!(
+                        "photo {}: unsupported media type '{}'",
+                        photo.photo_id, photo.media_type
+                    )));
+                }
+                attached_to.insert(photo.photo_id.as_str(), item_id.clone());
+            }
+            (store.list_items_for_session(session_id)?, attached_to)
+        };
+        if photos.is_empty() {
+            return Ok(PhotoVisionOutcome::default());
+        }
+
+        let mut content = vec![ContentBlock::Text {
+            text: format!("Session items:\n{}\n\nThe photos follow, each after its photo_id.", format_items(&items)),
+        }];
+        for photo in photos {
+            content.push(ContentBlock::Text { text: format!("photo_id: {}", photo.photo_id) });
+            content.push(ContentBlock::image(photo.media_type.clone(), &photo.bytes));
+        }
+        let request = CompletionRequest {
+            system: "You look at photos a tradesperson took during a site walk. Caption what each \
+                     photo actually shows, plainly. Match a photo to a listed item only when it \
+                     clearly illustrates that item, and say \"high\" only when there is no doubt. \
+                     Draft a new item only for something clearly visible — a safety hazard, a \
+                     damaged part — never a guess."
+                .into(),
+            messages: vec![Message { role: Role::User, content }],
+            tools: vec![describe_photos_tool_spec()],
+            max_tokens: self.max_tokens,
+            tool_choice: Some(DESCRIBE_PHOTOS.to_string()),
+            cache: CacheHints::default(),
+            thinking: None,
+        };
+        let result = forced_tool_call::<VisionInput>(
+            self.provider.as_ref(),
+            request,
+            None,
+            self.observer.as_deref(),
+            &self.cancel,
+            "photo_vision",
+        )
+        .await;
+        let (input, usage, model) = match result {
+            Ok(ForcedOutput { value, usage, model }) => (value, usage, model),
+            Err(run_err) => {
+                // Best effort: the model's error is what the caller must see,
+                // never a lock or DB failure logging its cost.
+                if run_err.usage != Usage::default() {
+                    if let Ok(store) = self.locked() {
+                        let _ = store.record_served_usage(
+                            Some(session_id),
+                            "photo_vision",
+                            &run_err.usage,
+                            &run_err.model,
+                        );
+                    }
+                }
+                return Err(run_err.source.into());
+            }
+        };
+
+        let store = self.locked()?;
+        // Captions, attachments, new items and their cost land together or
+        // not at all.
+        let tx = store.conn.unchecked_transaction()?;
+        let valid_items: HashSet<&str> = items.iter().map(|i| i.id.as_str()).collect();
+        let mut outcome = PhotoVisionOutcome { usage, ..Default::default() };
+        let mut seen = HashSet::new();
+        for row in input.photos {
+            let Ok(row) = serde_json::from_value::<PhotoRow>(row) else { continue };
+            let caption = row.caption.trim();
+            let Some(attached) = attached_to.get_mut(row.photo_id.as_str()) else { continue };
+            if caption.is_empty() || !seen.insert(row.photo_id.clone()) {
+                continue;
+            }
+            store.set_photo_caption(&row.photo_id, caption)?;
+            outcome.captioned += 1;
+            if let Some(item_id) = row.item_id.filter(|id| valid_items.contains(id.as_str())) {
+                if attached.is_none() && row.match_confidence.as_deref() == Some("high") {
+                    store.attach_photo_to_item(&row.photo_id, &item_id)?;
+                    *attached = Some(item_id);
+                    outcome.attached += 1;
+                }
+            }
+        }
+        for row in input.new_items {
+            let Ok(row) = serde_json::from_value::<NewItemRow>(row) else { continue };
+            let text = row.text.trim();
+            if text.is_empty() || !VALID_ITEM_KINDS.contains(&row.kind.as_str()) {
+                continue;
+            }
+            // A reprocess that started meanwhile owns the board now.
+            let Some(item) = store.add_item_if_status(
+                session_id,
+                &row.kind,
+                text,
+                SessionStatus::Processed,
+                ItemSource::Authoritative,
+            )?
+            else {
+                break;
+            };
+            if let Some(photo_id) = row.photo_id {
+                if let Some(attached @ None) = attached_to.get_mut(photo_id.as_str()) {
+                    store.attach_photo_to_item(&photo_id, &item.id)?;
+                    *attached = Some(item.id.clone());
+                    outcome.attached += 1;
+                }
+            }
+            outcome.new_item_ids.push(item.id);
+        }
+        store.record_served_usage(Some(session_id), "photo_vision", &outcome.usage, &model)?;
+        tx.commit()?;
+        Ok(outcome)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use harness::{CompletionResponse, HarnessError, MockProvider, StopReason};
+
+    use super::*;
+
+    fn describe_call(input: serde_json::Value) -> CompletionResponse {
+        CompletionResponse {
+            content: vec![ContentBlock::ToolUse { id: "tu_1".into(), name: DESCRIBE_PHOTOS.into(), input }],
+            stop_reason: StopReason::ToolUse,
+            usage: Usage { input_tokens: 1800, output_tokens: 60, ..Default::default() },
+            model: None,
+        }
+    }
+
+    /// A processed session with one authoritative item per `(kind, text)`.
+    fn processed_session(texts: &[(&str, &str)]) -> (Store, String, Vec<CapturedItem>) {
+        let store = Store::open_in_memory("device-a").unwrap();
+        let session = store.start_session(None).unwrap();
+        let items: Vec<CapturedItem> = texts
+            .iter()
+            .map(|(kind, text)| {
+                store.add_item_with_source(&session.id, kind, text, ItemSource::Authoritative).unwrap()
+            })
+            .collect();
+        let ids: Vec<String> = items.iter().map(|i| i.id.clone()).collect();
+        store.append_transcript(&session.id, "site walk").unwrap();
+        store.end_and_record_session(&session.id).unwrap();
+        store.finish_session_processed(
+            &session.id,
+            "Walked the site.",
+            &Usage::default(),
+            &harness::ServedBy::default(),
+            &ids,
+        )
+        .unwrap();
+        (store, session.id, items)
+    }
+
+    fn jpeg(photo_id: &str) -> PhotoBytes {
+        PhotoBytes { photo_id: photo_id.into(), media_type: "image/jpeg".into(), bytes: b"jpeg".to_vec() }
+    }
+
+    #[tokio::test]
+    async fn captions_attaches_high_confidence_matches_and_drafts_items() {
+        let (store, sid, items) = processed_session(&[("todo", "replace deck boards"), ("note", "gate sticks")]);
+        let deck = store.add_photo(&sid, None, "deck.jpg", None).unwrap();
+        let gate = store.add_photo(&sid, None, "gate.jpg", None).unwrap();
+        let cord = store.add_photo(&sid, None, "cord.jpg", None).unwrap();
+        let provider = Arc::new(MockProvider::new(vec![describe_call(serde_json::json!({
+            "photos": [
+                {"photo_id": deck.id, "caption": "rotted deck boards", "item_id": items[0].id, "match_confidence": "high"},
+                {"photo_id": gate.id, "caption": "a wooden gate", "item_id": items[1].id, "match_confidence": "medium"},
+                {"photo_id": cord.id, "caption": "frayed extension cord by the shed"},
+                {"photo_id": "not-shown", "caption": "ignored"},
+            ],
+            "new_items": [
+                {"kind": "safety", "text": "frayed extension cord by the shed", "photo_id": cord.id},
+                {"kind": "invented", "text": "dropped"},
+            ],
+        }))]));
+        let store = Arc::new(Mutex::new(store));
+        let vision = PhotoVision::new(provider.clone(), store.clone());
+
+        let out = vision.describe(&sid, &[jpeg(&deck.id), jpeg(&gate.id), jpeg(&cord.id)]).await.unwrap();
+        assert_eq!((out.captioned, out.attached, out.new_item_ids.len()), (3, 2, 1));
+
+        let request = &provider.requests()[0];
+        let images = request.messages[0].content.iter().filter(|b| matches!(b, ContentBlock::Image { .. })).count();
+        assert_eq!(images, 3);
+
+        let s = store.lock().unwrap();
+        let deck = s.get_photo(&deck.id).unwrap();
+        assert_eq!(deck.caption.as_deref(), Some("rotted deck boards"));
+        assert_eq!(deck.item_id.as_deref(), Some(items[0].id.as_str()), "high confidence attaches");
+        assert_eq!(s.get_photo(&gate.id).unwrap().item_id, None, "medium confidence only captions");
+        let safety = s.get_item(&out.new_item_ids[0]).unwrap();
+        assert_eq!((safety.kind.as_str(), safety.source), ("safety", ItemSource::Authoritative));
+        assert_eq!(s.get_photo(&cord.id).unwrap().item_id.as_deref(), Some(safety.id.as_str()), "drafted from it");
+        let rows: Vec<_> =
+            s.list_llm_usage_for_session(&sid).unwrap().into_iter().filter(|r| r.purpose == "photo_vision").collect();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].input_tokens, 1800);
+    }
+
+    #[tokio::test]
+    async fn a_photo_the_user_attached_keeps_its_item() {
+        let (store, sid, items) = processed_session(&[("todo", "paint fence"), ("todo", "fix gate")]);
+        let p = store.add_photo(&sid, Some(&items[0].id), "fence.jpg", None).unwrap();
+        let provider = Arc::new(MockProvider::new(vec![describe_call(serde_json::json!({
+            "photos": [{"photo_id": p.id, "caption": "a gate", "item_id": items[1].id, "match_confidence": "high"}],
+        }))]));
+        let store = Arc::new(Mutex::new(store));
+        let out = PhotoVision::new(provider, store.clone()).describe(&sid, &[jpeg(&p.id)]).await.unwrap();
+        assert_eq!((out.captioned, out.attached), (1, 0));
+        assert_eq!(store.lock().unwrap().get_photo(&p.id).unwrap().item_id.as_deref(), Some(items[0].id.as_str()));
+    }
+
+    #[tokio::test]
+    async fn rejects_foreign_photos_bad_media_and_unprocessed_sessions_before_calling() {
+        let (store, sid, _) = processed_session(&[]);
+        let other = store.start_session(None).unwrap();
+        let foreign = store.add_photo(&other.id, None, "x.jpg", None).unwrap();
+        let own = store.add_photo(&sid, None, "y.jpg", None).unwrap();
+        let provider = Arc::new(MockProvider::new(vec![]));
+        let store = Arc::new(Mutex::new(store));
+        let vision = PhotoVision::new(provider.clone(), store);
+
+        let err = vision.describe(&sid, &[jpeg(&foreign.id)]).await.unwrap_err();
+        assert!(matches!(err, CoreError::InvalidState(m) if m.contains("not a live photo")));
+        let heic = PhotoBytes { media_type: "image/heic".into(), ..jpeg(&own.id) };
+        assert!(matches!(vision.describe(&sid, &[heic]).await, Err(CoreError::InvalidState(_))));
+        let err = vision.describe(&other.id, &[jpeg(&foreign.id)]).await.unwrap_err();
+        assert!(matches!(err, CoreError::InvalidState(m) if m.contains("recording")));
+        let out = vision.describe(&sid, &[]).await.unwrap();
+        assert_eq!(out.captioned, 0);
+        assert!(provider.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn a_failed_call_writes_nothing_but_its_usage() {
+        let (store, sid, _) = processed_session(&[]);
+        let p = store.add_photo(&sid, None, "y.jpg", None).unwrap();
+        let prose = CompletionResponse {
+            content: vec![ContentBlock::Text { text: "nice photo".into() }],
+            stop_reason: StopReason::EndTurn,
+            usage: Usage { input_tokens: 1700, output_tokens: 5, ..Default::default() },
+            model: None,
+        };
+        let provider = Arc::new(MockProvider::new(vec![prose.clone(), prose]));
+        let store = Arc::new(Mutex::new(store));
+        let err = PhotoVision::new(provider, store.clone()).describe(&sid, &[jpeg(&p.id)]).await.unwrap_err();
+        assert!(matches!(err, CoreError::Agent(HarnessError::Provider(_))));
+        let s = store.lock().unwrap();
+        assert_eq!(s.get_photo(&p.id).unwrap().caption, None);
+        let rows: Vec<_> =
+            s.list_llm_usage_for_session(&sid).unwrap().into_iter().filter(|r| r.purpose == "photo_vision").collect();
+        assert_eq!(rows[0].input_tokens, 3400, "the call and its repair");
+    }
+
+    #[tokio::test]
+    async fn a_failing_write_neither_masks_the_model_error_nor_leaves_half_a_pass() {
+        let (store, sid, items) = processed_session(&[("todo", "replace deck boards")]);
+        let p = store.add_photo(&sid, None, "deck.jpg", None).unwrap();
+        // Every usage row now fails to insert.
+        store.conn.execute_batch("DROP TABLE llm_usage").unwrap();
+        let prose = CompletionResponse {
+            content: vec![ContentBlock::Text { text: "nice photo".into() }],
+            stop_reason: StopReason::EndTurn,
+            usage: Usage { input_tokens: 1700, output_tokens: 5, ..Default::default() },
+            model: None,
+        };
+        let described = describe_call(serde_json::json!({
+            "photos": [
+                {"photo_id": p.id, "caption": "rotted deck boards", "item_id": items[0].id, "match_confidence": "high"},
+            ],
+            "new_items": [{"kind": "safety", "text": "loose railing"}],
+        }));
+        let provider = Arc::new(MockProvider::new(vec![prose.clone(), prose, described]));
+        let store = Arc::new(Mutex::new(store));
+        let vision = PhotoVision::new(provider, store.clone());
+
+        let err = vision.describe(&sid, &[jpeg(&p.id)]).await.unwrap_err();
+        assert!(matches!(err, CoreError::Agent(HarnessError::Provider(_))), "got {err:?}");
+
+        assert!(vision.describe(&sid, &[jpeg(&p.id)]).await.is_err());
+        let s = store.lock().unwrap();
+        let photo = s.get_photo(&p.id).unwrap();
+        assert_eq!((photo.caption, photo.item_id), (None, None));
+        assert_eq!(s.list_items_for_session(&sid).unwrap().len(), 1, "no drafted item without its cost row");
+    }
+}
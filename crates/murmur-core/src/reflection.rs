@@ -7,6 +7,7 @@
 
 use harness::ReflectionSignals;
 
+use crate::domain::MemoryScope;
 use crate::error::CoreError;
 use crate::store::Store;
 
@@ -91,11 +92,39 @@ impl Store {
     /// Reflection success exit (the coordinator calls this): records the
     /// reflection signals AND logs the LLM cost in one transaction — a crash
     /// between the two can't leave a recorded reflection with unlogged spend
-    /// (or vice versa). Mirrors `finish_session_processed`.
-    pub fn finish_reflection(&self, churn: f32, usage: &harness::Usage) -> Result<(), CoreError> {
+    /// (or vice versa). Mirrors `finish_session_processed`. With `memory`,
+    /// the rewritten memory commits in the same transaction (memory kept in
+    /// this store — `SqliteMemoryStore`).
+    pub fn finish_reflection(
+        &self,
+        churn: f32,
+        usage: &harness::Usage,
+        model: &harness::ServedBy,
+        memory: Option<&harness::Memory>,
+    ) -> Result<(), CoreError> {
         let tx = self.conn.unchecked_transaction()?;
+        if let Some(memory) = memory {
+            self.write_memory(&MemoryScope::Global, memory, &harness::MemoryChange::Reflection { churn })?;
+        }
         self.record_reflection(churn)?;
-        self.record_llm_usage(None, "reflection", usage)?;
+        self.record_served_usage(None, "reflection", usage, model)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Commits one scope's reflected memory with its cost. The cadence
+    /// signals follow the global reflection only (`finish_reflection`).
+    pub fn finish_scoped_reflection(
+        &self,
+        scope: &MemoryScope,
+        memory: &harness::Memory,
+        churn: f32,
+        usage: &harness::Usage,
+        model: &harness::ServedBy,
+    ) -> Result<(), CoreError> {
+        let tx = self.conn.unchecked_transaction()?;
+        self.write_memory(scope, memory, &harness::MemoryChange::Reflection { churn })?;
+        self.record_served_usage(None, "reflection", usage, model)?;
         tx.commit()?;
         Ok(())
     }
@@ -106,21 +135,46 @@ impl Store {
     /// excerpt; sessions still recording are excluded. Linked job names are
     /// prefixed so reflection can learn project vocabulary.
     pub fn activity_for_reflection(&self, max_sessions: usize) -> Result<Vec<String>, CoreError> {
+        self.scoped_activity_for_reflection(&MemoryScope::Global, max_sessions)
+    }
+
+    /// `activity_for_reflection` narrowed to the sessions a scoped memory is
+    /// about: those linked to the job, or to any of the client's jobs. The
+    /// global scope's activity is every session.
+    pub fn scoped_activity_for_reflection(
+        &self,
+        scope: &MemoryScope,
+        max_sessions: usize,
+    ) -> Result<Vec<String>, CoreError> {
         let since = self.last_reflected_at()? as i64;
         let mut stmt = self.conn.prepare(
-            "SELECT s.summary, s.transcript, j.name
+            "SELECT s.summary, s.transcript, j.name, j.id, j.client
              FROM sessions s LEFT JOIN jobs j ON j.id = s.job_id
              -- >= not >: last_reflected_at is stamped AFTER the reflection runs, so a
              -- session ending between the activity query and the stamp would land exactly
              -- ON the boundary and be orphaned forever by >; >= can at worst re-include
              -- a boundary session once (harmless), never lose one.
              WHERE s.deleted_at IS NULL AND s.ended_at IS NOT NULL AND s.ended_at >= ?1
-             ORDER BY s.ended_at DESC, s.id DESC
-             LIMIT ?2",
+             ORDER BY s.ended_at DESC, s.id DESC",
         )?;
-        let mut rows = stmt.query(rusqlite::params![since, max_sessions as i64])?;
+        let mut rows = stmt.query([since])?;
         let mut entries: Vec<String> = Vec::new();
+        let mut taken = 0;
         while let Some(row) = rows.next()? {
+            if taken == max_sessions {
+                break;
+            }
+            let job_id: Option<String> = row.get(3)?;
+            let client: Option<String> = row.get(4)?;
+            let in_scope = match scope {
+                MemoryScope::Global => true,
+                MemoryScope::Job(id) => job_id.as_deref() == Some(id.as_str()),
+                MemoryScope::Client(_) => client.as_deref().map(MemoryScope::client).as_ref() == Some(scope),
+            };
+            if !in_scope {
+                continue;
+            }
+            taken += 1;
             let summary: Option<String> = row.get(0)?;
             let transcript: String = row.get(1)?;
             let job_name: Option<String> = row.get(2)?;
@@ -188,7 +242,12 @@ mod tests {
     fn finish_reflection_records_signals_and_logs_cost() {
         let s = store();
         s.record_session_completed().unwrap();
-        s.finish_reflection(0.3, &harness::Usage { input_tokens: 200, output_tokens: 40 })
+        s.finish_reflection(
+            0.3,
+            &harness::Usage { input_tokens: 200, output_tokens: 40, ..Default::default() },
+            &harness::ServedBy::default(),
+            None,
+        )
             .unwrap();
         let signals = s.reflection_signals().unwrap();
         assert_eq!(signals.completed_reflections, 1);
@@ -203,6 +262,16 @@ mod tests {
         assert_eq!(purpose, "reflection");
     }
 
+    #[test]
+    fn finish_reflection_commits_memory_with_the_signals() {
+        let s = store();
+        let mut memory = harness::Memory::default();
+        memory.remember("people", "Dev — framer", 900);
+        s.finish_reflection(0.5, &harness::Usage::default(), &harness::ServedBy::default(), Some(&memory)).unwrap();
+        assert_eq!(s.load_memory().unwrap(), memory);
+        assert_eq!(s.reflection_signals().unwrap().completed_reflections, 1);
+    }
+
     #[test]
     fn activity_uses_summary_else_transcript_excerpt() {
         let s = store();
@@ -0,0 +1,185 @@
+//! SQLite backend for `harness::CachingProvider`. Cached answers live in
+//! their own database file, not in `Store`'s: they are disposable (deleting
+//! the file only costs misses), device-local, never synced, and a lookup
+//! never waits on the store's lock.
+
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use harness::{CacheBounds, CachedResponse, Clock, HarnessError, ResponseCache};
+use rusqlite::{Connection, OptionalExtension};
+
+use crate::error::CoreError;
+
+const SCHEMA: &str = r#"
+CREATE TABLE IF NOT EXISTS llm_cache (
+    key        TEXT PRIMARY KEY,
+    response   TEXT NOT NULL,
+    bytes      INTEGER NOT NULL,
+    stored_at  INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS idx_llm_cache_stored_at ON llm_cache(stored_at);
+"#;
+
+// epoch-seconds
+fn system_clock() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+pub struct SqliteResponseCache {
+    conn: Mutex<Connection>,
+    bounds: CacheBounds,
+    clock: Clock,
+}
+
+impl SqliteResponseCache {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, CoreError> {
+        Self::from_connection(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> Result<Self, CoreError> {
+        Self::from_connection(Connection::open_in_memory()?)
+    }
+
+    fn from_connection(conn: Connection) -> Result<Self, CoreError> {
+        conn.execute_batch(SCHEMA)?;
+        Ok(SqliteResponseCache {
+            conn: Mutex::new(conn),
+            bounds: CacheBounds::default(),
+            clock: Arc::new(system_clock),
+        })
+    }
+
+    pub fn with_bounds(mut self, bounds: CacheBounds) -> Self {
+        self.bounds = bounds;
+        self
+    }
+
+    /// Replaces the clock (tests inject deterministic time).
+    pub fn with_clock(mut self, clock: Clock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Entries stored at or before this are expired (`None` = nothing is).
+    fn expired_at(&self) -> Option<i64> {
+        let ttl = self.bounds.ttl?.as_secs();
+        Some((self.clock)().saturating_sub(ttl) as i64)
+    }
+
+    fn locked(&self) -> Result<std::sync::MutexGuard<'_, Connection>, HarnessError> {
+        self.conn.lock().map_err(|_| HarnessError::Storage("response cache lock poisoned".into()))
+    }
+}
+
+fn storage(e: impl std::fmt::Display) -> HarnessError {
+    HarnessError::Storage(format!("response cache: {e}"))
+}
+
+impl ResponseCache for SqliteResponseCache {
+    fn get(&self, key: &str) -> Result<Option<CachedResponse>, HarnessError> {
+        let raw: Option<String> = self
+            .locked()?
+            .query_row(
+                "SELECT response FROM llm_cache WHERE key = ?1 AND stored_at > ?2",
+                rusqlite::params![key, self.expired_at().unwrap_or(i64::MIN)],
+                |r| r.get(0),
+            )
+            .optional()
+            .map_err(storage)?;
+        raw.map(|raw| serde_json::from_str(&raw).map_err(storage)).transpose()
+    }
+
+    /// Stores `response`, then drops expired entries and, over `max_bytes`,
+    /// the oldest ones until the rest fit.
+    fn put(&self, key: &str, response: &CachedResponse) -> Result<(), HarnessError> {
+        let raw = serde_json::to_string(response).map_err(storage)?;
+        let conn = self.locked()?;
+        conn.execute(
+            "INSERT OR REPLACE INTO llm_cache (key, response, bytes, stored_at) VALUES (?1, ?2, ?3, ?4)",
+            rusqlite::params![key, raw, raw.len() as i64, (self.clock)() as i64],
+        )
+        .map_err(storage)?;
+        if let Some(expired_at) = self.expired_at() {
+            conn.execute("DELETE FROM llm_cache WHERE stored_at <= ?1", [expired_at]).map_err(storage)?;
+        }
+        if let Some(max_bytes) = self.bounds.max_bytes {
+            conn.execute(
+                "DELETE FROM llm_cache WHERE key IN (
+                     SELECT key FROM (
+                         SELECT key, SUM(bytes) OVER (ORDER BY stored_at DESC, rowid DESC) AS kept
+                         FROM llm_cache
+                     ) WHERE kept > ?1
+                 )",
+                [max_bytes as i64],
+            )
+            .map_err(storage)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::time::Duration;
+
+    use harness::{ContentBlock, StopReason};
+
+    use super::*;
+
+    fn answer(text: &str) -> CachedResponse {
+        CachedResponse {
+            content: vec![ContentBlock::Text { text: text.into() }],
+            stop_reason: StopReason::EndTurn,
+            model: Some("model-a".into()),
+        }
+    }
+
+    fn cache_at(now: &Arc<AtomicU64>, bounds: CacheBounds) -> SqliteResponseCache {
+        let now = now.clone();
+        SqliteResponseCache::open_in_memory()
+            .unwrap()
+            .with_bounds(bounds)
+            .with_clock(Arc::new(move || now.load(Ordering::SeqCst)))
+    }
+
+    #[test]
+    fn entries_round_trip_and_expire_after_the_ttl() {
+        let now = Arc::new(AtomicU64::new(1000));
+        let cache = cache_at(&now, CacheBounds { ttl: Some(Duration::from_secs(60)), max_bytes: None });
+        assert_eq!(cache.get("k").unwrap(), None);
+        cache.put("k", &answer("hi")).unwrap();
+        now.store(1059, Ordering::SeqCst);
+        assert_eq!(cache.get("k").unwrap(), Some(answer("hi")));
+        now.store(1060, Ordering::SeqCst);
+        assert_eq!(cache.get("k").unwrap(), None);
+    }
+
+    #[test]
+    fn the_oldest_entries_are_evicted_past_the_size_bound() {
+        let now = Arc::new(AtomicU64::new(1000));
+        let entry_bytes = serde_json::to_string(&answer("a")).unwrap().len() as u64;
+        let cache = cache_at(&now, CacheBounds { ttl: None, max_bytes: Some(2 * entry_bytes) });
+        for (i, key) in ["a", "b", "c"].into_iter().enumerate() {
+            now.store(1000 + i as u64, Ordering::SeqCst);
+            cache.put(key, &answer(key)).unwrap();
+        }
+        assert_eq!(cache.get("a").unwrap(), None);
+        assert_eq!(cache.get("b").unwrap(), Some(answer("b")));
+        assert_eq!(cache.get("c").unwrap(), Some(answer("c")));
+    }
+
+    #[test]
+    fn entries_survive_reopening_the_file() {
+        let dir = std::env::temp_dir().join(format!("murmur-cache-test-{}", crate::ids::new_id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("cache.db");
+        SqliteResponseCache::open(&path).unwrap().put("k", &answer("kept")).unwrap();
+        assert_eq!(SqliteResponseCache::open(&path).unwrap().get("k").unwrap(), Some(answer("kept")));
+        std::fs::remove_dir_all(dir).ok();
+    }
+}
@@ -0,0 +1,133 @@
+use rusqlite::Row;
+
+use crate::domain::BatchJob;
+use crate::error::CoreError;
+use crate::store::Store;
+
+const BATCH_JOB_COLS: &str = "id, batch_id, session_id, kind, state, created_at, device_id";
+
+fn batch_job_from_row(row: &Row) -> Result<BatchJob, CoreError> {
+    Ok(BatchJob {
+        id: row.get("id").map_err(CoreError::Sqlite)?,
+        batch_id: row.get("batch_id").map_err(CoreError::Sqlite)?,
+        session_id: row.get("session_id").map_err(CoreError::Sqlite)?,
+        kind: row.get("kind").map_err(CoreError::Sqlite)?,
+        state: row.get("state").map_err(CoreError::Sqlite)?,
+        created_at: row.get::<_, i64>("created_at").map_err(CoreError::Sqlite)? as u64,
+        device_id: row.get("device_id").map_err(CoreError::Sqlite)?,
+    })
+}
+
+impl Store {
+    /// Records a job submitted as part of `batch_id`. `id` is chosen by the
+    /// caller before submission — the batch request's `custom_id` is built
+    /// from it, so a result can be matched back to its job.
+    pub fn add_batch_job(
+        &self,
+        id: &str,
+        batch_id: &str,
+        session_id: &str,
+        kind: &str,
+        state: &str,
+    ) -> Result<BatchJob, CoreError> {
+        let job = BatchJob {
+            id: id.to_string(),
+            batch_id: batch_id.to_string(),
+            session_id: session_id.to_string(),
+            kind: kind.to_string(),
+            state: state.to_string(),
+            created_at: self.now(),
+            device_id: self.device_id.clone(),
+        };
+        self.conn.execute(
+            "INSERT INTO batch_jobs (id, batch_id, session_id, kind, state, created_at, device_id)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            rusqlite::params![
+                job.id,
+                job.batch_id,
+                job.session_id,
+                job.kind,
+                job.state,
+                job.created_at as i64,
+                job.device_id,
+            ],
+        )?;
+        Ok(job)
+    }
+
+    /// Outstanding jobs of one kind, oldest first.
+    pub fn list_batch_jobs(&self, kind: &str) -> Result<Vec<BatchJob>, CoreError> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!("SELECT {BATCH_JOB_COLS} FROM batch_jobs WHERE kind = ?1 ORDER BY id ASC"))?;
+        let mut rows = stmt.query([kind])?;
+        let mut jobs = Vec::new();
+        while let Some(row) = rows.next()? {
+            jobs.push(batch_job_from_row(row)?);
+        }
+        Ok(jobs)
+    }
+
+    pub fn has_batch_job(&self, session_id: &str, kind: &str) -> Result<bool, CoreError> {
+        self.conn
+            .query_row(
+                "SELECT EXISTS(SELECT 1 FROM batch_jobs WHERE session_id = ?1 AND kind = ?2)",
+                [session_id, kind],
+                |r| r.get(0),
+            )
+            .map_err(CoreError::Sqlite)
+    }
+
+    /// Claims a job's result: deletes the row, and says whether it was still
+    /// there. Only the caller that gets `true` applies the result, so a
+    /// result is applied at most once. Claim in the transaction that applies
+    /// the result: a crash mid-apply then leaves the job for the next resume.
+    pub fn take_batch_job(&self, id: &str) -> Result<bool, CoreError> {
+        Ok(self.conn.execute("DELETE FROM batch_jobs WHERE id = ?1", [id])? > 0)
+    }
+
+    /// Abandons a session's outstanding jobs of `kind` — its work is being
+    /// redone synchronously, and a late batch result must not land over it.
+    /// Returns the dropped jobs, whose `state` may hold spend still to log.
+    pub fn drop_batch_jobs_for_session(&self, session_id: &str, kind: &str) -> Result<Vec<BatchJob>, CoreError> {
+        let mut stmt = self.conn.prepare(&format!(
+            "DELETE FROM batch_jobs WHERE session_id = ?1 AND kind = ?2 RETURNING {BATCH_JOB_COLS}"
+        ))?;
+        let mut rows = stmt.query([session_id, kind])?;
+        let mut jobs = Vec::new();
+        while let Some(row) = rows.next()? {
+            jobs.push(batch_job_from_row(row)?);
+        }
+        Ok(jobs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::store::Store;
+
+    #[test]
+    fn jobs_are_listed_by_kind_and_claimed_once() {
+        let s = Store::open_in_memory("device-a").unwrap().with_clock(std::sync::Arc::new(|| 1000));
+        let a = s.start_session(None).unwrap().id;
+        let b = s.start_session(None).unwrap().id;
+        s.add_batch_job("job-1", "msgbatch_1", &a, "notes", "{}").unwrap();
+        s.add_batch_job("job-2", "msgbatch_1", &b, "notes", "{}").unwrap();
+        let doc = s.add_batch_job("job-3", "msgbatch_2", &a, "document", r#"{"doc_kind":"estimate"}"#).unwrap();
+        assert_eq!(doc.created_at, 1000);
+
+        let notes: Vec<String> = s.list_batch_jobs("notes").unwrap().into_iter().map(|j| j.id).collect();
+        assert_eq!(notes, vec!["job-1", "job-2"]);
+        assert_eq!(s.list_batch_jobs("document").unwrap(), vec![doc]);
+        assert!(s.has_batch_job(&a, "notes").unwrap());
+
+        assert!(s.take_batch_job("job-1").unwrap());
+        assert!(!s.take_batch_job("job-1").unwrap(), "a result is claimed once");
+        assert!(!s.has_batch_job(&a, "notes").unwrap());
+        assert!(s.has_batch_job(&a, "document").unwrap(), "other kinds untouched");
+
+        let dropped = s.drop_batch_jobs_for_session(&b, "notes").unwrap();
+        assert_eq!(dropped.iter().map(|j| j.id.as_str()).collect::<Vec<_>>(), ["job-2"]);
+        assert!(s.list_batch_jobs("notes").unwrap().is_empty());
+    }
+}
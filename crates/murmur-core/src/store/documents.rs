@@ -57,7 +57,7 @@ impl Store {
         existing_number: Option<u64>,
         mut payload: serde_json::Value,
     ) -> Result<Artifact, CoreError> {
-        let tx = self.conn.unchecked_transaction()?;
+        let tx = self.transaction()?;
         let number = match existing_number {
             Some(n) => n,
             None => self.bump_document_sequence(doc_kind)?,
@@ -68,7 +68,9 @@ impl Store {
         // transaction; an error drops `tx` and rolls the sequence bump back.
         let artifact =
             self.add_artifact(session_id, "document", &format!("{doc_kind} #{number}"), &body)?;
-        tx.commit()?;
+        if let Some(tx) = tx {
+            tx.commit()?;
+        }
         Ok(artifact)
     }
 }
@@ -0,0 +1,591 @@
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use harness::{
+    FactSource, FileMemoryStore, HarnessError, Memory, MemoryChange, MemoryDiff, MemoryEntry, MemoryStore,
+};
+use rusqlite::Row;
+
+use crate::domain::{MemoryRevision, MemoryScope};
+use crate::error::CoreError;
+use crate::ids::new_id;
+use crate::store::Store;
+
+fn source_name(source: FactSource) -> &'static str {
+    match source {
+        FactSource::Inferred => "inferred",
+        FactSource::Stated => "stated",
+        FactSource::Corrected => "corrected",
+    }
+}
+
+fn parse_source(raw: &str) -> Result<FactSource, CoreError> {
+    match raw {
+        "inferred" => Ok(FactSource::Inferred),
+        "stated" => Ok(FactSource::Stated),
+        "corrected" => Ok(FactSource::Corrected),
+        other => Err(CoreError::Corrupt(format!("unknown memory source: {other}"))),
+    }
+}
+
+const REVISION_COLS: &str = "id, scope, change, memory, created_at, device_id";
+
+/// The entry's typed fact as stored in `memory_entries.fact`.
+fn fact_json(entry: &MemoryEntry) -> Result<Option<String>, CoreError> {
+    Ok(entry.fact.as_ref().map(serde_json::to_string).transpose()?)
+}
+
+fn revision_from_row(row: &Row) -> Result<MemoryRevision, CoreError> {
+    let change: String = row.get("change")?;
+    let memory: String = row.get("memory")?;
+    let scope: String = row.get("scope")?;
+    Ok(MemoryRevision {
+        id: row.get("id")?,
+        scope: MemoryScope::parse(&scope)?,
+        change: serde_json::from_str(&change)?,
+        memory: serde_json::from_str(&memory)?,
+        created_at: row.get::<_, i64>("created_at")? as u64,
+        device_id: row.get("device_id")?,
+    })
+}
+
+/// A live `memory_entries` row, as `write_memory` diffs against it.
+struct EntryRow {
+    id: String,
+    section: String,
+    position: i64,
+    entry: MemoryEntry,
+}
+
+impl Store {
+    /// The user's live memory (the global scope).
+    pub fn load_memory(&self) -> Result<Memory, CoreError> {
+        self.load_scoped_memory(&MemoryScope::Global)
+    }
+
+    /// One scope's live memory: every untombstoned entry, each section in
+    /// insertion order. No rows is an empty memory.
+    pub fn load_scoped_memory(&self, scope: &MemoryScope) -> Result<Memory, CoreError> {
+        let mut memory = Memory::default();
+        for row in self.live_memory_rows(scope)? {
+            memory.sections.entry(row.section).or_default().push(row.entry);
+        }
+        Ok(memory)
+    }
+
+    /// `save_scoped_memory` for the user's own memory.
+    pub fn save_memory(&self, memory: &Memory, change: &MemoryChange) -> Result<(), CoreError> {
+        self.save_scoped_memory(&MemoryScope::Global, memory, change)
+    }
+
+    /// Makes the stored memory of `scope` equal `memory`, in one
+    /// transaction, logging a revision for `change` if anything changed.
+    pub fn save_scoped_memory(
+        &self,
+        scope: &MemoryScope,
+        memory: &Memory,
+        change: &MemoryChange,
+    ) -> Result<(), CoreError> {
+        let tx = self.conn.unchecked_transaction()?;
+        self.write_memory(scope, memory, change)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Scopes that hold any live memory besides the global one.
+    pub fn memory_scopes(&self) -> Result<Vec<MemoryScope>, CoreError> {
+        let mut stmt = self.conn.prepare(
+            "SELECT DISTINCT scope FROM memory_entries WHERE deleted_at IS NULL AND scope != '' ORDER BY scope",
+        )?;
+        let mut rows = stmt.query([])?;
+        let mut scopes = Vec::new();
+        while let Some(row) = rows.next()? {
+            let key: String = row.get(0)?;
+            scopes.push(MemoryScope::parse(&key)?);
+        }
+        Ok(scopes)
+    }
+
+    /// `save_memory` without its own transaction, for callers that commit
+    /// memory together with something else (`finish_reflection`). Entries
+    /// are matched to rows by section and text: a match keeps its id and is
+    /// updated only if it changed, an unmatched entry gets a new row, and
+    /// an unmatched row is tombstoned. Returns whether anything changed.
+    ///
+    /// The first change to a memory that predates the log (v15) first logs
+    /// it as it stood, as a `Baseline` to restore to.
+    pub(crate) fn write_memory(
+        &self,
+        scope: &MemoryScope,
+        memory: &Memory,
+        change: &MemoryChange,
+    ) -> Result<bool, CoreError> {
+        let now = self.now() as i64;
+        let key = scope.key();
+        let rows = self.live_memory_rows(scope)?;
+        let logged: bool = self.conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM memory_revisions WHERE scope = ?1)",
+            [&key],
+            |r| r.get(0),
+        )?;
+        if !logged && !rows.is_empty() {
+            let mut before = Memory::default();
+            for row in &rows {
+                before.sections.entry(row.section.clone()).or_default().push(row.entry.clone());
+            }
+            self.append_memory_revision(scope, &before, &MemoryChange::Baseline)?;
+        }
+        let mut changed = false;
+        let mut live: Vec<Option<EntryRow>> = rows.into_iter().map(Some).collect();
+        for (section, entries) in &memory.sections {
+            for (position, entry) in entries.iter().enumerate() {
+                let position = position as i64;
+                let matched = live
+                    .iter_mut()
+                    .find(|row| matches!(row, Some(r) if &r.section == section && r.entry.text == entry.text))
+                    .and_then(Option::take);
+                match matched {
+                    Some(row) if row.position == position && &row.entry == entry => {}
+                    Some(row) => {
+                        changed = true;
+                        self.conn.execute(
+                            "UPDATE memory_entries
+                             SET position = ?1, last_touched = ?2, source = ?3, session_id = ?4, fact = ?5,
+                                 updated_at = ?6, device_id = ?7
+                             WHERE id = ?8",
+                            rusqlite::params![
+                                position,
+                                entry.last_touched as i64,
+                                source_name(entry.source),
+                                entry.session,
+                                fact_json(entry)?,
+                                now,
+                                self.device_id,
+                                row.id,
+                            ],
+                        )?;
+                    }
+                    None => {
+                        changed = true;
+                        self.conn.execute(
+                            "INSERT INTO memory_entries
+                             (id, scope, section, position, text, last_touched, source, session_id, fact,
+                              created_at, updated_at, device_id)
+                             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?10, ?11)",
+                            rusqlite::params![
+                                new_id(),
+                                key,
+                                section,
+                                position,
+                                entry.text,
+                                entry.last_touched as i64,
+                                source_name(entry.source),
+                                entry.session,
+                                fact_json(entry)?,
+                                now,
+                                self.device_id,
+                            ],
+                        )?;
+                    }
+                }
+            }
+        }
+        for row in live.into_iter().flatten() {
+            changed = true;
+            self.conn.execute(
+                "UPDATE memory_entries SET deleted_at = ?1, updated_at = ?1 WHERE id = ?2 AND deleted_at IS NULL",
+                rusqlite::params![now, row.id],
+            )?;
+        }
+        if changed {
+            self.append_memory_revision(scope, memory, change)?;
+        }
+        Ok(changed)
+    }
+
+    fn append_memory_revision(
+        &self,
+        scope: &MemoryScope,
+        memory: &Memory,
+        change: &MemoryChange,
+    ) -> Result<(), CoreError> {
+        self.conn.execute(
+            "INSERT INTO memory_revisions (id, scope, change, memory, created_at, device_id)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            rusqlite::params![
+                new_id(),
+                scope.key(),
+                serde_json::to_string(change)?,
+                serde_json::to_string(memory)?,
+                self.now() as i64,
+                self.device_id,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// The revision log of every scope, newest first, at most `limit`
+    /// revisions.
+    pub fn memory_revisions(&self, limit: usize) -> Result<Vec<MemoryRevision>, CoreError> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!("SELECT {REVISION_COLS} FROM memory_revisions ORDER BY rowid DESC LIMIT ?1"))?;
+        let mut rows = stmt.query([limit as i64])?;
+        let mut revisions = Vec::new();
+        while let Some(row) = rows.next()? {
+            revisions.push(revision_from_row(row)?);
+        }
+        Ok(revisions)
+    }
+
+    pub fn memory_revision(&self, id: &str) -> Result<MemoryRevision, CoreError> {
+        self.conn
+            .query_row(&format!("SELECT {REVISION_COLS} FROM memory_revisions WHERE id = ?1"), [id], |row| {
+                Ok(revision_from_row(row))
+            })
+            .map_err(|e| match e {
+                rusqlite::Error::QueryReturnedNoRows => {
+                    CoreError::NotFound { entity: "memory revision", id: id.to_string() }
+                }
+                other => CoreError::Sqlite(other),
+            })?
+    }
+
+    /// What changed from revision `from` to revision `to` — either order,
+    /// and across scopes if asked.
+    pub fn diff_memory_revisions(&self, from: &str, to: &str) -> Result<MemoryDiff, CoreError> {
+        Ok(self.memory_revision(from)?.memory.diff(&self.memory_revision(to)?.memory))
+    }
+
+    /// Makes revision `id`'s memory the live one of its scope again, logged
+    /// as a new `Restore` revision (the log is never rewound). Returns that
+    /// memory.
+    pub fn restore_memory_revision(&self, id: &str) -> Result<Memory, CoreError> {
+        let revision = self.memory_revision(id)?;
+        let change = MemoryChange::Restore { revision: id.to_string() };
+        self.save_scoped_memory(&revision.scope, &revision.memory, &change)?;
+        Ok(revision.memory)
+    }
+
+    /// One-time move of a `FileMemoryStore` JSON file into the store, for
+    /// installs that kept memory on disk before v14. Only runs while the
+    /// table has never had a row; the file is then renamed to
+    /// `<path>.imported` (kept as a rollback point, never read again).
+    /// Returns whether anything was imported.
+    ///
+    /// A file that can't be read or parsed falls back to its newest readable
+    /// rotated snapshot (`FileMemoryStore::snapshots`), as loading it used to
+    /// fall back to empty memory; either way it is moved aside to
+    /// `<path>.corrupt` so later opens don't retry it. Only store failures
+    /// are errors.
+    pub fn import_memory_file(&self, path: impl AsRef<Path>) -> Result<bool, CoreError> {
+        let path = path.as_ref();
+        if !path.exists() {
+            return Ok(false);
+        }
+        let has_rows: bool =
+            self.conn.query_row("SELECT EXISTS(SELECT 1 FROM memory_entries)", [], |r| r.get(0))?;
+        if has_rows {
+            return Ok(false);
+        }
+        let file = FileMemoryStore::new(path);
+        let memory = match file.load() {
+            Ok(memory) => memory,
+            // Unreadable: fall back to its newest snapshot, if any.
+            Err(_) => {
+                let snapshot = file.snapshots().into_iter().next();
+                // Best effort: moved aside, the file is never retried; left in
+                // place, the next open lands here again — same outcome.
+                let _ = std::fs::rename(path, format!("{}.corrupt", path.display()));
+                match snapshot {
+                    Some(memory) => memory,
+                    None => return Ok(false),
+                }
+            }
+        };
+        self.save_memory(&memory, &MemoryChange::Import)?;
+        // Best effort: with rows in place the file is never imported again
+        // whether or not the rename lands.
+        if path.exists() {
+            let _ = std::fs::rename(path, format!("{}.imported", path.display()));
+        }
+        Ok(true)
+    }
+
+    fn live_memory_rows(&self, scope: &MemoryScope) -> Result<Vec<EntryRow>, CoreError> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, section, position, text, last_touched, source, session_id, fact
+             FROM memory_entries WHERE deleted_at IS NULL AND scope = ?1
+             ORDER BY section ASC, position ASC, id ASC",
+        )?;
+        let mut rows = stmt.query([scope.key()])?;
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            let source: String = row.get("source")?;
+            let fact: Option<String> = row.get("fact")?;
+            out.push(EntryRow {
+                id: row.get("id")?,
+                section: row.get("section")?,
+                position: row.get("position")?,
+                entry: MemoryEntry {
+                    text: row.get("text")?,
+                    last_touched: row.get::<_, i64>("last_touched")? as u64,
+                    source: parse_source(&source)?,
+                    session: row.get("session_id")?,
+                    fact: fact.map(|json| serde_json::from_str(&json)).transpose()?,
+                },
+            });
+        }
+        Ok(out)
+    }
+}
+
+/// `MemoryStore` over the shared `Store`: memory rows sync, carry
+/// tombstones like every other table, and can commit with a reflection
+/// (`ReflectionCoordinator::in_store`). Reads and writes the global scope
+/// unless `with_scope` says otherwise.
+pub struct SqliteMemoryStore {
+    store: Arc<Mutex<Store>>,
+    scope: MemoryScope,
+}
+
+impl SqliteMemoryStore {
+    pub fn new(store: Arc<Mutex<Store>>) -> Self {
+        SqliteMemoryStore { store, scope: MemoryScope::Global }
+    }
+
+    pub fn with_scope(mut self, scope: MemoryScope) -> Self {
+        self.scope = scope;
+        self
+    }
+
+    fn locked(&self) -> Result<std::sync::MutexGuard<'_, Store>, HarnessError> {
+        self.store.lock().map_err(|_| HarnessError::Storage("store lock poisoned".into()))
+    }
+}
+
+impl MemoryStore for SqliteMemoryStore {
+    fn load(&self) -> Result<Memory, HarnessError> {
+        self.locked()?.load_scoped_memory(&self.scope).map_err(|e| HarnessError::Storage(e.to_string()))
+    }
+
+    fn save(&self, memory: &Memory) -> Result<(), HarnessError> {
+        self.save_change(memory, &MemoryChange::Other)
+    }
+
+    fn save_change(&self, memory: &Memory, change: &MemoryChange) -> Result<(), HarnessError> {
+        self.locked()?
+            .save_scoped_memory(&self.scope, memory, change)
+            .map_err(|e| HarnessError::Storage(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use harness::FactSource;
+
+    use super::*;
+
+    fn store_at(now: u64) -> Store {
+        Store::open_in_memory("device-a").unwrap().with_clock(Arc::new(move || now))
+    }
+
+    fn sample() -> Memory {
+        let mut m = Memory::default();
+        m.remember_from("people", "Dev — framer", 10, FactSource::Stated, Some("s1".into()));
+        m.remember("vocabulary", "french drain", 11);
+        m.remember("vocabulary", "sleeper wall", 12);
+        m
+    }
+
+    fn row_count(s: &Store, live_only: bool) -> i64 {
+        let sql = if live_only {
+            "SELECT COUNT(*) FROM memory_entries WHERE deleted_at IS NULL"
+        } else {
+            "SELECT COUNT(*) FROM memory_entries"
+        };
+        s.conn.query_row(sql, [], |r| r.get(0)).unwrap()
+    }
+
+    #[test]
+    fn memory_round_trips_in_insertion_order() {
+        let s = store_at(1000);
+        assert_eq!(s.load_memory().unwrap(), Memory::default());
+        s.save_memory(&sample(), &MemoryChange::Other).unwrap();
+        assert_eq!(s.load_memory().unwrap(), sample());
+    }
+
+    #[test]
+    fn unchanged_entries_keep_their_rows_and_removed_ones_are_tombstoned() {
+        let s = store_at(1000);
+        s.save_memory(&sample(), &MemoryChange::Other).unwrap();
+        let id_of = |text: &str| -> String {
+            s.conn
+                .query_row("SELECT id FROM memory_entries WHERE text = ?1", [text], |r| r.get(0))
+                .unwrap()
+        };
+        let dev = id_of("Dev — framer");
+
+        let mut next = sample();
+        next.forget("vocabulary", "french drain");
+        next.remember("vocabulary", "ledger board", 20);
+        s.save_memory(&next, &MemoryChange::Other).unwrap();
+
+        assert_eq!(s.load_memory().unwrap(), next);
+        assert_eq!(id_of("Dev — framer"), dev, "an unchanged entry keeps its id");
+        assert_eq!(row_count(&s, true), 3);
+        assert_eq!(row_count(&s, false), 4, "the forgotten entry is a tombstone");
+        let deleted_at: Option<i64> = s
+            .conn
+            .query_row("SELECT deleted_at FROM memory_entries WHERE text = 'french drain'", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(deleted_at, Some(1000));
+    }
+
+    #[test]
+    fn typed_facts_are_stored_with_their_entry() {
+        let s = store_at(1000);
+        let mut m = sample();
+        let price = harness::TypedFact::Price { item: "sod".into(), unit: Some("sq ft".into()), amount_cents: 110 };
+        m.remember_fact("pricing", price.clone(), 13, FactSource::Stated, None);
+        s.save_memory(&m, &MemoryChange::Other).unwrap();
+
+        let loaded = s.load_memory().unwrap();
+        assert_eq!(loaded, m);
+        assert_eq!(loaded.facts().collect::<Vec<_>>(), vec![("pricing", &price)]);
+        let fact: Option<String> = s
+            .conn
+            .query_row("SELECT fact FROM memory_entries WHERE text = 'Dev — framer'", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(fact, None, "plain entries keep a NULL fact");
+    }
+
+    #[test]
+    fn each_change_is_logged_with_its_cause_and_can_be_restored() {
+        let s = store_at(1000);
+        s.save_memory(&sample(), &MemoryChange::SeedPack { pack: "landscaping:1".into() }).unwrap();
+        s.save_memory(&sample(), &MemoryChange::Other).unwrap();
+        let mut bad = sample();
+        bad.forget("people", "Dev — framer");
+        s.save_memory(&bad, &MemoryChange::Reflection { churn: 0.6 }).unwrap();
+
+        let log = s.memory_revisions(10).unwrap();
+        assert_eq!(log.len(), 2, "a save that changed nothing isn't logged");
+        assert_eq!(log[0].change, MemoryChange::Reflection { churn: 0.6 });
+        assert_eq!(log[0].memory, bad);
+        assert_eq!(log[1].change, MemoryChange::SeedPack { pack: "landscaping:1".into() });
+
+        let diff = s.diff_memory_revisions(&log[1].id, &log[0].id).unwrap();
+        assert_eq!(diff.sections.keys().collect::<Vec<_>>(), vec!["people"]);
+        assert_eq!(diff.sections["people"].removed[0].text, "Dev — framer");
+
+        assert_eq!(s.restore_memory_revision(&log[1].id).unwrap(), sample());
+        assert_eq!(s.load_memory().unwrap(), sample());
+        let log = s.memory_revisions(10).unwrap();
+        assert_eq!(log.len(), 3, "a restore appends, never rewinds");
+        assert_eq!(log[0].change, MemoryChange::Restore { revision: log[2].id.clone() });
+        assert!(matches!(s.memory_revision("nope"), Err(CoreError::NotFound { .. })));
+    }
+
+    #[test]
+    fn memory_older_than_the_log_is_kept_as_a_baseline() {
+        let s = store_at(1000);
+        s.save_memory(&sample(), &MemoryChange::Other).unwrap();
+        s.conn.execute("DELETE FROM memory_revisions", []).unwrap();
+
+        s.save_memory(&Memory::default(), &MemoryChange::Vocabulary).unwrap();
+        let log = s.memory_revisions(10).unwrap();
+        assert_eq!(log.len(), 2);
+        assert_eq!(log[1].change, MemoryChange::Baseline);
+        assert_eq!(log[1].memory, sample());
+    }
+
+    #[test]
+    fn a_legacy_json_file_is_imported_once() {
+        let dir = std::env::temp_dir().join(format!("murmur-memory-import-{}", new_id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("murmur.db.memory.json");
+        std::fs::write(&path, serde_json::to_string(&sample()).unwrap()).unwrap();
+
+        let s = store_at(1000);
+        assert!(s.import_memory_file(&path).unwrap());
+        assert_eq!(s.load_memory().unwrap(), sample());
+        assert!(!path.exists());
+        assert!(dir.join("murmur.db.memory.json.imported").exists());
+
+        // A file that reappears is never imported over rows.
+        std::fs::write(&path, "{\"sections\":{}}").unwrap();
+        assert!(!s.import_memory_file(&path).unwrap());
+        assert_eq!(s.load_memory().unwrap(), sample());
+        assert!(!s.import_memory_file(dir.join("missing.json")).unwrap());
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn an_unreadable_legacy_file_falls_back_to_its_newest_snapshot_and_is_moved_aside() {
+        let dir = std::env::temp_dir().join(format!("murmur-memory-corrupt-{}", new_id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("murmur.db.memory.json");
+        std::fs::write(&path, "{not json").unwrap();
+
+        let s = store_at(1000);
+        assert!(!s.import_memory_file(&path).unwrap(), "no snapshot: nothing imported, no error");
+        assert!(!path.exists());
+        assert!(dir.join("murmur.db.memory.json.corrupt").exists());
+
+        std::fs::write(&path, "{not json").unwrap();
+        std::fs::write(path.with_extension("1"), serde_json::to_string(&sample()).unwrap()).unwrap();
+        std::fs::write(path.with_extension("2"), "{\"sections\":{}}").unwrap();
+        assert!(s.import_memory_file(&path).unwrap());
+        assert_eq!(s.load_memory().unwrap(), sample());
+        assert!(!path.exists());
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn scopes_keep_separate_memories_and_logs() {
+        let s = store_at(1000);
+        let job = MemoryScope::Job("j1".into());
+        let client = MemoryScope::client("  Mrs.  Alvarez ");
+        assert_eq!(client, MemoryScope::Client("mrs. alvarez".into()));
+        let mut gate = Memory::default();
+        gate.remember("access", "gate code 4412", 10);
+        s.save_memory(&sample(), &MemoryChange::Other).unwrap();
+        s.save_scoped_memory(&job, &gate, &MemoryChange::Other).unwrap();
+
+        assert_eq!(s.load_memory().unwrap(), sample());
+        assert_eq!(s.load_scoped_memory(&job).unwrap(), gate);
+        assert_eq!(s.load_scoped_memory(&client).unwrap(), Memory::default());
+        assert_eq!(s.memory_scopes().unwrap(), vec![job.clone()]);
+
+        // Clearing the job's memory leaves the user's alone, and each save
+        // is logged under its own scope.
+        s.save_scoped_memory(&job, &Memory::default(), &MemoryChange::Other).unwrap();
+        assert_eq!(s.load_memory().unwrap(), sample());
+        assert!(s.memory_scopes().unwrap().is_empty());
+        let log = s.memory_revisions(10).unwrap();
+        let scopes: Vec<_> = log.iter().map(|r| r.scope.clone()).collect();
+        assert_eq!(scopes, vec![job.clone(), job.clone(), MemoryScope::Global]);
+
+        // Restoring a job revision restores the job's memory.
+        s.restore_memory_revision(&log[1].id).unwrap();
+        assert_eq!(s.load_scoped_memory(&job).unwrap(), gate);
+        assert_eq!(s.load_memory().unwrap(), sample());
+
+        for scope in [MemoryScope::Global, job, client] {
+            assert_eq!(MemoryScope::parse(&scope.key()).unwrap(), scope);
+        }
+        assert!(matches!(MemoryScope::parse("team:x"), Err(CoreError::Corrupt(_))));
+    }
+
+    #[test]
+    fn sqlite_memory_store_persists_through_the_shared_store() {
+        let store = Arc::new(Mutex::new(store_at(1000)));
+        let memory_store = SqliteMemoryStore::new(store.clone());
+        memory_store.save(&sample()).unwrap();
+        assert_eq!(memory_store.load().unwrap(), sample());
+        assert_eq!(store.lock().unwrap().load_memory().unwrap(), sample());
+    }
+}
@@ -173,6 +173,100 @@ pub(crate) const MIGRATIONS: &[&str] = &[
     );
     CREATE INDEX idx_document_schemas_kind ON document_schemas(kind) WHERE deleted_at IS NULL;
     "#,
+    // v8: sessions.failure_kind / failure_status — the provider-error kind
+    // (`ProviderErrorKind::as_str`) and HTTP status of the attempt that left
+    // the session Failed. Both NULL for every other status, and for failures
+    // that weren't the provider's. Pre-v8 Failed rows read back as unknown.
+    r#"
+    ALTER TABLE sessions ADD COLUMN failure_kind TEXT;
+    ALTER TABLE sessions ADD COLUMN failure_status INTEGER;
+    "#,
+    // v9: sessions.provisional — 1 when a Processed session's outputs came
+    // from the on-device fallback model and still owe a cloud reprocess.
+    // Every pre-v9 row was processed by the cloud model (or not at all): 0.
+    r#"
+    ALTER TABLE sessions ADD COLUMN provisional INTEGER NOT NULL DEFAULT 0;
+    "#,
+    // v10: llm_usage cache columns — prompt-cache writes and reads, billed
+    // apart from (and not included in) input_tokens. Pre-v10 calls never
+    // set breakpoints: 0.
+    r#"
+    ALTER TABLE llm_usage ADD COLUMN cache_creation_input_tokens INTEGER NOT NULL DEFAULT 0;
+    ALTER TABLE llm_usage ADD COLUMN cache_read_input_tokens INTEGER NOT NULL DEFAULT 0;
+    "#,
+    // v11: llm_usage.model — the model(s) that served the row's calls, which
+    // a routing fallback can make differ from the configured one. NULL when
+    // unknown, including every pre-v11 row.
+    r#"
+    ALTER TABLE llm_usage ADD COLUMN model TEXT;
+    "#,
+    // v12: photos.caption — what the vision pass saw in the photo. NULL until
+    // a pass has looked at it, including every pre-v12 row.
+    r#"
+    ALTER TABLE photos ADD COLUMN caption TEXT;
+    "#,
+    // v13: batch_jobs — pipeline calls waiting in a provider message batch,
+    // so a result that lands after the app quit is still applied on the
+    // next launch. Device-local bookkeeping (the batch lives in this
+    // device's API account): never synced, hard-deleted once claimed.
+    r#"
+    CREATE TABLE batch_jobs (
+        id          TEXT PRIMARY KEY,
+        batch_id    TEXT NOT NULL,
+        session_id  TEXT NOT NULL REFERENCES sessions(id),
+        kind        TEXT NOT NULL,
+        state       TEXT NOT NULL,
+        created_at  INTEGER NOT NULL,
+        device_id   TEXT NOT NULL
+    );
+    CREATE INDEX idx_batch_jobs_session ON batch_jobs(session_id);
+    "#,
+    // v14: memory_entries — the agent memory as rows, so it syncs and
+    // commits in the same transaction as the reflection that rewrote it.
+    // `position` keeps each section's insertion order. `session_id` is
+    // provenance only (no FK: a fact outlives the session it came from).
+    r#"
+    CREATE TABLE memory_entries (
+        id            TEXT PRIMARY KEY,
+        section       TEXT NOT NULL,
+        position      INTEGER NOT NULL,
+        text          TEXT NOT NULL,
+        last_touched  INTEGER NOT NULL,
+        source        TEXT NOT NULL,
+        session_id    TEXT,
+        created_at    INTEGER NOT NULL,
+        updated_at    INTEGER NOT NULL,
+        device_id     TEXT NOT NULL,
+        deleted_at    INTEGER
+    );
+    CREATE INDEX idx_memory_entries_section ON memory_entries(section, position);
+    "#,
+    // v15: memory_revisions — append-only log of the whole memory after each
+    // save that changed it, with why (`harness::MemoryChange` JSON). Rows
+    // are never updated or deleted; rowid is revision order.
+    r#"
+    CREATE TABLE memory_revisions (
+        id          TEXT PRIMARY KEY,
+        change      TEXT NOT NULL,
+        memory      TEXT NOT NULL,
+        created_at  INTEGER NOT NULL,
+        device_id   TEXT NOT NULL
+    );
+    "#,
+    // v16: memory scopes — memory kept for one job or client alongside the
+    // user's own. '' is the global scope, so existing rows stay global
+    // (`MemoryScope::key`).
+    r#"
+    ALTER TABLE memory_entries ADD COLUMN scope TEXT NOT NULL DEFAULT '';
+    ALTER TABLE memory_revisions ADD COLUMN scope TEXT NOT NULL DEFAULT '';
+    DROP INDEX idx_memory_entries_section;
+    CREATE INDEX idx_memory_entries_scope ON memory_entries(scope, section, position);
+    "#,
+    // v17: memory_entries.fact — the entry's typed fact (`harness::TypedFact`
+    // JSON), NULL for plain-text entries.
+    r#"
+    ALTER TABLE memory_entries ADD COLUMN fact TEXT;
+    "#,
 ];
 
 pub(crate) fn migrate(conn: &Connection) -> Result<(), CoreError> {
@@ -5,15 +5,19 @@
 pub(crate) mod migrations;
 
 mod artifacts;
+mod batches;
 mod contacts;
 mod documents;
 mod items;
 mod jobs;
+mod memory;
 mod photos;
 pub(crate) mod schemas;
 mod sessions;
 mod usage;
 
+pub use memory::SqliteMemoryStore;
+
 use std::path::Path;
 use std::sync::Arc;
 
@@ -66,6 +70,16 @@ impl Store {
     pub(crate) fn now(&self) -> u64 {
         (self.clock)()
     }
+
+    /// Opens a transaction for a multi-statement write — or, when the caller
+    /// already holds one, returns `None` and lets the write join it, landing
+    /// or rolling back with the caller's (a batch result's claim and apply).
+    pub(crate) fn transaction(&self) -> Result<Option<rusqlite::Transaction<'_>>, CoreError> {
+        if !self.conn.is_autocommit() {
+            return Ok(None);
+        }
+        Ok(Some(self.conn.unchecked_transaction()?))
+    }
 }
 
 #[cfg(test)]
@@ -8,7 +8,7 @@ use crate::ids::new_id;
 use crate::store::Store;
 
 const PHOTO_COLS: &str =
-    "id, session_id, item_id, filename, captured_at, created_at, updated_at, device_id";
+    "id, session_id, item_id, filename, caption, captured_at, created_at, updated_at, device_id";
 
 fn photo_from_row(row: &Row) -> Result<Photo, CoreError> {
     Ok(Photo {
@@ -16,6 +16,7 @@ fn photo_from_row(row: &Row) -> Result<Photo, CoreError> {
         session_id: row.get("session_id").map_err(CoreError::Sqlite)?,
         item_id: row.get("item_id").map_err(CoreError::Sqlite)?,
         filename: row.get("filename").map_err(CoreError::Sqlite)?,
+        caption: row.get("caption").map_err(CoreError::Sqlite)?,
         captured_at: row.get::<_, i64>("captured_at").map_err(CoreError::Sqlite)? as u64,
         created_at: row.get::<_, i64>("created_at").map_err(CoreError::Sqlite)? as u64,
         updated_at: row.get::<_, i64>("updated_at").map_err(CoreError::Sqlite)? as u64,
@@ -53,6 +54,7 @@ impl Store {
             session_id: session_id.to_string(),
             item_id: item_id.map(str::to_string),
             filename: filename.to_string(),
+            caption: None,
             captured_at: captured_at.unwrap_or(now),
             created_at: now,
             updated_at: now,
@@ -100,6 +102,39 @@ impl Store {
         Ok(photos)
     }
 
+    /// Records what the vision pass saw in a live photo. `NotFound` if the
+    /// photo is missing or tombstoned.
+    pub fn set_photo_caption(&self, id: &str, caption: &str) -> Result<(), CoreError> {
+        let now = self.now() as i64;
+        let changed = self.conn.execute(
+            "UPDATE photos SET caption = ?1, updated_at = ?2 WHERE id = ?3 AND deleted_at IS NULL",
+            rusqlite::params![caption, now, id],
+        )?;
+        if changed == 0 {
+            return Err(CoreError::NotFound { entity: "photo", id: id.to_string() });
+        }
+        Ok(())
+    }
+
+    /// Attaches a live photo to a live item of the SAME session — the
+    /// membership rule `add_photo` enforces (`InvalidState` otherwise).
+    /// Re-attaching an already-attached photo moves it.
+    pub fn attach_photo_to_item(&self, photo_id: &str, item_id: &str) -> Result<(), CoreError> {
+        let photo = self.get_photo(photo_id)?;
+        let item = self.get_item(item_id)?;
+        if item.session_id != photo.session_id {
+            return Err(CoreError::InvalidState(format!(
+                "item {item_id} does not belong to session {}",
+                photo.session_id
+            )));
+        }
+        self.conn.execute(
+            "UPDATE photos SET item_id = ?1, updated_at = ?2 WHERE id = ?3",
+            rusqlite::params![item_id, self.now() as i64, photo_id],
+        )?;
+        Ok(())
+    }
+
     pub fn remove_photo(&self, id: &str) -> Result<(), CoreError> {
         let now = self.now() as i64;
         let changed = self.conn.execute(
@@ -232,6 +267,25 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn caption_and_attach_update_a_live_photo() {
+        let (s, sid) = store_with_session();
+        let item = s.add_item(&sid, "safety", "frayed cord").unwrap();
+        let p = s.add_photo(&sid, None, "c.jpg", None).unwrap();
+        assert_eq!(p.caption, None);
+        s.set_photo_caption(&p.id, "extension cord with a split jacket").unwrap();
+        s.attach_photo_to_item(&p.id, &item.id).unwrap();
+        let got = s.get_photo(&p.id).unwrap();
+        assert_eq!(got.caption.as_deref(), Some("extension cord with a split jacket"));
+        assert_eq!(got.item_id.as_deref(), Some(item.id.as_str()));
+
+        let other = s.start_session(None).unwrap();
+        let other_item = s.add_item(&other.id, "todo", "x").unwrap();
+        assert!(matches!(s.attach_photo_to_item(&p.id, &other_item.id), Err(CoreError::InvalidState(_))));
+        s.remove_photo(&p.id).unwrap();
+        assert!(matches!(s.set_photo_caption(&p.id, "gone"), Err(CoreError::NotFound { .. })));
+    }
+
     #[test]
     fn add_photo_to_missing_session_is_not_found() {
         let (s, _) = store_with_session();
@@ -340,7 +394,14 @@ mod tests {
         // This run extracts one authoritative item A1.
         let a1 = s.add_item_with_source(&sid, "todo", "auth", ItemSource::Authoritative).unwrap();
         s.end_session(&sid).unwrap();
-        s.finish_session_processed(&sid, "done", &harness::Usage::default(), std::slice::from_ref(&a1.id)).unwrap();
+        s.finish_session_processed(
+            &sid,
+            "done",
+            &harness::Usage::default(),
+            &harness::ServedBy::default(),
+            std::slice::from_ref(&a1.id),
+        )
+        .unwrap();
 
         // I1 swept (deleted_at=6000). A1 survives. Both photos survive at session scope.
         let items: Vec<String> = s.list_items_for_session(&sid).unwrap().into_iter().map(|i| i.id).collect();
@@ -367,7 +428,14 @@ mod tests {
         let pa = s.add_photo(&sid, Some(&a1.id), "pa.jpg", None).unwrap();
         s.end_session(&sid).unwrap();
         // manual survives (never swept); a1 is in run_item_ids (survives) → both linkages kept.
-        s.finish_session_processed(&sid, "done", &harness::Usage::default(), std::slice::from_ref(&a1.id)).unwrap();
+        s.finish_session_processed(
+            &sid,
+            "done",
+            &harness::Usage::default(),
+            &harness::ServedBy::default(),
+            std::slice::from_ref(&a1.id),
+        )
+        .unwrap();
         let photos = s.list_photos_for_session(&sid).unwrap();
         assert_eq!(photos.iter().find(|p| p.id == pm.id).unwrap().item_id.as_deref(), Some(manual.id.as_str()));
         assert_eq!(photos.iter().find(|p| p.id == pa.id).unwrap().item_id.as_deref(), Some(a1.id.as_str()));
@@ -1,12 +1,12 @@
 use rusqlite::Row;
 
-use crate::domain::{Session, SessionStatus, SessionSummary, WalkSummary};
+use crate::domain::{Session, SessionFailure, SessionStatus, SessionSummary, WalkSummary};
 use crate::error::CoreError;
 use crate::ids::new_id;
 use crate::store::Store;
 
 const SESSION_COLS: &str =
-    "id, job_id, template, status, transcript, summary, started_at, ended_at, created_at, updated_at, device_id";
+    "id, job_id, template, status, transcript, summary, started_at, ended_at, created_at, updated_at, device_id, failure_kind, failure_status, provisional";
 
 const SUMMARY_COLS: &str =
     "id, job_id, status, summary, started_at, ended_at, length(transcript) AS transcript_chars";
@@ -28,9 +28,22 @@ fn session_from_row(row: &Row) -> Result<Session, CoreError> {
         created_at: row.get::<_, i64>("created_at").map_err(CoreError::Sqlite)? as u64,
         updated_at: row.get::<_, i64>("updated_at").map_err(CoreError::Sqlite)? as u64,
         device_id: row.get("device_id").map_err(CoreError::Sqlite)?,
+        failure: failure_from_row(row)?,
+        provisional: row.get("provisional").map_err(CoreError::Sqlite)?,
     })
 }
 
+/// Tolerant: a kind string this build doesn't know reads back as no
+/// recorded failure rather than a corrupt row.
+fn failure_from_row(row: &Row) -> Result<Option<SessionFailure>, CoreError> {
+    let kind: Option<String> = row.get("failure_kind").map_err(CoreError::Sqlite)?;
+    let status: Option<i64> = row.get("failure_status").map_err(CoreError::Sqlite)?;
+    Ok(kind
+        .as_deref()
+        .and_then(harness::ProviderErrorKind::parse)
+        .map(|kind| SessionFailure { kind, http_status: status.map(|s| s as u16) }))
+}
+
 fn summary_from_row(row: &Row) -> Result<SessionSummary, CoreError> {
     let status_raw: String = row.get("status").map_err(CoreError::Sqlite)?;
     Ok(SessionSummary {
@@ -73,6 +86,8 @@ impl Store {
             created_at: now,
             updated_at: now,
             device_id: self.device_id.clone(),
+            failure: None,
+            provisional: false,
         };
         self.conn.execute(
             "INSERT INTO sessions (id, job_id, status, transcript, started_at, created_at, updated_at, device_id)
@@ -175,6 +190,8 @@ impl Store {
         self.transition_ended(id, SessionStatus::Failed, None)
     }
 
+    /// Every transition clears the recorded failure and the provisional flag;
+    /// `finish_session_failed` / `finish_session_provisional` set them after.
     fn transition_ended(
         &self,
         id: &str,
@@ -183,9 +200,11 @@ impl Store {
     ) -> Result<Session, CoreError> {
         let session = self.get_session(id)?;
         // Allowlist: Processed/Failed are only reachable FROM AwaitingProcessing
-        // (first attempt) or Failed (retry path). Processed is terminal.
+        // (first attempt) or Failed (retry path). Processed is terminal unless
+        // it is provisional (the cloud reprocess of an on-device result).
         match session.status {
             SessionStatus::AwaitingProcessing | SessionStatus::Failed => {}
+            SessionStatus::Processed if session.provisional => {}
             SessionStatus::Recording => {
                 return Err(CoreError::InvalidState(
                     "session is still recording".to_string(),
@@ -199,11 +218,15 @@ impl Store {
         }
         match summary {
             Some(text) => self.conn.execute(
-                "UPDATE sessions SET status = ?1, summary = ?2, updated_at = ?3 WHERE id = ?4",
+                "UPDATE sessions SET status = ?1, summary = ?2, updated_at = ?3,
+                        failure_kind = NULL, failure_status = NULL, provisional = 0
+                 WHERE id = ?4",
                 rusqlite::params![to.as_str(), text, self.now() as i64, id],
             )?,
             None => self.conn.execute(
-                "UPDATE sessions SET status = ?1, updated_at = ?2 WHERE id = ?3",
+                "UPDATE sessions SET status = ?1, updated_at = ?2,
+                        failure_kind = NULL, failure_status = NULL, provisional = 0
+                 WHERE id = ?3",
                 rusqlite::params![to.as_str(), self.now() as i64, id],
             )?,
         };
@@ -375,6 +398,7 @@ impl Store {
     pub fn list_walk_summaries(&self) -> Result<Vec<WalkSummary>, CoreError> {
         let mut stmt = self.conn.prepare(
             "SELECT s.id, s.job_id, s.template, s.status, s.summary, s.started_at, s.ended_at,
+                    s.failure_kind, s.failure_status, s.provisional,
                     (SELECT COUNT(*) FROM items i
                       WHERE i.session_id = s.id AND i.deleted_at IS NULL) AS item_count,
                     EXISTS(SELECT 1 FROM artifacts a
@@ -401,11 +425,29 @@ impl Store {
                     .map(|v| v as u64),
                 item_count: row.get::<_, i64>("item_count").map_err(CoreError::Sqlite)? as u64,
                 has_document: row.get::<_, bool>("has_document").map_err(CoreError::Sqlite)?,
+                failure: failure_from_row(row)?,
+                provisional: row.get("provisional").map_err(CoreError::Sqlite)?,
             });
         }
         Ok(out)
     }
 
+    /// Provisional (on-device) results still owed a cloud reprocess, newest
+    /// first like the status queues.
+    pub fn list_provisional_session_summaries(&self) -> Result<Vec<SessionSummary>, CoreError> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {SUMMARY_COLS} FROM sessions
+             WHERE status = 'processed' AND provisional = 1 AND deleted_at IS NULL
+             ORDER BY started_at DESC, id DESC"
+        ))?;
+        let mut rows = stmt.query([])?;
+        let mut out = Vec::new();
+        while let Some(row) = rows.next()? {
+            out.push(summary_from_row(row)?);
+        }
+        Ok(out)
+    }
+
     /// Queue polling without transcripts (processing pull, zombie sweep).
     pub fn list_session_summaries_by_status(
         &self,
@@ -449,9 +491,38 @@ impl Store {
         session_id: &str,
         summary: &str,
         usage: &harness::Usage,
+        model: &harness::ServedBy,
         run_item_ids: &[String],
     ) -> Result<Session, CoreError> {
-        let tx = self.conn.unchecked_transaction()?;
+        self.finish_processed(session_id, summary, usage, model, run_item_ids, false)
+    }
+
+    /// `finish_session_processed` for a run on the on-device fallback model:
+    /// same swap, but the session is flagged provisional (a cloud reprocess
+    /// is still owed) and the tokens are logged as `processing_local` — they
+    /// cost compute, not money, and must not feed the spend meter's cloud
+    /// estimate as if they did.
+    pub fn finish_session_provisional(
+        &self,
+        session_id: &str,
+        summary: &str,
+        usage: &harness::Usage,
+        model: &harness::ServedBy,
+        run_item_ids: &[String],
+    ) -> Result<Session, CoreError> {
+        self.finish_processed(session_id, summary, usage, model, run_item_ids, true)
+    }
+
+    fn finish_processed(
+        &self,
+        session_id: &str,
+        summary: &str,
+        usage: &harness::Usage,
+        model: &harness::ServedBy,
+        run_item_ids: &[String],
+        provisional: bool,
+    ) -> Result<Session, CoreError> {
+        let tx = self.transaction()?;
         let now = self.now() as i64;
 
         let mut sql = String::from(
@@ -478,22 +549,82 @@ impl Store {
         // before commit, same tx.
         self.demote_photos_of_tombstoned_items(session_id)?;
 
-        let session = self.mark_session_processed(session_id, summary)?;
-        self.record_llm_usage(Some(session_id), "processing", usage)?;
-        tx.commit()?;
+        let mut session = self.mark_session_processed(session_id, summary)?;
+        if provisional {
+            self.conn.execute(
+                "UPDATE sessions SET provisional = 1 WHERE id = ?1",
+                [session_id],
+            )?;
+            session.provisional = true;
+        }
+        let purpose = if provisional { "processing_local" } else { "processing" };
+        self.record_served_usage(Some(session_id), purpose, usage, model)?;
+        if let Some(tx) = tx {
+            tx.commit()?;
+        }
         Ok(session)
     }
 
-    /// Pipeline failure exit: marks the session Failed AND records the LLM
-    /// cost in one transaction (R9: cost is logged even on failure).
+    /// Pipeline failure exit: marks the session Failed, records why (when it
+    /// was the provider's failure), AND records the LLM cost in one
+    /// transaction (R9: cost is logged even on failure).
     pub fn finish_session_failed(
         &self,
         session_id: &str,
         usage: &harness::Usage,
+        model: &harness::ServedBy,
+        failure: Option<SessionFailure>,
     ) -> Result<(), CoreError> {
-        let tx = self.conn.unchecked_transaction()?;
+        let tx = self.transaction()?;
         self.mark_session_failed(session_id)?;
-        self.record_llm_usage(Some(session_id), "processing", usage)?;
+        if let Some(failure) = failure {
+            self.conn.execute(
+                "UPDATE sessions SET failure_kind = ?1, failure_status = ?2 WHERE id = ?3",
+                rusqlite::params![
+                    failure.kind.as_str(),
+                    failure.http_status.map(i64::from),
+                    session_id
+                ],
+            )?;
+        }
+        self.record_served_usage(Some(session_id), "processing", usage, model)?;
+        if let Some(tx) = tx {
+            tx.commit()?;
+        }
+        Ok(())
+    }
+
+    /// Exit for a failed or cancelled reprocess of a provisional walk: the
+    /// walk keeps its on-device result — still Processed and provisional, so
+    /// a later reprocess retries it — and only the attempt is undone. In one
+    /// transaction: tombstones the items the attempt created (`run_item_ids`)
+    /// and every artifact not in `kept_artifact_ids`, and logs the attempt's
+    /// cost under `purpose` (R9).
+    pub fn abandon_provisional_reprocess(
+        &self,
+        session_id: &str,
+        purpose: &str,
+        usage: &harness::Usage,
+        model: &harness::ServedBy,
+        run_item_ids: &[String],
+        kept_artifact_ids: &[String],
+    ) -> Result<(), CoreError> {
+        let now = self.now() as i64;
+        let tx = self.conn.unchecked_transaction()?;
+        for id in run_item_ids {
+            self.conn.execute(
+                "UPDATE items SET deleted_at = ?1, updated_at = ?1
+                 WHERE id = ?2 AND session_id = ?3 AND deleted_at IS NULL",
+                rusqlite::params![now, id, session_id],
+            )?;
+        }
+        for artifact in self.list_artifacts_for_session(session_id)? {
+            if !kept_artifact_ids.contains(&artifact.id) {
+                self.delete_artifact(&artifact.id)?;
+            }
+        }
+        self.demote_photos_of_tombstoned_items(session_id)?;
+        self.record_served_usage(Some(session_id), purpose, usage, model)?;
         tx.commit()?;
         Ok(())
     }
@@ -532,7 +663,7 @@ impl Store {
 mod tests {
     use std::sync::Arc;
 
-    use crate::domain::{NewJob, SessionStatus};
+    use crate::domain::{NewJob, SessionFailure, SessionStatus};
     use crate::error::CoreError;
     use crate::store::Store;
 
@@ -973,7 +1104,14 @@ mod tests {
         let a2 = s.add_item_with_source(&sid, "safety", "new 2", ItemSource::Authoritative).unwrap();
 
         s.end_session(&sid).unwrap();
-        s.finish_session_processed(&sid, "done", &harness::Usage::default(), &[a1.id.clone(), a2.id.clone()]).unwrap();
+        s.finish_session_processed(
+            &sid,
+            "done",
+            &harness::Usage::default(),
+            &harness::ServedBy::default(),
+            &[a1.id.clone(), a2.id.clone()],
+        )
+        .unwrap();
 
         let ids: Vec<String> = s.list_items_for_session(&sid).unwrap().into_iter().map(|i| i.id).collect();
         assert!(ids.contains(&manual.id), "manual survives the swap");
@@ -995,7 +1133,14 @@ mod tests {
         s.add_item_with_source(&sid, "todo", "live", ItemSource::Live).unwrap();
         let manual = s.add_item_with_source(&sid, "note", "manual", ItemSource::Manual).unwrap();
         s.end_session(&sid).unwrap();
-        s.finish_session_processed(&sid, "(empty session)", &harness::Usage::default(), &[]).unwrap();
+        s.finish_session_processed(
+            &sid,
+            "(empty session)",
+            &harness::Usage::default(),
+            &harness::ServedBy::default(),
+            &[],
+        )
+        .unwrap();
         let ids: Vec<String> = s.list_items_for_session(&sid).unwrap().into_iter().map(|i| i.id).collect();
         assert_eq!(ids, vec![manual.id], "only manual survives an empty-run swap");
     }
@@ -1008,7 +1153,7 @@ mod tests {
         let sid = session.id.clone();
         let live = s.add_item_with_source(&sid, "todo", "live", ItemSource::Live).unwrap();
         s.end_session(&sid).unwrap();
-        s.finish_session_failed(&sid, &harness::Usage::default()).unwrap();
+        s.finish_session_failed(&sid, &harness::Usage::default(), &harness::ServedBy::default(), None).unwrap();
         let ids: Vec<String> = s.list_items_for_session(&sid).unwrap().into_iter().map(|i| i.id).collect();
         assert_eq!(ids, vec![live.id], "a failed process must not sweep the live board (the whole fix)");
         assert_eq!(s.get_session(&sid).unwrap().status, SessionStatus::Failed);
@@ -1141,9 +1286,87 @@ mod tests {
             ended_at: _,
             item_count: _,
             has_document: _,
+            failure: _,
+            provisional: _,
         } = walks[0].clone();
     }
 
+    #[test]
+    fn finish_failed_records_the_provider_failure_and_processing_clears_it() {
+        use harness::ProviderErrorKind;
+        let s = store();
+        let session = s.start_session(None).unwrap();
+        s.end_session(&session.id).unwrap();
+        let rejected = SessionFailure { kind: ProviderErrorKind::Auth, http_status: Some(401) };
+        s.finish_session_failed(
+            &session.id,
+            &harness::Usage::default(),
+            &harness::ServedBy::default(),
+            Some(rejected),
+        )
+        .unwrap();
+        assert_eq!(s.get_session(&session.id).unwrap().failure, Some(rejected));
+        assert_eq!(s.list_walk_summaries().unwrap()[0].failure, Some(rejected));
+
+        // A retry that fails for a non-provider reason replaces, not keeps, it.
+        s.finish_session_failed(&session.id, &harness::Usage::default(), &harness::ServedBy::default(), None).unwrap();
+        assert_eq!(s.get_session(&session.id).unwrap().failure, None);
+
+        let offline = SessionFailure { kind: ProviderErrorKind::Unreachable, http_status: None };
+        s.finish_session_failed(
+            &session.id,
+            &harness::Usage::default(),
+            &harness::ServedBy::default(),
+            Some(offline),
+        )
+        .unwrap();
+        assert_eq!(s.get_session(&session.id).unwrap().failure, Some(offline));
+        s.mark_session_processed(&session.id, "done").unwrap();
+        assert_eq!(s.get_session(&session.id).unwrap().failure, None);
+    }
+
+    #[test]
+    fn provisional_result_is_reprocessable_and_its_tokens_stay_off_the_meter() {
+        let s = store();
+        let session = s.start_session(None).unwrap();
+        s.end_session(&session.id).unwrap();
+        let local = harness::Usage { input_tokens: 900, output_tokens: 300, ..Default::default() };
+        let done = s.finish_session_provisional(
+            &session.id,
+            "on-device notes",
+            &local,
+            &harness::ServedBy::default(),
+            &[],
+        )
+        .unwrap();
+        assert_eq!(done.status, SessionStatus::Processed);
+        assert!(done.provisional);
+        assert!(s.get_session(&session.id).unwrap().provisional);
+        assert!(s.list_walk_summaries().unwrap()[0].provisional);
+        assert_eq!(s.list_provisional_session_summaries().unwrap().len(), 1);
+        assert_eq!(s.usage_totals().unwrap(), (0, 0), "local tokens are not spend");
+        assert_eq!(s.list_llm_usage_for_session(&session.id).unwrap()[0].purpose, "processing_local");
+
+        // Unlike a cloud result, a provisional one may be processed again —
+        // and the cloud result clears the flag and is terminal as usual.
+        let cloud = harness::Usage { input_tokens: 100, output_tokens: 20, ..Default::default() };
+        let done = s.finish_session_processed(
+            &session.id,
+            "cloud notes",
+            &cloud,
+            &harness::ServedBy::default(),
+            &[],
+        )
+        .unwrap();
+        assert!(!done.provisional);
+        assert!(s.list_provisional_session_summaries().unwrap().is_empty());
+        assert_eq!(s.usage_totals().unwrap(), (100, 20));
+        assert!(matches!(
+            s.mark_session_processed(&session.id, "again?"),
+            Err(CoreError::InvalidState(_))
+        ));
+    }
+
     #[test]
     fn processed_session_summary_carries_full_lifecycle_fields() {
         let s = store();
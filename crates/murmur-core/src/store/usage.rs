@@ -1,4 +1,4 @@
-use harness::Usage;
+use harness::{ServedBy, Usage};
 use rusqlite::Row;
 
 use crate::domain::LlmUsageRow;
@@ -13,6 +13,11 @@ fn usage_from_row(row: &Row) -> Result<LlmUsageRow, CoreError> {
         purpose: row.get("purpose").map_err(CoreError::Sqlite)?,
         input_tokens: row.get::<_, i64>("input_tokens").map_err(CoreError::Sqlite)? as u64,
         output_tokens: row.get::<_, i64>("output_tokens").map_err(CoreError::Sqlite)? as u64,
+        cache_creation_input_tokens: row.get::<_, i64>("cache_creation_input_tokens").map_err(CoreError::Sqlite)?
+            as u64,
+        cache_read_input_tokens: row.get::<_, i64>("cache_read_input_tokens").map_err(CoreError::Sqlite)?
+            as u64,
+        model: row.get("model").map_err(CoreError::Sqlite)?,
         created_at: row.get::<_, i64>("created_at").map_err(CoreError::Sqlite)? as u64,
         device_id: row.get("device_id").map_err(CoreError::Sqlite)?,
     })
@@ -20,22 +25,28 @@ fn usage_from_row(row: &Row) -> Result<LlmUsageRow, CoreError> {
 
 impl Store {
     /// Logs one LLM call's token cost (R9). `session_id` is None for
-    /// session-independent work (reflection).
+    /// session-independent work (reflection); `model` is what served it.
     pub fn record_llm_usage(
         &self,
         session_id: Option<&str>,
         purpose: &str,
         usage: &Usage,
+        model: Option<&str>,
     ) -> Result<(), CoreError> {
         self.conn.execute(
-            "INSERT INTO llm_usage (id, session_id, purpose, input_tokens, output_tokens, created_at, device_id)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            "INSERT INTO llm_usage (id, session_id, purpose, input_tokens, output_tokens,
+                                    cache_creation_input_tokens, cache_read_input_tokens,
+                                    model, created_at, device_id)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
             rusqlite::params![
                 new_id(),
                 session_id,
                 purpose,
                 usage.input_tokens as i64,
                 usage.output_tokens as i64,
+                usage.cache_creation_input_tokens as i64,
+                usage.cache_read_input_tokens as i64,
+                model,
                 self.now() as i64,
                 self.device_id,
             ],
@@ -43,9 +54,26 @@ impl Store {
         Ok(())
     }
 
+    /// `record_llm_usage` for a run that may have been served by several
+    /// models (a fallback answered part of it): one row per model, each with
+    /// its own spend (`ServedBy::rows`).
+    pub fn record_served_usage(
+        &self,
+        session_id: Option<&str>,
+        purpose: &str,
+        usage: &Usage,
+        served: &ServedBy,
+    ) -> Result<(), CoreError> {
+        for (model, usage) in served.rows(usage) {
+            self.record_llm_usage(session_id, purpose, &usage, model)?;
+        }
+        Ok(())
+    }
+
     pub fn list_llm_usage_for_session(&self, session_id: &str) -> Result<Vec<LlmUsageRow>, CoreError> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, session_id, purpose, input_tokens, output_tokens, created_at, device_id
+            "SELECT id, session_id, purpose, input_tokens, output_tokens, cache_creation_input_tokens,
+                    cache_read_input_tokens, model, created_at, device_id
              FROM llm_usage WHERE session_id = ?1 ORDER BY id ASC",
         )?;
         let mut rows = stmt.query([session_id])?;
@@ -56,16 +84,40 @@ impl Store {
         Ok(out)
     }
 
-    /// (total input tokens, total output tokens) across all recorded calls —
-    /// the spend meter's raw feed.
+    /// (total input tokens, total output tokens) across all recorded cloud
+    /// calls — the spend meter's raw feed. On-device (`processing_local`)
+    /// tokens are logged but cost nothing, so they're left out.
     pub fn usage_totals(&self) -> Result<(u64, u64), CoreError> {
         let (i, o): (i64, i64) = self.conn.query_row(
-            "SELECT COALESCE(SUM(input_tokens), 0), COALESCE(SUM(output_tokens), 0) FROM llm_usage",
+            "SELECT COALESCE(SUM(input_tokens), 0), COALESCE(SUM(output_tokens), 0) FROM llm_usage
+             WHERE purpose != 'processing_local'",
             [],
             |r| Ok((r.get(0)?, r.get(1)?)),
         )?;
         Ok((i as u64, o as u64))
     }
+
+    /// Like `usage_totals`, but with the prompt-cache write/read tokens kept
+    /// apart, so the spend meter can price them at their own rates
+    /// (`Usage::billable_input_tokens`) instead of as full-price input.
+    /// Thinking isn't logged apart — it's billed as the output it's part of.
+    pub fn usage_breakdown(&self) -> Result<Usage, CoreError> {
+        let (i, o, created, read): (i64, i64, i64, i64) = self.conn.query_row(
+            "SELECT COALESCE(SUM(input_tokens), 0), COALESCE(SUM(output_tokens), 0),
+                    COALESCE(SUM(cache_creation_input_tokens), 0),
+                    COALESCE(SUM(cache_read_input_tokens), 0)
+             FROM llm_usage WHERE purpose != 'processing_local'",
+            [],
+            |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?)),
+        )?;
+        Ok(Usage {
+            input_tokens: i as u64,
+            output_tokens: o as u64,
+            cache_creation_input_tokens: created as u64,
+            cache_read_input_tokens: read as u64,
+            ..Default::default()
+        })
+    }
 }
 
 #[cfg(test)]
@@ -87,7 +139,8 @@ mod tests {
         s.record_llm_usage(
             Some(&session.id),
             "processing",
-            &Usage { input_tokens: 900, output_tokens: 120 },
+            &Usage { input_tokens: 900, output_tokens: 120, ..Default::default() },
+            Some("claude-haiku-4-5"),
         )
         .unwrap();
         let rows = s.list_llm_usage_for_session(&session.id).unwrap();
@@ -95,13 +148,14 @@ mod tests {
         assert_eq!(rows[0].purpose, "processing");
         assert_eq!(rows[0].input_tokens, 900);
         assert_eq!(rows[0].output_tokens, 120);
+        assert_eq!(rows[0].model.as_deref(), Some("claude-haiku-4-5"));
         assert_eq!(rows[0].created_at, 1000);
     }
 
     #[test]
     fn sessionless_usage_is_allowed() {
         let s = store();
-        s.record_llm_usage(None, "reflection", &Usage { input_tokens: 300, output_tokens: 80 })
+        s.record_llm_usage(None, "reflection", &Usage { input_tokens: 300, output_tokens: 80, ..Default::default() }, None)
             .unwrap();
         assert_eq!(s.usage_totals().unwrap(), (300, 80));
     }
@@ -110,18 +164,46 @@ mod tests {
     fn totals_sum_across_rows() {
         let s = store();
         let session = s.start_session(None).unwrap();
-        s.record_llm_usage(Some(&session.id), "processing", &Usage { input_tokens: 10, output_tokens: 1 })
+        s.record_llm_usage(Some(&session.id), "processing", &Usage { input_tokens: 10, output_tokens: 1, ..Default::default() }, None)
             .unwrap();
-        s.record_llm_usage(None, "reflection", &Usage { input_tokens: 5, output_tokens: 2 })
+        s.record_llm_usage(None, "reflection", &Usage { input_tokens: 5, output_tokens: 2, ..Default::default() }, None)
             .unwrap();
         assert_eq!(s.usage_totals().unwrap(), (15, 3));
         assert_eq!(s.list_llm_usage_for_session(&session.id).unwrap().len(), 1);
     }
 
+    #[test]
+    fn cache_tokens_persist_and_feed_the_breakdown() {
+        let s = store();
+        let session = s.start_session(None).unwrap();
+        let warm = Usage {
+            input_tokens: 40,
+            output_tokens: 10,
+            cache_creation_input_tokens: 0,
+            cache_read_input_tokens: 2000,
+            ..Default::default()
+        };
+        s.record_llm_usage(Some(&session.id), "live_extraction", &warm, None).unwrap();
+        s.record_llm_usage(
+            Some(&session.id),
+            "processing_local",
+            &Usage { input_tokens: 500, cache_read_input_tokens: 500, ..Default::default() },
+            None,
+        )
+        .unwrap();
+
+        let rows = s.list_llm_usage_for_session(&session.id).unwrap();
+        assert_eq!(rows[0].cache_read_input_tokens, 2000);
+        assert_eq!(rows[0].cache_creation_input_tokens, 0);
+        let totals = s.usage_breakdown().unwrap();
+        assert_eq!(totals, warm, "local rows stay off the meter");
+        assert_eq!(totals.billable_input_tokens(), 240);
+    }
+
     #[test]
     fn unknown_session_is_rejected() {
         let s = store();
-        let err = s.record_llm_usage(Some("nope"), "processing", &Usage::default());
+        let err = s.record_llm_usage(Some("nope"), "processing", &Usage::default(), None);
         assert!(err.is_err(), "FK to sessions must hold");
     }
 }
@@ -0,0 +1,331 @@
+//! Batch mode end to end: the drains run each extraction at once but send
+//! the notes calls as one message batch, and `resume_batches` finishes the
+//! sessions once it has ended — polled against a wiremock stub of the
+//! Message Batches API, the same way a later app launch would.
+
+use std::sync::{Arc, Mutex};
+
+use harness::{
+    AnthropicProvider, CompletionResponse, ContentBlock, HarnessError, Memory, MemoryStore, MockProvider,
+    ServedBy, StopReason, Usage,
+};
+use murmur_core::{DocumentBuilder, ItemSource, QueuedDocument, SessionProcessor, SessionStatus, Store};
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+struct NullMemoryStore;
+impl MemoryStore for NullMemoryStore {
+    fn load(&self) -> Result<Memory, HarnessError> {
+        Ok(Memory::default())
+    }
+    fn save(&self, _m: &Memory) -> Result<(), HarnessError> {
+        Ok(())
+    }
+}
+
+fn usage(input_tokens: u64, output_tokens: u64) -> Usage {
+    Usage { input_tokens, output_tokens, ..Default::default() }
+}
+
+fn tool_use(name: &str, input: serde_json::Value) -> CompletionResponse {
+    CompletionResponse {
+        content: vec![ContentBlock::ToolUse { id: "tu".into(), name: name.into(), input }],
+        stop_reason: StopReason::ToolUse,
+        usage: usage(100, 20),
+        model: None,
+    }
+}
+
+fn done() -> CompletionResponse {
+    CompletionResponse {
+        content: vec![ContentBlock::Text { text: "done".into() }],
+        stop_reason: StopReason::EndTurn,
+        usage: usage(50, 5),
+        model: None,
+    }
+}
+
+/// One extraction run: a todo, then end of turn.
+fn extraction(todo: &str) -> [CompletionResponse; 2] {
+    [tool_use("add_item", serde_json::json!({"kind": "todo", "text": todo})), done()]
+}
+
+fn batch(status: &str) -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_json(serde_json::json!({
+        "id": "msgbatch_1",
+        "type": "message_batch",
+        "processing_status": status,
+        "request_counts": {"processing": 0, "succeeded": 0, "errored": 0, "canceled": 0, "expired": 0},
+    }))
+}
+
+fn succeeded(custom_id: &str, tool: &str, input: serde_json::Value) -> serde_json::Value {
+    serde_json::json!({"custom_id": custom_id, "result": {"type": "succeeded", "message": {
+        "model": "claude-batch",
+        "content": [{"type": "tool_use", "id": "tu", "name": tool, "input": input}],
+        "stop_reason": "tool_use",
+        "usage": {"input_tokens": 300, "output_tokens": 40},
+    }}})
+}
+
+/// A batch that is submitted, polled once while still running, and ended
+/// on every later poll. Results are mounted by the test once it knows the
+/// job ids.
+async fn batch_server() -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/messages/batches"))
+        .respond_with(batch("in_progress"))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/v1/messages/batches/msgbatch_1"))
+        .respond_with(batch("in_progress"))
+        .up_to_n_times(1)
+        .with_priority(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/v1/messages/batches/msgbatch_1"))
+        .respond_with(batch("ended"))
+        .mount(&server)
+        .await;
+    server
+}
+
+async fn mount_results(server: &MockServer, lines: &[serde_json::Value]) {
+    let jsonl: String = lines.iter().map(|line| format!("{line}\n")).collect();
+    Mock::given(method("GET"))
+        .and(path("/v1/messages/batches/msgbatch_1/results"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(jsonl))
+        .mount(server)
+        .await;
+}
+
+fn ended_session(store: &Store, transcript: &str) -> String {
+    let session = store.start_session(None).unwrap();
+    store.append_transcript(&session.id, transcript).unwrap();
+    store.end_and_record_session(&session.id).unwrap();
+    session.id
+}
+
+fn processor(provider: MockProvider, store: &Arc<Mutex<Store>>, server: &MockServer) -> SessionProcessor {
+    let batches = AnthropicProvider::new("sk-test", "claude-batch").with_base_url(server.uri());
+    SessionProcessor::new(
+        Arc::new(provider),
+        store.clone(),
+        Arc::new(Mutex::new(Memory::default())),
+        Arc::new(NullMemoryStore),
+    )
+    .with_batches(Arc::new(batches))
+}
+
+#[tokio::test]
+async fn pending_sessions_finish_from_a_notes_batch_on_resume() {
+    let server = batch_server().await;
+    let store = Store::open_in_memory("field-phone").unwrap();
+    let deck = ended_session(&store, "deck ledger is soft, sister two joists");
+    let fence = ended_session(&store, "fence post leaning by the gate");
+    let store = Arc::new(Mutex::new(store));
+    let [a, b] = extraction("sister two joists");
+    let [c, d] = extraction("reset the fence post");
+    let processor = processor(MockProvider::new(vec![a, b, c, d]), &store, &server);
+
+    // Both extractions run now; both notes calls go out in one batch and
+    // the sessions wait where they were.
+    let staged = processor.process_pending().await.unwrap();
+    assert_eq!(staged.len(), 2);
+    for (_, outcome) in &staged {
+        let outcome = outcome.as_ref().unwrap();
+        assert_eq!(outcome.batch_id.as_deref(), Some("msgbatch_1"));
+        assert_eq!(outcome.session.status, SessionStatus::AwaitingProcessing);
+        assert_eq!(outcome.usage, usage(150, 25));
+    }
+    let submitted: serde_json::Value = server.received_requests().await.unwrap()[0].body_json().unwrap();
+    let requests = submitted["requests"].as_array().unwrap();
+    assert_eq!(requests.len(), 2);
+    assert!(requests.iter().all(|r| r["params"]["tool_choice"]["name"] == "write_notes"));
+
+    // A second drain leaves sessions with a notes call out alone.
+    assert!(processor.process_pending().await.unwrap().is_empty());
+
+    // Still running: nothing finishes, nothing is claimed.
+    assert!(processor.resume_batches().await.unwrap().is_empty());
+
+    let jobs = store.lock().unwrap().list_batch_jobs("notes").unwrap();
+    let job_of = |session: &str| jobs.iter().find(|j| j.session_id == session).unwrap().id.clone();
+    mount_results(
+        &server,
+        &[
+            succeeded(
+                &format!("{}_notes", job_of(&deck)),
+                "write_notes",
+                serde_json::json!({"summary": "Soft ledger; sister two joists."}),
+            ),
+            serde_json::json!({"custom_id": format!("{}_notes", job_of(&fence)), "result": {"type": "errored", "error": {
+                "type": "error", "error": {"type": "overloaded_error", "message": "Overloaded"},
+            }}}),
+        ],
+    )
+    .await;
+
+    let mut resumed = processor.resume_batches().await.unwrap();
+    resumed.sort_by_key(|(id, _)| id != &deck);
+    assert_eq!(resumed.len(), 2);
+    let processed = resumed[0].1.as_ref().unwrap();
+    assert_eq!(processed.session.status, SessionStatus::Processed);
+    assert_eq!(processed.session.summary.as_deref(), Some("Soft ledger; sister two joists."));
+    assert_eq!(processed.usage, usage(450, 65), "extraction plus the batched notes call");
+    assert!(resumed[1].1.is_err());
+
+    let s = store.lock().unwrap();
+    assert_eq!(s.get_session(&fence).unwrap().status, SessionStatus::Failed);
+    assert_eq!(s.list_items_for_session(&deck).unwrap().len(), 1, "the extraction's item survives the swap");
+    assert!(s.list_batch_jobs("notes").unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn a_synchronous_process_supersedes_a_pending_notes_batch() {
+    let server = batch_server().await;
+    let store = Store::open_in_memory("field-phone").unwrap();
+    let deck = ended_session(&store, "deck ledger is soft, sister two joists");
+    let store = Arc::new(Mutex::new(store));
+    let [a, b] = extraction("sister two joists");
+    let [c, d] = extraction("sister two joists at the ledger");
+    let notes = tool_use("write_notes", serde_json::json!({"summary": "Sister two joists now."}));
+    let processor = processor(MockProvider::new(vec![a, b, c, d, notes]), &store, &server);
+
+    processor.process_pending().await.unwrap();
+    // The user opens the walk and asks for it now.
+    let outcome = processor.process(&deck).await.unwrap();
+    assert_eq!(outcome.session.status, SessionStatus::Processed);
+    assert_eq!(outcome.batch_id, None);
+    assert!(store.lock().unwrap().list_batch_jobs("notes").unwrap().is_empty());
+
+    // The batch's stale notes never land.
+    mount_results(&server, &[]).await;
+    assert!(processor.resume_batches().await.unwrap().is_empty());
+    let s = store.lock().unwrap();
+    assert_eq!(s.get_session(&deck).unwrap().summary.as_deref(), Some("Sister two joists now."));
+    assert_eq!(s.usage_totals().unwrap(), (400, 70), "the superseded extraction's spend is still logged");
+}
+
+#[tokio::test]
+async fn a_failed_apply_leaves_the_notes_job_for_the_next_resume() {
+    let server = batch_server().await;
+    let db = std::env::temp_dir().join(format!("murmur-batch-{}.db", murmur_core::new_id()));
+    let store = Store::open(&db, "field-phone").unwrap();
+    let deck = ended_session(&store, "deck ledger is soft, sister two joists");
+    let store = Arc::new(Mutex::new(store));
+    let [a, b] = extraction("sister two joists");
+    let processor = processor(MockProvider::new(vec![a, b]), &store, &server);
+    processor.process_pending().await.unwrap();
+    assert!(processor.resume_batches().await.unwrap().is_empty(), "still running");
+
+    let job = store.lock().unwrap().list_batch_jobs("notes").unwrap().remove(0);
+    mount_results(
+        &server,
+        &[succeeded(
+            &format!("{}_notes", job.id),
+            "write_notes",
+            serde_json::json!({"summary": "Soft ledger; sister two joists."}),
+        )],
+    )
+    .await;
+    // Another connection makes the finish's usage write fail midway.
+    let other = rusqlite::Connection::open(&db).unwrap();
+    other
+        .execute_batch("CREATE TRIGGER full BEFORE INSERT ON llm_usage BEGIN SELECT RAISE(ABORT, 'disk full'); END;")
+        .unwrap();
+    let failed = processor.resume_batches().await.unwrap();
+    assert!(failed[0].1.is_err());
+    // Nothing landed, and the claim rolled back with it.
+    assert_eq!(store.lock().unwrap().get_session(&deck).unwrap().status, SessionStatus::AwaitingProcessing);
+    assert_eq!(store.lock().unwrap().list_batch_jobs("notes").unwrap().len(), 1);
+
+    other.execute_batch("DROP TRIGGER full;").unwrap();
+    let resumed = processor.resume_batches().await.unwrap();
+    assert_eq!(resumed[0].1.as_ref().unwrap().session.status, SessionStatus::Processed);
+    assert!(store.lock().unwrap().list_batch_jobs("notes").unwrap().is_empty());
+    let _ = std::fs::remove_file(&db);
+}
+
+#[tokio::test]
+async fn a_failed_submission_fails_the_staged_sessions() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/messages/batches"))
+        .respond_with(ResponseTemplate::new(529).set_body_string("overloaded"))
+        .mount(&server)
+        .await;
+    let store = Store::open_in_memory("field-phone").unwrap();
+    let deck = ended_session(&store, "deck ledger is soft, sister two joists");
+    let store = Arc::new(Mutex::new(store));
+    let [a, b] = extraction("sister two joists");
+    let processor = processor(MockProvider::new(vec![a, b]), &store, &server);
+
+    let results = processor.process_pending().await.unwrap();
+    assert_eq!(results.len(), 1);
+    assert!(results[0].1.is_err());
+    let s = store.lock().unwrap();
+    assert_eq!(s.get_session(&deck).unwrap().status, SessionStatus::Failed);
+    assert_eq!(s.usage_totals().unwrap(), (150, 25), "the extraction's spend is still logged");
+    assert!(s.list_batch_jobs("notes").unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn a_queued_estimate_is_priced_from_the_batch_on_resume() {
+    let server = batch_server().await;
+    let store = Store::open_in_memory("field-phone").unwrap();
+    let session = store.start_session_with_template(None, "landscape").unwrap();
+    let mulch = store.add_item_with_source(&session.id, "todo", "mulch the front beds", ItemSource::Authoritative).unwrap();
+    store.append_transcript(&session.id, "site walk").unwrap();
+    store.end_and_record_session(&session.id).unwrap();
+    store
+        .finish_session_processed(
+            &session.id,
+            "Walked the site.",
+            &Usage::default(),
+            &ServedBy::default(),
+            std::slice::from_ref(&mulch.id),
+        )
+        .unwrap();
+    let store = Arc::new(Mutex::new(store));
+    let batches = AnthropicProvider::new("sk-test", "claude-batch").with_base_url(server.uri());
+    let builder = DocumentBuilder::new(
+        Arc::new(MockProvider::new(vec![])),
+        store.clone(),
+        Arc::new(Mutex::new(Memory::default())),
+        Arc::new(NullMemoryStore),
+    )
+    .with_batches(Arc::new(batches));
+
+    let queued = builder.queue(&session.id, "estimate").await.unwrap();
+    assert!(matches!(queued, QueuedDocument::Batched { ref batch_id } if batch_id == "msgbatch_1"));
+    assert!(builder.resume_batches().await.unwrap().is_empty(), "still running");
+
+    let job = store.lock().unwrap().list_batch_jobs("document").unwrap().remove(0);
+    mount_results(
+        &server,
+        &[succeeded(
+            &format!("{}_pricing", job.id),
+            "price_items",
+            serde_json::json!({"prices": [{"item_id": mulch.id, "amount_cents": 42_000}]}),
+        )],
+    )
+    .await;
+    let resumed = builder.resume_batches().await.unwrap();
+    assert_eq!(resumed.len(), 1);
+    let outcome = resumed[0].1.as_ref().unwrap();
+    assert!(!outcome.queued);
+    assert_eq!(outcome.usage, usage(300, 40));
+
+    let s = store.lock().unwrap();
+    let artifacts = s.list_artifacts_for_session(&session.id).unwrap();
+    let document = artifacts.iter().find(|a| a.id == outcome.document_artifact_id).unwrap();
+    let body: serde_json::Value = serde_json::from_str(&document.body).unwrap();
+    assert_eq!(body["lines"][0]["amount_cents"], 42_000);
+    assert_eq!(body["lines"][0]["is_gap"], false);
+    assert_eq!(s.usage_totals().unwrap(), (300, 40));
+}
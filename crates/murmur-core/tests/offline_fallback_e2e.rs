@@ -0,0 +1,126 @@
+//! No signal on site: the cloud provider can't be reached, so the walk is
+//! processed on a local inference server (a wiremock stub standing in for
+//! Ollama), lands provisional, and is later reprocessed in the cloud.
+
+use std::sync::{Arc, Mutex};
+
+use harness::{
+    AnthropicProvider, CompletionResponse, ContentBlock, HarnessError, LocalProvider, Memory,
+    MemoryStore, MockProvider, StopReason, Usage,
+};
+use murmur_core::{SessionProcessor, SessionStatus, Store};
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+struct NullMemoryStore;
+impl MemoryStore for NullMemoryStore {
+    fn load(&self) -> Result<Memory, HarnessError> {
+        Ok(Memory::default())
+    }
+    fn save(&self, _m: &Memory) -> Result<(), HarnessError> {
+        Ok(())
+    }
+}
+
+/// One `/api/chat` reply in the emulated-tools shape the local provider
+/// constrains the model to.
+fn local_reply(reply: serde_json::Value) -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_json(serde_json::json!({
+        "model": "llama3.2",
+        "message": {"role": "assistant", "content": reply.to_string()},
+        "done": true,
+        "done_reason": "stop",
+        "prompt_eval_count": 400,
+        "eval_count": 60
+    }))
+}
+
+fn cloud_tool_use(name: &str, input: serde_json::Value) -> CompletionResponse {
+    CompletionResponse {
+        content: vec![ContentBlock::ToolUse { id: "tu".into(), name: name.into(), input }],
+        stop_reason: StopReason::ToolUse,
+        usage: Usage { input_tokens: 100, output_tokens: 25, ..Default::default() },
+        model: None,
+    }
+}
+
+#[tokio::test]
+async fn offline_walk_is_processed_locally_then_reprocessed_in_the_cloud() {
+    let local_server = MockServer::start().await;
+    // Extraction turn 1: one todo. Turn 2: done. Then the forced notes call.
+    for reply in [
+        serde_json::json!({"text": "", "tool_calls": [
+            {"name": "add_item", "input": {"kind": "todo", "text": "sister two joists"}}
+        ]}),
+        serde_json::json!({"text": "done", "tool_calls": []}),
+        serde_json::json!({"text": "", "tool_calls": [
+            {"name": "write_notes", "input": {"summary": "Deck ledger is soft; sister two joists."}}
+        ]}),
+    ] {
+        Mock::given(method("POST"))
+            .and(path("/api/chat"))
+            .respond_with(local_reply(reply))
+            .up_to_n_times(1)
+            .mount(&local_server)
+            .await;
+    }
+
+    let store = Store::open_in_memory("field-phone").unwrap();
+    let session = store.start_session(None).unwrap();
+    store
+        .append_transcript(&session.id, "deck ledger is soft, need to sister two joists")
+        .unwrap();
+    store.end_and_record_session(&session.id).unwrap();
+    let store = Arc::new(Mutex::new(store));
+
+    // Nothing listens on the discard port: every cloud call is unreachable.
+    let cloud = AnthropicProvider::new("sk-test", "claude-test").with_base_url("http://127.0.0.1:9");
+    let local = LocalProvider::new("llama3.2").with_base_url(local_server.uri());
+    let processor = SessionProcessor::new(
+        Arc::new(cloud),
+        store.clone(),
+        Arc::new(Mutex::new(Memory::default())),
+        Arc::new(NullMemoryStore),
+    )
+    .with_local_fallback(Arc::new(local));
+
+    let outcome = processor.process(&session.id).await.unwrap();
+    assert_eq!(outcome.session.status, SessionStatus::Processed);
+    assert!(outcome.session.provisional);
+    assert_eq!(outcome.usage, Usage { input_tokens: 1200, output_tokens: 180, ..Default::default() });
+    {
+        let s = store.lock().unwrap();
+        let items = s.list_items_for_session(&session.id).unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].text, "sister two joists");
+        assert_eq!(s.usage_totals().unwrap(), (0, 0), "on-device tokens are free");
+    }
+
+    // Back in signal: the cloud pass replaces the provisional result.
+    let reconnected = SessionProcessor::new(
+        Arc::new(MockProvider::new(vec![
+            cloud_tool_use("add_item", serde_json::json!({"kind": "todo", "text": "sister two joists at the ledger"})),
+            CompletionResponse {
+                content: vec![ContentBlock::Text { text: "done".into() }],
+                stop_reason: StopReason::EndTurn,
+                usage: Usage { input_tokens: 60, output_tokens: 10, ..Default::default() },
+                model: None,
+            },
+            cloud_tool_use("write_notes", serde_json::json!({"summary": "Soft ledger; sister two joists."})),
+        ])),
+        store.clone(),
+        Arc::new(Mutex::new(Memory::default())),
+        Arc::new(NullMemoryStore),
+    );
+    let results = reconnected.reprocess_provisional_sessions().await.unwrap();
+    assert_eq!(results.len(), 1);
+    let session = &results[0].1.as_ref().unwrap().session;
+    assert!(!session.provisional);
+    assert_eq!(session.summary.as_deref(), Some("Soft ledger; sister two joists."));
+
+    let s = store.lock().unwrap();
+    let items = s.list_items_for_session(&session.id).unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].text, "sister two joists at the ledger");
+    assert_eq!(s.usage_totals().unwrap(), (260, 60));
+}
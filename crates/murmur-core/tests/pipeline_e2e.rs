@@ -25,7 +25,8 @@ fn tool_use(name: &str, input: serde_json::Value) -> CompletionResponse {
     CompletionResponse {
         content: vec![ContentBlock::ToolUse { id: "tu".into(), name: name.into(), input }],
         stop_reason: StopReason::ToolUse,
-        usage: Usage { input_tokens: 100, output_tokens: 25 },
+        usage: Usage { input_tokens: 100, output_tokens: 25, ..Default::default() },
+        model: None,
     }
 }
 
@@ -33,7 +34,8 @@ fn end_turn(text: &str) -> CompletionResponse {
     CompletionResponse {
         content: vec![ContentBlock::Text { text: text.into() }],
         stop_reason: StopReason::EndTurn,
-        usage: Usage { input_tokens: 60, output_tokens: 10 },
+        usage: Usage { input_tokens: 60, output_tokens: 10, ..Default::default() },
+        model: None,
     }
 }
 
@@ -0,0 +1,116 @@
+//! A walk recorded once and replayed on a fresh store: the cassette stands in
+//! for the model, every store id differs between the runs, and the replayed
+//! run lands the same items, summary and prices.
+
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use harness::{
+    CompletionResponse, ContentBlock, HarnessError, LlmProvider, Memory, MemoryStore,
+    MockProvider, ReplayProvider, StopReason, Usage,
+};
+use murmur_core::{DocumentBuilder, SessionProcessor, Store};
+
+struct NullMemoryStore;
+impl MemoryStore for NullMemoryStore {
+    fn load(&self) -> Result<Memory, HarnessError> {
+        Ok(Memory::default())
+    }
+    fn save(&self, _m: &Memory) -> Result<(), HarnessError> {
+        Ok(())
+    }
+}
+
+fn tool_use(name: &str, input: serde_json::Value) -> CompletionResponse {
+    CompletionResponse {
+        content: vec![ContentBlock::ToolUse { id: "tu".into(), name: name.into(), input }],
+        stop_reason: StopReason::ToolUse,
+        usage: Usage { input_tokens: 100, output_tokens: 25, ..Default::default() },
+        model: None,
+    }
+}
+
+fn cassette(tag: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("murmur-replay-{tag}-{}.json", murmur_core::new_id()))
+}
+
+/// Processes a landscape walk and prices an estimate for it. Returns the
+/// summary and each line's (title, price).
+async fn walk(
+    processing: Arc<dyn LlmProvider>,
+    pricing: impl FnOnce(&str) -> Arc<dyn LlmProvider>,
+) -> (String, Vec<(String, Option<i64>)>) {
+    let store = Store::open_in_memory("field-phone").unwrap();
+    let session = store.start_session_with_template(None, "landscape").unwrap();
+    store.append_transcript(&session.id, "spread six yards of mulch, and the gate latch is broken").unwrap();
+    store.end_and_record_session(&session.id).unwrap();
+    let store = Arc::new(Mutex::new(store));
+    let memory = Arc::new(Mutex::new(Memory::default()));
+
+    let outcome = SessionProcessor::new(processing, store.clone(), memory.clone(), Arc::new(NullMemoryStore))
+        .process(&session.id)
+        .await
+        .unwrap();
+    let mulch_id = store.lock().unwrap().list_items_for_session(&session.id).unwrap()[0].id.clone();
+
+    let built = DocumentBuilder::new(pricing(&mulch_id), store.clone(), memory, Arc::new(NullMemoryStore))
+        .build(&session.id, "estimate")
+        .await
+        .unwrap();
+    let store = store.lock().unwrap();
+    let doc: serde_json::Value =
+        serde_json::from_str(&store.get_artifact(&built.document_artifact_id).unwrap().body).unwrap();
+    let lines = doc["lines"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|l| (l["title"].as_str().unwrap().to_string(), l["amount_cents"].as_i64()))
+        .collect();
+    (outcome.session.summary.unwrap(), lines)
+}
+
+#[tokio::test]
+async fn a_recorded_walk_replays_offline_against_new_ids() {
+    let (processing_tape, pricing_tape) = (cassette("processing"), cassette("pricing"));
+
+    let recording = Arc::new(ReplayProvider::recording(
+        Arc::new(MockProvider::new(vec![
+            tool_use("add_item", serde_json::json!({"kind": "todo", "text": "spread six yards of mulch"})),
+            tool_use("add_item", serde_json::json!({"kind": "safety", "text": "gate latch is broken"})),
+            CompletionResponse {
+                content: vec![ContentBlock::Text { text: "done".into() }],
+                stop_reason: StopReason::EndTurn,
+                usage: Usage { input_tokens: 60, output_tokens: 10, ..Default::default() },
+                model: None,
+            },
+            tool_use("write_notes", serde_json::json!({"summary": "Mulch the beds; fix the gate latch."})),
+        ])),
+        &processing_tape,
+    ));
+    let recorded = walk(recording.clone(), |mulch_id| {
+        Arc::new(ReplayProvider::recording(
+            Arc::new(MockProvider::new(vec![tool_use(
+                "price_items",
+                serde_json::json!({"prices": [{"item_id": mulch_id, "amount_cents": 34500}]}),
+            )])),
+            &pricing_tape,
+        ))
+    })
+    .await;
+    assert_eq!(recording.unplayed(), 4);
+
+    // Offline: no inner provider at all.
+    let replaying = Arc::new(ReplayProvider::replaying(&processing_tape).unwrap());
+    let pricing_replay = Arc::new(ReplayProvider::replaying(&pricing_tape).unwrap());
+    let replayed = walk(replaying.clone(), |_| pricing_replay.clone()).await;
+
+    assert_eq!(replayed, recorded);
+    assert_eq!(
+        replayed.1,
+        [("spread six yards of mulch".to_string(), Some(34500)), ("gate latch is broken".to_string(), None)],
+        "the echoed item id was mapped onto the replay run's item"
+    );
+    assert_eq!((replaying.unplayed(), pricing_replay.unplayed()), (0, 0));
+    std::fs::remove_file(processing_tape).unwrap();
+    std::fs::remove_file(pricing_tape).unwrap();
+}